@@ -1,11 +1,29 @@
-use std::sync::Arc;
+use std::{ffi::OsStr, path::PathBuf, sync::Arc};
 
 use crossterm::event::KeyEvent;
+use nix::{sys::signal::Signal, unistd::Pid};
 use ratatui::layout::Size;
+use regex::Regex;
+use strum::Display;
 
 use crate::{
+  cli::options::ActivePane,
   event::TracerEvent,
-  tui::{copy_popup::CopyPopupState, details_popup::DetailsPopupState},
+  tracer::BreakpointDecision,
+  tui::{
+    breakpoint_popup::BreakpointPopupState,
+    columns_popup::ColumnsPopupState,
+    copy_popup::CopyPopupState,
+    details_popup::DetailsPopupState,
+    diff_popup::DiffPopupState,
+    event_list::{EventColumn, EventSort, ListFilter},
+    export_popup::{ExportFormat, ExportPopupState, ExportScope},
+    help::HelpPopupState,
+    kill_popup::KillPopupState,
+    new_trace_popup::NewTracePopupState,
+    process_picker::ProcessPickerState,
+    sort_popup::SortPopupState,
+  },
 };
 
 #[derive(Debug, Clone)]
@@ -18,6 +36,8 @@ pub enum Action {
   Resize(Size),
   // Navigation
   NextItem,
+  /// Select the item at the row of the event list
+  SelectItem(usize),
   PrevItem,
   PageDown,
   PageUp,
@@ -30,17 +50,42 @@ pub enum Action {
   ScrollToStart,
   ScrollToEnd,
   ToggleFollow,
+  /// Pin the process of the selected event, or unpin the pinned process
+  TogglePin,
   ToggleEnvDisplay,
+  ToggleWrap,
+  ToggleGroup,
+  /// Collapse or expand the group of the selected event
+  ToggleCollapse,
+  /// Hide or show the events of the descendants of the selected process
+  ToggleSubtree,
   StopFollow,
   // Sizing
   ShrinkPane,
   GrowPane,
   // Layout
   SwitchLayout,
+  /// Maximize the active pane, or restore the split
+  ToggleZoom,
   // Pane
   SwitchActivePane,
+  SetActivePane(ActivePane),
+  ResizePane(u16),
+  /// Scroll the pseudo terminal by the lines, positive values go back into the history
+  ScrollTerminal(isize),
+  /// Select the text between the positions on the screen of the pseudo terminal
+  SelectTerminalText {
+    from: (u16, u16),
+    to: (u16, u16),
+  },
+  YankTerminalSelection,
+  ExitTerminalCopyMode,
   // Popup
   SetActivePopup(ActivePopup),
+  /// Open the file in `$EDITOR`, suspending the TUI until the editor exits
+  OpenInEditor(PathBuf),
+  /// Open the file in `$PAGER`, suspending the TUI until the pager exits
+  OpenInPager(PathBuf),
   CancelCurrentPopup,
   // Clipboard
   ShowCopyDialog(Arc<TracerEvent>),
@@ -50,6 +95,94 @@ pub enum Action {
   },
   // Terminal
   HandleTerminalKeyPress(KeyEvent),
+  // Search
+  BeginSearch,
+  CancelSearchInput,
+  Search(Regex),
+  ClearSearch,
+  NextMatch,
+  PrevMatch,
+  // Filter
+  BeginFilter,
+  SetFilter(Option<ListFilter>),
+  ClearFilter,
+  BeginWatch,
+  /// Notify when an exec matching the regex occurs
+  AddWatch {
+    regex: Regex,
+    select: bool,
+  },
+  ClearWatches,
+  BeginBreakpoint,
+  /// Stop the tracer at the execs whose command line matches the regex
+  AddBreakpoint(Regex),
+  ClearBreakpoints,
+  /// Continue the exec that is stopped at a breakpoint
+  ResumeBreakpoint(BreakpointDecision),
+  BeginGoTo,
+  GoTo(GoToTarget),
+  // Process
+  TogglePause,
+  KillProcess {
+    pid: Pid,
+    signal: Signal,
+  },
+  // Export
+  ExportEvents {
+    format: ExportFormat,
+    scope: ExportScope,
+    path: PathBuf,
+  },
+  // Columns
+  SetColumns(Vec<EventColumn>),
+  // Sorting
+  SetSort(Option<EventSort>),
+  // Tabs
+  /// Trace the command in a new tab
+  NewTrace(String),
+  /// Type the command into the pseudo terminal and run it
+  RunInTerminal(String),
+  /// Trace the running process, with its command line as the title
+  Attach(Pid, String),
+  SwitchTab(usize),
+  // Bookmarks
+  ToggleBookmark,
+  NextBookmark,
+  // Failures
+  NextFailure,
+  PrevFailure,
+  // Diff
+  /// Mark the selected exec event as the base that other exec events are compared against
+  MarkDiffBase,
+  // Process Tree
+  ToggleProcessTree,
+  NextProcess,
+  PrevProcess,
+  /// Select the process at the row of the process tree pane
+  SelectProcess(usize),
+  // Statistics
+  ToggleStats,
+  ToggleStatsOrder,
+  NextStatsRow,
+  PrevStatsRow,
+  // Timeline
+  ToggleTimeline,
+  ScrollTimelineDown,
+  ScrollTimelineUp,
+  JumpToProcess(Pid),
+  /// Select the most recent exec event of the parent of the selected process
+  JumpToParent,
+  /// Cycle through the exec events of the children of the selected process
+  NextChild,
+}
+
+/// Where the go-to prompt jumps to in the event list
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GoToTarget {
+  /// The event by its number among all the events, starting from 1
+  Event(usize),
+  FirstOf(Pid),
+  LastOf(Pid),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -58,23 +191,127 @@ pub enum CopyTarget {
   Commandline(SupportedShell),
   CommandlineWithStdio(SupportedShell),
   CommandlineWithFds(SupportedShell),
+  /// The command line that starts from an empty environment and sets all the variables
+  CommandlineWithFullEnv(SupportedShell),
   Env,
   Argv,
   Filename,
   SyscallResult,
   EnvDiff,
+  Json,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
+#[strum(serialize_all = "lowercase")]
 pub enum SupportedShell {
   Bash,
   Sh,
+  Zsh,
   Fish,
+  Nushell,
+  PowerShell,
+  Xonsh,
+}
+
+impl SupportedShell {
+  pub const ALL: [Self; 7] = [
+    Self::Bash,
+    Self::Sh,
+    Self::Zsh,
+    Self::Fish,
+    Self::Nushell,
+    Self::PowerShell,
+    Self::Xonsh,
+  ];
+
+  /// Whether the shell understands redirections like `2>&-`
+  pub fn supports_redirections(self) -> bool {
+    matches!(self, Self::Bash | Self::Sh | Self::Zsh | Self::Fish)
+  }
+
+  /// Quote the string so that the shell passes it as a single argument without interpreting it
+  pub fn quote(self, s: impl AsRef<OsStr>) -> String {
+    let s = s.as_ref();
+    match self {
+      // zsh understands the $'...' strings of bash
+      Self::Bash | Self::Zsh => String::from_utf8_lossy(&shell_quote::Bash::quote(s)).into_owned(),
+      Self::Sh => String::from_utf8_lossy(&shell_quote::Sh::quote(s)).into_owned(),
+      _ => {
+        let s = s.to_string_lossy();
+        if !s.is_empty()
+          && s
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./".contains(c))
+        {
+          return s.into_owned();
+        }
+        match self {
+          Self::Fish => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'")),
+          Self::Nushell => {
+            // Raw strings can contain anything but their own delimiter
+            let mut hashes = "#".to_string();
+            while s.contains(&format!("'{hashes}")) {
+              hashes.push('#');
+            }
+            format!("r{hashes}'{s}'{hashes}")
+          }
+          Self::PowerShell => {
+            // Quotes are escaped by doubling them, PowerShell treats the typographic quotes as quotes too
+            let mut quoted = String::from("'");
+            for c in s.chars() {
+              if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+                quoted.push(c);
+              }
+              quoted.push(c);
+            }
+            quoted.push('\'');
+            quoted
+          }
+          Self::Xonsh => {
+            // Quoted arguments are python string literals
+            let mut quoted = String::from("'");
+            for c in s.chars() {
+              match c {
+                '\\' => quoted.push_str("\\\\"),
+                '\'' => quoted.push_str("\\'"),
+                '\n' => quoted.push_str("\\n"),
+                '\t' => quoted.push_str("\\t"),
+                c if c.is_control() => quoted.push_str(&format!("\\x{:02x}", c as u32)),
+                c => quoted.push(c),
+              }
+            }
+            quoted.push('\'');
+            quoted
+          }
+          Self::Bash | Self::Zsh | Self::Sh => unreachable!(),
+        }
+      }
+    }
+  }
+
+  /// Quote an environment variable assignment like `KEY=VALUE` for the env command
+  pub fn quote_assignment(self, key: &str, value: &str) -> String {
+    match self {
+      Self::Bash | Self::Sh | Self::Zsh | Self::Fish => {
+        format!("{}={}", self.quote(key), self.quote(value))
+      }
+      // These shells can't concatenate quoted strings into a single argument
+      _ => self.quote(format!("{key}={value}")),
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
 pub enum ActivePopup {
-  Help,
+  Help(HelpPopupState),
   ViewDetails(DetailsPopupState),
   CopyTargetSelection(CopyPopupState),
+  KillConfirmation(KillPopupState),
+  Export(ExportPopupState),
+  Columns(ColumnsPopupState),
+  NewTrace(NewTracePopupState),
+  Diff(DiffPopupState),
+  Breakpoint(BreakpointPopupState),
+  ProcessPicker(ProcessPickerState),
+  Sort(SortPopupState),
 }
@@ -14,6 +14,12 @@ macro_rules! syscall_res_from_regs {
   };
 }
 
+macro_rules! stack_pointer_from_regs {
+  ($regs:ident) => {
+    $regs.sp
+  };
+}
+
 macro_rules! syscall_arg {
   ($regs:ident, 0) => {
     $regs.a0
@@ -35,6 +41,7 @@ macro_rules! syscall_arg {
   };
 }
 
+pub(crate) use stack_pointer_from_regs;
 pub(crate) use syscall_arg;
 pub(crate) use syscall_no_from_regs;
 pub(crate) use syscall_res_from_regs;
@@ -1,15 +1,33 @@
-use std::{num::ParseFloatError, path::PathBuf};
+use std::{
+  io::{self, stdin, stdout},
+  num::{ParseFloatError, ParseIntError},
+  path::{Path, PathBuf},
+  time::Duration,
+};
 
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand};
+use clap_complete::{generate, Shell};
+use clap_complete_nushell::Nushell;
+use color_eyre::eyre::{bail, WrapErr};
+use nix::sys::signal::Signal;
 
-use crate::tui::app::AppLayout;
+use crate::{
+  output::{OutputFormat, OutputSpec},
+  pty::PtySize,
+  tui::app::AppLayout,
+};
 
 use self::{
-  args::{ModifierArgs, TracerEventArgs, LogModeArgs},
-  options::{ActivePane, Color},
+  args::{ExporterArgs, LimitArgs, LogModeArgs, ModifierArgs, TracerEventArgs},
+  config::Config,
+  options::{
+    ActivePane, ClipboardProvider, Color, CompletionShell, Compression, SchemaFormat, StdinSource,
+  },
 };
 
+mod alias;
 pub mod args;
+pub mod config;
 pub mod options;
 #[cfg(test)]
 mod test;
@@ -17,8 +35,12 @@ mod test;
 #[derive(Parser, Debug)]
 #[clap(author, version, about)]
 pub struct Cli {
-  #[arg(long, default_value_t = Color::Auto, help = "Control whether colored output is enabled. This flag has no effect on TUI mode.")]
-  pub color: Color,
+  #[arg(
+    long,
+    env = "TRACEXEC_COLOR",
+    help = "Control whether colored output is enabled, auto by default. The TUI is shown without colors if it is disabled."
+  )]
+  pub color: Option<Color>,
   #[arg(
     short = 'C',
     long,
@@ -28,48 +50,165 @@ pub struct Cli {
   #[arg(
     short,
     long,
-    help = "Run as user. This option is only available when running tracexec as root"
+    help = "Run as user, with the user's groups, HOME, USER, LOGNAME and SHELL. This option is only available when running tracexec as root"
   )]
   pub user: Option<String>,
+  #[arg(
+    long,
+    help = "With --user, start from a login environment like su -l: only TERM is kept, PATH is reset and the command runs in the user's home directory",
+    requires = "user"
+  )]
+  pub login: bool,
+  #[arg(
+    long,
+    env = "TRACEXEC_CONFIG",
+    help = "Path to the config file, config.toml in $TRACEXEC_CONFIG_DIR or ~/.config/tracexec by default. The options given on the command line take precedence over the config file"
+  )]
+  pub config: Option<PathBuf>,
+  #[arg(
+    long,
+    env = "TRACEXEC_PROFILE",
+    help = "Use the named profile of the config file, which is defined in a [profile.NAME] table and overrides the rest of the config"
+  )]
+  pub profile: Option<String>,
+  #[arg(
+    long,
+    global = true,
+    help = "Check the config file, the options and whether the command can be traced, print the effective settings and exit without running anything"
+  )]
+  pub dry_run: bool,
   #[clap(subcommand)]
   pub cmd: CliCommand,
 }
 
-#[derive(Subcommand, Debug)]
+const CMD_FILE_HELP: &str = "Read the command to be executed from this file instead, one argument per line, or separated by NUL bytes if there is any. A single hyphen '-' represents stdin";
+
+#[derive(Subcommand, Debug, strum::IntoStaticStr)]
+#[strum(serialize_all = "kebab-case")]
 pub enum CliCommand {
-  #[clap(about = "Run tracexec in logging mode")]
+  #[clap(
+    about = "Run tracexec in logging mode",
+    group(ArgGroup::new("command").args(["cmd", "cmd_file", "from_file"]).required(true))
+  )]
   Log {
-    #[arg(last = true, required = true, help = "command to be executed")]
+    #[arg(last = true, help = "command to be executed")]
     cmd: Vec<String>,
+    #[clap(long, value_name = "PATH", help = CMD_FILE_HELP)]
+    cmd_file: Option<PathBuf>,
     #[clap(flatten)]
     tracing_args: LogModeArgs,
     #[clap(flatten)]
     modifier_args: ModifierArgs,
     #[clap(flatten)]
     tracer_event_args: TracerEventArgs,
+    #[clap(flatten)]
+    exporter_args: ExporterArgs,
+    #[clap(flatten)]
+    limit_args: LimitArgs,
     #[clap(
       short,
       long,
-      help = "Output, stderr by default. A single hyphen '-' represents stdout."
+      env = "TRACEXEC_OUTPUT",
+      value_name = "[FORMAT:]PATH",
+      help = "Output, can be specified multiple times. A single hyphen '-' represents stdout. \
+              PATH can also be a FIFO or unix:SOCKET_PATH to stream events to a program listening on a Unix socket. \
+              FORMAT overrides --output-format for this output. The human readable log goes to stderr unless such an output is given."
     )]
-    output: Option<PathBuf>,
+    output: Vec<OutputSpec>,
+    #[clap(
+      long,
+      value_name = "FORMAT",
+      env = "TRACEXEC_OUTPUT_FORMAT",
+      help = "The format of the outputs. Without --output, the human readable formats go to stderr and the others to stdout [default: human]"
+    )]
+    output_format: Option<OutputFormat>,
+    #[clap(
+      long,
+      help = "Rotate the output file once it grows beyond this size, e.g. 512K, 100M or 2G",
+      requires = "output",
+      value_parser = size_parser
+    )]
+    rotate_size: Option<u64>,
+    #[clap(
+      long,
+      help = "Rotate the output file after this much time, e.g. 30m, 12h or 1d",
+      requires = "output",
+      value_parser = duration_parser
+    )]
+    rotate_interval: Option<Duration>,
+    #[clap(
+      long,
+      help = "Number of rotated output files to keep",
+      default_value_t = 5
+    )]
+    rotate_keep: usize,
+    #[clap(
+      long,
+      help = "Compress output files. By default, files ending with .gz or .zst are compressed with gzip or zstd respectively"
+    )]
+    compress: Option<Compression>,
+    #[clap(
+      long,
+      help = "Run the command under a pseudo terminal, so that it thinks it is interactive. Its input and output pass through tracexec's stdin and stdout",
+      conflicts_with = "foreground"
+    )]
+    pty: bool,
+    #[clap(
+      long,
+      value_name = "COLSxROWS",
+      help = "Open the pseudo terminal with this size, e.g. 120x40, instead of following the size of tracexec's terminal",
+      requires = "pty",
+      value_parser = pty_size_parser
+    )]
+    pty_size: Option<PtySize>,
+    #[clap(
+      long,
+      value_name = "PATH",
+      help = "Print a trace recorded by tracexec record instead of running a command",
+      conflicts_with = "pty"
+    )]
+    from_file: Option<PathBuf>,
   },
-  #[clap(about = "Run tracexec in TUI mode, stdin/out/err are redirected to /dev/null by default")]
+  #[clap(
+    about = "Run tracexec in TUI mode, stdin/out/err are redirected to /dev/null by default",
+    group(ArgGroup::new("command").args(["cmd", "cmd_file"]))
+  )]
   Tui {
-    #[arg(last = true, required = true, help = "command to be executed")]
+    #[arg(
+      last = true,
+      help = "command to be executed, a running process to attach to is picked in the TUI if it is omitted"
+    )]
     cmd: Vec<String>,
+    #[clap(long, value_name = "PATH", help = CMD_FILE_HELP)]
+    cmd_file: Option<PathBuf>,
     #[clap(flatten)]
     modifier_args: ModifierArgs,
     #[clap(flatten)]
     tracer_event_args: TracerEventArgs,
+    #[clap(flatten)]
+    limit_args: LimitArgs,
     #[clap(
       long,
       short,
-      help = "Allocate a pseudo terminal and show it alongside the TUI"
+      env = "TRACEXEC_TTY",
+      help = "Allocate a pseudo terminal and show it alongside the TUI",
+      requires = "command"
     )]
     tty: bool,
+    #[clap(
+      long,
+      help = "Don't allocate a pseudo terminal, even if the config does",
+      conflicts_with = "tty"
+    )]
+    no_tty: bool,
     #[clap(long, short, help = "Keep the event list scrolled to the bottom")]
     follow: bool,
+    #[clap(
+      long,
+      help = "Don't keep the event list scrolled to the bottom, even if the config does",
+      conflicts_with = "follow"
+    )]
+    no_follow: bool,
     #[clap(
       long,
       help = "Instead of waiting for the root child to exit, terminate when the TUI exits",
@@ -78,34 +217,220 @@ pub enum CliCommand {
     terminate_on_exit: bool,
     #[clap(
       long,
-      help = "Instead of waiting for the root child to exit, kill when the TUI exits"
+      value_name = "SIGNAL",
+      num_args = 0..=1,
+      require_equals = true,
+      default_missing_value = "SIGKILL",
+      value_parser = signal_parser,
+      help = "Instead of waiting for the root child to exit, kill when the TUI exits. The signal is SIGKILL unless given like --kill-on-exit=SIGTERM"
     )]
-    kill_on_exit: bool,
+    kill_on_exit: Option<Signal>,
+    #[clap(
+      long,
+      value_name = "DURATION",
+      requires = "kill_on_exit",
+      value_parser = duration_parser,
+      help = "Send SIGKILL to the root child if it is still running this long after the signal of --kill-on-exit, e.g. 5s"
+    )]
+    kill_grace_period: Option<Duration>,
     #[clap(
       long,
       short = 'A',
-      help = "Set the default active pane to use when TUI launches",
-      requires = "tty",
-      default_value_t
+      help = "Set the default active pane to use when TUI launches, defaults to the one of the config or the last session",
+      requires = "tty"
     )]
-    active_pane: ActivePane,
+    active_pane: Option<ActivePane>,
     #[clap(
       long,
       short = 'L',
-      help = "Set the layout of the TUI when it launches",
+      help = "Set the layout of the TUI when it launches, defaults to the one of the config or the last session",
+      requires = "tty"
+    )]
+    layout: Option<AppLayout>,
+    #[clap(
+      long,
+      value_name = "COLSxROWS",
+      help = "Open the pseudo terminal with this size, e.g. 120x40, instead of fitting it to its pane",
       requires = "tty",
-      default_value_t
+      value_parser = pty_size_parser
+    )]
+    pty_size: Option<PtySize>,
+    #[clap(
+      long,
+      value_name = "null|pty|-|PATH",
+      requires = "command",
+      help = "Where the stdin of the command comes from: /dev/null, the pseudo terminal, the stdin of tracexec if it is a pipe, or a file or named pipe. Defaults to pty with --tty and null otherwise"
     )]
-    layout: AppLayout,
+    stdin: Option<StdinSource>,
+    #[clap(
+      long,
+      help = "Don't capture the mouse, so that the terminal's own text selection works"
+    )]
+    no_mouse: bool,
+    #[clap(
+      long,
+      help = "Capture the mouse, even if the config doesn't",
+      conflicts_with = "no_mouse"
+    )]
+    mouse: bool,
+    #[clap(
+      long,
+      help = "Forget the layout, the columns and the filter of the last session instead of restoring them"
+    )]
+    reset_state: bool,
+    #[clap(
+      long,
+      help = "How to copy to the clipboard, OSC 52 is supported by most terminals and works over SSH. Defaults to auto"
+    )]
+    clipboard: Option<ClipboardProvider>,
     #[clap(
       long,
       short = 'F',
-      help = "Set the frame rate of the TUI",
-      default_value = "60.0",
+      env = "TRACEXEC_FRAME_RATE",
+      help = "Set the frame rate of the TUI, 60 by default",
       value_parser = frame_rate_parser
     )]
-    frame_rate: f64,
+    frame_rate: Option<f64>,
+  },
+  #[clap(
+    about = "Record the complete event stream of a command to a file without any UI, for analyzing it later",
+    group(ArgGroup::new("command").args(["cmd", "cmd_file"]).required(true))
+  )]
+  Record {
+    #[arg(last = true, help = "command to be executed")]
+    cmd: Vec<String>,
+    #[clap(long, value_name = "PATH", help = CMD_FILE_HELP)]
+    cmd_file: Option<PathBuf>,
+    #[clap(flatten)]
+    modifier_args: ModifierArgs,
+    #[clap(flatten)]
+    limit_args: LimitArgs,
+    #[clap(
+      short,
+      long,
+      value_name = "PATH",
+      help = "The file to record the trace to, which is zstd compressed, e.g. trace.tracexec"
+    )]
+    output: PathBuf,
   },
+  #[clap(about = "Browse a trace recorded by tracexec record in the TUI")]
+  Open {
+    #[arg(help = "The recorded trace")]
+    file: PathBuf,
+    #[clap(flatten)]
+    tracer_event_args: TracerEventArgs,
+  },
+  #[clap(
+    about = "Compare the execs of two traces recorded by tracexec record, exits with 1 if they differ"
+  )]
+  Diff {
+    #[arg(help = "The trace to compare against, e.g. of a good build")]
+    old: PathBuf,
+    #[arg(help = "The trace that is compared, e.g. of a bad build")]
+    new: PathBuf,
+    #[clap(long, help = "Do not compare the environment variables of the execs")]
+    ignore_env: bool,
+  },
+  #[clap(about = "Print the shell completions of tracexec to stdout")]
+  Completions {
+    #[arg(help = "The shell to generate completions for")]
+    shell: CompletionShell,
+  },
+  #[clap(about = "Print the default config file with all the options commented out to stdout")]
+  GenerateConfig,
+  #[clap(
+    about = "Print the fields of the structured output formats, generated from the types they are written from"
+  )]
+  Schema {
+    #[arg(long, default_value_t = SchemaFormat::JsonSchema, help = "The format of the schema")]
+    format: SchemaFormat,
+    #[arg(
+      help = "The output format to describe, all structured formats by default [possible values: jsonl, csv]"
+    )]
+    output_format: Option<OutputFormat>,
+  },
+}
+
+impl Cli {
+  /// Parse the command line after expanding the aliases of the config file,
+  /// which is returned if it had to be loaded for them
+  pub fn parse_with_aliases() -> color_eyre::Result<(Self, Option<Config>)> {
+    let (args, config) = alias::expand_aliases(std::env::args_os().collect())?;
+    let mut cli = Self::parse_from(args);
+    // Read before --cwd takes effect, so that relative paths are relative to where tracexec is run
+    cli.cmd.read_cmd_file()?;
+    Ok((cli, config))
+  }
+
+  pub fn print_completions(shell: CompletionShell) {
+    let mut cmd = Self::command();
+    let name = cmd.get_name().to_string();
+    let out = &mut stdout();
+    match shell {
+      CompletionShell::Bash => generate(Shell::Bash, &mut cmd, name, out),
+      CompletionShell::Elvish => generate(Shell::Elvish, &mut cmd, name, out),
+      CompletionShell::Fish => generate(Shell::Fish, &mut cmd, name, out),
+      CompletionShell::Nushell => generate(Nushell, &mut cmd, name, out),
+      CompletionShell::PowerShell => generate(Shell::PowerShell, &mut cmd, name, out),
+      CompletionShell::Zsh => generate(Shell::Zsh, &mut cmd, name, out),
+    }
+  }
+}
+
+impl CliCommand {
+  /// Take the command from --cmd-file, which is an alternative to the trailing arguments
+  fn read_cmd_file(&mut self) -> color_eyre::Result<()> {
+    if let Self::Tui {
+      cmd_file: Some(path),
+      stdin: Some(StdinSource::Inherit),
+      ..
+    } = self
+    {
+      if path.as_os_str() == "-" {
+        bail!("--cmd-file - and --stdin - can't both read from stdin");
+      }
+    }
+    let (cmd, cmd_file) = match self {
+      Self::Log { cmd, cmd_file, .. }
+      | Self::Tui { cmd, cmd_file, .. }
+      | Self::Record { cmd, cmd_file, .. } => (cmd, cmd_file),
+      _ => return Ok(()),
+    };
+    if let Some(path) = cmd_file.take() {
+      *cmd = read_cmd_file(&path)?;
+    }
+    Ok(())
+  }
+}
+
+/// Read the arguments of a command, which are separated by newlines,
+/// or by NUL bytes if there is any so that the arguments can contain newlines.
+///
+/// Empty arguments can only be given with NUL bytes, blank lines at the end are ignored.
+fn read_cmd_file(path: &Path) -> color_eyre::Result<Vec<String>> {
+  let content = if path.as_os_str() == "-" {
+    io::read_to_string(stdin()).wrap_err("Failed to read the command from stdin")?
+  } else {
+    std::fs::read_to_string(path)
+      .wrap_err_with(|| format!("Failed to read the command from {}", path.display()))?
+  };
+  let cmd: Vec<String> = if content.contains('\0') {
+    content.split_terminator('\0').map(str::to_string).collect()
+  } else {
+    let lines: Vec<&str> = content.trim_end_matches(['\n', '\r']).lines().collect();
+    if let Some(line) = lines.iter().position(|line| line.is_empty()) {
+      bail!(
+        "Line {} of {} is empty, separate the arguments with NUL bytes to pass an empty one",
+        line + 1,
+        path.display()
+      );
+    }
+    lines.into_iter().map(str::to_string).collect()
+  };
+  if cmd.is_empty() {
+    bail!("No command in {}", path.display());
+  }
+  Ok(cmd)
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -125,7 +450,10 @@ impl From<ParseFloatError> for ParseFrameRateError {
 }
 
 fn frame_rate_parser(s: &str) -> Result<f64, ParseFrameRateError> {
-  let v = s.parse::<f64>()?;
+  check_frame_rate(s.parse::<f64>()?)
+}
+
+fn check_frame_rate(v: f64) -> Result<f64, ParseFrameRateError> {
   if v < 0.0 || v.is_nan() || v.is_infinite() {
     Err(ParseFrameRateError::InvalidFrameRate)
   } else if v < 5.0 {
@@ -134,3 +462,110 @@ fn frame_rate_parser(s: &str) -> Result<f64, ParseFrameRateError> {
     Ok(v)
   }
 }
+
+#[derive(thiserror::Error, Debug)]
+enum ParseSizeError {
+  #[error("Failed to parse size {0} as an integer")]
+  ParseIntError(ParseIntError),
+  #[error("Unknown size unit {0:?}, expected one of K, M, G")]
+  UnknownUnit(String),
+  #[error("Size too large")]
+  TooLarge,
+  #[error("Size must not be zero")]
+  Zero,
+}
+
+impl From<ParseIntError> for ParseSizeError {
+  fn from(e: ParseIntError) -> Self {
+    Self::ParseIntError(e)
+  }
+}
+
+/// Split a string like `100M` into its number and unit
+fn split_unit(s: &str) -> (&str, &str) {
+  s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()))
+}
+
+fn size_parser(s: &str) -> Result<u64, ParseSizeError> {
+  let (num, unit) = split_unit(s);
+  let num = num.parse::<u64>()?;
+  let multiplier: u64 = match unit {
+    "" | "B" => 1,
+    "K" | "KiB" => 1 << 10,
+    "M" | "MiB" => 1 << 20,
+    "G" | "GiB" => 1 << 30,
+    _ => return Err(ParseSizeError::UnknownUnit(unit.to_string())),
+  };
+  match num.checked_mul(multiplier) {
+    Some(0) => Err(ParseSizeError::Zero),
+    Some(v) => Ok(v),
+    None => Err(ParseSizeError::TooLarge),
+  }
+}
+
+#[derive(thiserror::Error, Debug)]
+enum ParseDurationError {
+  #[error("Failed to parse duration {0} as an integer")]
+  ParseIntError(ParseIntError),
+  #[error("Unknown time unit {0:?}, expected one of s, m, h, d")]
+  UnknownUnit(String),
+  #[error("Duration must not be zero")]
+  Zero,
+}
+
+impl From<ParseIntError> for ParseDurationError {
+  fn from(e: ParseIntError) -> Self {
+    Self::ParseIntError(e)
+  }
+}
+
+fn duration_parser(s: &str) -> Result<Duration, ParseDurationError> {
+  let (num, unit) = split_unit(s);
+  let num = num.parse::<u64>()?;
+  let secs = match unit {
+    "s" => num,
+    "m" => num.saturating_mul(60),
+    "h" => num.saturating_mul(60 * 60),
+    "d" => num.saturating_mul(24 * 60 * 60),
+    _ => return Err(ParseDurationError::UnknownUnit(unit.to_string())),
+  };
+  if secs == 0 {
+    return Err(ParseDurationError::Zero);
+  }
+  Ok(Duration::from_secs(secs))
+}
+
+/// Parse the size of a pseudo terminal like `120x40`
+fn pty_size_parser(s: &str) -> Result<PtySize, String> {
+  let (cols, rows) = s
+    .split_once('x')
+    .ok_or_else(|| format!("Invalid size {s:?}, expected COLSxROWS like 120x40"))?;
+  let parse = |n: &str| {
+    n.parse::<u16>()
+      .ok()
+      .filter(|&n| n > 0)
+      .ok_or_else(|| format!("Invalid size {s:?}, expected COLSxROWS like 120x40"))
+  };
+  Ok(PtySize {
+    rows: parse(rows)?,
+    cols: parse(cols)?,
+    pixel_width: 0,
+    pixel_height: 0,
+  })
+}
+
+/// Parse a signal given by its name, with or without the SIG prefix, or by its number
+fn signal_parser(s: &str) -> Result<Signal, String> {
+  if let Ok(number) = s.parse::<i32>() {
+    return Signal::try_from(number).map_err(|_| format!("Unknown signal number {number}"));
+  }
+  let name = s.to_ascii_uppercase();
+  let name = if name.starts_with("SIG") {
+    name
+  } else {
+    format!("SIG{name}")
+  };
+  name
+    .parse()
+    .map_err(|_| format!("Unknown signal {s:?}, e.g. TERM, SIGKILL or 9"))
+}
@@ -0,0 +1,110 @@
+//! Aliases of the config file, which are bundles of arguments, e.g. `tracexec @build log -- make`.
+
+use std::{ffi::OsString, path::PathBuf};
+
+use clap::CommandFactory;
+use color_eyre::eyre::bail;
+
+use super::{config::Config, Cli};
+
+#[cfg(test)]
+mod test;
+
+/// Expand the `@NAME` arguments in front of the subcommand to the arguments of the aliases.
+///
+/// The arguments of the aliases go right after the subcommand, as they are usually options of it.
+/// An alias can also start with the subcommand itself, e.g. `alias.build = ["log", "--show-cmdline"]`,
+/// in which case it takes the place of the subcommand, which may only be given again if it is the same.
+///
+/// The config file is only loaded if there are aliases, and then returned so that it isn't loaded twice.
+pub fn expand_aliases(args: Vec<OsString>) -> color_eyre::Result<(Vec<OsString>, Option<Config>)> {
+  let command = Cli::command();
+  let is_subcommand = |arg: &str| {
+    arg == "help"
+      || command
+        .get_subcommands()
+        .any(|sub| sub.get_name() == arg || sub.get_all_aliases().any(|alias| alias == arg))
+  };
+  // Whether the global option is followed by its value as a separate argument
+  let takes_value = |arg: &str| {
+    command.get_arguments().any(|global| {
+      global.get_action().takes_values()
+        && (arg
+          .strip_prefix("--")
+          .is_some_and(|long| global.get_long() == Some(long))
+          || arg.strip_prefix('-').is_some_and(|short| {
+            let mut chars = short.chars();
+            chars.next() == global.get_short() && chars.next().is_none()
+          }))
+    })
+  };
+  let mut args = args.into_iter();
+  let mut globals = args.next().into_iter().collect::<Vec<_>>();
+  let mut aliases = vec![];
+  let mut config = std::env::var_os("TRACEXEC_CONFIG").map(PathBuf::from);
+  let mut profile = std::env::var("TRACEXEC_PROFILE").ok();
+  let mut rest = vec![];
+  while let Some(arg) = args.next() {
+    let Some(s) = arg.to_str() else {
+      globals.push(arg);
+      continue;
+    };
+    if let Some(name) = s.strip_prefix('@').filter(|name| !name.is_empty()) {
+      aliases.push(name.to_string());
+      continue;
+    }
+    if s == "--" || is_subcommand(s) {
+      rest.push(arg);
+      break;
+    }
+    // The config file and the profile of the command line decide which aliases are defined
+    let value = if takes_value(s) { args.next() } else { None };
+    match (s, &value) {
+      ("--config", Some(value)) => config = Some(value.into()),
+      ("--profile", Some(value)) => profile = value.to_str().map(str::to_string),
+      _ => {
+        if let Some(path) = s.strip_prefix("--config=") {
+          config = Some(path.into());
+        } else if let Some(name) = s.strip_prefix("--profile=") {
+          profile = Some(name.to_string());
+        }
+      }
+    }
+    globals.push(arg);
+    globals.extend(value);
+  }
+  if aliases.is_empty() {
+    globals.extend(rest);
+    globals.extend(args);
+    return Ok((globals, None));
+  }
+  let config = Config::load(config.as_deref(), profile.as_deref())?;
+  let mut subcommand = rest
+    .first()
+    .and_then(|arg| arg.to_str())
+    .filter(|arg| is_subcommand(arg))
+    .map(str::to_string);
+  let given = subcommand.is_some();
+  let mut expanded = vec![];
+  for name in aliases {
+    let Some(alias) = config.alias.get(&name) else {
+      bail!("Alias @{name} is not defined in the config file");
+    };
+    let mut alias = alias.iter();
+    if let Some(first) = alias.clone().next().filter(|arg| is_subcommand(arg)) {
+      match &subcommand {
+        Some(sub) if sub != first => {
+          bail!("Alias @{name} is for the {first} subcommand, but {sub} is used")
+        }
+        _ => subcommand = Some(first.clone()),
+      }
+      alias.next();
+    }
+    expanded.extend(alias.map(OsString::from));
+  }
+  globals.extend(subcommand.map(OsString::from));
+  globals.extend(expanded);
+  globals.extend(rest.into_iter().skip(given as usize));
+  globals.extend(args);
+  Ok((globals, Some(config)))
+}
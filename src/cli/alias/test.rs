@@ -0,0 +1,108 @@
+use std::{ffi::OsString, path::PathBuf};
+
+use super::expand_aliases;
+
+/// Write a config file with the aliases of these tests, unique to the test
+fn write_config(test: &str) -> PathBuf {
+  let path =
+    std::env::temp_dir().join(format!("tracexec-alias-{}-{test}.toml", std::process::id()));
+  std::fs::write(
+    &path,
+    r#"
+[alias]
+build = ["--seccomp-bpf", "on", "--exclude-comm", "sed"]
+verbose = ["log", "--show-cmdline"]
+quiet = ["tui", "-t"]
+"#,
+  )
+  .unwrap();
+  path
+}
+
+fn expand(test: &str, args: &[&str]) -> color_eyre::Result<Vec<String>> {
+  let config = write_config(test);
+  let args = ["tracexec", "--config", config.to_str().unwrap()]
+    .iter()
+    .chain(args)
+    .map(OsString::from)
+    .collect();
+  let result = expand_aliases(args);
+  std::fs::remove_file(config).unwrap();
+  let (args, config) = result?;
+  assert!(config.is_some());
+  Ok(
+    args
+      .into_iter()
+      .skip(3)
+      .map(|arg| arg.into_string().unwrap())
+      .collect(),
+  )
+}
+
+#[test]
+fn without_aliases_the_config_is_not_loaded() {
+  let args = [
+    "tracexec",
+    "--config",
+    "/nonexistent",
+    "log",
+    "--",
+    "@build",
+  ]
+  .map(OsString::from);
+  let (expanded, config) = expand_aliases(args.to_vec()).unwrap();
+  assert_eq!(expanded, args);
+  assert!(config.is_none());
+}
+
+#[test]
+fn alias_goes_after_the_subcommand() {
+  assert_eq!(
+    expand("after", &["@build", "log", "--", "make"]).unwrap(),
+    [
+      "log",
+      "--seccomp-bpf",
+      "on",
+      "--exclude-comm",
+      "sed",
+      "--",
+      "make"
+    ]
+  );
+}
+
+#[test]
+fn alias_takes_the_place_of_the_subcommand() {
+  assert_eq!(
+    expand("place", &["@verbose", "--", "make"]).unwrap(),
+    ["log", "--show-cmdline", "--", "make"]
+  );
+}
+
+#[test]
+fn same_subcommand_is_not_repeated() {
+  assert_eq!(
+    expand("same", &["@build", "@verbose", "log", "--", "make"]).unwrap(),
+    [
+      "log",
+      "--seccomp-bpf",
+      "on",
+      "--exclude-comm",
+      "sed",
+      "--show-cmdline",
+      "--",
+      "make"
+    ]
+  );
+}
+
+#[test]
+fn conflicting_subcommands_are_rejected() {
+  assert!(expand("conflict", &["@verbose", "tui", "--", "make"]).is_err());
+  assert!(expand("conflict-aliases", &["@verbose", "@quiet", "--", "make"]).is_err());
+}
+
+#[test]
+fn undefined_alias_is_rejected() {
+  assert!(expand("undefined", &["@nope", "log", "--", "make"]).is_err());
+}
@@ -1,11 +1,25 @@
+use std::{path::PathBuf, time::Duration};
+
 use clap::{Args, ValueEnum};
 use color_eyre::eyre::bail;
 use enumflags2::BitFlags;
+use nix::{sched::CloneFlags, sys::signal::Signal};
+use regex::Regex;
 
-use crate::event::TracerEventKind;
+use crate::{
+  event::TracerEventKind,
+  filter::{ExcludePattern, ExecFilter, FilterExpr},
+  printer::LogTemplate,
+};
 
 #[cfg(feature = "seccomp-bpf")]
 use super::options::SeccompBpf;
+use super::{
+  config::{EventFilterConfig, LogModeConfig, ModifierConfig},
+  duration_parser,
+  options::{ArgvEscape, EnvDisplay, MaxEventsAction, PidDisplay},
+  signal_parser,
+};
 
 #[derive(Args, Debug, Default, Clone)]
 pub struct ModifierArgs {
@@ -14,18 +28,39 @@ pub struct ModifierArgs {
   pub seccomp_bpf: SeccompBpf,
   #[clap(long, help = "Only show successful calls", default_value_t = false)]
   pub successful_only: bool,
+  #[clap(
+    long,
+    help = "Show failed calls too, even if the config only shows successful ones",
+    default_value_t = false,
+    conflicts_with = "successful_only"
+  )]
+  pub no_successful_only: bool,
   #[clap(
     long,
     help = "[Experimental] Try to reproduce file descriptors in commandline. This might result in an unexecutable cmdline if pipes, sockets, etc. are involved.",
     default_value_t = false
   )]
   pub fd_in_cmdline: bool,
+  #[clap(
+    long,
+    help = "Do not reproduce file descriptors in commandline",
+    default_value_t = false,
+    conflicts_with = "fd_in_cmdline"
+  )]
+  pub no_fd_in_cmdline: bool,
   #[clap(
     long,
     help = "[Experimental] Try to reproduce stdio in commandline. This might result in an unexecutable cmdline if pipes, sockets, etc. are involved.",
     default_value_t = false
   )]
   pub stdio_in_cmdline: bool,
+  #[clap(
+    long,
+    help = "Do not reproduce stdio in commandline",
+    default_value_t = false,
+    conflicts_with_all = ["stdio_in_cmdline", "fd_in_cmdline"]
+  )]
+  pub no_stdio_in_cmdline: bool,
   #[clap(long, help = "Resolve /proc/self/exe symlink", default_value_t = false)]
   pub resolve_proc_self_exe: bool,
   #[clap(
@@ -35,9 +70,128 @@ pub struct ModifierArgs {
     conflicts_with = "resolve_proc_self_exe"
   )]
   pub no_resolve_proc_self_exe: bool,
+  #[clap(
+    long,
+    help = "Resolve the filename of exec through symlinks and PATH to an absolute canonical path",
+    default_value_t = false
+  )]
+  pub resolve_filename: bool,
+  #[clap(
+    long,
+    help = "Show the filename of exec as it is given",
+    default_value_t = false,
+    conflicts_with = "resolve_filename"
+  )]
+  pub no_resolve_filename: bool,
+  #[clap(
+    long,
+    value_name = "GLOB",
+    help = "Also redact the values of environment variables whose names match this glob pattern (case-insensitive). Can be specified multiple times",
+    conflicts_with = "no_redact"
+  )]
+  pub redact: Vec<String>,
+  #[clap(
+    long,
+    help = "Do not redact the values of environment variables that look like secrets, which are redacted by default",
+    default_value_t = false
+  )]
+  pub no_redact: bool,
+  #[clap(
+    long,
+    help = "Only trace the root process itself and not its children, so only the execs it performs directly are reported",
+    default_value_t = false
+  )]
+  pub no_follow_forks: bool,
+  #[clap(
+    long,
+    help = "Trace the children of the root process too, even if the config doesn't",
+    default_value_t = false,
+    conflicts_with = "no_follow_forks"
+  )]
+  pub follow_forks: bool,
+  #[clap(
+    long,
+    help = "Report lost events, unreadable /proc entries and unexpected ptrace stops as warnings regardless of the filter. In log mode, exit with a nonzero status if the trace is incomplete",
+    default_value_t = false
+  )]
+  pub strict: bool,
+  #[clap(
+    long,
+    help = "Do not report incomplete traces regardless of the filter, even if the config does",
+    default_value_t = false,
+    conflicts_with = "strict"
+  )]
+  pub no_strict: bool,
+  #[clap(
+    long,
+    value_enum,
+    help = "How the pids of processes in another PID namespace, e.g. in a container, are shown [default: tracer]"
+  )]
+  pub pid_display: Option<PidDisplay>,
+  #[clap(
+    long,
+    help = "Launch the command in a new network namespace, which only has a loopback device that is down"
+  )]
+  pub unshare_net: bool,
+  #[clap(
+    long,
+    help = "Launch the command in a new PID namespace as its init process. With --unshare-mount, a new /proc is mounted",
+    conflicts_with = "no_follow_forks"
+  )]
+  pub unshare_pid: bool,
+  #[clap(long, help = "Launch the command in a new mount namespace")]
+  pub unshare_mount: bool,
+  #[clap(
+    long,
+    help = "Launch the command in a new user namespace, which unprivileged users need for the other namespaces"
+  )]
+  pub unshare_user: bool,
+  #[clap(
+    long,
+    help = "Map the current user to root in the new user namespace. Implies --unshare-user"
+  )]
+  pub map_root_user: bool,
 }
 
 impl ModifierArgs {
+  /// Fill in the options that are not given on the command line from the config
+  pub fn merge_config(&mut self, config: &ModifierConfig) {
+    #[cfg(feature = "seccomp-bpf")]
+    if self.seccomp_bpf == SeccompBpf::Auto {
+      self.seccomp_bpf = config.seccomp_bpf.unwrap_or_default();
+    }
+    // The --no-* counterparts turn off what the config turns on
+    self.successful_only |= config.successful_only && !self.no_successful_only;
+    self.fd_in_cmdline |=
+      config.fd_in_cmdline && !self.no_fd_in_cmdline && !self.no_stdio_in_cmdline;
+    self.stdio_in_cmdline |= config.stdio_in_cmdline && !self.no_stdio_in_cmdline;
+    self.resolve_filename |= config.resolve_filename && !self.no_resolve_filename;
+    self.no_redact |= config.no_redact && self.redact.is_empty();
+    self.no_follow_forks |= config.no_follow_forks && !self.follow_forks && !self.unshare_pid;
+    self.strict |= config.strict && !self.no_strict;
+    self.pid_display = self.pid_display.or(config.pid_display);
+    if !self.resolve_proc_self_exe && !self.no_resolve_proc_self_exe {
+      match config.resolve_proc_self_exe {
+        Some(true) => self.resolve_proc_self_exe = true,
+        Some(false) => self.no_resolve_proc_self_exe = true,
+        None => {}
+      }
+    }
+  }
+
+  /// The namespaces the command is launched in
+  pub fn namespaces(&self) -> CloneFlags {
+    let mut flags = CloneFlags::empty();
+    flags.set(CloneFlags::CLONE_NEWNET, self.unshare_net);
+    flags.set(CloneFlags::CLONE_NEWPID, self.unshare_pid);
+    flags.set(CloneFlags::CLONE_NEWNS, self.unshare_mount);
+    flags.set(
+      CloneFlags::CLONE_NEWUSER,
+      self.unshare_user || self.map_root_user,
+    );
+    flags
+  }
+
   pub fn processed(mut self) -> Self {
     self.stdio_in_cmdline = self.fd_in_cmdline || self.stdio_in_cmdline;
     self.resolve_proc_self_exe = match (self.resolve_proc_self_exe, self.no_resolve_proc_self_exe) {
@@ -49,7 +203,7 @@ impl ModifierArgs {
   }
 }
 
-#[derive(Args, Debug, Default)]
+#[derive(Args, Debug, Default, Clone)]
 pub struct TracerEventArgs {
   // TODO:
   //   This isn't really compatible with logging mode
@@ -61,13 +215,22 @@ pub struct TracerEventArgs {
   pub show_all_events: bool,
   #[clap(
     long,
-    help = "Set the default filter for events.",
-    value_parser = tracer_event_filter_parser,
-    default_value = "warning,error,exec,tracee-exit"
+    env = "TRACEXEC_FILTER",
+    help = "Set the default filter for events, warning,error,exec,tracee-exit by default",
+    value_parser = tracer_event_filter_parser
   )]
-  pub filter: BitFlags<TracerEventKind>,
+  pub filter: Option<BitFlags<TracerEventKind>>,
+  #[clap(
+    long = "where",
+    value_name = "EXPR",
+    env = "TRACEXEC_WHERE",
+    help = "Only keep the exec events that match this expression, e.g. 'comm == \"rustc\" && argv ~ \"--edition 2021\"'. \
+      In log mode, --grep and --grep-v are added to it as argv ~ and argv !~"
+  )]
+  pub filter_expr: Option<FilterExpr>,
   #[clap(
     long,
+    env = "TRACEXEC_FILTER_INCLUDE",
     help = "Aside from the default filter, also include the events specified here.",
     required = false,
     value_parser = tracer_event_filter_parser,
@@ -76,14 +239,29 @@ pub struct TracerEventArgs {
   pub filter_include: BitFlags<TracerEventKind>,
   #[clap(
     long,
+    env = "TRACEXEC_FILTER_EXCLUDE",
     help = "Exclude the events specified here from the default filter.",
     value_parser = tracer_event_filter_parser,
     default_value_t = BitFlags::empty()
   )]
   pub filter_exclude: BitFlags<TracerEventKind>,
+  #[clap(
+    long,
+    value_name = "PATTERN",
+    help = "Drop the execs of the programs whose file name matches the glob, e.g. 'uname', or the regex after re:, e.g. 're:^(sed|awk)$'. Can be used multiple times."
+  )]
+  pub exclude_comm: Vec<ExcludePattern>,
+  #[clap(
+    long,
+    value_name = "PATTERN",
+    help = "Drop the execs of the programs whose path, as given to exec, matches the glob, e.g. '/usr/lib/*', or the regex after re:. Can be used multiple times."
+  )]
+  pub exclude_path: Vec<ExcludePattern>,
 }
 
-fn tracer_event_filter_parser(filter: &str) -> Result<BitFlags<TracerEventKind>, String> {
+pub(super) fn tracer_event_filter_parser(
+  filter: &str,
+) -> Result<BitFlags<TracerEventKind>, String> {
   let mut result = BitFlags::empty();
   if filter == "<empty>" {
     return Ok(result);
@@ -102,11 +280,39 @@ fn tracer_event_filter_parser(filter: &str) -> Result<BitFlags<TracerEventKind>,
 }
 
 impl TracerEventArgs {
+  /// Fill in the filters that are not given on the command line from the config
+  pub fn merge_config(&mut self, config: &EventFilterConfig) {
+    if !self.show_all_events && self.filter.is_none() {
+      self.show_all_events = config.show_all_events;
+      self.filter = config.filter.map(Into::into);
+    }
+    if self.filter_expr.is_none() {
+      self.filter_expr.clone_from(&config.filter_expr);
+    }
+    if self.filter_include.is_empty() {
+      self.filter_include = config.filter_include.map(Into::into).unwrap_or_default();
+    }
+    if self.filter_exclude.is_empty() {
+      self.filter_exclude = config.filter_exclude.map(Into::into).unwrap_or_default();
+    }
+    if self.exclude_comm.is_empty() {
+      self.exclude_comm.clone_from(&config.exclude_comm);
+    }
+    if self.exclude_path.is_empty() {
+      self.exclude_path.clone_from(&config.exclude_path);
+    }
+  }
+
   pub fn filter(&self) -> color_eyre::Result<BitFlags<TracerEventKind>> {
     let default_filter = if self.show_all_events {
       BitFlags::all()
     } else {
-      self.filter
+      self.filter.unwrap_or(
+        TracerEventKind::Warning
+          | TracerEventKind::Error
+          | TracerEventKind::Exec
+          | TracerEventKind::TraceeExit,
+      )
     };
     if self.filter_include.intersects(self.filter_exclude) {
       bail!("filter_include and filter_exclude cannot contain common events");
@@ -115,14 +321,156 @@ impl TracerEventArgs {
     filter.remove(self.filter_exclude);
     Ok(filter)
   }
+
+  /// Add `--grep` and `--grep-v` of log mode to the filter expression
+  pub fn merge_grep(&mut self, grep: Option<Regex>, grep_v: Option<Regex>) {
+    let exprs = grep
+      .map(|regex| FilterExpr::argv_matches(regex, false))
+      .into_iter()
+      .chain(grep_v.map(|regex| FilterExpr::argv_matches(regex, true)));
+    for expr in exprs {
+      self.filter_expr = Some(match self.filter_expr.take() {
+        Some(filter_expr) => filter_expr.and(expr),
+        None => expr,
+      });
+    }
+  }
+
+  /// The filter that exec events have to pass, made of the filter expression and the exclusions
+  pub fn exec_filter(&self) -> ExecFilter {
+    ExecFilter {
+      expr: self.filter_expr.clone(),
+      exclude_comm: self.exclude_comm.clone(),
+      exclude_path: self.exclude_path.clone(),
+    }
+  }
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LimitArgs {
+  #[clap(
+    long,
+    help = "Signal the traced processes if the command is still running after this much time, e.g. 30s, 10m or 1h",
+    value_parser = duration_parser
+  )]
+  pub timeout: Option<Duration>,
+  #[clap(
+    long,
+    help = "The signal sent when the timeout fires, by name or number",
+    default_value = "SIGTERM",
+    value_parser = signal_parser,
+    requires = "timeout"
+  )]
+  pub timeout_signal: Signal,
+  #[clap(
+    long,
+    help = "Stop tracing after this many exec events, so that huge traces don't use unbounded memory or disk",
+    value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+  )]
+  pub max_events: Option<usize>,
+  #[clap(
+    long,
+    help = "What happens to the traced processes when --max-events is reached. Detaching turns seccomp-bpf off",
+    default_value_t,
+    requires = "max_events"
+  )]
+  pub on_max_events: MaxEventsAction,
+}
+
+impl LimitArgs {
+  pub fn timeout(&self) -> Option<(Duration, Signal)> {
+    self.timeout.map(|timeout| (timeout, self.timeout_signal))
+  }
+
+  pub fn max_events(&self) -> Option<(usize, MaxEventsAction)> {
+    self.max_events.map(|max| (max, self.on_max_events))
+  }
+}
+
+#[derive(Args, Debug, Default)]
+pub struct ExporterArgs {
+  #[clap(
+    long,
+    help = "Export traced processes as OpenTelemetry spans to an OTLP/HTTP collector, e.g. http://localhost:4318"
+  )]
+  pub otlp_endpoint: Option<String>,
+  #[clap(
+    long,
+    help = "Service name reported to the OTLP collector",
+    default_value = "tracexec",
+    requires = "otlp_endpoint"
+  )]
+  pub otlp_service_name: String,
+  #[clap(
+    long,
+    help = "Send events to systemd-journald as structured entries with TRACEXEC_* fields"
+  )]
+  pub journald: bool,
+  #[clap(long, help = "Send events to the local syslog daemon via /dev/log")]
+  pub syslog: bool,
+  #[clap(
+    long,
+    help = "Write the traced execs to a bash script that replays them with their cwd and environment changes"
+  )]
+  pub script: Option<PathBuf>,
+}
+
+impl LogModeArgs {
+  /// Fill in the formatting options that are not given on the command line from the config
+  pub fn merge_config(&mut self, config: &LogModeConfig) {
+    if self.format.is_none() && !self.compact && !self.print0 && !self.show_cmdline {
+      self.format = config.format.clone();
+      self.compact = config.compact && self.format.is_none();
+    }
+    if self.argv_escape.is_none() && !self.print0 && !self.show_cmdline {
+      self.argv_escape = config.argv_escape;
+    }
+    if !(self.show_cmdline
+      || self.diff_env
+      || self.no_diff_env
+      || self.show_env
+      || self.no_show_env)
+    {
+      self.env = self.env.or(config.env);
+    }
+  }
 }
 
 #[derive(Args, Debug, Default)]
 pub struct LogModeArgs {
+  #[clap(
+    long,
+    env = "TRACEXEC_FORMAT",
+    help = "Print exec events using this template instead of the default format, e.g. '{timestamp} {pid} {comm}: {argv}'. \
+            Available fields: timestamp, pid, ns_pid, ppid, depth, comm, filename, argv, cwd, env, result. \
+            Use {{ and }} for literal braces. --show-elapsed, --show-delta and --indent still apply before it, --show-ppid and --show-depth after it.",
+    conflicts_with = "show_cmdline"
+  )]
+  pub format: Option<LogTemplate>,
+  #[clap(
+    long,
+    help = "Only print pid, comm and shell quoted argv of exec events, without env or decorations. Same as --output-format compact",
+    conflicts_with_all = ["format", "show_cmdline"]
+  )]
+  pub compact: bool,
+  #[clap(
+    short = '0',
+    long,
+    help = "Print exec events as NUL terminated fields for consumption by scripts: pid, result, filename, argc and then each argument. Other events are not printed. Same as --output-format print0",
+    conflicts_with_all = ["format", "compact", "show_cmdline"]
+  )]
+  pub print0: bool,
+  #[clap(
+    long,
+    value_name = "MODE",
+    help = "How to escape arguments in argv so that non-printable bytes can not mess up the terminal",
+    conflicts_with_all = ["print0", "show_cmdline"]
+  )]
+  pub argv_escape: Option<ArgvEscape>,
   #[clap(
     long,
     help = "Print commandline that (hopefully) reproduces what was executed. Note: file descriptors are not handled for now.",
-    conflicts_with_all = ["show_env", "diff_env", "show_argv"]
+    conflicts_with_all = ["show_env", "diff_env", "env", "show_argv"]
 )]
   pub show_cmdline: bool,
   #[clap(long, help = "Try to show script interpreter indicated by shebang")]
@@ -164,6 +512,12 @@ pub struct LogModeArgs {
     conflicts_with = "show_fd"
   )]
   pub no_show_fd: bool,
+  #[clap(
+    long,
+    help = "How to show environment variables, diffed against tracexec's own environment by default",
+    conflicts_with_all = ["diff_env", "no_diff_env", "show_env", "no_show_env"]
+  )]
+  pub env: Option<EnvDisplay>,
   #[clap(
     long,
     help = "Diff environment variables with the original environment",
@@ -191,6 +545,56 @@ pub struct LogModeArgs {
     conflicts_with = "show_env"
   )]
   pub no_show_env: bool,
+  #[clap(
+    long,
+    help = "Prefix each line with the time elapsed since the trace started"
+  )]
+  pub show_elapsed: bool,
+  #[clap(
+    long,
+    help = "Prefix each line with the time elapsed since the previous line"
+  )]
+  pub show_delta: bool,
+  #[clap(
+    long,
+    help = "Print a line with the exit code or fatal signal when a traced process exits. Exec lines are numbered so that they can be matched with exit lines."
+  )]
+  pub show_exit: bool,
+  #[clap(
+    long,
+    help = "Print a summary with process and exec counts, failures and CPU time per program when the root child exits"
+  )]
+  pub summary: bool,
+  #[clap(long, help = "Show the parent pid of processes")]
+  pub show_ppid: bool,
+  #[clap(
+    long,
+    help = "Fold consecutive identical execs(same filename, argv, cwd and result) into one line with a repeat count. Structured outputs still get every exec.",
+    conflicts_with = "print0"
+  )]
+  pub fold: bool,
+  #[clap(
+    long,
+    help = "Show the depth of processes in the process tree, the root child has a depth of 0"
+  )]
+  pub show_depth: bool,
+  #[clap(
+    long,
+    help = "Indent lines by the depth of processes in the process tree"
+  )]
+  pub indent: bool,
+  #[clap(
+    long,
+    value_name = "REGEX",
+    help = "Only keep exec events whose arguments, joined by spaces, match this regex, like --where 'argv ~ \"REGEX\"'"
+  )]
+  pub grep: Option<Regex>,
+  #[clap(
+    long,
+    value_name = "REGEX",
+    help = "Drop exec events whose arguments, joined by spaces, match this regex, like --where 'argv !~ \"REGEX\"'"
+  )]
+  pub grep_v: Option<Regex>,
   #[clap(long, help = "Show comm", conflicts_with = "no_show_comm")]
   pub show_comm: bool,
   #[clap(long, help = "Do not show comm", conflicts_with = "show_comm")]
@@ -0,0 +1,440 @@
+//! The config file, which is located at `~/.config/tracexec/config.toml` by default.
+
+use std::{
+  collections::BTreeMap,
+  io,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+use color_eyre::eyre::{bail, WrapErr};
+use enumflags2::BitFlags;
+use nix::sys::signal::Signal;
+use owo_colors::{AnsiColors, Effect, Style, XtermColors};
+use serde::Deserialize;
+
+use crate::{
+  event::TracerEventKind,
+  filter::{ExcludePattern, FilterExpr},
+  log::get_config_dir,
+  output::{OutputFormat, OutputSpec},
+  printer::LogTemplate,
+  term::{term_caps, TermColor},
+  tui::{
+    app::AppLayout,
+    event_list::EventColumn,
+    keymap::{KeyAction, KeyBinding, KeymapPreset},
+    theme::{ThemePreset, ThemeStyle},
+  },
+};
+
+#[cfg(feature = "seccomp-bpf")]
+use super::options::SeccompBpf;
+use super::{
+  args::tracer_event_filter_parser,
+  check_frame_rate, duration_parser,
+  options::{self, ActivePane, ArgvEscape, ClipboardProvider, EnvDisplay, PidDisplay},
+  signal_parser,
+};
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// The commented default config printed by `tracexec generate-config`
+pub const DEFAULT_CONFIG: &str = include_str!("default_config.toml");
+
+/// The config, which may also have named profiles in `[profile.<name>]` tables.
+/// A profile selected with `--profile` is laid over the rest of the config file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  /// Whether colored output is enabled, `auto`, `always` or `never`
+  pub color: Option<options::Color>,
+  pub log: LogModeConfig,
+  pub tui: TuiModeConfig,
+  pub redact: RedactConfig,
+  pub modifier: ModifierConfig,
+  pub events: EventFilterConfig,
+  /// Bundles of arguments, which are expanded from `@NAME` in front of the subcommand
+  pub alias: BTreeMap<String, Vec<String>>,
+}
+
+/// Defaults for the options that modify the traced execs, shared by all modes
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModifierConfig {
+  #[cfg(feature = "seccomp-bpf")]
+  pub seccomp_bpf: Option<SeccompBpf>,
+  pub successful_only: bool,
+  pub fd_in_cmdline: bool,
+  pub stdio_in_cmdline: bool,
+  pub resolve_proc_self_exe: Option<bool>,
+  pub resolve_filename: bool,
+  pub no_redact: bool,
+  pub no_follow_forks: bool,
+  pub strict: bool,
+  pub pid_display: Option<PidDisplay>,
+}
+
+/// Defaults for the filters of events, written like on the command line, e.g. `filter = "exec,warning"`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventFilterConfig {
+  pub show_all_events: bool,
+  pub filter: Option<EventFilterSpec>,
+  /// Like `--where`, only keep the exec events that match this expression
+  #[serde(rename = "where")]
+  pub filter_expr: Option<FilterExpr>,
+  pub filter_include: Option<EventFilterSpec>,
+  pub filter_exclude: Option<EventFilterSpec>,
+  pub exclude_comm: Vec<ExcludePattern>,
+  pub exclude_path: Vec<ExcludePattern>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TuiModeConfig {
+  /// The built-in keymap to start from, `default` or `vim`
+  pub keymap: KeymapPreset,
+  /// Overrides for the keys of the event list, e.g. `page_down = ["ctrl+d", "pagedown"]`
+  pub keys: BTreeMap<KeyAction, Vec<KeyBinding>>,
+  /// The columns shown before each event in the event list, in order, e.g. `["status", "timestamp", "user"]`
+  pub columns: Option<Vec<EventColumn>>,
+  /// The number of lines kept in the scrollback history of the pseudo terminal, 1000 by default
+  pub scrollback: Option<usize>,
+  /// The number of events kept in the event list, 1000000 by default.
+  /// The oldest events are forgotten beyond it, which keeps long traces from using up the memory.
+  pub history: Option<usize>,
+  pub theme: ThemeConfig,
+  /// Allocate a pseudo terminal when a command is given
+  pub tty: bool,
+  /// Takes precedence over the last session, like the rest of the config
+  pub follow: Option<bool>,
+  pub terminate_on_exit: bool,
+  /// `true` to kill the root child with SIGKILL when the TUI exits, or the signal to kill it with
+  pub kill_on_exit: Option<KillOnExit>,
+  /// How long the root child gets to exit after the signal of `kill_on_exit` before it gets SIGKILL
+  pub kill_grace_period: Option<DurationSpec>,
+  pub active_pane: Option<ActivePane>,
+  pub layout: Option<AppLayout>,
+  pub no_mouse: bool,
+  pub clipboard: Option<ClipboardProvider>,
+  pub frame_rate: Option<FrameRate>,
+}
+
+/// The colors used in TUI mode
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+  /// The built-in theme to start from, `default` or `light`
+  pub preset: ThemePreset,
+  /// Overrides for the styles of the theme, e.g. `selection = "bold on blue"`
+  pub styles: BTreeMap<String, ThemeStyle>,
+}
+
+/// Redaction of secrets in environment variables
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactConfig {
+  /// Glob patterns of environment variable names whose values are redacted.
+  /// The built-in patterns are used if this is not set.
+  pub patterns: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogModeConfig {
+  pub palette: PaletteConfig,
+  /// The outputs used when none is given on the command line, written like `--output`
+  pub output: Vec<OutputSpec>,
+  /// The format of the outputs that don't have one, like `--output-format`
+  pub output_format: Option<OutputFormat>,
+  /// A template for exec events like `--format`, e.g. `{pid} {comm}: {argv}`
+  pub format: Option<LogTemplate>,
+  pub compact: bool,
+  pub env: Option<EnvDisplay>,
+  pub argv_escape: Option<ArgvEscape>,
+}
+
+/// Overrides for the colors used in log mode and by `tracexec diff`, e.g. `comm = "bright-cyan bold"`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PaletteConfig {
+  pub pid: Option<StyleSpec>,
+  pub pid_success: Option<StyleSpec>,
+  pub pid_failure: Option<StyleSpec>,
+  pub pid_enoent: Option<StyleSpec>,
+  pub comm: Option<StyleSpec>,
+  pub label: Option<StyleSpec>,
+  pub filename: Option<StyleSpec>,
+  pub argv: Option<StyleSpec>,
+  pub cwd: Option<StyleSpec>,
+  pub added_env: Option<StyleSpec>,
+  pub modified_env: Option<StyleSpec>,
+  pub modified_env_value: Option<StyleSpec>,
+  pub removed_env: Option<StyleSpec>,
+  pub error: Option<StyleSpec>,
+  pub errno: Option<StyleSpec>,
+  pub warning: Option<StyleSpec>,
+  pub timestamp: Option<StyleSpec>,
+}
+
+impl Config {
+  /// Where the config file is loaded from when no path is given
+  pub fn default_path() -> PathBuf {
+    get_config_dir().join(CONFIG_FILE)
+  }
+
+  /// Load the config file from `path`, or from the default location if `path` is `None`,
+  /// with the named `profile` laid over it.
+  ///
+  /// It is not an error if the config file doesn't exist at the default location,
+  /// unless a profile is selected.
+  pub fn load(path: Option<&Path>, profile: Option<&str>) -> color_eyre::Result<Self> {
+    let (path, is_default) = match path {
+      Some(path) => (path.to_path_buf(), false),
+      None => (Self::default_path(), true),
+    };
+    let content = match std::fs::read_to_string(&path) {
+      Ok(content) => content,
+      Err(e) if is_default && profile.is_none() && e.kind() == io::ErrorKind::NotFound => {
+        return Ok(Self::default())
+      }
+      Err(e) => return Err(e).wrap_err_with(|| format!("Failed to read config file {path:?}")),
+    };
+    let mut table: toml::Table =
+      toml::from_str(&content).wrap_err_with(|| format!("Failed to parse config file {path:?}"))?;
+    let profiles = table.remove("profile");
+    if let Some(name) = profile {
+      let Some(overlay) = profiles
+        .as_ref()
+        .and_then(|profiles| profiles.get(name))
+        .and_then(toml::Value::as_table)
+      else {
+        bail!("Profile {name:?} is not defined in config file {path:?}");
+      };
+      merge_tables(&mut table, overlay.clone());
+    }
+    toml::Value::Table(table)
+      .try_into()
+      .wrap_err_with(|| format!("Failed to parse config file {path:?}"))
+  }
+}
+
+/// Tables are merged key by key, any other value of the overlay replaces the original one
+fn merge_tables(table: &mut toml::Table, overlay: toml::Table) {
+  for (key, value) in overlay {
+    match (table.get_mut(&key), value) {
+      (Some(toml::Value::Table(table)), toml::Value::Table(overlay)) => {
+        merge_tables(table, overlay)
+      }
+      (_, value) => {
+        table.insert(key, value);
+      }
+    }
+  }
+}
+
+/// A set of event kinds written as comma separated names, e.g. `warning,error,exec`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct EventFilterSpec(BitFlags<TracerEventKind>);
+
+impl From<EventFilterSpec> for BitFlags<TracerEventKind> {
+  fn from(spec: EventFilterSpec) -> Self {
+    spec.0
+  }
+}
+
+impl TryFrom<String> for EventFilterSpec {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    tracer_event_filter_parser(&value).map(Self)
+  }
+}
+
+/// The frame rate of the TUI, which is checked like the one on the command line
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "f64")]
+pub struct FrameRate(f64);
+
+impl From<FrameRate> for f64 {
+  fn from(rate: FrameRate) -> Self {
+    rate.0
+  }
+}
+
+impl TryFrom<f64> for FrameRate {
+  type Error = String;
+
+  fn try_from(value: f64) -> Result<Self, Self::Error> {
+    check_frame_rate(value).map(Self).map_err(|e| e.to_string())
+  }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum KillOnExit {
+  Enabled(bool),
+  Signal(SignalSpec),
+}
+
+impl KillOnExit {
+  pub fn signal(self) -> Option<Signal> {
+    match self {
+      Self::Enabled(true) => Some(Signal::SIGKILL),
+      Self::Enabled(false) => None,
+      Self::Signal(SignalSpec(signal)) => Some(signal),
+    }
+  }
+}
+
+/// A signal written by its name, with or without the SIG prefix, or by its number
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct SignalSpec(Signal);
+
+impl TryFrom<String> for SignalSpec {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    signal_parser(&value).map(Self)
+  }
+}
+
+/// A duration written like `30s`, `10m` or `1h`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct DurationSpec(Duration);
+
+impl From<DurationSpec> for Duration {
+  fn from(spec: DurationSpec) -> Self {
+    spec.0
+  }
+}
+
+impl TryFrom<String> for DurationSpec {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    duration_parser(&value).map(Self).map_err(|e| e.to_string())
+  }
+}
+
+/// A text style written as space separated words, e.g. `bright-red bold on black` or `#ff8700 italic`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct StyleSpec(Style);
+
+impl From<StyleSpec> for Style {
+  fn from(spec: StyleSpec) -> Self {
+    spec.0
+  }
+}
+
+impl TryFrom<String> for StyleSpec {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    let mut style = Style::new();
+    let mut words = value.split_ascii_whitespace();
+    while let Some(word) = words.next() {
+      if word == "on" {
+        let Some(color) = words.next() else {
+          return Err(format!(
+            "Expected a background color after 'on' in {value:?}"
+          ));
+        };
+        style = match parse_color(color)? {
+          Color::Ansi(c) => style.on_color(c),
+          Color::Term(TermColor::Rgb(r, g, b)) => style.on_truecolor(r, g, b),
+          Color::Term(TermColor::Xterm(c)) => style.on_color(XtermColors::from(c)),
+          Color::Term(TermColor::Ansi(c)) => style.on_color(ANSI_COLORS[c as usize]),
+        };
+      } else if let Some(effect) = parse_effect(word) {
+        style = style.effect(effect);
+      } else {
+        style = match parse_color(word)? {
+          Color::Ansi(c) => style.color(c),
+          Color::Term(TermColor::Rgb(r, g, b)) => style.truecolor(r, g, b),
+          Color::Term(TermColor::Xterm(c)) => style.color(XtermColors::from(c)),
+          Color::Term(TermColor::Ansi(c)) => style.color(ANSI_COLORS[c as usize]),
+        };
+      }
+    }
+    Ok(Self(style))
+  }
+}
+
+enum Color {
+  Ansi(AnsiColors),
+  /// A hex color, downgraded to what the terminal supports
+  Term(TermColor),
+}
+
+const ANSI_COLORS: [AnsiColors; 16] = [
+  AnsiColors::Black,
+  AnsiColors::Red,
+  AnsiColors::Green,
+  AnsiColors::Yellow,
+  AnsiColors::Blue,
+  AnsiColors::Magenta,
+  AnsiColors::Cyan,
+  AnsiColors::White,
+  AnsiColors::BrightBlack,
+  AnsiColors::BrightRed,
+  AnsiColors::BrightGreen,
+  AnsiColors::BrightYellow,
+  AnsiColors::BrightBlue,
+  AnsiColors::BrightMagenta,
+  AnsiColors::BrightCyan,
+  AnsiColors::BrightWhite,
+];
+
+fn parse_color(s: &str) -> Result<Color, String> {
+  if let Some(hex) = s.strip_prefix('#') {
+    let rgb = u32::from_str_radix(hex, 16)
+      .ok()
+      .filter(|_| hex.len() == 6)
+      .ok_or_else(|| format!("Invalid hex color {s:?}, expected #rrggbb"))?;
+    return Ok(Color::Term(term_caps().color_level.rgb(
+      (rgb >> 16) as u8,
+      (rgb >> 8) as u8,
+      rgb as u8,
+    )));
+  }
+  Ok(Color::Ansi(match s {
+    "black" => AnsiColors::Black,
+    "red" => AnsiColors::Red,
+    "green" => AnsiColors::Green,
+    "yellow" => AnsiColors::Yellow,
+    "blue" => AnsiColors::Blue,
+    "magenta" | "purple" => AnsiColors::Magenta,
+    "cyan" => AnsiColors::Cyan,
+    "white" => AnsiColors::White,
+    "default" => AnsiColors::Default,
+    "bright-black" => AnsiColors::BrightBlack,
+    "bright-red" => AnsiColors::BrightRed,
+    "bright-green" => AnsiColors::BrightGreen,
+    "bright-yellow" => AnsiColors::BrightYellow,
+    "bright-blue" => AnsiColors::BrightBlue,
+    "bright-magenta" | "bright-purple" => AnsiColors::BrightMagenta,
+    "bright-cyan" => AnsiColors::BrightCyan,
+    "bright-white" => AnsiColors::BrightWhite,
+    _ => return Err(format!("Unknown color or effect {s:?}")),
+  }))
+}
+
+fn parse_effect(s: &str) -> Option<Effect> {
+  Some(match s {
+    "bold" => Effect::Bold,
+    "dimmed" => Effect::Dimmed,
+    "italic" => Effect::Italic,
+    "underline" => Effect::Underline,
+    "blink" => Effect::Blink,
+    "reversed" => Effect::Reversed,
+    "hidden" => Effect::Hidden,
+    "strikethrough" => Effect::Strikethrough,
+    _ => return None,
+  })
+}
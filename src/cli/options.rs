@@ -1,17 +1,48 @@
+use std::{
+  ffi::OsStr,
+  fs::File,
+  io::{stdin, IsTerminal},
+  os::fd::{AsFd, OwnedFd},
+  path::{Path, PathBuf},
+  str::FromStr,
+};
+
 use clap::ValueEnum;
+use color_eyre::eyre::{bail, WrapErr};
+use nix::unistd::Pid;
+use serde::{Deserialize, Serialize};
 use strum::Display;
 
-#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display)]
+use crate::term::term_caps;
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display, Deserialize)]
 #[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
 pub enum Color {
   Auto,
   Always,
   Never,
 }
 
+impl Color {
+  /// Decide whether output to a stream should be colored,
+  /// respecting NO_COLOR, CLICOLOR, CLICOLOR_FORCE and dumb terminals in auto mode.
+  pub fn should_colorize(self, is_terminal: bool) -> bool {
+    match self {
+      Color::Always => true,
+      Color::Never => false,
+      Color::Auto => {
+        let caps = term_caps();
+        caps.env_color.unwrap_or_else(|| is_terminal && !caps.dumb)
+      }
+    }
+  }
+}
+
 #[cfg(feature = "seccomp-bpf")]
-#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display, Default)]
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display, Default, Deserialize)]
 #[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
 pub enum SeccompBpf {
   #[default]
   Auto,
@@ -19,10 +50,187 @@ pub enum SeccompBpf {
   Off,
 }
 
-#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display, Default)]
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display, Default, Serialize, Deserialize)]
 #[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
 pub enum ActivePane {
   #[default]
   Terminal,
   Events,
+  ProcessTree,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display, Default, Deserialize)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum ClipboardProvider {
+  /// Use OSC 52 over SSH or if the system clipboard is unavailable, otherwise the system clipboard
+  #[default]
+  Auto,
+  /// Use the system clipboard of X11 or Wayland
+  System,
+  /// Let the terminal set its clipboard with the OSC 52 escape sequence, which also works over SSH and in tmux
+  Osc52,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display, Deserialize)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum EnvDisplay {
+  /// Only show environment variables that are added, removed or modified compared to tracexec's own environment
+  Diff,
+  /// Show all environment variables
+  All,
+  /// Do not show environment variables
+  None,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display, Deserialize)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum ArgvEscape {
+  /// Quote arguments for bash, using $'...' for non-printable characters
+  Shell,
+  /// Double quote arguments and use C escape sequences for non-printable characters
+  C,
+  /// Print the bytes of arguments in hexadecimal
+  Hex,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display, Default, Deserialize)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum PidDisplay {
+  /// The pid as seen by tracexec
+  #[default]
+  Tracer,
+  /// The pid inside the PID namespace of the process, as its own logs show it
+  Namespace,
+  /// Both, like 12345/7 when the pids differ
+  Both,
+}
+
+impl PidDisplay {
+  /// Show the pid of a process, whose pid in its own PID namespace is `ns_pid` if it differs
+  pub fn format(self, pid: Pid, ns_pid: Option<Pid>) -> String {
+    match (self, ns_pid) {
+      (Self::Namespace, Some(ns_pid)) => ns_pid.to_string(),
+      (Self::Both, Some(ns_pid)) => format!("{pid}/{ns_pid}"),
+      _ => pid.to_string(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display, Default)]
+#[strum(serialize_all = "kebab-case")]
+pub enum MaxEventsAction {
+  /// Stop tracing and let the traced processes run to completion
+  #[default]
+  Detach,
+  /// Kill the traced processes
+  Kill,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum SchemaFormat {
+  /// A JSON schema, which validators and code generators understand
+  JsonSchema,
+  /// Tables of the fields for reading
+  Markdown,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum CompletionShell {
+  Bash,
+  Elvish,
+  Fish,
+  Nushell,
+  #[value(name = "powershell")]
+  #[strum(serialize = "powershell")]
+  PowerShell,
+  Zsh,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Display)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Compression {
+  /// Do not compress, even if the file extension suggests otherwise
+  None,
+  Gzip,
+  Zstd,
+}
+
+impl Compression {
+  /// Detect the compression from `.gz` and `.zst` file extensions
+  pub fn from_extension(path: &Path) -> Self {
+    match path.extension().and_then(OsStr::to_str) {
+      Some("gz") => Self::Gzip,
+      Some("zst") => Self::Zstd,
+      _ => Self::None,
+    }
+  }
+}
+
+/// Where the stdin of the command comes from in TUI mode, given as `null`, `pty`, `-` or a path
+#[derive(Debug, Clone, PartialEq)]
+pub enum StdinSource {
+  /// `/dev/null`, the default without a pseudo terminal
+  Null,
+  /// The pseudo terminal, the default with one
+  Pty,
+  /// The stdin of tracexec, which has to be a pipe or a file as the terminal is used by the TUI
+  Inherit,
+  /// A file or a named pipe
+  File(PathBuf),
+}
+
+impl FromStr for StdinSource {
+  type Err = std::convert::Infallible;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Ok(match s {
+      "null" => Self::Null,
+      "pty" => Self::Pty,
+      "-" => Self::Inherit,
+      path => Self::File(path.into()),
+    })
+  }
+}
+
+impl StdinSource {
+  /// Check whether the stdin can be used without opening it, which waits for a named pipe
+  pub fn check(&self, with_tty: bool) -> color_eyre::Result<()> {
+    match self {
+      Self::Pty if !with_tty => bail!("--stdin pty is only available with --tty!"),
+      Self::Inherit if stdin().is_terminal() => {
+        bail!("--stdin - needs the stdin of tracexec to be a pipe or a file, the terminal is used by the TUI!")
+      }
+      Self::File(path) => {
+        path
+          .metadata()
+          .wrap_err_with(|| format!("{path:?} for the stdin of the command can't be opened"))?;
+      }
+      _ => {}
+    }
+    Ok(())
+  }
+
+  /// Open the file descriptor that replaces the stdin of the command,
+  /// nothing if the stdin that the tracer sets up is kept
+  pub fn open(&self, with_tty: bool) -> color_eyre::Result<Option<OwnedFd>> {
+    self.check(with_tty)?;
+    Ok(match self {
+      Self::Null if with_tty => Some(File::open("/dev/null")?.into()),
+      Self::Null | Self::Pty => None,
+      Self::Inherit => Some(stdin().as_fd().try_clone_to_owned()?),
+      // Opening a named pipe waits for the other end
+      Self::File(path) => Some(
+        File::open(path)
+          .wrap_err_with(|| format!("Failed to open {path:?} for the stdin of the command"))?
+          .into(),
+      ),
+    })
+  }
 }
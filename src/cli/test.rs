@@ -1,6 +1,19 @@
 use assert_cmd::prelude::*;
+use clap::Parser;
+use nix::{sys::signal::Signal, unistd::Pid};
 use predicates::prelude::*;
-use std::process::Command;
+use regex::Regex;
+use std::{process::Command, time::Duration};
+
+use crate::event::TracerEventKind;
+
+use super::{
+  args::{tracer_event_filter_parser, ModifierArgs, TracerEventArgs},
+  config::{EventFilterConfig, ModifierConfig},
+  duration_parser,
+  options::PidDisplay,
+  pty_size_parser, read_cmd_file, signal_parser, Cli, ParseDurationError,
+};
 
 #[test]
 fn log_mode_without_args_works() -> Result<(), Box<dyn std::error::Error>> {
@@ -12,3 +25,180 @@ fn log_mode_without_args_works() -> Result<(), Box<dyn std::error::Error>> {
     .stderr(predicate::str::contains("/proc/self/exe"));
   Ok(())
 }
+
+#[test]
+fn filter_only_takes_event_kinds() {
+  assert_eq!(
+    tracer_event_filter_parser("exec,warning"),
+    Ok(TracerEventKind::Exec | TracerEventKind::Warning)
+  );
+  assert_eq!(
+    tracer_event_filter_parser("<empty>"),
+    Ok(Default::default())
+  );
+  assert!(tracer_event_filter_parser("exec,exec")
+    .unwrap_err()
+    .contains("already included"));
+  assert!(tracer_event_filter_parser("exec,warnin").is_err());
+  // Expressions go to --where
+  assert!(tracer_event_filter_parser("comm == \"sh\"").is_err());
+}
+
+#[test]
+fn grep_is_added_to_where() {
+  let mut args = TracerEventArgs {
+    filter_expr: Some("comm == \"sh\"".parse().unwrap()),
+    ..Default::default()
+  };
+  args.merge_grep(
+    Some(Regex::new("-c").unwrap()),
+    Some(Regex::new("x").unwrap()),
+  );
+  assert_eq!(
+    args.exec_filter().expr.unwrap().as_str(),
+    r#"((comm == "sh") && (argv ~ "-c")) && (argv !~ "x")"#
+  );
+  let mut args = TracerEventArgs::default();
+  args.merge_grep(None, Some(Regex::new("x").unwrap()));
+  assert_eq!(args.filter_expr.unwrap().as_str(), r#"argv !~ "x""#);
+}
+
+#[test]
+fn where_in_config() {
+  let config: EventFilterConfig = toml::from_str("where = 'pid == 1'").unwrap();
+  let mut args = TracerEventArgs::default();
+  args.merge_config(&config);
+  assert_eq!(args.filter_expr.unwrap().as_str(), "pid == 1");
+  assert!(toml::from_str::<EventFilterConfig>("where = 'pid = 1'").is_err());
+}
+
+#[test]
+fn config_flags_can_be_turned_off() {
+  let config: ModifierConfig =
+    toml::from_str("successful_only = true\nstrict = true\nno_follow_forks = true").unwrap();
+  let mut args = ModifierArgs::default();
+  args.merge_config(&config);
+  assert!(args.successful_only && args.strict && args.no_follow_forks);
+  let mut args = ModifierArgs {
+    no_successful_only: true,
+    no_strict: true,
+    follow_forks: true,
+    ..Default::default()
+  };
+  args.merge_config(&config);
+  assert!(!args.successful_only && !args.strict && !args.no_follow_forks);
+}
+
+#[test]
+fn durations_are_parsed() {
+  assert_eq!(duration_parser("30s").unwrap(), Duration::from_secs(30));
+  assert_eq!(duration_parser("5m").unwrap(), Duration::from_secs(5 * 60));
+  assert_eq!(
+    duration_parser("2h").unwrap(),
+    Duration::from_secs(2 * 60 * 60)
+  );
+  assert_eq!(
+    duration_parser("1d").unwrap(),
+    Duration::from_secs(24 * 60 * 60)
+  );
+  assert!(matches!(
+    duration_parser("0s"),
+    Err(ParseDurationError::Zero)
+  ));
+  assert!(matches!(
+    duration_parser("10"),
+    Err(ParseDurationError::UnknownUnit(_))
+  ));
+  assert!(matches!(
+    duration_parser("10ms"),
+    Err(ParseDurationError::UnknownUnit(_))
+  ));
+  assert!(matches!(
+    duration_parser("s"),
+    Err(ParseDurationError::ParseIntError(_))
+  ));
+}
+
+#[test]
+fn pty_sizes_are_parsed() {
+  let size = pty_size_parser("120x40").unwrap();
+  assert_eq!((size.cols, size.rows), (120, 40));
+  assert!(pty_size_parser("120").is_err());
+  assert!(pty_size_parser("0x40").is_err());
+  assert!(pty_size_parser("120x").is_err());
+  assert!(pty_size_parser("120x70000").is_err());
+}
+
+#[test]
+fn signals_are_parsed() {
+  assert_eq!(signal_parser("TERM"), Ok(Signal::SIGTERM));
+  assert_eq!(signal_parser("sigkill"), Ok(Signal::SIGKILL));
+  assert_eq!(signal_parser("SIGINT"), Ok(Signal::SIGINT));
+  assert_eq!(signal_parser("9"), Ok(Signal::SIGKILL));
+  assert!(signal_parser("0").is_err());
+  assert!(signal_parser("NOPE").is_err());
+}
+
+#[test]
+fn tui_pty_size_requires_tty() {
+  assert!(Cli::try_parse_from(["tracexec", "tui", "--pty-size", "80x24", "--", "true"]).is_err());
+  assert!(
+    Cli::try_parse_from(["tracexec", "tui", "-t", "--pty-size", "80x24", "--", "true"]).is_ok()
+  );
+}
+
+#[test]
+fn pids_are_displayed_as_chosen() {
+  let pid = Pid::from_raw(12345);
+  let ns_pid = Some(Pid::from_raw(7));
+  assert_eq!(PidDisplay::Tracer.format(pid, ns_pid), "12345");
+  assert_eq!(PidDisplay::Namespace.format(pid, ns_pid), "7");
+  assert_eq!(PidDisplay::Both.format(pid, ns_pid), "12345/7");
+  // Processes in the PID namespace of tracexec only have one pid
+  for display in [PidDisplay::Tracer, PidDisplay::Namespace, PidDisplay::Both] {
+    assert_eq!(display.format(pid, None), "12345");
+  }
+}
+
+/// Read a command file with this content, which is written to a file unique to the test
+fn read_cmd(test: &str, content: &str) -> color_eyre::Result<Vec<String>> {
+  let path = std::env::temp_dir().join(format!("tracexec-cmd-{}-{test}", std::process::id()));
+  std::fs::write(&path, content).unwrap();
+  let result = read_cmd_file(&path);
+  std::fs::remove_file(path).unwrap();
+  result
+}
+
+#[test]
+fn cmd_file_is_split_by_lines() {
+  assert_eq!(
+    read_cmd("lines", "echo\nhello world\n").unwrap(),
+    ["echo", "hello world"]
+  );
+  assert_eq!(read_cmd("crlf", "echo\r\nhi\r\n").unwrap(), ["echo", "hi"]);
+  assert_eq!(read_cmd("trailing", "true\n\n\n").unwrap(), ["true"]);
+}
+
+#[test]
+fn cmd_file_is_split_by_nul_bytes() {
+  assert_eq!(
+    read_cmd("nul", "printf\0%s\\n\0a\nb\0\0").unwrap(),
+    ["printf", "%s\\n", "a\nb", ""]
+  );
+}
+
+#[test]
+fn cmd_file_rejects_empty_lines_and_commands() {
+  assert!(read_cmd("empty-line", "echo\n\nhi\n").is_err());
+  assert!(read_cmd("empty", "").is_err());
+  assert!(read_cmd("blank", "\n\n").is_err());
+}
+
+#[test]
+fn log_mode_takes_exactly_one_command() {
+  assert!(Cli::try_parse_from(["tracexec", "log"]).is_err());
+  assert!(Cli::try_parse_from(["tracexec", "log", "--cmd-file", "cmd", "--", "true"]).is_err());
+  assert!(Cli::try_parse_from(["tracexec", "log", "--from-file", "trace", "--", "true"]).is_err());
+  assert!(Cli::try_parse_from(["tracexec", "log", "--cmd-file", "cmd"]).is_ok());
+  assert!(Cli::try_parse_from(["tracexec", "log", "--from-file", "trace"]).is_ok());
+}
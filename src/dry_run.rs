@@ -0,0 +1,168 @@
+//! `--dry-run`, which checks the options and whether the command can be traced without running it,
+//! so that mistakes are caught before a long traced build is started.
+
+use std::{
+  fmt::Debug,
+  os::unix::fs::PermissionsExt,
+  path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::bail;
+use nix::{
+  libc,
+  sys::{
+    ptrace,
+    signal::{kill, raise, Signal},
+    wait::{waitpid, WaitStatus},
+  },
+  unistd::{fork, ForkResult},
+};
+
+pub struct DryRun {
+  subcommand: &'static str,
+  settings: Vec<(&'static str, String)>,
+  checks: Vec<color_eyre::Result<String>>,
+}
+
+impl DryRun {
+  pub fn new(subcommand: &'static str) -> Self {
+    Self {
+      subcommand,
+      settings: vec![],
+      checks: vec![],
+    }
+  }
+
+  /// An effective setting, after the config file is merged into the command line
+  pub fn setting(mut self, name: &'static str, value: impl Debug) -> Self {
+    self.settings.push((name, format!("{value:#?}")));
+    self
+  }
+
+  /// A check that either describes what passed or fails with the problem
+  pub fn check(mut self, check: color_eyre::Result<String>) -> Self {
+    self.checks.push(check);
+    self
+  }
+
+  /// Check whether the command can be found and traced,
+  /// or whether processes can be attached to if there is no command
+  pub fn check_tracing(self, cmd: &[String]) -> Self {
+    match cmd.first() {
+      Some(program) => self
+        .check(find_program(program).map(|path| format!("{program} is {}", path.display())))
+        .check(check_ptrace().map(|_| "ptrace is permitted".to_string())),
+      None => self.check(check_attach().map(|_| "attaching is permitted".to_string())),
+    }
+  }
+
+  /// Print the settings and the results of the checks, failing if any check failed
+  pub fn finish(self) -> color_eyre::Result<()> {
+    println!(
+      "Dry run of tracexec {}, nothing is executed\n",
+      self.subcommand
+    );
+    println!("Settings:");
+    for (name, value) in self.settings.iter() {
+      println!("  {name}: {}", value.replace('\n', "\n  "));
+    }
+    println!("\nChecks:");
+    let mut problems = 0;
+    for check in self.checks.iter() {
+      match check {
+        Ok(msg) => println!("  ok: {msg}"),
+        Err(e) => {
+          problems += 1;
+          println!("  problem: {e}");
+        }
+      }
+    }
+    if problems > 0 {
+      bail!("The dry run found {problems} problem(s)");
+    }
+    Ok(())
+  }
+}
+
+/// Find the program like exec does, in PATH if it is a bare name
+fn find_program(program: &str) -> color_eyre::Result<PathBuf> {
+  let is_executable = |path: &Path| {
+    path
+      .metadata()
+      .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+  };
+  if program.contains('/') {
+    if is_executable(Path::new(program)) {
+      return Ok(PathBuf::from(program));
+    }
+    bail!("{program} is not an executable file");
+  }
+  let path = std::env::var_os("PATH").unwrap_or_default();
+  match std::env::split_paths(&path)
+    .map(|dir| dir.join(program))
+    .find(|path| is_executable(path))
+  {
+    Some(path) => Ok(path),
+    None => bail!("{program} is not found in PATH"),
+  }
+}
+
+/// The ptrace_scope of the Yama LSM, 0 if it is not enabled
+fn ptrace_scope() -> u8 {
+  std::fs::read_to_string("/proc/sys/kernel/yama/ptrace_scope")
+    .ok()
+    .and_then(|scope| scope.trim().parse().ok())
+    .unwrap_or(0)
+}
+
+/// Trace a forked child that stops right away, like the root child of tracexec does.
+/// This fails if ptrace is forbidden, e.g. by the seccomp profile of a container.
+fn check_ptrace() -> color_eyre::Result<()> {
+  // SAFETY: the child only makes system calls and exits without running the destructors of tracexec
+  match unsafe { fork() }? {
+    ForkResult::Child => {
+      let code = match ptrace::traceme().and_then(|_| raise(Signal::SIGSTOP)) {
+        Ok(_) => 0,
+        Err(_) => 1,
+      };
+      unsafe { libc::_exit(code) }
+    }
+    ForkResult::Parent { child } => match waitpid(child, None)? {
+      WaitStatus::Stopped(_, Signal::SIGSTOP) => {
+        kill(child, Signal::SIGKILL)?;
+        waitpid(child, None)?;
+        Ok(())
+      }
+      _ if ptrace_scope() == 3 => bail!("ptrace is disabled by kernel.yama.ptrace_scope = 3"),
+      _ => bail!("ptrace is not permitted, e.g. by the seccomp profile of a container"),
+    },
+  }
+}
+
+/// Whether tracexec has CAP_SYS_PTRACE in its effective capabilities,
+/// which root may lack in a container and other users may be given
+fn has_cap_sys_ptrace() -> bool {
+  const CAP_SYS_PTRACE: u32 = 19;
+  std::fs::read_to_string("/proc/self/status")
+    .ok()
+    .and_then(|status| {
+      status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))
+        .and_then(|caps| u64::from_str_radix(caps.trim(), 16).ok())
+    })
+    .is_some_and(|caps| caps & (1 << CAP_SYS_PTRACE) != 0)
+}
+
+/// Attaching to processes that are not children of tracexec is restricted by Yama
+fn check_attach() -> color_eyre::Result<()> {
+  match ptrace_scope() {
+    0 => Ok(()),
+    1 | 2 if has_cap_sys_ptrace() => Ok(()),
+    1 | 2 => bail!(
+      "attaching needs CAP_SYS_PTRACE because kernel.yama.ptrace_scope is {}",
+      ptrace_scope()
+    ),
+    _ => bail!("attaching is disabled by kernel.yama.ptrace_scope = 3"),
+  }
+}
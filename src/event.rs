@@ -1,76 +1,166 @@
-use std::{borrow::Cow, ffi::OsStr, io::Write, path::PathBuf, sync::Arc, usize};
+use std::{borrow::Cow, ffi::OsStr, io::Write, path::PathBuf, sync::Arc, time::SystemTime, usize};
 
 use clap::ValueEnum;
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyEvent, MouseEvent};
 use enumflags2::BitFlags;
 use filterable_enum::FilterableEnum;
 use itertools::{chain, Itertools};
-use nix::{fcntl::OFlag, sys::signal::Signal, unistd::Pid};
+use nix::{
+  fcntl::OFlag,
+  sys::signal::Signal,
+  unistd::{Pid, Uid},
+};
 use ratatui::{
   layout::Size,
   style::Styled,
   text::{Line, Span},
 };
+use serde::{Deserialize, Serialize};
 use strum::Display;
 use tokio::sync::mpsc;
 
 use crate::{
-  action::CopyTarget,
+  action::{CopyTarget, SupportedShell},
   cli::args::ModifierArgs,
-  printer::{escape_str_for_bash, ListPrinter},
+  export::event_to_json,
+  printer::ListPrinter,
   proc::{BaselineInfo, EnvDiff, FileDescriptorInfoCollection, Interpreter},
+  redact::Secrets,
   tracer::InspectError,
   tui::theme::THEME,
 };
 
+pub mod nix_serde;
+
 #[derive(Debug, Clone, Display, PartialEq)]
 pub enum Event {
   ShouldQuit,
   Key(KeyEvent),
+  Mouse(MouseEvent),
   Tracer(TracerEvent),
+  /// An event of a trace started from within the TUI, for the tab with that index
+  TabTracer(usize, TracerEvent),
   Render,
   Resize(Size),
   Init,
   Error,
+  /// A program that is opened from the TUI, e.g. the editor, exited and the TUI can be resumed
+  ExternalProgramExited {
+    program: String,
+    error: Option<String>,
+  },
 }
 
-#[derive(Debug, Clone, PartialEq, FilterableEnum)]
+#[derive(Debug, Clone, PartialEq, FilterableEnum, Serialize, Deserialize)]
 #[filterable_enum(kind_extra_derive=ValueEnum, kind_extra_derive=Display, kind_extra_attrs="strum(serialize_all = \"kebab-case\")")]
 pub enum TracerEvent {
   Info(TracerMessage),
   Warning(TracerMessage),
   Error(TracerMessage),
   NewChild {
+    #[serde(with = "nix_serde::raw")]
     ppid: Pid,
     pcomm: String,
+    #[serde(with = "nix_serde::raw")]
     pid: Pid,
+    timestamp: SystemTime,
+    /// When the parent and the child started, see `ProcessState::start_time`
+    #[serde(default)]
+    pstart_time: u64,
+    #[serde(default)]
+    start_time: u64,
   },
   Exec(Box<ExecEvent>),
-  TraceeSpawn(Pid),
+  TraceeSpawn(#[serde(with = "nix_serde::raw")] Pid),
   TraceeExit {
+    #[serde(with = "nix_serde::option")]
     signal: Option<Signal>,
     exit_code: i32,
   },
+  ProcessExit {
+    #[serde(with = "nix_serde::raw")]
+    pid: Pid,
+    #[serde(with = "nix_serde::option")]
+    ppid: Option<Pid>,
+    comm: String,
+    status: ProcessExitStatus,
+    timestamp: SystemTime,
+    /// When the process started, see `ProcessState::start_time`
+    #[serde(default)]
+    start_time: u64,
+  },
+  /// A signal is delivered to a tracee
+  Signal {
+    #[serde(with = "nix_serde::raw")]
+    pid: Pid,
+    comm: String,
+    #[serde(with = "nix_serde::raw")]
+    signal: Signal,
+    timestamp: SystemTime,
+  },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ProcessExitStatus {
+  Exited(i32),
+  Signaled(#[serde(with = "nix_serde::raw")] Signal),
+}
+
+impl ProcessExitStatus {
+  pub fn is_success(&self) -> bool {
+    matches!(self, Self::Exited(0))
+  }
+}
+
+impl std::fmt::Display for ProcessExitStatus {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Exited(code) => write!(f, "exited with code {code}"),
+      Self::Signaled(sig) => write!(f, "killed by {sig}"),
+    }
+  }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TracerMessage {
+  #[serde(with = "nix_serde::option")]
   pub pid: Option<Pid>,
   pub msg: String,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ExecEvent {
+  #[serde(with = "nix_serde::raw")]
   pub pid: Pid,
+  /// The pid in the PID namespace of the process, if it differs from the one of tracexec
+  #[serde(with = "nix_serde::option", default)]
+  pub ns_pid: Option<Pid>,
+  #[serde(with = "nix_serde::option")]
+  pub ppid: Option<Pid>,
   pub cwd: PathBuf,
+  /// The effective user of the process, if it could be read
+  #[serde(with = "nix_serde::option")]
+  pub uid: Option<Uid>,
   pub comm: String,
+  #[serde(with = "nix_serde::result")]
   pub filename: Result<PathBuf, InspectError>,
+  pub resolved_filename: Option<PathBuf>,
+  #[serde(with = "nix_serde::arc_result")]
   pub argv: Arc<Result<Vec<String>, InspectError>>,
+  #[serde(with = "nix_serde::arc_result")]
   pub envp: Arc<Result<Vec<String>, InspectError>>,
+  /// The original values of the redacted variables of `envp`, which are not recorded
+  #[serde(skip)]
+  pub secrets: Arc<Secrets>,
   pub interpreter: Vec<Interpreter>,
+  #[serde(with = "nix_serde::result")]
   pub env_diff: Result<EnvDiff, InspectError>,
   pub fdinfo: Arc<FileDescriptorInfoCollection>,
   pub result: i64,
+  pub timestamp: SystemTime,
+  /// When the process started, see `ProcessState::start_time`
+  #[serde(default)]
+  pub start_time: u64,
 }
 
 macro_rules! tracer_event_spans {
@@ -78,7 +168,7 @@ macro_rules! tracer_event_spans {
         chain!([
             Some($pid.to_string().set_style(if $result == 0 {
               THEME.pid_success
-            } else if $result == (-nix::libc::ENOENT).into() {
+            } else if $result == i64::from(-nix::libc::ENOENT) {
               THEME.pid_enoent
             } else {
               THEME.pid_failure
@@ -90,6 +180,18 @@ macro_rules! tracer_event_spans {
 }
 
 impl TracerEvent {
+  /// The process that the event is about, if there is one
+  pub fn pid(&self) -> Option<Pid> {
+    match self {
+      Self::NewChild { pid, .. } | Self::ProcessExit { pid, .. } | Self::Signal { pid, .. } => {
+        Some(*pid)
+      }
+      Self::Exec(exec) => Some(exec.pid),
+      Self::TraceeSpawn(pid) => Some(*pid),
+      Self::Info(_) | Self::Warning(_) | Self::Error(_) | Self::TraceeExit { .. } => None,
+    }
+  }
+
   /// Convert the event to a TUI line
   ///
   /// This method is resource intensive and the caller should cache the result
@@ -125,7 +227,9 @@ impl TracerEvent {
         [": ".into(), msg.clone().set_style(THEME.tracer_error)]
       )
       .collect(),
-      TracerEvent::NewChild { ppid, pcomm, pid } => {
+      TracerEvent::NewChild {
+        ppid, pcomm, pid, ..
+      } => {
         let spans = tracer_event_spans!(
           ppid,
           pcomm,
@@ -135,194 +239,269 @@ impl TracerEvent {
         );
         spans.flatten().collect()
       }
-      TracerEvent::Exec(exec) => {
-        let ExecEvent {
+      TracerEvent::Exec(exec) => Self::exec_to_line(
+        exec,
+        baseline,
+        cmdline_only,
+        modifier,
+        if env_in_cmdline {
+          EnvInCmdline::Diff
+        } else {
+          EnvInCmdline::None
+        },
+        SupportedShell::Bash,
+      ),
+      TracerEvent::TraceeExit { signal, exit_code } => format!(
+        "tracee exit: signal: {:?}, exit_code: {}",
+        signal, exit_code
+      )
+      .into(),
+      TracerEvent::TraceeSpawn(pid) => format!("tracee spawned: {}", pid).into(),
+      TracerEvent::ProcessExit {
+        pid, comm, status, ..
+      } => {
+        let spans = tracer_event_spans!(
           pid,
-          cwd,
           comm,
-          filename,
-          argv,
-          interpreter: _,
-          env_diff,
-          result,
-          fdinfo,
-          ..
-        } = exec.as_ref();
-        let mut spans: Vec<Span> = if !cmdline_only {
-          tracer_event_spans!(
-            pid,
-            comm,
-            *result,
-            Some("env".set_style(THEME.tracer_event)),
-          )
-          .flatten()
-          .collect()
-        } else {
-          vec!["env".set_style(THEME.tracer_event)]
-        };
-        let space: Span = " ".into();
+          if status.is_success() { 0 } else { 1 },
+          Some(status.to_string().set_style(THEME.tracer_event)),
+        );
+        spans.flatten().collect()
+      }
+      TracerEvent::Signal {
+        pid, comm, signal, ..
+      } => {
+        let spans = tracer_event_spans!(
+          pid,
+          comm,
+          0,
+          Some(format!("received {signal}").set_style(THEME.tracer_event)),
+        );
+        spans.flatten().collect()
+      }
+    }
+  }
+}
 
-        // Handle argv[0]
-        let _ = argv.as_deref().inspect(|v| {
-          v.first().inspect(|&arg0| {
-            if filename.is_ok() && filename.as_ref().unwrap().as_os_str() != OsStr::new(arg0) {
-              spans.push(space.clone());
-              spans.push(format!("-a {}", escape_str_for_bash!(arg0)).set_style(THEME.arg0))
-            }
-          });
-        });
-        // Handle cwd
-        if cwd != &baseline.cwd {
+/// How the environment is shown in the command line of an exec event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EnvInCmdline {
+  None,
+  /// Only the changes compared to the baseline environment
+  Diff,
+  /// The full environment, starting from an empty one with `env -i`
+  Full,
+}
+
+impl TracerEvent {
+  fn exec_to_line(
+    exec: &ExecEvent,
+    baseline: &BaselineInfo,
+    cmdline_only: bool,
+    modifier: &ModifierArgs,
+    env: EnvInCmdline,
+    shell: SupportedShell,
+  ) -> Line<'static> {
+    let ExecEvent {
+      pid,
+      ns_pid,
+      cwd,
+      comm,
+      filename,
+      argv,
+      envp,
+      interpreter: _,
+      env_diff,
+      result,
+      fdinfo,
+      ..
+    } = exec;
+    let command = match (shell, env) {
+      (SupportedShell::Nushell, EnvInCmdline::Full) => "^env -i",
+      // nushell has its own env command
+      (SupportedShell::Nushell, _) => "^env",
+      (_, EnvInCmdline::Full) => "env -i",
+      _ => "env",
+    };
+    let mut spans: Vec<Span> = if !cmdline_only {
+      tracer_event_spans!(
+        modifier
+          .pid_display
+          .unwrap_or_default()
+          .format(*pid, *ns_pid),
+        comm,
+        *result,
+        Some(command.set_style(THEME.tracer_event)),
+      )
+      .flatten()
+      .collect()
+    } else {
+      vec![command.set_style(THEME.tracer_event)]
+    };
+    let space: Span = " ".into();
+
+    // Handle argv[0]
+    let _ = argv.as_deref().inspect(|v| {
+      v.first().inspect(|&arg0| {
+        if filename.is_ok() && filename.as_ref().unwrap().as_os_str() != OsStr::new(arg0) {
           spans.push(space.clone());
-          spans.push(format!("-C {}", escape_str_for_bash!(cwd)).set_style(THEME.cwd));
+          spans.push(format!("-a {}", shell.quote(arg0)).set_style(THEME.arg0))
         }
-        if env_in_cmdline {
-          if let Ok(env_diff) = env_diff {
-            // Handle env diff
-            for k in env_diff.removed.iter() {
-              spans.push(space.clone());
-              spans
-                .push(format!("-u {}", escape_str_for_bash!(k)).set_style(THEME.deleted_env_var));
-            }
-            for (k, v) in env_diff.added.iter() {
-              // Added env vars
-              spans.push(space.clone());
-              spans.push(
-                format!("{}={}", escape_str_for_bash!(k), escape_str_for_bash!(v))
-                  .set_style(THEME.added_env_var),
-              );
-            }
-            for (k, v) in env_diff.modified.iter() {
-              // Modified env vars
-              spans.push(space.clone());
-              spans.push(
-                format!("{}={}", escape_str_for_bash!(k), escape_str_for_bash!(v))
-                  .set_style(THEME.modified_env_var),
-              );
-            }
+      });
+    });
+    // Handle cwd
+    if cwd != &baseline.cwd {
+      spans.push(space.clone());
+      spans.push(format!("-C {}", shell.quote(cwd)).set_style(THEME.cwd));
+    }
+    if env == EnvInCmdline::Full {
+      match envp.as_ref() {
+        Ok(envp) => {
+          for var in envp.iter() {
+            let (k, v) = var.split_once('=').unwrap_or((var, ""));
+            spans.push(space.clone());
+            spans.push(shell.quote_assignment(k, v).set_style(THEME.added_env_var));
           }
         }
-        spans.push(space.clone());
-        // Filename
-        match filename {
-          Ok(filename) => {
-            spans.push(format!("{}", escape_str_for_bash!(filename)).set_style(THEME.filename));
-          }
-          Err(_) => {
-            spans.push("[failed to read filename]".set_style(THEME.inline_tracer_error));
-          }
+        Err(_) => {
+          spans.push(space.clone());
+          spans.push("[failed to read envp]".set_style(THEME.inline_tracer_error));
         }
-        // Argv[1..]
-        match argv.as_ref() {
-          Ok(argv) => {
-            for arg in argv.iter().skip(1) {
-              spans.push(space.clone());
-              spans.push(format!("{}", escape_str_for_bash!(arg)).set_style(THEME.argv));
-            }
-          }
-          Err(_) => {
-            spans.push(space.clone());
-            spans.push("[failed to read argv]".set_style(THEME.inline_tracer_error));
-          }
+      }
+    } else if env == EnvInCmdline::Diff {
+      if let Ok(env_diff) = env_diff {
+        // Handle env diff
+        for k in env_diff.removed.iter() {
+          spans.push(space.clone());
+          spans.push(format!("-u {}", shell.quote(k)).set_style(THEME.deleted_env_var));
         }
-
-        // Handle file descriptors
-        if modifier.stdio_in_cmdline {
-          let fdinfo_orig = baseline.fdinfo.stdin().unwrap();
-          if let Some(fdinfo) = fdinfo.stdin() {
-            if fdinfo.flags.contains(OFlag::O_CLOEXEC) {
-              // stdin will be closed
-              spans.push(space.clone());
-              spans.push("0>&-".set_style(THEME.cloexec_fd_in_cmdline));
-            } else if fdinfo.path != fdinfo_orig.path {
-              spans.push(space.clone());
-              spans.push("<".set_style(THEME.modified_fd_in_cmdline));
-              spans.push(
-                escape_str_for_bash!(&fdinfo.path)
-                  .into_owned()
-                  .set_style(THEME.modified_fd_in_cmdline),
-              );
-            }
-          } else {
-            // stdin is closed
-            spans.push(space.clone());
-            spans.push("0>&-".set_style(THEME.removed_fd_in_cmdline));
-          }
-          let fdinfo_orig = baseline.fdinfo.stdout().unwrap();
-          if let Some(fdinfo) = fdinfo.stdout() {
-            if fdinfo.flags.contains(OFlag::O_CLOEXEC) {
-              // stdout will be closed
-              spans.push(space.clone());
-              spans.push("1>&-".set_style(THEME.cloexec_fd_in_cmdline));
-            } else if fdinfo.path != fdinfo_orig.path {
-              spans.push(space.clone());
-              spans.push(">".set_style(THEME.modified_fd_in_cmdline));
-              spans.push(
-                escape_str_for_bash!(&fdinfo.path)
-                  .into_owned()
-                  .set_style(THEME.modified_fd_in_cmdline),
-              )
-            }
-          } else {
-            // stdout is closed
-            spans.push(space.clone());
-            spans.push("1>&-".set_style(THEME.removed_fd_in_cmdline));
-          }
-          let fdinfo_orig = baseline.fdinfo.stderr().unwrap();
-          if let Some(fdinfo) = fdinfo.stderr() {
-            if fdinfo.flags.contains(OFlag::O_CLOEXEC) {
-              // stderr will be closed
-              spans.push(space.clone());
-              spans.push("2>&-".set_style(THEME.cloexec_fd_in_cmdline));
-            } else if fdinfo.path != fdinfo_orig.path {
-              spans.push(space.clone());
-              spans.push("2>".set_style(THEME.modified_fd_in_cmdline));
-              spans.push(
-                escape_str_for_bash!(&fdinfo.path)
-                  .into_owned()
-                  .set_style(THEME.modified_fd_in_cmdline),
-              );
-            }
-          } else {
-            // stderr is closed
-            spans.push(space.clone());
-            spans.push("2>&-".set_style(THEME.removed_fd_in_cmdline));
-          }
+        for (k, v) in env_diff.added.iter() {
+          // Added env vars
+          spans.push(space.clone());
+          spans.push(shell.quote_assignment(k, v).set_style(THEME.added_env_var));
+        }
+        for (k, v) in env_diff.modified.iter() {
+          // Modified env vars
+          spans.push(space.clone());
+          spans.push(
+            shell
+              .quote_assignment(k, v)
+              .set_style(THEME.modified_env_var),
+          );
+        }
+      }
+    }
+    spans.push(space.clone());
+    // Filename
+    match filename {
+      Ok(filename) => {
+        spans.push(shell.quote(filename).set_style(THEME.filename));
+      }
+      Err(_) => {
+        spans.push("[failed to read filename]".set_style(THEME.inline_tracer_error));
+      }
+    }
+    // Argv[1..]
+    match argv.as_ref() {
+      Ok(argv) => {
+        for arg in argv.iter().skip(1) {
+          spans.push(space.clone());
+          spans.push(shell.quote(arg).set_style(THEME.argv));
         }
+      }
+      Err(_) => {
+        spans.push(space.clone());
+        spans.push("[failed to read argv]".set_style(THEME.inline_tracer_error));
+      }
+    }
 
-        if modifier.fd_in_cmdline {
-          for (&fd, fdinfo) in fdinfo.fdinfo.iter() {
-            if fd < 3 {
-              continue;
-            }
-            if fdinfo.flags.intersects(OFlag::O_CLOEXEC) {
-              // Skip fds that will be closed upon exec
-              continue;
-            }
-            spans.push(space.clone());
-            spans.push(fd.to_string().set_style(THEME.added_fd_in_cmdline));
-            spans.push(">".set_style(THEME.added_fd_in_cmdline));
-            spans.push(
-              escape_str_for_bash!(&fdinfo.path)
-                .into_owned()
-                .set_style(THEME.added_fd_in_cmdline),
-            )
-          }
+    // Handle file descriptors
+    if modifier.stdio_in_cmdline {
+      let fdinfo_orig = baseline.fdinfo.stdin().unwrap();
+      if let Some(fdinfo) = fdinfo.stdin() {
+        if fdinfo.flags.contains(OFlag::O_CLOEXEC) {
+          // stdin will be closed
+          spans.push(space.clone());
+          spans.push("0>&-".set_style(THEME.cloexec_fd_in_cmdline));
+        } else if fdinfo.path != fdinfo_orig.path {
+          spans.push(space.clone());
+          spans.push("<".set_style(THEME.modified_fd_in_cmdline));
+          spans.push(
+            shell
+              .quote(&fdinfo.path)
+              .set_style(THEME.modified_fd_in_cmdline),
+          );
         }
+      } else {
+        // stdin is closed
+        spans.push(space.clone());
+        spans.push("0>&-".set_style(THEME.removed_fd_in_cmdline));
+      }
+      let fdinfo_orig = baseline.fdinfo.stdout().unwrap();
+      if let Some(fdinfo) = fdinfo.stdout() {
+        if fdinfo.flags.contains(OFlag::O_CLOEXEC) {
+          // stdout will be closed
+          spans.push(space.clone());
+          spans.push("1>&-".set_style(THEME.cloexec_fd_in_cmdline));
+        } else if fdinfo.path != fdinfo_orig.path {
+          spans.push(space.clone());
+          spans.push(">".set_style(THEME.modified_fd_in_cmdline));
+          spans.push(
+            shell
+              .quote(&fdinfo.path)
+              .set_style(THEME.modified_fd_in_cmdline),
+          )
+        }
+      } else {
+        // stdout is closed
+        spans.push(space.clone());
+        spans.push("1>&-".set_style(THEME.removed_fd_in_cmdline));
+      }
+      let fdinfo_orig = baseline.fdinfo.stderr().unwrap();
+      if let Some(fdinfo) = fdinfo.stderr() {
+        if fdinfo.flags.contains(OFlag::O_CLOEXEC) {
+          // stderr will be closed
+          spans.push(space.clone());
+          spans.push("2>&-".set_style(THEME.cloexec_fd_in_cmdline));
+        } else if fdinfo.path != fdinfo_orig.path {
+          spans.push(space.clone());
+          spans.push("2>".set_style(THEME.modified_fd_in_cmdline));
+          spans.push(
+            shell
+              .quote(&fdinfo.path)
+              .set_style(THEME.modified_fd_in_cmdline),
+          );
+        }
+      } else {
+        // stderr is closed
+        spans.push(space.clone());
+        spans.push("2>&-".set_style(THEME.removed_fd_in_cmdline));
+      }
+    }
 
-        Line::default().spans(spans)
+    if modifier.fd_in_cmdline {
+      for (&fd, fdinfo) in fdinfo.fdinfo.iter() {
+        if fd < 3 {
+          continue;
+        }
+        if fdinfo.flags.intersects(OFlag::O_CLOEXEC) {
+          // Skip fds that will be closed upon exec
+          continue;
+        }
+        spans.push(space.clone());
+        spans.push(fd.to_string().set_style(THEME.added_fd_in_cmdline));
+        spans.push(">".set_style(THEME.added_fd_in_cmdline));
+        spans.push(
+          shell
+            .quote(&fdinfo.path)
+            .set_style(THEME.added_fd_in_cmdline),
+        )
       }
-      TracerEvent::TraceeExit { signal, exit_code } => format!(
-        "tracee exit: signal: {:?}, exit_code: {}",
-        signal, exit_code
-      )
-      .into(),
-      TracerEvent::TraceeSpawn(pid) => format!("tracee spawned: {}", pid).into(),
     }
+
+    Line::default().spans(spans)
   }
-}
 
-impl TracerEvent {
   pub fn text_for_copy<'a>(
     &'a self,
     baseline: &BaselineInfo,
@@ -336,31 +515,67 @@ impl TracerEvent {
         .to_string()
         .into();
     }
+    if let CopyTarget::Json = target {
+      let mut json = event_to_json(self);
+      // The exported object only has the diff of the environment
+      if let TracerEvent::Exec(event) = self {
+        json["envp"] = serde_json::json!(event.envp.as_ref().as_ref().ok());
+      }
+      return serde_json::to_string_pretty(&json).unwrap().into();
+    }
     // Other targets are only available for Exec events
     let TracerEvent::Exec(event) = self else {
       panic!("Copy target {:?} is only available for Exec events", target);
     };
     let mut modifier_args = ModifierArgs::default();
     match target {
-      CopyTarget::Commandline(_) => self
-        .to_tui_line(baseline, true, &modifier_args, true)
-        .to_string()
-        .into(),
-      CopyTarget::CommandlineWithStdio(_) => {
+      CopyTarget::Commandline(shell) => Self::exec_to_line(
+        event,
+        baseline,
+        true,
+        &modifier_args,
+        EnvInCmdline::Diff,
+        shell,
+      )
+      .to_string()
+      .into(),
+      CopyTarget::CommandlineWithStdio(shell) => {
         modifier_args.stdio_in_cmdline = true;
-        self
-          .to_tui_line(baseline, true, &modifier_args, true)
-          .to_string()
-          .into()
+        Self::exec_to_line(
+          event,
+          baseline,
+          true,
+          &modifier_args,
+          EnvInCmdline::Diff,
+          shell,
+        )
+        .to_string()
+        .into()
       }
-      CopyTarget::CommandlineWithFds(_) => {
+      CopyTarget::CommandlineWithFds(shell) => {
         modifier_args.fd_in_cmdline = true;
         modifier_args.stdio_in_cmdline = true;
-        self
-          .to_tui_line(baseline, true, &modifier_args, true)
-          .to_string()
-          .into()
+        Self::exec_to_line(
+          event,
+          baseline,
+          true,
+          &modifier_args,
+          EnvInCmdline::Diff,
+          shell,
+        )
+        .to_string()
+        .into()
       }
+      CopyTarget::CommandlineWithFullEnv(shell) => Self::exec_to_line(
+        event,
+        baseline,
+        true,
+        &modifier_args,
+        EnvInCmdline::Full,
+        shell,
+      )
+      .to_string()
+      .into(),
       CopyTarget::Env => match event.envp.as_ref() {
         Ok(envp) => envp.iter().join("\n").into(),
         Err(e) => format!("[failed to read envp: {e}]").into(),
@@ -393,7 +608,7 @@ impl TracerEvent {
       CopyTarget::Argv => Self::argv_to_string(&event.argv).into(),
       CopyTarget::Filename => Self::filename_to_cow(&event.filename),
       CopyTarget::SyscallResult => event.result.to_string().into(),
-      CopyTarget::Line => unreachable!(),
+      CopyTarget::Line | CopyTarget::Json => unreachable!(),
     }
   }
 
@@ -0,0 +1,148 @@
+//! (De)serializing the nix types in events as their raw values, for recorded traces
+
+use std::sync::Arc;
+
+use nix::{
+  errno::Errno,
+  fcntl::OFlag,
+  sys::signal::Signal,
+  unistd::{Pid, Uid},
+};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+pub trait Raw: Sized {
+  type Raw: Serialize + for<'de> Deserialize<'de>;
+
+  fn to_raw(&self) -> Self::Raw;
+  fn from_raw(raw: Self::Raw) -> Result<Self, String>;
+}
+
+impl Raw for Pid {
+  type Raw = i32;
+
+  fn to_raw(&self) -> i32 {
+    self.as_raw()
+  }
+
+  fn from_raw(raw: i32) -> Result<Self, String> {
+    Ok(Self::from_raw(raw))
+  }
+}
+
+impl Raw for Uid {
+  type Raw = u32;
+
+  fn to_raw(&self) -> u32 {
+    self.as_raw()
+  }
+
+  fn from_raw(raw: u32) -> Result<Self, String> {
+    Ok(Self::from_raw(raw))
+  }
+}
+
+impl Raw for Signal {
+  type Raw = i32;
+
+  fn to_raw(&self) -> i32 {
+    *self as i32
+  }
+
+  fn from_raw(raw: i32) -> Result<Self, String> {
+    Self::try_from(raw).map_err(|e| format!("invalid signal {raw}: {e}"))
+  }
+}
+
+impl Raw for OFlag {
+  type Raw = i32;
+
+  fn to_raw(&self) -> i32 {
+    self.bits()
+  }
+
+  fn from_raw(raw: i32) -> Result<Self, String> {
+    Ok(Self::from_bits_retain(raw))
+  }
+}
+
+impl Raw for Errno {
+  type Raw = i32;
+
+  fn to_raw(&self) -> i32 {
+    *self as i32
+  }
+
+  fn from_raw(raw: i32) -> Result<Self, String> {
+    Ok(Self::from_raw(raw))
+  }
+}
+
+pub mod raw {
+  use super::*;
+
+  pub fn serialize<T: Raw, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    value.to_raw().serialize(serializer)
+  }
+
+  pub fn deserialize<'de, T: Raw, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+    T::from_raw(T::Raw::deserialize(deserializer)?).map_err(D::Error::custom)
+  }
+}
+
+pub mod option {
+  use super::*;
+
+  pub fn serialize<T: Raw, S: Serializer>(
+    value: &Option<T>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    value.as_ref().map(Raw::to_raw).serialize(serializer)
+  }
+
+  pub fn deserialize<'de, T: Raw, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Option<T>, D::Error> {
+    Option::<T::Raw>::deserialize(deserializer)?
+      .map(T::from_raw)
+      .transpose()
+      .map_err(D::Error::custom)
+  }
+}
+
+/// For the results of inspecting the tracees, whose errors are errnos
+pub mod result {
+  use super::*;
+
+  pub fn serialize<T: Serialize, E: Raw, S: Serializer>(
+    value: &Result<T, E>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    value.as_ref().map_err(Raw::to_raw).serialize(serializer)
+  }
+
+  pub fn deserialize<'de, T: Deserialize<'de>, E: Raw, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Result<T, E>, D::Error> {
+    match Result::<T, E::Raw>::deserialize(deserializer)? {
+      Ok(value) => Ok(Ok(value)),
+      Err(raw) => E::from_raw(raw).map(Err).map_err(D::Error::custom),
+    }
+  }
+}
+
+pub mod arc_result {
+  use super::*;
+
+  pub fn serialize<T: Serialize, E: Raw, S: Serializer>(
+    value: &Arc<Result<T, E>>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    super::result::serialize(&**value, serializer)
+  }
+
+  pub fn deserialize<'de, T: Deserialize<'de>, E: Raw, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Arc<Result<T, E>>, D::Error> {
+    super::result::deserialize(deserializer).map(Arc::new)
+  }
+}
@@ -0,0 +1,120 @@
+//! Exporters consume tracer events in logging mode and forward them to external systems.
+
+use crate::{
+  cli::args::ExporterArgs,
+  event::{ExecEvent, TracerEvent},
+  printer::escape_str_for_bash,
+};
+
+use self::{
+  journald::JournaldExporter, otlp::OtlpExporter, script::ScriptExporter, syslog::SyslogExporter,
+};
+
+pub use self::{
+  formatter::{CsvFormatter, EventFormatter, FormatterExporter, StraceFormatter},
+  jsonl::{event_to_json, JsonlFormatter},
+};
+
+mod formatter;
+mod journald;
+mod jsonl;
+mod otlp;
+pub mod schema;
+mod script;
+mod syslog;
+
+pub trait Exporter {
+  /// Handle an event emitted by the tracer
+  fn on_event(&mut self, event: &TracerEvent) -> color_eyre::Result<()>;
+  /// Called once after the tracer stops, exporters should flush their buffers here.
+  fn finish(&mut self) -> color_eyre::Result<()>;
+}
+
+pub fn exporters_from_cli(args: &ExporterArgs) -> color_eyre::Result<Vec<Box<dyn Exporter>>> {
+  let mut exporters: Vec<Box<dyn Exporter>> = vec![];
+  if let Some(endpoint) = args.otlp_endpoint.as_deref() {
+    exporters.push(Box::new(OtlpExporter::new(
+      endpoint,
+      args.otlp_service_name.clone(),
+    )?));
+  }
+  if args.journald {
+    exporters.push(Box::new(JournaldExporter::new()?));
+  }
+  if args.syslog {
+    exporters.push(Box::new(SyslogExporter::new()?));
+  }
+  if let Some(path) = args.script.as_deref() {
+    exporters.push(Box::new(ScriptExporter::new(path)?));
+  }
+  Ok(exporters)
+}
+
+/// Syslog severity levels, also used by journald
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum Severity {
+  Error = 3,
+  Warning = 4,
+  Notice = 5,
+  Info = 6,
+  Debug = 7,
+}
+
+/// Summarize an event as a single line of text for line based log sinks
+fn event_summary(event: &TracerEvent) -> Option<(Severity, String)> {
+  Some(match event {
+    TracerEvent::Info(msg) => (Severity::Info, msg.msg.clone()),
+    TracerEvent::Warning(msg) => (Severity::Warning, msg.msg.clone()),
+    TracerEvent::Error(msg) => (Severity::Error, msg.msg.clone()),
+    TracerEvent::NewChild {
+      ppid, pcomm, pid, ..
+    } => (Severity::Debug, format!("{ppid}<{pcomm}>: new child {pid}")),
+    TracerEvent::Exec(exec) => (
+      if exec.result == 0 {
+        Severity::Info
+      } else {
+        Severity::Warning
+      },
+      exec_summary(exec),
+    ),
+    TracerEvent::ProcessExit {
+      pid, comm, status, ..
+    } => (
+      if status.is_success() {
+        Severity::Info
+      } else {
+        Severity::Notice
+      },
+      format!("{pid}<{comm}>: {status}"),
+    ),
+    // Signals are too frequent(think of SIGCHLD) for line based log sinks
+    TracerEvent::TraceeSpawn(_) | TracerEvent::TraceeExit { .. } | TracerEvent::Signal { .. } => {
+      return None
+    }
+  })
+}
+
+/// A single line summary of an exec, with its argv quoted for the shell
+pub fn exec_summary(exec: &ExecEvent) -> String {
+  let mut summary = format!(
+    "{}<{}>: {}",
+    exec.pid,
+    exec.comm,
+    TracerEvent::filename_to_cow(&exec.filename)
+  );
+  if let Ok(argv) = exec.argv.as_ref() {
+    for arg in argv.iter() {
+      summary.push(' ');
+      summary.push_str(&escape_str_for_bash!(arg.as_str()));
+    }
+  }
+  if exec.result != 0 {
+    summary.push_str(&format!(
+      " = {} ({})",
+      exec.result,
+      nix::errno::Errno::from_raw(-exec.result as i32)
+    ));
+  }
+  summary
+}
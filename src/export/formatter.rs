@@ -0,0 +1,209 @@
+//! Formats of the event stream that can be written to an output, e.g. `--output-format csv`.
+//!
+//! The human readable formats are written by the printer instead, as they need the state of the processes.
+
+use std::{io::Write, time::SystemTime};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use nix::{errno::Errno, unistd::Pid};
+use schemars::{schema_for, JsonSchema};
+
+use crate::{
+  cli::options::ArgvEscape,
+  event::{ExecEvent, ProcessExitStatus, TracerEvent},
+  printer::{escape_arg, escape_str_for_bash, PrinterOut},
+};
+
+use super::Exporter;
+
+#[cfg(test)]
+mod test;
+
+pub trait EventFormatter {
+  /// Written once before the first event, e.g. the header row of CSV
+  fn header(&mut self, _out: &mut dyn Write) -> std::io::Result<()> {
+    Ok(())
+  }
+
+  /// Write an event, the events that the format can't represent are skipped
+  fn format(&mut self, event: &TracerEvent, out: &mut dyn Write) -> std::io::Result<()>;
+}
+
+/// Writes the events to an output in the format of the formatter
+pub struct FormatterExporter {
+  formatter: Box<dyn EventFormatter>,
+  out: Box<PrinterOut>,
+}
+
+impl FormatterExporter {
+  pub fn new(
+    mut formatter: Box<dyn EventFormatter>,
+    mut out: Box<PrinterOut>,
+  ) -> std::io::Result<Self> {
+    formatter.header(&mut out)?;
+    Ok(Self { formatter, out })
+  }
+}
+
+impl Exporter for FormatterExporter {
+  fn on_event(&mut self, event: &TracerEvent) -> color_eyre::Result<()> {
+    self.formatter.format(event, &mut self.out)?;
+    // Flushing after every event lets rotation happen at line boundaries
+    self.out.flush()?;
+    Ok(())
+  }
+
+  fn finish(&mut self) -> color_eyre::Result<()> {
+    self.out.flush()?;
+    Ok(())
+  }
+}
+
+/// One row per exec, the other events are skipped
+pub struct CsvFormatter;
+
+/// A row of the CSV format. The columns of the header are the fields, in this order
+#[derive(JsonSchema)]
+pub struct CsvRow {
+  /// When the exec happened, in RFC 3339 with microseconds
+  timestamp: String,
+  pid: i32,
+  /// The parent process if it is traced, empty otherwise
+  ppid: Option<i32>,
+  /// The comm of the process
+  comm: String,
+  /// The filename passed to the exec
+  filename: String,
+  /// The argv passed to the exec, escaped for bash and separated by spaces. Empty if it couldn't be read
+  argv: String,
+  /// The working directory of the process
+  cwd: String,
+  /// The return value of the exec, 0 on success and a negated errno on failure
+  result: i64,
+  /// The pid inside the PID namespace of the process if it differs from pid, empty otherwise
+  ns_pid: Option<i32>,
+}
+
+impl CsvRow {
+  fn new(exec: &ExecEvent) -> Self {
+    let argv = match exec.argv.as_ref() {
+      Ok(argv) => argv
+        .iter()
+        .map(|arg| escape_str_for_bash!(arg.as_str()))
+        .collect::<Vec<_>>()
+        .join(" "),
+      Err(_) => String::new(),
+    };
+    Self {
+      timestamp: timestamp(exec.timestamp),
+      pid: exec.pid.as_raw(),
+      ppid: exec.ppid.map(Pid::as_raw),
+      comm: exec.comm.clone(),
+      filename: TracerEvent::filename_to_cow(&exec.filename).into_owned(),
+      argv,
+      cwd: exec.cwd.to_string_lossy().into_owned(),
+      result: exec.result,
+      ns_pid: exec.ns_pid.map(Pid::as_raw),
+    }
+  }
+}
+
+impl CsvFormatter {
+  /// Quote the field if it contains a delimiter, a quote or a line break, as RFC 4180 does
+  fn field(field: &str) -> std::borrow::Cow<str> {
+    if field.contains([',', '"', '\n', '\r']) {
+      format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+      field.into()
+    }
+  }
+}
+
+impl EventFormatter for CsvFormatter {
+  fn header(&mut self, out: &mut dyn Write) -> std::io::Result<()> {
+    // The header is generated from the schema so that it can't drift from the documented columns
+    let schema = schema_for!(CsvRow);
+    let columns = schema
+      .schema
+      .object
+      .iter()
+      .flat_map(|object| object.properties.keys())
+      .map(String::as_str)
+      .collect::<Vec<_>>();
+    writeln!(out, "{}", columns.join(","))
+  }
+
+  fn format(&mut self, event: &TracerEvent, out: &mut dyn Write) -> std::io::Result<()> {
+    let TracerEvent::Exec(exec) = event else {
+      return Ok(());
+    };
+    let row = CsvRow::new(exec);
+    writeln!(
+      out,
+      "{},{},{},{},{},{},{},{},{}",
+      row.timestamp,
+      row.pid,
+      row.ppid.map(|ppid| ppid.to_string()).unwrap_or_default(),
+      Self::field(&row.comm),
+      Self::field(&row.filename),
+      Self::field(&row.argv),
+      Self::field(&row.cwd),
+      row.result,
+      row
+        .ns_pid
+        .map(|ns_pid| ns_pid.to_string())
+        .unwrap_or_default(),
+    )
+  }
+}
+
+/// Lines like the ones of `strace -f -e trace=execve -o FILE`
+pub struct StraceFormatter;
+
+impl StraceFormatter {
+  fn exec(exec: &ExecEvent, out: &mut dyn Write) -> std::io::Result<()> {
+    let quote = |s: &str| escape_arg(s, ArgvEscape::C);
+    let argv = match exec.argv.as_ref() {
+      Ok(argv) => format!(
+        "[{}]",
+        argv
+          .iter()
+          .map(|arg| quote(arg))
+          .collect::<Vec<_>>()
+          .join(", ")
+      ),
+      Err(_) => "NULL".to_string(),
+    };
+    let envc = exec.envp.as_ref().as_ref().map_or(0, Vec::len);
+    write!(
+      out,
+      "{} execve({}, {argv}, /* {envc} vars */) = ",
+      exec.pid,
+      quote(&TracerEvent::filename_to_cow(&exec.filename)),
+    )?;
+    if exec.result == 0 {
+      writeln!(out, "0")
+    } else {
+      let errno = Errno::from_raw(-exec.result as i32);
+      writeln!(out, "-1 {errno:?} ({})", errno.desc())
+    }
+  }
+}
+
+impl EventFormatter for StraceFormatter {
+  fn format(&mut self, event: &TracerEvent, out: &mut dyn Write) -> std::io::Result<()> {
+    match event {
+      TracerEvent::Exec(exec) => Self::exec(exec, out),
+      TracerEvent::ProcessExit { pid, status, .. } => match status {
+        ProcessExitStatus::Exited(code) => writeln!(out, "{pid} +++ exited with {code} +++"),
+        ProcessExitStatus::Signaled(sig) => writeln!(out, "{pid} +++ killed by {sig} +++"),
+      },
+      TracerEvent::Signal { pid, signal, .. } => writeln!(out, "{pid} --- {signal} ---"),
+      _ => Ok(()),
+    }
+  }
+}
+
+fn timestamp(timestamp: SystemTime) -> String {
+  DateTime::<Local>::from(timestamp).to_rfc3339_opts(SecondsFormat::Micros, false)
+}
@@ -0,0 +1,88 @@
+use std::{path::PathBuf, sync::Arc, time::SystemTime};
+
+use nix::{errno::Errno, unistd::Pid};
+
+use crate::event::{ExecEvent, TracerEvent};
+
+use super::{CsvFormatter, EventFormatter};
+
+fn exec_event() -> ExecEvent {
+  ExecEvent {
+    pid: Pid::from_raw(12345),
+    ns_pid: Some(Pid::from_raw(7)),
+    ppid: Some(Pid::from_raw(12344)),
+    cwd: PathBuf::from("/tmp"),
+    uid: None,
+    comm: "sh".to_string(),
+    filename: Ok(PathBuf::from("/bin/echo")),
+    resolved_filename: None,
+    argv: Arc::new(Ok(vec!["echo".to_string(), "a,b".to_string()])),
+    envp: Arc::new(Ok(vec![])),
+    secrets: Default::default(),
+    interpreter: vec![],
+    env_diff: Err(Errno::ENOENT),
+    fdinfo: Default::default(),
+    result: 0,
+    timestamp: SystemTime::now(),
+    start_time: 0,
+  }
+}
+
+/// Split a CSV line into its fields, honoring the quotes of RFC 4180
+fn split_csv(line: &str) -> Vec<String> {
+  let mut fields = vec![String::new()];
+  let mut quoted = false;
+  let mut chars = line.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '"' if quoted && chars.peek() == Some(&'"') => {
+        chars.next();
+        fields.last_mut().unwrap().push('"');
+      }
+      '"' => quoted = !quoted,
+      ',' if !quoted => fields.push(String::new()),
+      _ => fields.last_mut().unwrap().push(c),
+    }
+  }
+  fields
+}
+
+#[test]
+fn csv_rows_line_up_with_the_header() {
+  let mut formatter = CsvFormatter;
+  let mut out = vec![];
+  formatter.header(&mut out).unwrap();
+  formatter
+    .format(&TracerEvent::Exec(Box::new(exec_event())), &mut out)
+    .unwrap();
+  let out = String::from_utf8(out).unwrap();
+  let lines: Vec<&str> = out.lines().collect();
+  assert_eq!(lines.len(), 2);
+  let header = split_csv(lines[0]);
+  let row = split_csv(lines[1]);
+  assert_eq!(
+    header,
+    [
+      "timestamp",
+      "pid",
+      "ppid",
+      "comm",
+      "filename",
+      "argv",
+      "cwd",
+      "result",
+      "ns_pid"
+    ]
+  );
+  assert_eq!(row.len(), header.len());
+  let field = |name: &str| &row[header.iter().position(|column| column == name).unwrap()];
+  assert_eq!(field("pid"), "12345");
+  assert_eq!(field("ppid"), "12344");
+  assert_eq!(field("comm"), "sh");
+  assert_eq!(field("filename"), "/bin/echo");
+  // The comma of the argument is quoted, so it doesn't split the field
+  assert!(field("argv").starts_with("echo ") && field("argv").contains("a,b"));
+  assert_eq!(field("cwd"), "/tmp");
+  assert_eq!(field("result"), "0");
+  assert_eq!(field("ns_pid"), "7");
+}
@@ -0,0 +1,136 @@
+//! Send events to systemd-journald using its native protocol.
+//!
+//! See https://systemd.io/JOURNAL_NATIVE_PROTOCOL/
+
+use std::os::unix::net::UnixDatagram;
+
+use tracing::warn;
+
+use crate::event::{ProcessExitStatus, TracerEvent};
+
+use super::{event_summary, Exporter};
+
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+pub struct JournaldExporter {
+  socket: UnixDatagram,
+}
+
+impl JournaldExporter {
+  pub fn new() -> color_eyre::Result<Self> {
+    let socket = UnixDatagram::unbound()?;
+    socket.connect(JOURNALD_SOCKET)?;
+    Ok(Self { socket })
+  }
+}
+
+/// Append a field to a journal entry.
+///
+/// Values containing newlines must use the binary encoding: the field name followed by a newline,
+/// then the value length as a little endian u64, then the value itself.
+fn push_field(entry: &mut Vec<u8>, key: &str, value: &[u8]) {
+  entry.extend_from_slice(key.as_bytes());
+  if value.contains(&b'\n') {
+    entry.push(b'\n');
+    entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+  } else {
+    entry.push(b'=');
+  }
+  entry.extend_from_slice(value);
+  entry.push(b'\n');
+}
+
+impl Exporter for JournaldExporter {
+  fn on_event(&mut self, event: &TracerEvent) -> color_eyre::Result<()> {
+    let Some((severity, message)) = event_summary(event) else {
+      return Ok(());
+    };
+    let mut entry = Vec::new();
+    push_field(&mut entry, "MESSAGE", message.as_bytes());
+    push_field(
+      &mut entry,
+      "PRIORITY",
+      (severity as u8).to_string().as_bytes(),
+    );
+    push_field(&mut entry, "SYSLOG_IDENTIFIER", b"tracexec");
+    match event {
+      TracerEvent::Info(msg) | TracerEvent::Warning(msg) | TracerEvent::Error(msg) => {
+        if let Some(pid) = msg.pid {
+          push_field(&mut entry, "TRACEXEC_PID", pid.to_string().as_bytes());
+        }
+      }
+      TracerEvent::NewChild {
+        ppid, pcomm, pid, ..
+      } => {
+        push_field(&mut entry, "TRACEXEC_PID", pid.to_string().as_bytes());
+        push_field(&mut entry, "TRACEXEC_PPID", ppid.to_string().as_bytes());
+        push_field(&mut entry, "TRACEXEC_COMM", pcomm.as_bytes());
+      }
+      TracerEvent::Exec(exec) => {
+        push_field(&mut entry, "TRACEXEC_PID", exec.pid.to_string().as_bytes());
+        if let Some(ppid) = exec.ppid {
+          push_field(&mut entry, "TRACEXEC_PPID", ppid.to_string().as_bytes());
+        }
+        push_field(&mut entry, "TRACEXEC_COMM", exec.comm.as_bytes());
+        push_field(
+          &mut entry,
+          "TRACEXEC_FILENAME",
+          TracerEvent::filename_to_cow(&exec.filename).as_bytes(),
+        );
+        // journald allows a field to appear multiple times, so every argument gets its own field.
+        if let Ok(argv) = exec.argv.as_ref() {
+          for arg in argv.iter() {
+            push_field(&mut entry, "TRACEXEC_ARGV", arg.as_bytes());
+          }
+        }
+        push_field(
+          &mut entry,
+          "TRACEXEC_CWD",
+          exec.cwd.to_string_lossy().as_bytes(),
+        );
+        push_field(
+          &mut entry,
+          "TRACEXEC_RESULT",
+          exec.result.to_string().as_bytes(),
+        );
+      }
+      TracerEvent::ProcessExit {
+        pid,
+        ppid,
+        comm,
+        status,
+        ..
+      } => {
+        push_field(&mut entry, "TRACEXEC_PID", pid.to_string().as_bytes());
+        if let Some(ppid) = ppid {
+          push_field(&mut entry, "TRACEXEC_PPID", ppid.to_string().as_bytes());
+        }
+        push_field(&mut entry, "TRACEXEC_COMM", comm.as_bytes());
+        match status {
+          ProcessExitStatus::Exited(code) => {
+            push_field(
+              &mut entry,
+              "TRACEXEC_EXIT_CODE",
+              code.to_string().as_bytes(),
+            );
+          }
+          ProcessExitStatus::Signaled(sig) => {
+            push_field(&mut entry, "TRACEXEC_EXIT_SIGNAL", sig.as_str().as_bytes());
+          }
+        }
+      }
+      TracerEvent::TraceeSpawn(_) | TracerEvent::TraceeExit { .. } | TracerEvent::Signal { .. } => {
+      }
+    }
+    // Entries that are too large for a datagram would have to be passed via a memfd.
+    // Just drop them instead of aborting the whole trace.
+    if let Err(e) = self.socket.send(&entry) {
+      warn!("Failed to send event to journald: {e}");
+    }
+    Ok(())
+  }
+
+  fn finish(&mut self) -> color_eyre::Result<()> {
+    Ok(())
+  }
+}
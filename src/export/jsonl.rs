@@ -0,0 +1,232 @@
+//! Write events as JSON lines, one object per event.
+//!
+//! The objects are serialized from the types here, which `tracexec schema` also documents.
+
+use std::{
+  borrow::Cow,
+  collections::{BTreeMap, BTreeSet},
+  io::Write,
+  time::SystemTime,
+};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use nix::unistd::Pid;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+  event::{ExecEvent, ProcessExitStatus, TracerEvent},
+  proc::EnvDiff,
+};
+
+use super::formatter::EventFormatter;
+
+pub struct JsonlFormatter;
+
+impl EventFormatter for JsonlFormatter {
+  fn format(&mut self, event: &TracerEvent, out: &mut dyn Write) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *out, &JsonEvent::from(event))?;
+    writeln!(out)
+  }
+}
+
+/// An event of the JSON lines format, which is an object on a line of its own
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum JsonEvent<'a> {
+  /// A message of tracexec
+  Info {
+    /// The process that the message is about
+    pid: Option<i32>,
+    msg: &'a str,
+  },
+  /// A warning of tracexec, e.g. when a detail of an exec can't be read
+  Warning {
+    /// The process that the warning is about
+    pid: Option<i32>,
+    msg: &'a str,
+  },
+  /// An error of tracexec
+  Error {
+    /// The process that the error is about
+    pid: Option<i32>,
+    msg: &'a str,
+  },
+  /// A traced process forked a child
+  NewChild {
+    /// The parent process
+    ppid: i32,
+    /// The comm of the parent process
+    pcomm: &'a str,
+    /// The child process
+    pid: i32,
+  },
+  /// A traced process called execve or execveat
+  Exec {
+    /// When the exec happened, in RFC 3339 with microseconds
+    timestamp: String,
+    pid: i32,
+    /// The pid inside the PID namespace of the process, if it differs from pid
+    ns_pid: Option<i32>,
+    /// The parent process, if it is traced
+    ppid: Option<i32>,
+    /// The comm of the process
+    comm: &'a str,
+    /// The filename passed to the exec, null if it couldn't be read
+    filename: Option<Cow<'a, str>>,
+    /// The filename resolved through symlinks and PATH, with --resolve-filename
+    resolved_filename: Option<Cow<'a, str>>,
+    /// The argv passed to the exec, null if it couldn't be read
+    argv: Option<&'a Vec<String>>,
+    /// The working directory of the process
+    cwd: Cow<'a, str>,
+    /// The environment compared to the one of tracexec, null if it couldn't be read
+    env: Option<JsonEnvDiff<'a>>,
+    /// The return value of the exec, 0 on success and a negated errno on failure
+    result: i64,
+  },
+  /// The root process is spawned
+  TraceeSpawn { pid: i32 },
+  /// The root process exited
+  TraceeExit {
+    /// The signal that killed the root process
+    signal: Option<&'static str>,
+    exit_code: i32,
+  },
+  /// A traced process exited
+  Exit {
+    /// When the process exited, in RFC 3339 with microseconds
+    timestamp: String,
+    pid: i32,
+    /// The parent process, if it is traced
+    ppid: Option<i32>,
+    comm: &'a str,
+    /// The exit code, null if the process was killed by a signal
+    exit_code: Option<i32>,
+    /// The signal that killed the process
+    signal: Option<&'static str>,
+  },
+  /// A signal is delivered to a traced process
+  Signal {
+    /// When the signal was delivered, in RFC 3339 with microseconds
+    timestamp: String,
+    pid: i32,
+    comm: &'a str,
+    /// The name of the signal, e.g. SIGINT
+    signal: &'static str,
+  },
+}
+
+/// The changes of an environment compared to the one of tracexec
+#[derive(Serialize, JsonSchema)]
+#[schemars(rename = "EnvDiff")]
+pub struct JsonEnvDiff<'a> {
+  /// The added variables and their values
+  added: &'a BTreeMap<String, String>,
+  /// The modified variables and their new values
+  modified: &'a BTreeMap<String, String>,
+  /// The names of the removed variables
+  removed: &'a BTreeSet<String>,
+}
+
+impl<'a> From<&'a EnvDiff> for JsonEnvDiff<'a> {
+  fn from(diff: &'a EnvDiff) -> Self {
+    Self {
+      added: &diff.added,
+      modified: &diff.modified,
+      removed: &diff.removed,
+    }
+  }
+}
+
+fn timestamp_to_string(timestamp: SystemTime) -> String {
+  DateTime::<Local>::from(timestamp).to_rfc3339_opts(SecondsFormat::Micros, false)
+}
+
+impl<'a> From<&'a ExecEvent> for JsonEvent<'a> {
+  fn from(exec: &'a ExecEvent) -> Self {
+    Self::Exec {
+      timestamp: timestamp_to_string(exec.timestamp),
+      pid: exec.pid.as_raw(),
+      ns_pid: exec.ns_pid.map(Pid::as_raw),
+      ppid: exec.ppid.map(Pid::as_raw),
+      comm: &exec.comm,
+      filename: exec.filename.as_ref().ok().map(|f| f.to_string_lossy()),
+      resolved_filename: exec.resolved_filename.as_ref().map(|f| f.to_string_lossy()),
+      argv: exec.argv.as_ref().as_ref().ok(),
+      cwd: exec.cwd.to_string_lossy(),
+      env: exec.env_diff.as_ref().ok().map(JsonEnvDiff::from),
+      result: exec.result,
+    }
+  }
+}
+
+impl<'a> From<&'a TracerEvent> for JsonEvent<'a> {
+  fn from(event: &'a TracerEvent) -> Self {
+    match event {
+      TracerEvent::Info(msg) => Self::Info {
+        pid: msg.pid.map(Pid::as_raw),
+        msg: &msg.msg,
+      },
+      TracerEvent::Warning(msg) => Self::Warning {
+        pid: msg.pid.map(Pid::as_raw),
+        msg: &msg.msg,
+      },
+      TracerEvent::Error(msg) => Self::Error {
+        pid: msg.pid.map(Pid::as_raw),
+        msg: &msg.msg,
+      },
+      TracerEvent::NewChild {
+        ppid, pcomm, pid, ..
+      } => Self::NewChild {
+        ppid: ppid.as_raw(),
+        pcomm,
+        pid: pid.as_raw(),
+      },
+      TracerEvent::Exec(exec) => Self::from(exec.as_ref()),
+      TracerEvent::TraceeSpawn(pid) => Self::TraceeSpawn { pid: pid.as_raw() },
+      TracerEvent::TraceeExit { signal, exit_code } => Self::TraceeExit {
+        signal: signal.map(|s| s.as_str()),
+        exit_code: *exit_code,
+      },
+      TracerEvent::ProcessExit {
+        pid,
+        ppid,
+        comm,
+        status,
+        timestamp,
+        ..
+      } => {
+        let (exit_code, signal) = match status {
+          ProcessExitStatus::Exited(code) => (Some(*code), None),
+          ProcessExitStatus::Signaled(sig) => (None, Some(sig.as_str())),
+        };
+        Self::Exit {
+          timestamp: timestamp_to_string(*timestamp),
+          pid: pid.as_raw(),
+          ppid: ppid.map(Pid::as_raw),
+          comm,
+          exit_code,
+          signal,
+        }
+      }
+      TracerEvent::Signal {
+        pid,
+        comm,
+        signal,
+        timestamp,
+      } => Self::Signal {
+        timestamp: timestamp_to_string(*timestamp),
+        pid: pid.as_raw(),
+        comm,
+        signal: signal.as_str(),
+      },
+    }
+  }
+}
+
+pub fn event_to_json(event: &TracerEvent) -> Value {
+  // The events only have string keys, which can't fail to serialize
+  serde_json::to_value(JsonEvent::from(event)).unwrap()
+}
@@ -0,0 +1,439 @@
+//! Export traced processes as OpenTelemetry spans via OTLP/HTTP with JSON encoding.
+//!
+//! Every traced process becomes a span that lasts from the time it is first seen until it exits.
+//! The span of the parent process is used as the parent span and exec attempts are recorded as span events.
+//! The spans are sent by a thread of their own so that a slow collector doesn't hold up tracing.
+
+use std::{
+  collections::HashMap,
+  io::{BufRead, BufReader, Read, Write},
+  net::{TcpStream, ToSocketAddrs},
+  sync::mpsc::{sync_channel, SyncSender, TrySendError},
+  thread::JoinHandle,
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use color_eyre::eyre::{bail, eyre};
+use nix::unistd::Pid;
+use serde_json::{json, Value};
+use tracing::{debug, warn};
+
+use crate::event::{ExecEvent, ProcessExitStatus, TracerEvent};
+
+use super::Exporter;
+
+/// Number of finished spans to buffer before sending them to the collector
+const BATCH_SIZE: usize = 256;
+const DEFAULT_OTLP_HTTP_PORT: u16 = 4318;
+/// An unresponsive collector must not hold up the sender for long
+const OTLP_TIMEOUT: Duration = Duration::from_secs(10);
+/// Number of batches waiting to be sent, the batches beyond it are dropped
+const QUEUE_SIZE: usize = 16;
+
+struct HttpEndpoint {
+  host: String,
+  port: u16,
+  path: String,
+}
+
+impl HttpEndpoint {
+  fn parse(endpoint: &str) -> color_eyre::Result<Self> {
+    let Some(rest) = endpoint.strip_prefix("http://") else {
+      bail!("Unsupported OTLP endpoint {endpoint:?}, only plain http:// endpoints are supported");
+    };
+    let (authority, path) = match rest.find('/') {
+      Some(idx) => rest.split_at(idx),
+      None => (rest, ""),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+      Some((host, port)) if !port.ends_with(']') => (host, port.parse::<u16>()?),
+      _ => (authority, DEFAULT_OTLP_HTTP_PORT),
+    };
+    let path = if path.is_empty() || path == "/" {
+      "/v1/traces"
+    } else {
+      path
+    };
+    Ok(Self {
+      host: host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string(),
+      port,
+      path: path.to_string(),
+    })
+  }
+
+  fn connect(&self) -> color_eyre::Result<TcpStream> {
+    let mut last_error = None;
+    for addr in (self.host.as_str(), self.port).to_socket_addrs()? {
+      match TcpStream::connect_timeout(&addr, OTLP_TIMEOUT) {
+        Ok(stream) => {
+          stream.set_read_timeout(Some(OTLP_TIMEOUT))?;
+          stream.set_write_timeout(Some(OTLP_TIMEOUT))?;
+          return Ok(stream);
+        }
+        Err(e) => last_error = Some(e),
+      }
+    }
+    match last_error {
+      Some(e) => Err(e.into()),
+      None => bail!("OTLP endpoint host {:?} has no addresses", self.host),
+    }
+  }
+
+  fn post_json(&self, body: &[u8]) -> color_eyre::Result<()> {
+    let mut stream = self.connect()?;
+    write!(
+      stream,
+      "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+      self.path,
+      self.host,
+      self.port,
+      body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()?;
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+    let status = status_line.split_ascii_whitespace().nth(1).unwrap_or("");
+    if !status.starts_with('2') {
+      bail!("OTLP collector responded with {:?}", status_line.trim_end());
+    }
+    Ok(())
+  }
+}
+
+struct PendingSpan {
+  pid: Pid,
+  span_id: u64,
+  ppid: Option<Pid>,
+  name: Option<String>,
+  start: SystemTime,
+  attributes: Vec<Value>,
+  events: Vec<Value>,
+}
+
+pub struct OtlpExporter {
+  service_name: String,
+  trace_id: [u8; 16],
+  next_span_id: u64,
+  /// Spans of processes that haven't exited yet
+  spans: HashMap<Pid, PendingSpan>,
+  /// Span ids of processes that have exited, for the children that outlive their parents
+  exited: HashMap<Pid, u64>,
+  /// Spans waiting to be sent to the collector
+  finished: Vec<Value>,
+  /// The queue of the thread that sends the batches, None once finished
+  sender: Option<SyncSender<Vec<u8>>>,
+  /// The thread that sends the batches, which reports how many of them failed
+  worker: Option<JoinHandle<color_eyre::Result<()>>>,
+  /// Number of spans dropped because the queue was full
+  dropped: usize,
+}
+
+impl OtlpExporter {
+  pub fn new(endpoint: &str, service_name: String) -> color_eyre::Result<Self> {
+    let mut trace_id = [0u8; 16];
+    std::fs::File::open("/dev/urandom")?.read_exact(&mut trace_id)?;
+    let endpoint = HttpEndpoint::parse(endpoint)?;
+    let (sender, receiver) = sync_channel::<Vec<u8>>(QUEUE_SIZE);
+    let worker = std::thread::Builder::new()
+      .name("otlp-exporter".to_string())
+      .spawn(move || {
+        let mut failed = 0;
+        let mut last_error = None;
+        for body in receiver {
+          if let Err(e) = endpoint.post_json(&body) {
+            warn!("Failed to export spans to OTLP collector: {e}");
+            failed += 1;
+            last_error = Some(e);
+          }
+        }
+        match last_error {
+          Some(e) => Err(e.wrap_err(format!(
+            "{failed} batches of spans failed to be exported to the OTLP collector"
+          ))),
+          None => Ok(()),
+        }
+      })?;
+    Ok(Self {
+      service_name,
+      trace_id,
+      next_span_id: 1,
+      spans: HashMap::new(),
+      exited: HashMap::new(),
+      finished: vec![],
+      sender: Some(sender),
+      worker: Some(worker),
+      dropped: 0,
+    })
+  }
+
+  fn new_span(&mut self, pid: Pid, seen_at: SystemTime) -> PendingSpan {
+    let span_id = self.next_span_id;
+    self.next_span_id += 1;
+    // The pid is reused by a new process
+    self.exited.remove(&pid);
+    PendingSpan {
+      pid,
+      span_id,
+      ppid: None,
+      name: None,
+      start: seen_at,
+      attributes: vec![int_attr("process.pid", pid.as_raw().into())],
+      events: vec![],
+    }
+  }
+
+  /// Get the span of a process, creating it if the process hasn't been seen yet.
+  fn span_mut(&mut self, pid: Pid, seen_at: SystemTime) -> &mut PendingSpan {
+    if !self.spans.contains_key(&pid) {
+      let span = self.new_span(pid, seen_at);
+      self.spans.insert(pid, span);
+    }
+    let span = self.spans.get_mut(&pid).unwrap();
+    // The process must have been started before any of the events we received about it
+    span.start = span.start.min(seen_at);
+    span
+  }
+
+  fn on_exec(&mut self, exec: &ExecEvent) {
+    let filename = TracerEvent::filename_to_cow(&exec.filename).into_owned();
+    let mut event_attributes = vec![
+      str_attr("process.executable.path", filename.clone()),
+      int_attr("tracexec.exec.result", exec.result),
+    ];
+    if let Ok(argv) = exec.argv.as_ref() {
+      event_attributes.push(str_array_attr("process.command_args", argv));
+    }
+    let span = self.span_mut(exec.pid, exec.timestamp);
+    span.ppid = exec.ppid.or(span.ppid);
+    if exec.result == 0 {
+      span.name = Some(filename);
+      span.attributes = vec![
+        int_attr("process.pid", exec.pid.as_raw().into()),
+        str_attr("process.working_directory", exec.cwd.to_string_lossy()),
+      ];
+      if let Some(ppid) = exec.ppid {
+        span
+          .attributes
+          .push(int_attr("process.parent_pid", ppid.as_raw().into()));
+      }
+      span.attributes.extend(event_attributes.iter().cloned());
+    }
+    span.events.push(json!({
+      "timeUnixNano": unix_nanos(exec.timestamp),
+      "name": "exec",
+      "attributes": event_attributes,
+    }));
+  }
+
+  fn on_process_exit(
+    &mut self,
+    pid: Pid,
+    ppid: Option<Pid>,
+    comm: &str,
+    status: &ProcessExitStatus,
+    timestamp: SystemTime,
+  ) {
+    let mut span = match self.spans.remove(&pid) {
+      Some(span) => span,
+      None => self.new_span(pid, timestamp),
+    };
+    span.ppid = ppid.or(span.ppid);
+    span.name.get_or_insert_with(|| comm.to_string());
+    match status {
+      ProcessExitStatus::Exited(code) => {
+        span
+          .attributes
+          .push(int_attr("process.exit.code", (*code).into()));
+      }
+      ProcessExitStatus::Signaled(sig) => {
+        span
+          .attributes
+          .push(str_attr("tracexec.exit.signal", sig.as_str()));
+      }
+    }
+    // Processes that are not traced, e.g. the parent of an attached process, have no spans
+    let parent_span_id = span.ppid.and_then(|ppid| self.span_id_of(ppid));
+    self.exited.insert(pid, span.span_id);
+    let span = span_to_json(
+      &self.trace_id,
+      span,
+      parent_span_id,
+      timestamp,
+      Some(status),
+    );
+    self.finished.push(span);
+    if self.finished.len() >= BATCH_SIZE {
+      let spans = self.finished.len();
+      let body = match self.payload() {
+        Ok(body) => body,
+        Err(e) => {
+          warn!("Failed to encode spans for OTLP collector: {e}");
+          return;
+        }
+      };
+      let Some(sender) = self.sender.as_ref() else {
+        return;
+      };
+      match sender.try_send(body) {
+        Ok(()) | Err(TrySendError::Disconnected(_)) => {}
+        Err(TrySendError::Full(_)) => {
+          warn!("OTLP collector is too slow, dropping {spans} spans");
+          self.dropped += spans;
+        }
+      }
+    }
+  }
+
+  fn span_id_of(&self, pid: Pid) -> Option<u64> {
+    self
+      .spans
+      .get(&pid)
+      .map(|span| span.span_id)
+      .or_else(|| self.exited.get(&pid).copied())
+  }
+
+  /// Take the finished spans and encode them as the body of a request to the collector
+  fn payload(&mut self) -> color_eyre::Result<Vec<u8>> {
+    let spans = std::mem::take(&mut self.finished);
+    debug!("Exporting {} spans to OTLP collector", spans.len());
+    let payload = json!({
+      "resourceSpans": [{
+        "resource": {
+          "attributes": [str_attr("service.name", self.service_name.as_str())],
+        },
+        "scopeSpans": [{
+          "scope": {
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+          },
+          "spans": spans,
+        }],
+      }],
+    });
+    Ok(serde_json::to_vec(&payload)?)
+  }
+}
+
+impl Exporter for OtlpExporter {
+  fn on_event(&mut self, event: &TracerEvent) -> color_eyre::Result<()> {
+    match event {
+      TracerEvent::Exec(exec) => self.on_exec(exec),
+      TracerEvent::ProcessExit {
+        pid,
+        ppid,
+        comm,
+        status,
+        timestamp,
+        ..
+      } => self.on_process_exit(*pid, *ppid, comm, status, *timestamp),
+      _ => {}
+    }
+    Ok(())
+  }
+
+  fn finish(&mut self) -> color_eyre::Result<()> {
+    // Processes that are still alive end now.
+    let now = SystemTime::now();
+    let span_ids: HashMap<Pid, u64> = self
+      .spans
+      .iter()
+      .map(|(pid, span)| (*pid, span.span_id))
+      .collect();
+    for (_, span) in self.spans.drain() {
+      let parent_span_id = span.ppid.and_then(|ppid| {
+        span_ids
+          .get(&ppid)
+          .or_else(|| self.exited.get(&ppid))
+          .copied()
+      });
+      self.finished.push(span_to_json(
+        &self.trace_id,
+        span,
+        parent_span_id,
+        now,
+        None,
+      ));
+    }
+    if let Some(sender) = self.sender.take() {
+      if !self.finished.is_empty() {
+        // The last batch waits for room in the queue, as tracing is over
+        sender.send(self.payload()?).ok();
+      }
+    }
+    if let Some(worker) = self.worker.take() {
+      worker
+        .join()
+        .map_err(|_| eyre!("The OTLP exporter thread panicked"))??;
+    }
+    if self.dropped > 0 {
+      bail!(
+        "{} spans were dropped because the OTLP collector was too slow",
+        self.dropped
+      );
+    }
+    Ok(())
+  }
+}
+
+fn span_to_json(
+  trace_id: &[u8],
+  span: PendingSpan,
+  parent_span_id: Option<u64>,
+  end: SystemTime,
+  status: Option<&ProcessExitStatus>,
+) -> Value {
+  let status = match status {
+    // STATUS_CODE_UNSET
+    None => json!({ "code": 0 }),
+    // STATUS_CODE_OK
+    Some(status) if status.is_success() => json!({ "code": 1 }),
+    // STATUS_CODE_ERROR
+    Some(status) => json!({ "code": 2, "message": status.to_string() }),
+  };
+  let mut value = json!({
+    "traceId": hex(trace_id),
+    "spanId": hex(&span.span_id.to_be_bytes()),
+    "name": span.name.unwrap_or_else(|| format!("process {}", span.pid)),
+    // SPAN_KIND_INTERNAL
+    "kind": 1,
+    "startTimeUnixNano": unix_nanos(span.start),
+    "endTimeUnixNano": unix_nanos(end),
+    "attributes": span.attributes,
+    "events": span.events,
+    "status": status,
+  });
+  if let Some(parent_span_id) = parent_span_id {
+    value["parentSpanId"] = hex(&parent_span_id.to_be_bytes()).into();
+  }
+  value
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+  time
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_nanos()
+    .to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn str_attr(key: &str, value: impl Into<String>) -> Value {
+  json!({ "key": key, "value": { "stringValue": value.into() } })
+}
+
+fn int_attr(key: &str, value: i64) -> Value {
+  // int64 values are encoded as strings in OTLP/JSON
+  json!({ "key": key, "value": { "intValue": value.to_string() } })
+}
+
+fn str_array_attr(key: &str, values: &[String]) -> Value {
+  let values: Vec<Value> = values.iter().map(|v| json!({ "stringValue": v })).collect();
+  json!({ "key": key, "value": { "arrayValue": { "values": values } } })
+}
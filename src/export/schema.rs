@@ -0,0 +1,199 @@
+//! The schemas of the structured output formats, printed by `tracexec schema`.
+//!
+//! They are generated from the types that the formats are serialized from.
+
+use std::fmt::Write;
+
+use clap::ValueEnum;
+use color_eyre::eyre::bail;
+use schemars::{
+  schema::{InstanceType, RootSchema, Schema, SchemaObject, SingleOrVec},
+  schema_for,
+};
+
+use crate::{cli::options::SchemaFormat, output::OutputFormat};
+
+use super::{formatter::CsvRow, jsonl::JsonEvent};
+
+/// The schema of the output format, if it is a structured format
+pub fn output_schema(format: OutputFormat) -> Option<RootSchema> {
+  Some(match format {
+    OutputFormat::Jsonl => schema_for!(JsonEvent<'static>),
+    OutputFormat::Csv => schema_for!(CsvRow),
+    _ => return None,
+  })
+}
+
+/// Render the schemas of the output format, or all structured formats if it is not given
+pub fn render_schema(
+  format: SchemaFormat,
+  output_format: Option<OutputFormat>,
+) -> color_eyre::Result<String> {
+  let schemas: Vec<(OutputFormat, RootSchema)> = match output_format {
+    Some(output_format) => match output_schema(output_format) {
+      Some(schema) => vec![(output_format, schema)],
+      None => bail!("The {output_format} output format is not a structured format!"),
+    },
+    None => OutputFormat::value_variants()
+      .iter()
+      .filter_map(|&format| output_schema(format).map(|schema| (format, schema)))
+      .collect(),
+  };
+  Ok(match format {
+    SchemaFormat::JsonSchema => {
+      let json = if let [(_, schema)] = schemas.as_slice() {
+        serde_json::to_value(schema)?
+      } else {
+        serde_json::Value::Object(
+          schemas
+            .iter()
+            .map(|(format, schema)| Ok((format.to_string(), serde_json::to_value(schema)?)))
+            .collect::<serde_json::Result<_>>()?,
+        )
+      };
+      format!("{}\n", serde_json::to_string_pretty(&json)?)
+    }
+    SchemaFormat::Markdown => {
+      let mut out = String::new();
+      for (format, schema) in schemas.iter() {
+        markdown(&mut out, *format, schema)?;
+      }
+      out
+    }
+  })
+}
+
+fn markdown(out: &mut String, format: OutputFormat, root: &RootSchema) -> std::fmt::Result {
+  writeln!(out, "# {format}\n")?;
+  if let Some(description) = description(&root.schema) {
+    writeln!(out, "{description}\n")?;
+  }
+  match root
+    .schema
+    .subschemas
+    .as_ref()
+    .and_then(|sub| sub.one_of.as_ref())
+  {
+    // An enum of objects, which are told apart by their type field
+    Some(variants) => {
+      for variant in variants {
+        let Schema::Object(variant) = variant else {
+          continue;
+        };
+        let name = variant
+          .object
+          .as_ref()
+          .and_then(|object| object.properties.get("type"))
+          .and_then(|tag| match tag {
+            Schema::Object(tag) => tag.enum_values.as_ref()?.first()?.as_str(),
+            Schema::Bool(_) => None,
+          })
+          .unwrap_or_default();
+        writeln!(out, "## `{name}`\n")?;
+        if let Some(description) = description(variant) {
+          writeln!(out, "{description}\n")?;
+        }
+        fields(out, variant)?;
+      }
+    }
+    None => fields(out, &root.schema)?,
+  }
+  for (name, definition) in root.definitions.iter() {
+    let Schema::Object(definition) = definition else {
+      continue;
+    };
+    writeln!(out, "## {name}\n")?;
+    if let Some(description) = description(definition) {
+      writeln!(out, "{description}\n")?;
+    }
+    fields(out, definition)?;
+  }
+  Ok(())
+}
+
+/// A table of the fields of an object
+fn fields(out: &mut String, schema: &SchemaObject) -> std::fmt::Result {
+  let Some(object) = schema.object.as_ref() else {
+    return Ok(());
+  };
+  writeln!(out, "| Field | Type | Description |")?;
+  writeln!(out, "| --- | --- | --- |")?;
+  for (name, field) in object.properties.iter() {
+    let (ty, description) = match field {
+      Schema::Object(field) => (type_name(field), description(field).unwrap_or_default()),
+      Schema::Bool(_) => ("any".to_string(), ""),
+    };
+    writeln!(
+      out,
+      "| `{name}` | {ty} | {} |",
+      description.replace('\n', " ")
+    )?;
+  }
+  writeln!(out)
+}
+
+fn description(schema: &SchemaObject) -> Option<&str> {
+  schema.metadata.as_ref()?.description.as_deref()
+}
+
+/// The type of a field, e.g. `integer or null`
+fn type_name(schema: &SchemaObject) -> String {
+  if let Some(reference) = schema.reference.as_deref() {
+    return reference
+      .rsplit('/')
+      .next()
+      .unwrap_or(reference)
+      .to_string();
+  }
+  if let Some(values) = schema.enum_values.as_ref() {
+    return values
+      .iter()
+      .map(|value| format!("`{value}`"))
+      .collect::<Vec<_>>()
+      .join(" or ");
+  }
+  let subschemas = schema
+    .subschemas
+    .as_ref()
+    .and_then(|sub| sub.any_of.as_ref().or(sub.one_of.as_ref()));
+  if let Some(subschemas) = subschemas {
+    return subschemas
+      .iter()
+      .map(|schema| match schema {
+        Schema::Object(schema) => type_name(schema),
+        Schema::Bool(_) => "any".to_string(),
+      })
+      .collect::<Vec<_>>()
+      .join(" or ");
+  }
+  let types: Vec<InstanceType> = match schema.instance_type.as_ref() {
+    Some(SingleOrVec::Single(ty)) => vec![**ty],
+    Some(SingleOrVec::Vec(types)) => types.clone(),
+    None => return "any".to_string(),
+  };
+  types
+    .into_iter()
+    .map(|ty| match ty {
+      InstanceType::Array => {
+        let items = match schema.array.as_ref().and_then(|array| array.items.as_ref()) {
+          Some(SingleOrVec::Single(items)) => match items.as_ref() {
+            Schema::Object(items) => type_name(items),
+            Schema::Bool(_) => "any".to_string(),
+          },
+          _ => "any".to_string(),
+        };
+        format!("array of {items}")
+      }
+      InstanceType::Object => match schema
+        .object
+        .as_ref()
+        .and_then(|object| object.additional_properties.as_deref())
+      {
+        Some(Schema::Object(values)) => format!("object of {}", type_name(values)),
+        _ => "object".to_string(),
+      },
+      ty => format!("{ty:?}").to_lowercase(),
+    })
+    .collect::<Vec<_>>()
+    .join(" or ")
+}
@@ -0,0 +1,115 @@
+//! Turn the traced exec sequence into a shell script that replays it.
+//!
+//! Every exec becomes one bash subshell that restores the working directory and the
+//! environment changes relative to tracexec's own environment, so a single step can be
+//! copied out and run standalone.
+
+use std::{
+  ffi::OsStr,
+  fs::{File, OpenOptions, Permissions},
+  io::{BufWriter, Write},
+  os::unix::fs::PermissionsExt,
+  path::Path,
+};
+
+use crate::{
+  event::{ExecEvent, TracerEvent},
+  printer::escape_str_for_bash,
+};
+
+use super::Exporter;
+
+pub struct ScriptExporter {
+  out: BufWriter<File>,
+}
+
+impl ScriptExporter {
+  pub fn new(path: &Path) -> color_eyre::Result<Self> {
+    let file = OpenOptions::new()
+      .create(true)
+      .truncate(true)
+      .write(true)
+      .open(path)?;
+    file.set_permissions(Permissions::from_mode(0o755))?;
+    let mut out = BufWriter::new(file);
+    // The arguments are quoted for bash and `exec -a` is not in POSIX sh
+    writeln!(out, "#!/usr/bin/env bash")?;
+    writeln!(
+      out,
+      "# Generated by tracexec. Execs are replayed in the order they happened."
+    )?;
+    writeln!(out, "# Failed execs are commented out.")?;
+    Ok(Self { out })
+  }
+
+  fn write_exec(&mut self, exec: &ExecEvent) -> color_eyre::Result<()> {
+    let (Ok(filename), Ok(argv)) = (exec.filename.as_ref(), exec.argv.as_ref()) else {
+      writeln!(
+        self.out,
+        "\n# pid {}: failed to read filename or argv",
+        exec.pid
+      )?;
+      return Ok(());
+    };
+    write!(self.out, "\n# pid {}", exec.pid)?;
+    if let Some(ppid) = exec.ppid {
+      write!(self.out, ", ppid {ppid}")?;
+    }
+    writeln!(self.out, ", comm {}", exec.comm)?;
+    if let Err(e) = exec.env_diff.as_ref() {
+      writeln!(
+        self.out,
+        "# failed to read envp: {e}, the environment is not reproduced"
+      )?;
+    }
+    if exec.result != 0 {
+      writeln!(
+        self.out,
+        "# failed with {} ({})",
+        exec.result,
+        nix::errno::Errno::from_raw(-exec.result as i32)
+      )?;
+      write!(self.out, "# ")?;
+    }
+    write!(self.out, "(cd {}", escape_str_for_bash!(&exec.cwd))?;
+    if let Ok(diff) = exec.env_diff.as_ref() {
+      for k in diff.removed.iter() {
+        write!(self.out, " && unset {}", escape_str_for_bash!(k))?;
+      }
+      for (k, v) in diff.added.iter().chain(diff.modified.iter()) {
+        write!(
+          self.out,
+          " && export {}={}",
+          escape_str_for_bash!(k),
+          escape_str_for_bash!(v)
+        )?;
+      }
+    }
+    write!(self.out, " && exec")?;
+    if let Some(arg0) = argv.first() {
+      if filename.as_os_str() != OsStr::new(arg0) {
+        write!(self.out, " -a {}", escape_str_for_bash!(arg0))?;
+      }
+    }
+    write!(self.out, " {}", escape_str_for_bash!(filename))?;
+    for arg in argv.iter().skip(1) {
+      write!(self.out, " {}", escape_str_for_bash!(arg))?;
+    }
+    writeln!(self.out, ")")?;
+    Ok(())
+  }
+}
+
+impl Exporter for ScriptExporter {
+  fn on_event(&mut self, event: &TracerEvent) -> color_eyre::Result<()> {
+    if let TracerEvent::Exec(exec) = event {
+      self.write_exec(exec)?;
+    }
+    Ok(())
+  }
+
+  fn finish(&mut self) -> color_eyre::Result<()> {
+    self.out.flush()?;
+    Ok(())
+  }
+}
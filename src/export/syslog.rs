@@ -0,0 +1,49 @@
+//! Send events to the local syslog daemon in RFC 3164 format.
+
+use std::os::unix::net::UnixDatagram;
+
+use nix::unistd::getpid;
+use tracing::warn;
+
+use crate::event::TracerEvent;
+
+use super::{event_summary, Exporter};
+
+const SYSLOG_SOCKET: &str = "/dev/log";
+/// LOG_USER
+const FACILITY: u8 = 1;
+
+pub struct SyslogExporter {
+  socket: UnixDatagram,
+}
+
+impl SyslogExporter {
+  pub fn new() -> color_eyre::Result<Self> {
+    let socket = UnixDatagram::unbound()?;
+    socket.connect(SYSLOG_SOCKET)?;
+    Ok(Self { socket })
+  }
+}
+
+impl Exporter for SyslogExporter {
+  fn on_event(&mut self, event: &TracerEvent) -> color_eyre::Result<()> {
+    let Some((severity, message)) = event_summary(event) else {
+      return Ok(());
+    };
+    // The timestamp and hostname are filled in by the syslog daemon for local messages.
+    let line = format!(
+      "<{}>tracexec[{}]: {}",
+      FACILITY * 8 + severity as u8,
+      getpid(),
+      message
+    );
+    if let Err(e) = self.socket.send(line.as_bytes()) {
+      warn!("Failed to send event to syslog: {e}");
+    }
+    Ok(())
+  }
+
+  fn finish(&mut self) -> color_eyre::Result<()> {
+    Ok(())
+  }
+}
@@ -0,0 +1,489 @@
+//! Filter expressions that events are matched against, e.g. `comm == "rustc" && argv ~ "--edition 2021"`.
+//!
+//! An expression compares the fields of an event to values, and combines the comparisons
+//! with `&&`, `||`, `!` and parentheses. Strings are double quoted and numbers are not.
+//!
+//! - `pid`, `ppid`, `uid` and `result` are numbers, compared with `==`, `!=`, `<`, `<=`, `>` and `>=`
+//! - `comm`, `filename`, `cwd`, `argv` and `env` are strings, compared with `==` and `!=`
+//!   or matched against a regex with `~` and `!~`
+//! - `argv` is the arguments joined by spaces, `env` matches if any of the `KEY=VALUE` entries does
+//!
+//! A comparison is false for the events that don't have the field, e.g. `filename` of a signal.
+//!
+//! The exclusion patterns of `--exclude-comm` and `--exclude-path` are also here.
+
+use std::{borrow::Cow, fmt::Display, path::Path, str::FromStr};
+
+use regex::Regex;
+use serde::Deserialize;
+use strum::{Display, EnumString};
+
+use crate::{event::TracerEvent, redact::glob_match};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct FilterExpr {
+  source: String,
+  root: Node,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+  And(Box<Node>, Box<Node>),
+  Or(Box<Node>, Box<Node>),
+  Not(Box<Node>),
+  Compare { field: Field, op: Op, value: Value },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "lowercase")]
+enum Field {
+  Pid,
+  Ppid,
+  Uid,
+  Result,
+  Comm,
+  Filename,
+  Cwd,
+  Argv,
+  Env,
+}
+
+impl Field {
+  fn is_numeric(self) -> bool {
+    matches!(self, Self::Pid | Self::Ppid | Self::Uid | Self::Result)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+  Eq,
+  Ne,
+  Match,
+  NotMatch,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+  Int(i64),
+  Str(String),
+  Regex(Regex),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+  Ident(String),
+  Str(String),
+  Int(i64),
+  Op(Op),
+  And,
+  Or,
+  Not,
+  LParen,
+  RParen,
+}
+
+impl Display for Token {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Ident(ident) => write!(f, "{ident}"),
+      Self::Str(s) => write!(f, "{s:?}"),
+      Self::Int(i) => write!(f, "{i}"),
+      Self::Op(op) => write!(
+        f,
+        "{}",
+        match op {
+          Op::Eq => "==",
+          Op::Ne => "!=",
+          Op::Match => "~",
+          Op::NotMatch => "!~",
+          Op::Lt => "<",
+          Op::Le => "<=",
+          Op::Gt => ">",
+          Op::Ge => ">=",
+        }
+      ),
+      Self::And => write!(f, "&&"),
+      Self::Or => write!(f, "||"),
+      Self::Not => write!(f, "!"),
+      Self::LParen => write!(f, "("),
+      Self::RParen => write!(f, ")"),
+    }
+  }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+  let mut tokens = vec![];
+  let mut chars = s.chars().peekable();
+  while let Some(c) = chars.next() {
+    let token = match c {
+      c if c.is_whitespace() => continue,
+      '(' => Token::LParen,
+      ')' => Token::RParen,
+      '"' => {
+        let mut string = String::new();
+        loop {
+          match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+              Some('n') => string.push('\n'),
+              Some('t') => string.push('\t'),
+              Some(c) => string.push(c),
+              None => return Err("Unterminated string".to_string()),
+            },
+            Some(c) => string.push(c),
+            None => return Err("Unterminated string".to_string()),
+          }
+        }
+        Token::Str(string)
+      }
+      '&' if chars.next_if_eq(&'&').is_some() => Token::And,
+      '|' if chars.next_if_eq(&'|').is_some() => Token::Or,
+      '=' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Eq),
+      '!' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ne),
+      '!' if chars.next_if_eq(&'~').is_some() => Token::Op(Op::NotMatch),
+      '!' => Token::Not,
+      '~' => Token::Op(Op::Match),
+      '<' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Le),
+      '<' => Token::Op(Op::Lt),
+      '>' if chars.next_if_eq(&'=').is_some() => Token::Op(Op::Ge),
+      '>' => Token::Op(Op::Gt),
+      c if c == '-' || c.is_ascii_digit() => {
+        let mut number = c.to_string();
+        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+          number.push(digit);
+        }
+        Token::Int(
+          number
+            .parse()
+            .map_err(|_| format!("Invalid number {number}"))?,
+        )
+      }
+      c if c.is_ascii_alphabetic() => {
+        let mut ident = c.to_string();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+          ident.push(c);
+        }
+        Token::Ident(ident)
+      }
+      c => return Err(format!("Unexpected character {c:?}")),
+    };
+    tokens.push(token);
+  }
+  Ok(tokens)
+}
+
+/// A recursive descent parser, `&&` binds tighter than `||`
+struct Parser {
+  tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+  fn expect_end(&mut self) -> Result<(), String> {
+    match self.tokens.next() {
+      None => Ok(()),
+      Some(token) => Err(format!("Unexpected {token}")),
+    }
+  }
+
+  fn or(&mut self) -> Result<Node, String> {
+    let mut node = self.and()?;
+    while self.tokens.next_if_eq(&Token::Or).is_some() {
+      node = Node::Or(Box::new(node), Box::new(self.and()?));
+    }
+    Ok(node)
+  }
+
+  fn and(&mut self) -> Result<Node, String> {
+    let mut node = self.unary()?;
+    while self.tokens.next_if_eq(&Token::And).is_some() {
+      node = Node::And(Box::new(node), Box::new(self.unary()?));
+    }
+    Ok(node)
+  }
+
+  fn unary(&mut self) -> Result<Node, String> {
+    match self.tokens.next() {
+      Some(Token::Not) => Ok(Node::Not(Box::new(self.unary()?))),
+      Some(Token::LParen) => {
+        let node = self.or()?;
+        match self.tokens.next() {
+          Some(Token::RParen) => Ok(node),
+          Some(token) => Err(format!("Expected ) but found {token}")),
+          None => Err("Expected )".to_string()),
+        }
+      }
+      Some(Token::Ident(ident)) => self.comparison(&ident),
+      Some(token) => Err(format!("Expected a field but found {token}")),
+      None => Err("Expected a field".to_string()),
+    }
+  }
+
+  fn comparison(&mut self, ident: &str) -> Result<Node, String> {
+    let field = Field::from_str(ident).map_err(|_| {
+      format!("Unknown field {ident}, expected one of pid, ppid, uid, result, comm, filename, cwd, argv and env")
+    })?;
+    let op = match self.tokens.next() {
+      Some(Token::Op(op)) => op,
+      Some(token) => {
+        return Err(format!(
+          "Expected an operator after {field} but found {token}"
+        ))
+      }
+      None => return Err(format!("Expected an operator after {field}")),
+    };
+    let value = match (self.tokens.next(), field.is_numeric()) {
+      (Some(Token::Int(i)), true) if !matches!(op, Op::Match | Op::NotMatch) => Value::Int(i),
+      (Some(Token::Int(_)), true) => {
+        return Err(format!(
+          "{field} is a number, it can't be matched against a regex"
+        ))
+      }
+      (Some(Token::Str(s)), false) if matches!(op, Op::Match | Op::NotMatch) => {
+        Value::Regex(Regex::new(&s).map_err(|e| {
+          // The last line is the most informative one of the multi-line regex errors
+          e.to_string().lines().last().unwrap_or_default().to_owned()
+        })?)
+      }
+      (Some(Token::Str(s)), false) if matches!(op, Op::Eq | Op::Ne) => Value::Str(s),
+      (Some(Token::Str(_)), false) => {
+        return Err(format!(
+          "{field} is a string, it can't be compared by order"
+        ))
+      }
+      (Some(token), true) => {
+        return Err(format!("Expected a number after {field} but found {token}"))
+      }
+      (Some(token), false) => {
+        return Err(format!("Expected a string after {field} but found {token}"))
+      }
+      (None, _) => return Err(format!("Expected a value after {field}")),
+    };
+    Ok(Node::Compare { field, op, value })
+  }
+}
+
+impl FromStr for FilterExpr {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parser = Parser {
+      tokens: tokenize(s)?.into_iter().peekable(),
+    };
+    let root = parser.or()?;
+    parser.expect_end()?;
+    Ok(Self {
+      source: s.to_owned(),
+      root,
+    })
+  }
+}
+
+impl TryFrom<String> for FilterExpr {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+impl FilterExpr {
+  /// `argv ~ regex`, or `argv !~ regex` if negated, which `--grep` and `--grep-v` are short for
+  pub fn argv_matches(regex: Regex, negated: bool) -> Self {
+    let op = if negated { Op::NotMatch } else { Op::Match };
+    Self {
+      source: format!("argv {} {:?}", Token::Op(op), regex.as_str()),
+      root: Node::Compare {
+        field: Field::Argv,
+        op,
+        value: Value::Regex(regex),
+      },
+    }
+  }
+
+  /// The expression that matches if both of them match
+  pub fn and(self, other: Self) -> Self {
+    Self {
+      source: format!("({}) && ({})", self.source, other.source),
+      root: Node::And(Box::new(self.root), Box::new(other.root)),
+    }
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.source
+  }
+
+  pub fn matches(&self, event: &TracerEvent) -> bool {
+    eval(&self.root, event)
+  }
+}
+
+fn eval(node: &Node, event: &TracerEvent) -> bool {
+  match node {
+    Node::And(lhs, rhs) => eval(lhs, event) && eval(rhs, event),
+    Node::Or(lhs, rhs) => eval(lhs, event) || eval(rhs, event),
+    Node::Not(node) => !eval(node, event),
+    Node::Compare { field, op, value } => compare(*field, *op, value, event),
+  }
+}
+
+fn number_of(field: Field, event: &TracerEvent) -> Option<i64> {
+  Some(match (field, event) {
+    (Field::Pid, event) => event.pid()?.as_raw().into(),
+    (Field::Ppid, TracerEvent::Exec(exec)) => exec.ppid?.as_raw().into(),
+    (Field::Ppid, TracerEvent::ProcessExit { ppid, .. }) => ppid.as_ref()?.as_raw().into(),
+    (Field::Ppid, TracerEvent::NewChild { ppid, .. }) => ppid.as_raw().into(),
+    (Field::Uid, TracerEvent::Exec(exec)) => exec.uid?.as_raw().into(),
+    (Field::Result, TracerEvent::Exec(exec)) => exec.result,
+    _ => return None,
+  })
+}
+
+fn strings_of(field: Field, event: &TracerEvent) -> Option<Vec<Cow<str>>> {
+  Some(match (field, event) {
+    (Field::Comm, TracerEvent::Exec(exec)) => vec![exec.comm.as_str().into()],
+    (
+      Field::Comm,
+      TracerEvent::ProcessExit { comm, .. }
+      | TracerEvent::Signal { comm, .. }
+      | TracerEvent::NewChild { pcomm: comm, .. },
+    ) => vec![comm.as_str().into()],
+    (Field::Filename, TracerEvent::Exec(exec)) => {
+      vec![exec.filename.as_ref().ok()?.to_string_lossy()]
+    }
+    (Field::Cwd, TracerEvent::Exec(exec)) => vec![exec.cwd.to_string_lossy()],
+    (Field::Argv, TracerEvent::Exec(exec)) => {
+      vec![exec.argv.as_ref().as_ref().ok()?.join(" ").into()]
+    }
+    (Field::Env, TracerEvent::Exec(exec)) => exec
+      .envp
+      .as_ref()
+      .as_ref()
+      .ok()?
+      .iter()
+      .map(|entry| entry.as_str().into())
+      .collect(),
+    _ => return None,
+  })
+}
+
+fn compare(field: Field, op: Op, value: &Value, event: &TracerEvent) -> bool {
+  match value {
+    Value::Int(value) => {
+      let Some(number) = number_of(field, event) else {
+        return false;
+      };
+      match op {
+        Op::Eq => number == *value,
+        Op::Ne => number != *value,
+        Op::Lt => number < *value,
+        Op::Le => number <= *value,
+        Op::Gt => number > *value,
+        Op::Ge => number >= *value,
+        Op::Match | Op::NotMatch => unreachable!("rejected by the parser"),
+      }
+    }
+    Value::Str(value) => {
+      let Some(strings) = strings_of(field, event) else {
+        return false;
+      };
+      let any = strings.iter().any(|s| s == value);
+      if op == Op::Ne {
+        !any
+      } else {
+        any
+      }
+    }
+    Value::Regex(regex) => {
+      let Some(strings) = strings_of(field, event) else {
+        return false;
+      };
+      let any = strings.iter().any(|s| regex.is_match(s));
+      if op == Op::NotMatch {
+        !any
+      } else {
+        any
+      }
+    }
+  }
+}
+
+/// A glob pattern with `*` and `?`, or a regex if it starts with `re:`, e.g. `re:^(sed|uname)$`.
+/// Globs match the whole text and regexes match anywhere in it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ExcludePattern {
+  Glob(String),
+  Regex(Regex),
+}
+
+impl FromStr for ExcludePattern {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.strip_prefix("re:") {
+      Some(regex) => Regex::new(regex)
+        .map(Self::Regex)
+        .map_err(|e| e.to_string().lines().last().unwrap_or_default().to_owned()),
+      None => Ok(Self::Glob(s.to_owned())),
+    }
+  }
+}
+
+impl TryFrom<String> for ExcludePattern {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+impl ExcludePattern {
+  fn is_match(&self, text: &str) -> bool {
+    match self {
+      Self::Glob(glob) => glob_match(glob.as_bytes(), text.as_bytes()),
+      Self::Regex(regex) => regex.is_match(text),
+    }
+  }
+}
+
+/// Decides which exec events are kept, the other events are always kept
+#[derive(Debug, Clone, Default)]
+pub struct ExecFilter {
+  pub expr: Option<FilterExpr>,
+  /// Matched against the file name of the executed program, e.g. `sed`
+  pub exclude_comm: Vec<ExcludePattern>,
+  /// Matched against the path of the executed program as it is given to exec
+  pub exclude_path: Vec<ExcludePattern>,
+}
+
+impl ExecFilter {
+  pub fn keeps(&self, event: &TracerEvent) -> bool {
+    let TracerEvent::Exec(exec) = event else {
+      return true;
+    };
+    if let Ok(filename) = exec.filename.as_ref() {
+      if self.excludes(filename) {
+        return false;
+      }
+    }
+    self.expr.as_ref().map_or(true, |expr| expr.matches(event))
+  }
+
+  fn excludes(&self, filename: &Path) -> bool {
+    let comm = filename
+      .file_name()
+      .map(|name| name.to_string_lossy())
+      .unwrap_or_default();
+    let path = filename.to_string_lossy();
+    self.exclude_comm.iter().any(|p| p.is_match(&comm))
+      || self.exclude_path.iter().any(|p| p.is_match(&path))
+  }
+}
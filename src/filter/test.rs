@@ -0,0 +1,150 @@
+use std::time::SystemTime;
+
+use nix::{sys::signal::Signal, unistd::Pid};
+use regex::Regex;
+
+use crate::event::TracerEvent;
+
+use super::{ExcludePattern, FilterExpr, Node, Op, Value};
+
+fn signal(pid: i32, comm: &str) -> TracerEvent {
+  TracerEvent::Signal {
+    pid: Pid::from_raw(pid),
+    comm: comm.to_owned(),
+    signal: Signal::SIGINT,
+    timestamp: SystemTime::UNIX_EPOCH,
+  }
+}
+
+fn parse_err(s: &str) -> String {
+  s.parse::<FilterExpr>().unwrap_err()
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+  let expr: FilterExpr = "pid == 1 || pid == 2 && pid == 3".parse().unwrap();
+  assert!(matches!(&expr.root, Node::Or(_, rhs) if matches!(**rhs, Node::And(..))));
+  assert!(expr.matches(&signal(1, "sh")));
+  assert!(!expr.matches(&signal(2, "sh")));
+  let expr: FilterExpr = "(pid == 1 || pid == 2) && pid == 3".parse().unwrap();
+  assert!(matches!(&expr.root, Node::And(lhs, _) if matches!(**lhs, Node::Or(..))));
+  assert!(!expr.matches(&signal(1, "sh")));
+}
+
+#[test]
+fn not_binds_tighter_than_and() {
+  let expr: FilterExpr = "!pid == 1 && comm == \"sh\"".parse().unwrap();
+  assert!(matches!(&expr.root, Node::And(lhs, _) if matches!(**lhs, Node::Not(_))));
+  assert!(expr.matches(&signal(2, "sh")));
+  assert!(!expr.matches(&signal(1, "sh")));
+}
+
+#[test]
+fn not_match_is_one_operator() {
+  let expr: FilterExpr = "comm !~ \"^ba\"".parse().unwrap();
+  assert!(matches!(
+    expr.root,
+    Node::Compare {
+      op: Op::NotMatch,
+      ..
+    }
+  ));
+  assert!(!expr.matches(&signal(1, "bash")));
+  assert!(expr.matches(&signal(1, "zsh")));
+  // `!` before a field is a negation rather than `!~`
+  let expr: FilterExpr = "!comm ~ \"^ba\"".parse().unwrap();
+  assert!(matches!(expr.root, Node::Not(_)));
+  assert!(expr.matches(&signal(1, "zsh")));
+}
+
+#[test]
+fn missing_fields_never_match() {
+  let expr: FilterExpr = "result == 0".parse().unwrap();
+  assert!(!expr.matches(&signal(1, "sh")));
+  let expr: FilterExpr = "result != 0".parse().unwrap();
+  assert!(!expr.matches(&signal(1, "sh")));
+}
+
+#[test]
+fn quoted_strings_are_unescaped() {
+  let expr: FilterExpr = r#"comm == "a \"b\" \\ c\td\n""#.parse().unwrap();
+  let Node::Compare {
+    value: Value::Str(value),
+    ..
+  } = &expr.root
+  else {
+    panic!("not a string comparison: {:?}", expr.root);
+  };
+  assert_eq!(value, "a \"b\" \\ c\td\n");
+  assert!(expr.as_str().starts_with("comm =="));
+}
+
+#[test]
+fn negative_numbers() {
+  let expr: FilterExpr = "pid > -1".parse().unwrap();
+  assert!(expr.matches(&signal(1, "sh")));
+  assert_eq!(parse_err("pid > -"), "Invalid number -");
+}
+
+#[test]
+fn error_messages() {
+  assert_eq!(parse_err("comm == \"sh"), "Unterminated string");
+  assert_eq!(parse_err("comm == \"sh\\"), "Unterminated string");
+  assert_eq!(parse_err("comm == $sh"), "Unexpected character '$'");
+  assert_eq!(parse_err("pid = 1"), "Unexpected character '='");
+  assert_eq!(
+    parse_err("name == \"sh\""),
+    "Unknown field name, expected one of pid, ppid, uid, result, comm, filename, cwd, argv and env"
+  );
+  assert_eq!(parse_err("(pid == 1"), "Expected )");
+  assert_eq!(parse_err("(pid == 1 pid"), "Expected ) but found pid");
+  assert_eq!(parse_err("pid == 1)"), "Unexpected )");
+  assert_eq!(parse_err(""), "Expected a field");
+  assert_eq!(parse_err("&& pid == 1"), "Expected a field but found &&");
+  assert_eq!(parse_err("pid"), "Expected an operator after pid");
+  assert_eq!(
+    parse_err("pid pid"),
+    "Expected an operator after pid but found pid"
+  );
+  assert_eq!(parse_err("pid =="), "Expected a value after pid");
+  assert_eq!(
+    parse_err("pid ~ 1"),
+    "pid is a number, it can't be matched against a regex"
+  );
+  assert_eq!(
+    parse_err("pid == \"1\""),
+    "Expected a number after pid but found \"1\""
+  );
+  assert_eq!(
+    parse_err("comm < \"sh\""),
+    "comm is a string, it can't be compared by order"
+  );
+  assert_eq!(
+    parse_err("comm == 1"),
+    "Expected a string after comm but found 1"
+  );
+  assert!(parse_err("comm ~ \"(\"").contains("unclosed group"));
+}
+
+#[test]
+fn argv_matches_and_and() {
+  let expr = "comm == \"sh\""
+    .parse::<FilterExpr>()
+    .unwrap()
+    .and(FilterExpr::argv_matches(Regex::new("a\"b").unwrap(), true));
+  assert_eq!(expr.as_str(), r#"(comm == "sh") && (argv !~ "a\"b")"#);
+  // The source of the combined expression parses back to the same expression
+  assert!(expr.as_str().parse::<FilterExpr>().is_ok());
+}
+
+#[test]
+fn exclude_patterns() {
+  let glob: ExcludePattern = "s?d*".parse().unwrap();
+  assert!(glob.is_match("sed"));
+  assert!(glob.is_match("sudo"));
+  assert!(!glob.is_match("awk"));
+  let regex: ExcludePattern = "re:^(sed|awk)$".parse().unwrap();
+  assert!(regex.is_match("awk"));
+  assert!(!regex.is_match("gawk"));
+  assert!("re:(".parse::<ExcludePattern>().is_err());
+}
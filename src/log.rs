@@ -34,6 +34,11 @@ lazy_static! {
     std::env::var(format!("{}_DATA", PROJECT_NAME.clone()))
       .ok()
       .map(PathBuf::from);
+  // Not TRACEXEC_CONFIG, which is the path of the config file itself
+  pub static ref CONFIG_FOLDER: Option<PathBuf> =
+    std::env::var(format!("{}_CONFIG_DIR", PROJECT_NAME.clone()))
+      .ok()
+      .map(PathBuf::from);
   pub static ref LOG_ENV: String = format!("{}_LOGLEVEL", PROJECT_NAME.clone());
   pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
 }
@@ -53,6 +58,17 @@ pub fn get_data_dir() -> PathBuf {
   directory
 }
 
+pub fn get_config_dir() -> PathBuf {
+  let directory = if let Some(s) = CONFIG_FOLDER.clone() {
+    s
+  } else if let Some(proj_dirs) = project_directory() {
+    proj_dirs.config_dir().to_path_buf()
+  } else {
+    PathBuf::from(".").join(".config")
+  };
+  directory
+}
+
 pub fn initialize_logging() -> Result<()> {
   let directory = get_data_dir();
   std::fs::create_dir_all(directory.clone())?;
@@ -2,63 +2,134 @@ mod action;
 mod arch;
 mod cli;
 mod cmdbuilder;
+mod dry_run;
 mod event;
+mod export;
+mod filter;
 mod log;
+mod output;
 mod printer;
 mod proc;
 mod pty;
+mod record;
+mod redact;
 #[cfg(feature = "seccomp-bpf")]
 mod seccomp;
+mod term;
 mod tracer;
 mod tui;
 
 use std::{
-  io::{stderr, stdout, BufWriter},
+  io::{stderr, stdout, IsTerminal},
   os::unix::ffi::OsStrExt,
   process,
   sync::Arc,
 };
 
 use atoi::atoi;
-use clap::Parser;
 use cli::Cli;
-use color_eyre::eyre::{bail, OptionExt};
+use color_eyre::eyre::{bail, eyre, OptionExt};
 
-use nix::unistd::{Uid, User};
+use nix::{
+  sys::signal::Signal,
+  unistd::{Uid, User},
+};
 use tokio::sync::mpsc;
 
 use crate::{
-  cli::{args::LogModeArgs, options::Color, CliCommand},
-  event::TracerEvent,
+  cli::{
+    args::{LimitArgs, LogModeArgs, ModifierArgs, TracerEventArgs},
+    config::{Config, KillOnExit},
+    options::Color,
+    CliCommand,
+  },
+  dry_run::DryRun,
+  event::{FilterableTracerEvent, TracerEventKind},
+  export::{Exporter, FormatterExporter},
   log::initialize_panic_handler,
-  printer::PrinterOut,
+  output::{MultiWriter, OutputFormat, OutputSpec, OutputTarget, RotationPolicy},
+  printer::{set_palette, Palette, PrinterArgs, PrinterOut},
   proc::BaselineInfo,
-  pty::{native_pty_system, PtySize, PtySystem},
+  pty::{native_pty_system, PtySystem, StdioRelay},
+  record::{LogReplay, TraceReader, TraceRecorder},
+  redact::{set_redactor, Redactor},
+  term::term_caps,
   tracer::TracerMode,
-  tui::app::App,
+  tui::{
+    app::App,
+    keymap::Keymap,
+    state::TuiState,
+    tabs::{NewTrace, TraceTarget},
+    theme::{set_theme, Theme},
+  },
 };
 
+#[cfg(feature = "seccomp-bpf")]
+use crate::cli::options::SeccompBpf;
+
 #[tokio::main(worker_threads = 2)]
 async fn main() -> color_eyre::Result<()> {
-  let mut cli = Cli::parse();
-  if cli.color == Color::Auto && std::env::var_os("NO_COLOR").is_some() {
-    // Respect NO_COLOR if --color=auto
-    cli.color = Color::Never;
+  let (cli, config) = Cli::parse_with_aliases()?;
+  // Only the subcommands that trace can be checked without running anything
+  if cli.dry_run
+    && !matches!(
+      cli.cmd,
+      CliCommand::Log { .. } | CliCommand::Tui { .. } | CliCommand::Record { .. }
+    )
+  {
+    bail!(
+      "--dry-run is not supported for {}",
+      <&'static str>::from(&cli.cmd)
+    );
   }
-  if cli.color == Color::Always {
-    owo_colors::control::set_should_colorize(true);
-    color_eyre::install()?;
-  } else if cli.color == Color::Never {
-    owo_colors::control::set_should_colorize(false);
-  } else {
-    color_eyre::install()?;
+  match cli.cmd {
+    CliCommand::Completions { shell } => {
+      Cli::print_completions(shell);
+      return Ok(());
+    }
+    CliCommand::GenerateConfig => {
+      print!("{}", cli::config::DEFAULT_CONFIG);
+      return Ok(());
+    }
+    CliCommand::Schema {
+      format,
+      output_format,
+    } => {
+      print!("{}", export::schema::render_schema(format, output_format)?);
+      return Ok(());
+    }
+    _ => {}
   }
+  // The config is needed to decide the color, errors are reported once color_eyre is installed
+  let config = match config {
+    Some(config) => Ok(config),
+    None => Config::load(cli.config.as_deref(), cli.profile.as_deref()),
+  };
+  let color = cli
+    .color
+    .or(config.as_ref().ok().and_then(|config| config.color))
+    .unwrap_or(Color::Auto);
+  // This is decided again for log mode once we know where the output goes
+  let colorize = color.should_colorize(stderr().is_terminal());
+  owo_colors::control::set_should_colorize(colorize);
+  // Errors get their sections, e.g. suggestions, whether or not they are colored
+  color_eyre::config::HookBuilder::default()
+    .theme(if colorize {
+      color_eyre::config::Theme::dark()
+    } else {
+      color_eyre::config::Theme::new()
+    })
+    .install()?;
   initialize_panic_handler();
   log::initialize_logging()?;
   log::trace!("Commandline args: {:?}", cli);
+  let config = config?;
   if let Some(cwd) = cli.cwd {
     std::env::set_current_dir(cwd)?;
   }
+  let login = cli.login;
+  let dry_run = cli.dry_run;
+  let config_path = cli.config.clone().unwrap_or_else(Config::default_path);
   let user = if let Some(user) = cli.user.as_deref() {
     if !Uid::effective().is_root() {
       bail!("--user option is only available when running tracexec as root!");
@@ -79,60 +150,362 @@ async fn main() -> color_eyre::Result<()> {
   match cli.cmd {
     CliCommand::Log {
       cmd,
-      tracing_args,
-      modifier_args,
-      tracer_event_args,
-      output,
+      cmd_file: _,
+      mut tracing_args,
+      mut modifier_args,
+      mut tracer_event_args,
+      exporter_args,
+      limit_args,
+      mut output,
+      output_format,
+      rotate_size,
+      rotate_interval,
+      rotate_keep,
+      compress,
+      pty,
+      pty_size,
+      from_file,
     } => {
+      let output_format = output_format
+        .or(config.log.output_format)
+        .unwrap_or_default();
+      // Before the config is merged, so that its template doesn't apply
+      match output_format {
+        OutputFormat::Compact => tracing_args.compact = true,
+        OutputFormat::Print0 => tracing_args.print0 = true,
+        _ => {}
+      }
+      tracing_args.merge_config(&config.log);
+      if output.is_empty() {
+        output.clone_from(&config.log.output);
+      }
+      modifier_args.merge_config(&config.modifier);
+      tracer_event_args.merge_config(&config.events);
+      tracer_event_args.merge_grep(tracing_args.grep.take(), tracing_args.grep_v.take());
       let modifier_args = modifier_args.processed();
-      let output: Box<PrinterOut> = match output {
-        None => Box::new(stderr()),
-        Some(ref x) if x.as_os_str() == "-" => Box::new(stdout()),
-        Some(path) => {
-          let file = std::fs::OpenOptions::new()
-            .create(true)
-            .truncate(true)
-            .write(true)
-            .open(path)?;
-          if cli.color != Color::Always {
-            // Disable color by default when output is file
-            owo_colors::control::set_should_colorize(false);
+      set_redactor(Redactor::from_cli(&modifier_args, &config.redact));
+      set_palette(Palette::from_config(&config.log.palette));
+      let rotation = RotationPolicy {
+        max_size: rotate_size,
+        max_age: rotate_interval,
+        keep: rotate_keep,
+      };
+      if rotation.is_enabled() && !output.iter().any(OutputSpec::is_regular_file) {
+        bail!("Log rotation is only available when the output is a file!");
+      }
+      if dry_run {
+        let dry_run = DryRun::new("log")
+          .setting("config", &config_path)
+          .setting("profile", &cli.profile)
+          .setting("cmd", &cmd)
+          .setting("output format", output_format)
+          .setting("outputs", &output)
+          .setting("rotation", rotation)
+          .setting("log", &tracing_args)
+          .setting("modifier", &modifier_args)
+          .setting("events", &tracer_event_args)
+          .setting("limits", &limit_args)
+          .setting("exporters", &exporter_args)
+          .check(
+            tracer_event_args
+              .filter()
+              .map(|filter| format!("the events are filtered to {filter:?}")),
+          );
+        return match from_file {
+          Some(path) => dry_run.check(
+            TraceReader::open(&path).map(|_| format!("{} is a recorded trace", path.display())),
+          ),
+          None => dry_run.check_tracing(&cmd),
+        }
+        .finish();
+      }
+      let mut exporters = export::exporters_from_cli(&exporter_args)?;
+      if output.is_empty() && output_format.formatter().is_some() {
+        output.push(OutputSpec {
+          format: Some(output_format),
+          target: OutputTarget::Stdout,
+        });
+      }
+      // The human readable formats are written by the printer, which has a single format
+      let mut printer_format = None;
+      let mut text_outputs = vec![];
+      for spec in output.iter() {
+        let format = spec.format.unwrap_or(output_format);
+        match format.formatter() {
+          Some(formatter) => exporters.push(Box::new(FormatterExporter::new(
+            formatter,
+            spec.open(rotation, compress)?,
+          )?)),
+          None => {
+            if printer_format.is_some_and(|printer_format| printer_format != format) {
+              bail!("Only one of the human, compact and print0 formats can be used at a time!");
+            }
+            printer_format = Some(format);
+            text_outputs.push(spec);
           }
-          Box::new(BufWriter::new(file))
+        }
+      }
+      match printer_format {
+        Some(OutputFormat::Compact) => tracing_args.compact = true,
+        Some(OutputFormat::Print0) => tracing_args.print0 = true,
+        _ => {}
+      }
+      let output: Box<PrinterOut> = if text_outputs.is_empty() {
+        Box::new(stderr())
+      } else {
+        // Disable color by default when any of the outputs is a file
+        owo_colors::control::set_should_colorize(
+          text_outputs
+            .iter()
+            .all(|spec| color.should_colorize(spec.is_stdout() && stdout().is_terminal())),
+        );
+        let mut writers = text_outputs
+          .iter()
+          .map(|spec| spec.open(rotation, compress))
+          .collect::<Result<Vec<_>, _>>()?;
+        if writers.len() == 1 {
+          writers.pop().unwrap()
+        } else {
+          Box::new(MultiWriter::new(writers))
         }
       };
-      let baseline = BaselineInfo::new()?;
+      if let Some(path) = from_file {
+        let reader = TraceReader::open(&path)?;
+        let filter = tracer_event_args.filter()?;
+        let exec_filter = tracer_event_args.exec_filter();
+        let mut replay = LogReplay::new(
+          PrinterArgs::from_cli(&tracing_args, &modifier_args),
+          reader.header.clone(),
+          filter,
+          output,
+        )
+        .with_exec_filter(exec_filter.clone());
+        // Like the tracer does in log mode, the exporters also get the lifetime of the processes
+        let export_filter = filter
+          | TracerEventKind::TraceeExit
+          | TracerEventKind::ProcessExit
+          | TracerEventKind::Signal;
+        for event in reader {
+          let event = event?;
+          replay.on_event(&event)?;
+          if !exec_filter.keeps(&event) {
+            continue;
+          }
+          if let Some(event) = FilterableTracerEvent::from(event).filter_and_take(export_filter) {
+            for exporter in exporters.iter_mut() {
+              exporter.on_event(&event)?;
+            }
+          }
+        }
+        replay.finish()?;
+        for exporter in exporters.iter_mut() {
+          exporter.finish()?;
+        }
+        return Ok(());
+      }
+      let (baseline, tracer_mode, pty_master) = if pty {
+        let pair = native_pty_system().openpty(pty_size.unwrap_or_else(pty::terminal_pty_size))?;
+        (
+          BaselineInfo::with_pts(&pair.slave)?,
+          TracerMode::Log(Some(pair.slave)),
+          Some(pair.master),
+        )
+      } else {
+        (BaselineInfo::new()?, TracerMode::Log(None), None)
+      };
       let (tracer_tx, mut tracer_rx) = mpsc::unbounded_channel();
-      let tracer = Arc::new(tracer::Tracer::new(
-        TracerMode::Log,
-        tracing_args,
-        modifier_args,
-        tracer_event_args,
-        baseline,
-        tracer_tx,
-        user,
-      )?);
-      let tracer_thread = tracer.spawn(cmd, Some(output))?;
-      tracer_thread.join().unwrap()?;
-      loop {
-        if let Some(TracerEvent::TraceeExit { exit_code, .. }) = tracer_rx.recv().await {
-          process::exit(exit_code);
+      let tracer = Arc::new(
+        tracer::Tracer::new(
+          tracer_mode,
+          tracing_args,
+          modifier_args,
+          tracer_event_args,
+          baseline,
+          tracer_tx,
+          user,
+        )?
+        .with_login(login)
+        .with_timeout(limit_args.timeout())
+        .with_max_events(limit_args.max_events())?,
+      );
+      let relay = pty_master
+        .as_ref()
+        .map(|master| StdioRelay::start(master, pty_size.is_none()))
+        .transpose()?;
+      let tracer_thread = tracer.clone().spawn(cmd, Some(output))?;
+      let tracer_done = wait_for_tracer(tracer, tracer_thread, limit_args);
+      // Failing exporters are reported and dropped, the command still runs to completion
+      // and tracexec exits with its status
+      while let Some(event) = tracer_rx.recv().await {
+        exporters.retain_mut(|exporter| match exporter.on_event(&event) {
+          Ok(()) => true,
+          Err(e) => {
+            eprintln!("tracexec: failed to export events, stopped exporting to it: {e}");
+            false
+          }
+        });
+      }
+      for exporter in exporters.iter_mut() {
+        if let Err(e) = exporter.finish() {
+          eprintln!("tracexec: failed to finish exporting events: {e}");
         }
       }
+      // Compressed outputs are finished when dropped, which doesn't happen on process::exit
+      drop(exporters);
+      let root_exit_code = tracer_done.await??;
+      if let Some(relay) = relay {
+        relay.finish();
+      }
+      // Exit with the same status as the root child, 128 + the signal number if it is killed
+      if let Some(exit_code) = root_exit_code {
+        process::exit(exit_code);
+      }
+    }
+    CliCommand::Record {
+      cmd,
+      cmd_file: _,
+      mut modifier_args,
+      limit_args,
+      output,
+    } => {
+      modifier_args.merge_config(&config.modifier);
+      let modifier_args = modifier_args.processed();
+      set_redactor(Redactor::from_cli(&modifier_args, &config.redact));
+      if dry_run {
+        return DryRun::new("record")
+          .setting("config", &config_path)
+          .setting("profile", &cli.profile)
+          .setting("cmd", &cmd)
+          .setting("output", &output)
+          .setting("modifier", &modifier_args)
+          .setting("limits", &limit_args)
+          .check_tracing(&cmd)
+          .finish();
+      }
+      let baseline = BaselineInfo::new()?;
+      let mut recorder = TraceRecorder::create(&output, cmd.clone(), baseline.clone())?;
+      let (tracer_tx, mut tracer_rx) = mpsc::unbounded_channel();
+      let tracer = Arc::new(
+        tracer::Tracer::new(
+          TracerMode::Log(None),
+          LogModeArgs::default(),
+          modifier_args,
+          // The filters are applied when the trace is analyzed
+          TracerEventArgs {
+            show_all_events: true,
+            ..Default::default()
+          },
+          baseline,
+          tracer_tx,
+          user,
+        )?
+        .with_login(login)
+        .with_timeout(limit_args.timeout())
+        .with_max_events(limit_args.max_events())?,
+      );
+      // Nothing is printed while recording
+      let tracer_thread = tracer.clone().spawn(cmd, None)?;
+      let tracer_done = wait_for_tracer(tracer, tracer_thread, limit_args);
+      while let Some(event) = tracer_rx.recv().await {
+        recorder.on_event(&event)?;
+      }
+      recorder.finish()?;
+      drop(recorder);
+      if let Some(exit_code) = tracer_done.await?? {
+        process::exit(exit_code);
+      }
     }
     CliCommand::Tui {
       cmd,
-      modifier_args,
-      tracer_event_args,
+      cmd_file: _,
+      mut modifier_args,
+      mut tracer_event_args,
+      limit_args,
       tty,
+      no_tty,
       terminate_on_exit,
       active_pane,
       kill_on_exit,
+      kill_grace_period,
       layout,
       follow,
+      no_follow,
       frame_rate,
+      no_mouse,
+      mouse,
+      reset_state,
+      clipboard,
+      pty_size,
+      stdin,
     } => {
+      modifier_args.merge_config(&config.modifier);
+      tracer_event_args.merge_config(&config.events);
       let modifier_args = modifier_args.processed();
+      // A pseudo terminal from the config is only allocated when a command is given
+      let tty = tty || (config.tui.tty && !no_tty && !cmd.is_empty());
+      // The signal sent to the root child when the TUI exits
+      let (exit_signal, kill_grace_period) = if terminate_on_exit {
+        (Some(Signal::SIGTERM), None)
+      } else if kill_on_exit.is_some() {
+        (
+          kill_on_exit,
+          kill_grace_period.or(config.tui.kill_grace_period.map(Into::into)),
+        )
+      } else if config.tui.terminate_on_exit {
+        (Some(Signal::SIGTERM), None)
+      } else {
+        (
+          config.tui.kill_on_exit.and_then(KillOnExit::signal),
+          config.tui.kill_grace_period.map(Into::into),
+        )
+      };
+      let frame_rate = frame_rate
+        .or(config.tui.frame_rate.map(Into::into))
+        .unwrap_or(60.0);
+      set_redactor(Redactor::from_cli(&modifier_args, &config.redact));
+      set_theme(Theme::from_config(
+        &config.tui.theme,
+        color.should_colorize(true),
+      )?);
+      if dry_run {
+        return DryRun::new("tui")
+          .setting("config", &config_path)
+          .setting("profile", &cli.profile)
+          .setting("cmd", &cmd)
+          .setting("tty", tty)
+          .setting("stdin", &stdin)
+          .setting("exit signal", exit_signal)
+          .setting("kill grace period", kill_grace_period)
+          .setting("frame rate", frame_rate)
+          .setting("modifier", &modifier_args)
+          .setting("events", &tracer_event_args)
+          .setting("limits", &limit_args)
+          .check(if term_caps().dumb {
+            Err(eyre!("the TUI can't run on a dumb terminal"))
+          } else {
+            Ok("the terminal can show the TUI".to_string())
+          })
+          .check(
+            stdin
+              .as_ref()
+              .map_or(Ok(()), |stdin| stdin.check(tty))
+              .map(|_| "the stdin of the command can be used".to_string()),
+          )
+          .check(
+            tracer_event_args
+              .filter()
+              .map(|filter| format!("the events are filtered to {filter:?}")),
+          )
+          .check_tracing(&cmd)
+          .finish();
+      }
+      if term_caps().dumb {
+        bail!("The TUI can't run on a dumb terminal, use the log mode instead!");
+      }
+      let stdin_fd = match stdin.as_ref() {
+        Some(stdin) => stdin.open(tty)?,
+        None => None,
+      };
       // Disable owo-colors when running TUI
       owo_colors::control::set_should_colorize(false);
       log::debug!(
@@ -141,12 +514,7 @@ async fn main() -> color_eyre::Result<()> {
       );
       let (baseline, tracer_mode, pty_master) = if tty {
         let pty_system = native_pty_system();
-        let pair = pty_system.openpty(PtySize {
-          rows: 24,
-          cols: 80,
-          pixel_width: 0,
-          pixel_height: 0,
-        })?;
+        let pair = pty_system.openpty(pty_size.unwrap_or_default())?;
         (
           BaselineInfo::with_pts(&pair.slave)?,
           TracerMode::Tui(Some(pair.slave)),
@@ -155,15 +523,10 @@ async fn main() -> color_eyre::Result<()> {
       } else {
         (BaselineInfo::new()?, TracerMode::Tui(None), None)
       };
-      let tracing_args = LogModeArgs {
-        show_cmdline: false, // We handle cmdline in TUI
-        show_argv: true,
-        show_interpreter: true,
-        more_colors: false,
-        less_colors: false,
-        diff_env: true,
-        ..Default::default()
-      };
+      if reset_state {
+        TuiState::reset()?;
+      }
+      let tracing_args = tui_tracing_args();
       let mut app = App::new(
         &tracing_args,
         &modifier_args,
@@ -171,21 +534,92 @@ async fn main() -> color_eyre::Result<()> {
         pty_master,
         active_pane,
         layout,
-        follow,
-      )?;
+        (follow || no_follow).then_some(follow),
+        Keymap::new(config.tui.keymap, &config.tui.keys),
+        tracer_event_args.filter()?,
+        clipboard.or(config.tui.clipboard).unwrap_or_default(),
+        &config.tui,
+      )?
+      .with_fixed_pty_size(pty_size.is_some());
+      let breakpoints = app.breakpoints.clone();
+      app.trace_spawner = Some({
+        let breakpoints = breakpoints.clone();
+        let modifier_args = modifier_args.clone();
+        let tracer_event_args = tracer_event_args.clone();
+        let timeout = limit_args.timeout();
+        let max_events = limit_args.max_events();
+        let user = user.clone();
+        Box::new(move |target| {
+          let baseline = BaselineInfo::new()?;
+          let (tracer_tx, tracer_rx) = mpsc::unbounded_channel();
+          let modifier_args = match target {
+            // The seccomp filter can only be installed by the tracee before it execs
+            #[cfg(feature = "seccomp-bpf")]
+            TraceTarget::Process(_) => ModifierArgs {
+              seccomp_bpf: SeccompBpf::Off,
+              ..modifier_args.clone()
+            },
+            _ => modifier_args.clone(),
+          };
+          let tracer = Arc::new(
+            tracer::Tracer::new(
+              TracerMode::Tui(None),
+              tui_tracing_args(),
+              modifier_args,
+              tracer_event_args.clone(),
+              baseline.clone(),
+              tracer_tx,
+              user.clone(),
+            )?
+            .with_breakpoints(breakpoints.clone())
+            .with_login(login)
+            .with_timeout(timeout)
+            .with_max_events(max_events)?,
+          );
+          let pause = tracer.pause_handle();
+          let thread = match target {
+            TraceTarget::Command(cmd) => tracer.clone().spawn(cmd, None)?,
+            TraceTarget::Process(pid) => tracer.clone().spawn_attach(pid, None)?,
+          };
+          Ok(NewTrace {
+            baseline,
+            tracer_rx,
+            pause,
+            thread,
+            tracer,
+          })
+        })
+      });
       let (tracer_tx, tracer_rx) = mpsc::unbounded_channel();
-      let tracer = Arc::new(tracer::Tracer::new(
-        tracer_mode,
-        tracing_args,
-        modifier_args,
-        tracer_event_args,
-        baseline,
-        tracer_tx,
-        user,
-      )?);
-      let tracer_thread: std::thread::JoinHandle<Result<(), color_eyre::eyre::Error>> =
-        tracer.spawn(cmd, None)?;
-      let mut tui = tui::Tui::new()?.frame_rate(frame_rate);
+      let root_tracer = if cmd.is_empty() {
+        // The process to attach to is picked in the TUI, which traces it like a new tab
+        app.pick_process();
+        None
+      } else {
+        let tracer = Arc::new(
+          tracer::Tracer::new(
+            tracer_mode,
+            tracing_args,
+            modifier_args,
+            tracer_event_args,
+            baseline,
+            tracer_tx,
+            user,
+          )?
+          .with_breakpoints(breakpoints)
+          .with_login(login)
+          .with_stdin(stdin_fd)
+          .with_timeout(limit_args.timeout())
+          .with_max_events(limit_args.max_events())?,
+        );
+        app.tracer_pause = Some(tracer.pause_handle());
+        app.tracer = Some(tracer.clone());
+        Some((tracer.clone(), tracer.spawn(cmd, None)?))
+      };
+      let mut tui = tui::Tui::new()?
+        .frame_rate(frame_rate)
+        .mouse(mouse || (!no_mouse && !config.tui.no_mouse))
+        .activity(app.activity.clone());
       tui.enter(tracer_rx)?;
       app.run(&mut tui).await?;
       // Now when TUI exits, the tracer thread is still running.
@@ -193,14 +627,153 @@ async fn main() -> color_eyre::Result<()> {
       // 1. Wait for the tracer thread to exit.
       // 2. Terminate the root process so that the tracer thread exits.
       // 3. Kill the root process so that the tracer thread exits.
-      app.exit(terminate_on_exit, kill_on_exit)?;
+      app.exit(exit_signal, kill_grace_period)?;
       tui::restore_tui()?;
-      tracer_thread.join().unwrap()?;
+      let mut exit_code = None;
+      if let Some((tracer, tracer_thread)) = root_tracer {
+        tracer_thread.join().unwrap()?;
+        exit_code = tracer.root_exit_code();
+      }
+      for thread in app.tracer_threads.drain(..) {
+        thread.join().unwrap()?;
+      }
+      // Like in log mode, exit with the status of the command given on the command line
+      if let Some(exit_code) = exit_code {
+        process::exit(exit_code);
+      }
+    }
+    CliCommand::Open {
+      file,
+      mut tracer_event_args,
+    } => {
+      if term_caps().dumb {
+        bail!("The TUI can't run on a dumb terminal, use the log mode instead!");
+      }
+      tracer_event_args.merge_config(&config.events);
+      let mut modifier_args = ModifierArgs::default();
+      modifier_args.merge_config(&config.modifier);
+      let modifier_args = modifier_args.processed();
+      set_redactor(Redactor::from_cli(&modifier_args, &config.redact));
+      set_theme(Theme::from_config(
+        &config.tui.theme,
+        color.should_colorize(true),
+      )?);
+      owo_colors::control::set_should_colorize(false);
+      let reader = TraceReader::open(&file)?;
+      let exec_filter = tracer_event_args.exec_filter();
+      let mut app = App::new(
+        &tui_tracing_args(),
+        &modifier_args,
+        reader.header.baseline.clone(),
+        None,
+        None,
+        None,
+        None,
+        Keymap::new(config.tui.keymap, &config.tui.keys),
+        tracer_event_args.filter()?,
+        config.tui.clipboard.unwrap_or_default(),
+        &config.tui,
+      )?
+      .without_saving_state();
+      // The whole trace is loaded up front, nothing is traced
+      let (tracer_tx, tracer_rx) = mpsc::unbounded_channel();
+      for event in reader {
+        let event = event?;
+        if exec_filter.keeps(&event) {
+          tracer_tx.send(event)?;
+        }
+      }
+      drop(tracer_tx);
+      let mut tui = tui::Tui::new()?
+        .frame_rate(config.tui.frame_rate.map_or(60.0, Into::into))
+        .mouse(!config.tui.no_mouse)
+        .activity(app.activity.clone());
+      tui.enter(tracer_rx)?;
+      app.run(&mut tui).await?;
+      app.exit(None, None)?;
+      tui::restore_tui()?;
+    }
+    CliCommand::Diff {
+      old,
+      new,
+      ignore_env,
+    } => {
+      owo_colors::control::set_should_colorize(color.should_colorize(stdout().is_terminal()));
+      set_palette(Palette::from_config(&config.log.palette));
+      let differs = record::diff::diff_traces(
+        TraceReader::open(&old)?,
+        TraceReader::open(&new)?,
+        ignore_env,
+        &mut stdout().lock(),
+      )?;
+      // Like diff(1)
+      if differs {
+        process::exit(1);
+      }
+    }
+    CliCommand::Completions { .. } | CliCommand::GenerateConfig | CliCommand::Schema { .. } => {
+      unreachable!("these are handled right after parsing")
     }
   }
   Ok(())
 }
 
+/// Wait for the tracer thread of log or record mode and report why it stopped early.
+///
+/// The tracer is dropped once its thread exits, which closes the channel of its events
+/// and its end of the pseudo terminal. Returns the exit code of the root child.
+fn wait_for_tracer(
+  tracer: Arc<tracer::Tracer>,
+  tracer_thread: std::thread::JoinHandle<color_eyre::Result<()>>,
+  limit_args: LimitArgs,
+) -> tokio::task::JoinHandle<color_eyre::Result<Option<i32>>> {
+  tokio::task::spawn_blocking(move || {
+    tracer_thread.join().unwrap()?;
+    if tracer.timed_out() {
+      eprintln!(
+        "tracexec: timed out after {:?}, sent {} to the traced processes",
+        limit_args.timeout.unwrap_or_default(),
+        limit_args.timeout_signal
+      );
+    }
+    if tracer.max_events_reached() {
+      eprintln!(
+        "tracexec: stopped tracing after {} exec events",
+        limit_args.max_events.unwrap_or_default()
+      );
+    }
+    let incomplete = tracer.incomplete();
+    if incomplete > 0 {
+      if incomplete == 1 {
+        eprintln!("tracexec: the trace is incomplete, 1 problem was reported");
+      } else {
+        eprintln!("tracexec: the trace is incomplete, {incomplete} problems were reported");
+      }
+      // With --strict, a successful command doesn't hide an incomplete trace
+      return Ok(Some(
+        tracer
+          .root_exit_code()
+          .filter(|&code| code != 0)
+          .unwrap_or(1),
+      ));
+    }
+    Ok(tracer.root_exit_code())
+  })
+}
+
+/// The log mode options used for formatting the events in the TUI
+fn tui_tracing_args() -> LogModeArgs {
+  LogModeArgs {
+    show_cmdline: false, // We handle cmdline in TUI
+    show_argv: true,
+    show_interpreter: true,
+    more_colors: false,
+    less_colors: false,
+    diff_env: true,
+    ..Default::default()
+  }
+}
+
 fn is_current_kernel_greater_than(min_support: (u32, u32)) -> color_eyre::Result<bool> {
   let utsname = nix::sys::utsname::uname()?;
   let kstr = utsname.release().as_bytes();
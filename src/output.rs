@@ -0,0 +1,345 @@
+//! Output files for logging mode.
+
+use std::{
+  convert::Infallible,
+  fs::{self, File, OpenOptions},
+  io::{self, stdout, BufWriter, Write},
+  os::unix::{fs::FileTypeExt, net::UnixStream},
+  path::{Path, PathBuf},
+  str::FromStr,
+  time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use flate2::write::GzEncoder;
+use serde::Deserialize;
+use strum::{Display, EnumString};
+use tracing::warn;
+
+use crate::{
+  cli::options::Compression,
+  export::{CsvFormatter, EventFormatter, JsonlFormatter, StraceFormatter},
+  printer::PrinterOut,
+};
+
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq, Default, EnumString, Display, ValueEnum, Deserialize,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+  /// The human readable log
+  #[default]
+  #[strum(to_string = "human", serialize = "text")]
+  #[value(alias = "text")]
+  #[serde(alias = "text")]
+  Human,
+  /// Only pid, comm and shell quoted argv of exec events, like --compact
+  Compact,
+  /// NUL terminated fields of exec events for scripts, like --print0
+  Print0,
+  /// One JSON object per event
+  Jsonl,
+  /// One row per exec event, with a header row
+  Csv,
+  /// Exec events, exits and signals like strace -f prints them
+  Strace,
+}
+
+impl OutputFormat {
+  /// The formatter of the event stream, the human readable formats are written by the printer
+  pub fn formatter(self) -> Option<Box<dyn EventFormatter>> {
+    Some(match self {
+      Self::Human | Self::Compact | Self::Print0 => return None,
+      Self::Jsonl => Box::new(JsonlFormatter),
+      Self::Csv => Box::new(CsvFormatter),
+      Self::Strace => Box::new(StraceFormatter),
+    })
+  }
+}
+
+/// An output given on the command line as `[FORMAT:]PATH` or `[FORMAT:]unix:PATH`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct OutputSpec {
+  /// `--output-format` applies to the outputs without a format
+  pub format: Option<OutputFormat>,
+  pub target: OutputTarget,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputTarget {
+  /// Represented by a single hyphen `-`
+  Stdout,
+  /// A regular file or a FIFO
+  Path(PathBuf),
+  /// A Unix stream socket that some other program listens on
+  UnixSocket(PathBuf),
+}
+
+impl FromStr for OutputTarget {
+  type Err = Infallible;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Ok(if s == "-" {
+      Self::Stdout
+    } else if let Some(path) = s.strip_prefix("unix:") {
+      Self::UnixSocket(path.into())
+    } else {
+      Self::Path(s.into())
+    })
+  }
+}
+
+impl TryFrom<String> for OutputSpec {
+  type Error = Infallible;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+impl FromStr for OutputSpec {
+  type Err = Infallible;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    // Paths that happen to contain a colon are still treated as paths unless the prefix is a known format
+    if let Some((format, target)) = s.split_once(':') {
+      if let Ok(format) = OutputFormat::from_str(format) {
+        return Ok(Self {
+          format: Some(format),
+          target: target.parse()?,
+        });
+      }
+    }
+    Ok(Self {
+      format: None,
+      target: s.parse()?,
+    })
+  }
+}
+
+impl OutputSpec {
+  pub fn is_stdout(&self) -> bool {
+    self.target == OutputTarget::Stdout
+  }
+
+  /// Whether the output is a regular file, which can be rotated
+  pub fn is_regular_file(&self) -> bool {
+    matches!(&self.target, OutputTarget::Path(path) if !is_fifo(path))
+  }
+
+  /// Open the output. Rotation only applies to regular files and compression doesn't apply to stdout.
+  ///
+  /// If `compression` is `None`, it is detected from the file extension.
+  pub fn open(
+    &self,
+    rotation: RotationPolicy,
+    compression: Option<Compression>,
+  ) -> io::Result<Box<PrinterOut>> {
+    let path = match &self.target {
+      OutputTarget::Stdout => return Ok(Box::new(stdout())),
+      OutputTarget::UnixSocket(path) | OutputTarget::Path(path) => path,
+    };
+    let compression = compression.unwrap_or_else(|| Compression::from_extension(path));
+    Ok(match &self.target {
+      OutputTarget::UnixSocket(_) => Box::new(OutputFile::new(
+        BufWriter::new(UnixStream::connect(path)?),
+        compression,
+      )?),
+      _ if rotation.is_enabled() && self.is_regular_file() => {
+        Box::new(RotatingFile::open(path.clone(), rotation, compression)?)
+      }
+      // Opening a FIFO blocks until the other end is opened for reading
+      _ => Box::new(OutputFile::new(create(path)?, compression)?),
+    })
+  }
+}
+
+fn is_fifo(path: &Path) -> bool {
+  fs::metadata(path).is_ok_and(|m| m.file_type().is_fifo())
+}
+
+/// Writes everything to all of the inner writers
+pub struct MultiWriter {
+  writers: Vec<Box<PrinterOut>>,
+}
+
+impl MultiWriter {
+  pub fn new(writers: Vec<Box<PrinterOut>>) -> Self {
+    Self { writers }
+  }
+}
+
+impl Write for MultiWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    for writer in self.writers.iter_mut() {
+      writer.write_all(buf)?;
+    }
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    for writer in self.writers.iter_mut() {
+      writer.flush()?;
+    }
+    Ok(())
+  }
+}
+
+/// An output file that is optionally compressed. The compressed stream is finished when it is dropped.
+pub enum OutputFile<W: Write = BufWriter<File>> {
+  Plain(W),
+  Gzip(GzEncoder<W>),
+  Zstd(zstd::Encoder<'static, W>),
+}
+
+impl OutputFile {
+  pub fn create(path: &Path, compression: Compression) -> io::Result<Self> {
+    Self::new(create(path)?, compression)
+  }
+}
+
+impl<W: Write> OutputFile<W> {
+  pub fn new(inner: W, compression: Compression) -> io::Result<Self> {
+    Ok(match compression {
+      Compression::None => Self::Plain(inner),
+      Compression::Gzip => Self::Gzip(GzEncoder::new(inner, flate2::Compression::default())),
+      Compression::Zstd => Self::Zstd(zstd::Encoder::new(inner, 0)?),
+    })
+  }
+}
+
+impl<W: Write> Write for OutputFile<W> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    match self {
+      Self::Plain(w) => w.write(buf),
+      Self::Gzip(w) => w.write(buf),
+      Self::Zstd(w) => w.write(buf),
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    match self {
+      Self::Plain(w) => w.flush(),
+      // Output is flushed after every event. Flushing the encoders that often would ruin the
+      // compression ratio, so compressed data is only written out as the encoders see fit.
+      Self::Gzip(_) | Self::Zstd(_) => Ok(()),
+    }
+  }
+}
+
+impl<W: Write> Drop for OutputFile<W> {
+  fn drop(&mut self) {
+    // Unlike the gzip encoder, the zstd encoder doesn't finish the stream on drop
+    if let Self::Zstd(encoder) = self {
+      if let Err(e) = encoder.do_finish() {
+        warn!("Failed to finish zstd stream: {e}");
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RotationPolicy {
+  /// Rotate once the current file has grown to this many bytes
+  pub max_size: Option<u64>,
+  /// Rotate once the current file has been written to for this long
+  pub max_age: Option<Duration>,
+  /// How many rotated files(`<path>.1`, `<path>.2`, ...) to keep around
+  pub keep: usize,
+}
+
+impl RotationPolicy {
+  pub fn is_enabled(&self) -> bool {
+    self.max_size.is_some() || self.max_age.is_some()
+  }
+}
+
+/// A file that renames itself to `<path>.1` and starts over when the rotation policy says so.
+pub struct RotatingFile {
+  path: PathBuf,
+  policy: RotationPolicy,
+  compression: Compression,
+  file: OutputFile,
+  /// Bytes written before compression
+  written: u64,
+  opened_at: Instant,
+}
+
+impl RotatingFile {
+  pub fn open(path: PathBuf, policy: RotationPolicy, compression: Compression) -> io::Result<Self> {
+    let file = OutputFile::create(&path, compression)?;
+    Ok(Self {
+      path,
+      policy,
+      compression,
+      file,
+      written: 0,
+      opened_at: Instant::now(),
+    })
+  }
+
+  fn rotated_path(&self, index: usize) -> PathBuf {
+    let mut path = self.path.clone().into_os_string();
+    path.push(format!(".{index}"));
+    path.into()
+  }
+
+  fn should_rotate(&self) -> bool {
+    self.policy.max_size.is_some_and(|max| self.written >= max)
+      || self
+        .policy
+        .max_age
+        .is_some_and(|max| self.opened_at.elapsed() >= max)
+  }
+
+  fn rotate(&mut self) -> io::Result<()> {
+    self.file.flush()?;
+    if self.policy.keep > 0 {
+      // The oldest file gets overwritten by the rename
+      for index in (1..self.policy.keep).rev() {
+        let from = self.rotated_path(index);
+        if from.exists() {
+          fs::rename(from, self.rotated_path(index + 1))?;
+        }
+      }
+      fs::rename(&self.path, self.rotated_path(1))?;
+    } else {
+      // Unlink instead of truncating, so that finishing the compressed stream of the old file
+      // doesn't write into the new one.
+      fs::remove_file(&self.path)?;
+    }
+    // The old file is dropped here, which finishes its compressed stream
+    self.file = OutputFile::create(&self.path, self.compression)?;
+    self.written = 0;
+    self.opened_at = Instant::now();
+    Ok(())
+  }
+}
+
+impl Write for RotatingFile {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let written = self.file.write(buf)?;
+    self.written += written as u64;
+    Ok(written)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.file.flush()?;
+    // The printer flushes after every line, so rotating here never splits a line across files.
+    if self.should_rotate() {
+      self.rotate()?;
+    }
+    Ok(())
+  }
+}
+
+fn create(path: &Path) -> io::Result<BufWriter<File>> {
+  let file = OpenOptions::new()
+    .create(true)
+    .truncate(true)
+    .write(true)
+    .open(path)?;
+  Ok(BufWriter::new(file))
+}
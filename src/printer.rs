@@ -1,21 +1,29 @@
 use std::{
   cell::RefCell,
-  collections::BTreeMap,
+  collections::{BTreeMap, HashMap},
   ffi::OsStr,
   io::{self, Write},
-  path::Path,
-  sync::Arc,
+  os::unix::ffi::OsStrExt,
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+  },
+  time::Instant,
 };
 
 use crate::{
-  cli::args::{ModifierArgs, LogModeArgs},
-  event::TracerEvent,
+  cli::{
+    args::{LogModeArgs, ModifierArgs},
+    options::{ArgvEscape, EnvDisplay, PidDisplay},
+  },
+  event::{ProcessExitStatus, TracerEvent},
   proc::{diff_env, BaselineInfo, FileDescriptorInfoCollection, Interpreter},
   tracer::state::ProcessState,
   tracer::InspectError,
 };
 
-use itertools::chain;
+use itertools::{chain, Itertools};
 use nix::{fcntl::OFlag, libc::ENOENT, unistd::Pid};
 use owo_colors::{OwoColorize, Style};
 
@@ -29,6 +37,47 @@ macro_rules! escape_str_for_bash {
 
 pub(crate) use escape_str_for_bash;
 
+/// Escape an argument of argv for display
+pub fn escape_arg(arg: &str, mode: ArgvEscape) -> String {
+  match mode {
+    ArgvEscape::Shell => escape_str_for_bash!(arg).into_owned(),
+    ArgvEscape::C => {
+      let mut escaped = String::with_capacity(arg.len() + 2);
+      escaped.push('"');
+      for c in arg.chars() {
+        match c {
+          '"' => escaped.push_str("\\\""),
+          '\\' => escaped.push_str("\\\\"),
+          '\n' => escaped.push_str("\\n"),
+          '\r' => escaped.push_str("\\r"),
+          '\t' => escaped.push_str("\\t"),
+          // Octal escapes have at most 3 digits, so unlike \x they can't swallow the following characters
+          c if c.is_control() => {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+              escaped.push_str(&format!("\\{b:03o}"));
+            }
+          }
+          c => escaped.push(c),
+        }
+      }
+      escaped.push('"');
+      escaped
+    }
+    ArgvEscape::Hex if arg.is_empty() => "\"\"".to_string(),
+    ArgvEscape::Hex => arg.bytes().map(|b| format!("{b:02x}")).collect(),
+  }
+}
+
+mod palette;
+mod summary;
+mod template;
+
+pub use palette::{palette, set_palette, Palette};
+pub use template::LogTemplate;
+
+use self::summary::Summary;
+
 #[derive(Debug, Clone, Copy)]
 pub enum EnvPrintFormat {
   Diff,
@@ -65,6 +114,18 @@ pub struct PrinterArgs {
   pub color: ColorLevel,
   pub stdio_in_cmdline: bool,
   pub fd_in_cmdline: bool,
+  pub template: Option<LogTemplate>,
+  pub print0: bool,
+  pub show_elapsed: bool,
+  pub show_delta: bool,
+  pub show_exit: bool,
+  pub summary: bool,
+  pub fold: bool,
+  pub show_ppid: bool,
+  pub show_depth: bool,
+  pub indent: bool,
+  pub argv_escape: Option<ArgvEscape>,
+  pub pid_display: PidDisplay,
 }
 
 impl PrinterArgs {
@@ -73,14 +134,18 @@ impl PrinterArgs {
       trace_comm: !tracing_args.no_show_comm,
       trace_argv: !tracing_args.no_show_argv && !tracing_args.show_cmdline,
       trace_env: match (
+        tracing_args.env,
         tracing_args.show_cmdline,
         tracing_args.diff_env,
         tracing_args.no_diff_env,
         tracing_args.show_env,
         tracing_args.no_show_env,
       ) {
-        (true, ..) | (.., true) => EnvPrintFormat::None,
-        (false, .., true, _) | (false, _, true, ..) => EnvPrintFormat::Raw,
+        (Some(EnvDisplay::Diff), ..) => EnvPrintFormat::Diff,
+        (Some(EnvDisplay::All), ..) => EnvPrintFormat::Raw,
+        (Some(EnvDisplay::None), ..) => EnvPrintFormat::None,
+        (None, true, ..) | (None, .., true) => EnvPrintFormat::None,
+        (None, false, .., true, _) | (None, false, _, true, ..) => EnvPrintFormat::Raw,
         _ => EnvPrintFormat::Diff, // diff_env is enabled by default
       },
       trace_fd: match (
@@ -125,6 +190,22 @@ impl PrinterArgs {
       },
       stdio_in_cmdline: modifier_args.stdio_in_cmdline,
       fd_in_cmdline: modifier_args.fd_in_cmdline,
+      pid_display: modifier_args.pid_display.unwrap_or_default(),
+      template: if tracing_args.compact {
+        Some(LogTemplate::compact())
+      } else {
+        tracing_args.format.clone()
+      },
+      print0: tracing_args.print0,
+      show_elapsed: tracing_args.show_elapsed,
+      show_delta: tracing_args.show_delta,
+      show_exit: tracing_args.show_exit,
+      summary: tracing_args.summary,
+      fold: tracing_args.fold,
+      show_ppid: tracing_args.show_ppid,
+      show_depth: tracing_args.show_depth,
+      indent: tracing_args.indent,
+      argv_escape: tracing_args.argv_escape,
     }
   }
 }
@@ -147,8 +228,8 @@ impl Drop for DeferredWarnings {
   fn drop(&mut self) {
     Printer::OUT.with_borrow_mut(|out| {
       if let Some(out) = out {
-        write!(out, "{}", self.pid.bright_red()).unwrap();
-        write!(out, "[{}]: ", "warning".bright_yellow()).unwrap();
+        write!(out, "{}", self.pid.style(palette().pid_failure)).unwrap();
+        write!(out, "[{}]: ", "warning".style(palette().warning)).unwrap();
         match self.warning {
           DeferredWarningKind::NoArgv0 => {
             write!(
@@ -203,17 +284,43 @@ impl ListPrinter {
   }
 
   pub fn print_string_list(&self, out: &mut dyn Write, list: &[String]) -> io::Result<()> {
+    self.print_styled_string_list(out, list, Style::new())
+  }
+
+  pub fn print_styled_string_list(
+    &self,
+    out: &mut dyn Write,
+    list: &[String],
+    style: Style,
+  ) -> io::Result<()> {
+    self.print_list_with(out, list, |out, s| write!(out, "{:?}", s.style(style)))
+  }
+
+  pub fn print_escaped_string_list(
+    &self,
+    out: &mut dyn Write,
+    list: &[String],
+    style: Style,
+    escape: ArgvEscape,
+  ) -> io::Result<()> {
+    self.print_list_with(out, list, |out, s| {
+      write!(out, "{}", escape_arg(s, escape).style(style))
+    })
+  }
+
+  fn print_list_with(
+    &self,
+    out: &mut dyn Write,
+    list: &[String],
+    mut print_item: impl FnMut(&mut dyn Write, &String) -> io::Result<()>,
+  ) -> io::Result<()> {
     self.begin(out)?;
     if let Some((last, rest)) = list.split_last() {
-      if rest.is_empty() {
-        write!(out, "{:?}", last)?;
-      } else {
-        for s in rest {
-          write!(out, "{:?}", s)?;
-          self.comma(out)?;
-        }
-        write!(out, "{:?}", last)?;
+      for s in rest {
+        print_item(out, s)?;
+        self.comma(out)?;
       }
+      print_item(out, last)?;
     }
     self.end(out)
   }
@@ -222,11 +329,45 @@ impl ListPrinter {
 pub struct Printer {
   pub args: PrinterArgs,
   baseline: Arc<BaselineInfo>,
+  start_time: Instant,
+  last_event_time: Mutex<Instant>,
+  next_exec_seq: AtomicU64,
+  /// Sequence number of the last successful exec of processes that are still alive
+  exec_seqs: Mutex<HashMap<Pid, u64>>,
+  summary: Mutex<Summary>,
+  fold: Mutex<FoldState>,
+}
+
+/// What makes two execs identical for `--fold`
+#[derive(PartialEq)]
+struct FoldKey {
+  filename: Result<PathBuf, InspectError>,
+  argv: Arc<Result<Vec<String>, InspectError>>,
+  cwd: PathBuf,
+  result: i64,
+}
+
+#[derive(Default)]
+struct FoldState {
+  /// The last exec that has been printed, if nothing else has been printed after it
+  last: Option<FoldKey>,
+  /// How many times the last exec has been repeated since it was printed
+  repeats: u64,
 }
 
 impl Printer {
   pub fn new(args: PrinterArgs, baseline: Arc<BaselineInfo>) -> Self {
-    Printer { args, baseline }
+    let now = Instant::now();
+    Printer {
+      args,
+      baseline,
+      start_time: now,
+      last_event_time: Mutex::new(now),
+      next_exec_seq: AtomicU64::new(1),
+      exec_seqs: Mutex::new(HashMap::new()),
+      summary: Mutex::new(Summary::default()),
+      fold: Mutex::new(FoldState::default()),
+    }
   }
 
   thread_local! {
@@ -239,16 +380,192 @@ impl Printer {
     });
   }
 
+  pub fn print_exit(
+    &self,
+    state: &ProcessState,
+    status: ProcessExitStatus,
+  ) -> color_eyre::Result<()> {
+    if self.args.summary {
+      self.summary.lock().unwrap().on_exit(state, status);
+    }
+    if !self.args.show_exit || self.args.print0 {
+      return Ok(());
+    }
+    Self::OUT.with_borrow_mut(|out| {
+      let Some(out) = out else {
+        return Ok(());
+      };
+      self.break_fold(out)?;
+      let palette = palette();
+      self.print_time(out)?;
+      self.print_indent(out, state)?;
+      let pid = self.args.pid_display.format(state.pid, state.ns_pid);
+      if status.is_success() {
+        write!(out, "{}", pid.style(palette.pid_success))?;
+      } else {
+        write!(out, "{}", pid.style(palette.pid_failure))?;
+      }
+      if self.args.trace_comm {
+        write!(out, "<{}>", state.comm.style(palette.comm))?;
+      }
+      self.print_hierarchy(out, state)?;
+      // Correlate with the last successful exec of this process
+      if let Some(seq) = self.exec_seqs.lock().unwrap().remove(&state.pid) {
+        write!(out, " {}", format!("#{seq}").style(palette.label))?;
+      }
+      if status.is_success() {
+        writeln!(out, ": {}", status.style(palette.label))?;
+      } else {
+        writeln!(out, ": {}", status.style(palette.error))?;
+      }
+      out.flush()?;
+      Ok(())
+    })
+  }
+
+  /// Print what is left to print when the root child exits,
+  /// i.e. the pending repeat count of `--fold` and the summary.
+  pub fn finish(&self) -> color_eyre::Result<()> {
+    Self::OUT.with_borrow_mut(|out| {
+      let Some(out) = out else {
+        return Ok(());
+      };
+      self.break_fold(out)?;
+      if self.args.summary && !self.args.print0 {
+        self.summary.lock().unwrap().print(out)?;
+      }
+      out.flush()?;
+      Ok(())
+    })
+  }
+
+  /// Returns true if the exec is identical to the previous one and should not be printed.
+  fn fold_exec(&self, out: &mut dyn Write, state: &ProcessState, result: i64) -> io::Result<bool> {
+    let exec_data = state.exec_data.as_ref().unwrap();
+    let key = FoldKey {
+      filename: exec_data.filename.clone(),
+      argv: exec_data.argv.clone(),
+      cwd: exec_data.cwd.clone(),
+      result,
+    };
+    let mut fold = self.fold.lock().unwrap();
+    if fold.last.as_ref() == Some(&key) {
+      fold.repeats += 1;
+      return Ok(true);
+    }
+    Self::print_repeats(out, &mut fold)?;
+    fold.last = Some(key);
+    Ok(false)
+  }
+
+  /// Print the pending repeat count, if any, and start over because another line is about to be printed
+  fn break_fold(&self, out: &mut dyn Write) -> io::Result<()> {
+    if !self.args.fold {
+      return Ok(());
+    }
+    let mut fold = self.fold.lock().unwrap();
+    Self::print_repeats(out, &mut fold)?;
+    fold.last = None;
+    Ok(())
+  }
+
+  fn print_repeats(out: &mut dyn Write, fold: &mut FoldState) -> io::Result<()> {
+    if fold.repeats > 0 {
+      writeln!(
+        out,
+        "{}",
+        format!("... repeated {} more times", fold.repeats).style(palette().label)
+      )?;
+      fold.repeats = 0;
+    }
+    Ok(())
+  }
+
+  /// Indent the line by the depth of the process in the process tree
+  fn print_indent(&self, out: &mut dyn Write, state: &ProcessState) -> io::Result<()> {
+    if self.args.indent {
+      write!(out, "{:width$}", "", width = state.depth * 2)?;
+    }
+    Ok(())
+  }
+
+  /// Print the parent pid and the depth of the process in the process tree
+  fn print_hierarchy(&self, out: &mut dyn Write, state: &ProcessState) -> io::Result<()> {
+    if self.args.show_ppid {
+      if let Some(ppid) = state.ppid {
+        write!(out, " {}{}", "ppid=".style(palette().label), ppid)?;
+      }
+    }
+    if self.args.show_depth {
+      write!(out, " {}{}", "depth=".style(palette().label), state.depth)?;
+    }
+    Ok(())
+  }
+
+  /// Print the time elapsed since the trace started and/or since the previous event
+  fn print_time(&self, out: &mut dyn Write) -> io::Result<()> {
+    if !self.args.show_elapsed && !self.args.show_delta {
+      return Ok(());
+    }
+    let now = Instant::now();
+    let last = std::mem::replace(&mut *self.last_event_time.lock().unwrap(), now);
+    let style = palette().timestamp;
+    if self.args.show_elapsed {
+      let elapsed = now.duration_since(self.start_time).as_secs_f64();
+      write!(out, "{} ", format!("[{elapsed:>10.3}s]").style(style))?;
+    }
+    if self.args.show_delta {
+      let delta = now.duration_since(last).as_secs_f64();
+      write!(out, "{} ", format!("[+{delta:.3}s]").style(style))?;
+    }
+    Ok(())
+  }
+
   pub fn print_new_child(&self, state: &ProcessState, child: Pid) -> color_eyre::Result<()> {
     Self::OUT.with_borrow_mut(|out| {
       let Some(out) = out else {
         return Ok(());
       };
-      write!(out, "{}", state.pid.bright_yellow())?;
+      if self.args.print0 {
+        // Only exec events are printed in NUL delimited mode
+        return Ok(());
+      }
+      self.break_fold(out)?;
+      self.print_time(out)?;
+      self.print_indent(out, state)?;
+      let pid = self.args.pid_display.format(state.pid, state.ns_pid);
+      write!(out, "{}", pid.style(palette().pid))?;
       if self.args.trace_comm {
-        write!(out, "<{}>", state.comm.cyan())?;
+        write!(out, "<{}>", state.comm.style(palette().comm))?;
       }
-      writeln!(out, ": {}: {}", "new child".purple(), child.bright_yellow())?;
+      self.print_hierarchy(out, state)?;
+      writeln!(
+        out,
+        ": {}: {}",
+        "new child".style(palette().label),
+        child.style(palette().pid)
+      )?;
+      out.flush()?;
+      Ok(())
+    })
+  }
+
+  /// A warning that is not about an exec, e.g. of --strict
+  pub fn print_warning(&self, pid: Option<Pid>, msg: &str) -> color_eyre::Result<()> {
+    Self::OUT.with_borrow_mut(|out| {
+      let Some(out) = out else {
+        return Ok(());
+      };
+      if self.args.print0 {
+        return Ok(());
+      }
+      self.break_fold(out)?;
+      self.print_time(out)?;
+      match pid {
+        Some(pid) => write!(out, "{}", pid.style(palette().pid_failure))?,
+        None => write!(out, "tracexec")?,
+      }
+      writeln!(out, "[{}]: {msg}", "warning".style(palette().warning))?;
       out.flush()?;
       Ok(())
     })
@@ -261,7 +578,7 @@ impl Printer {
   ) -> io::Result<()> {
     match self.args.trace_fd {
       FdPrintFormat::Diff => {
-        write!(out, " {} ", "fd".purple())?;
+        write!(out, " {} ", "fd".style(palette().label))?;
         let list_printer = ListPrinter::new(self.args.color);
         list_printer.begin(out)?;
         // Stdio
@@ -360,7 +677,7 @@ impl Printer {
         list_printer.end(out)?;
       }
       FdPrintFormat::Raw => {
-        write!(out, " {} ", "fd".purple())?;
+        write!(out, " {} ", "fd".style(palette().label))?;
         let list_printer = ListPrinter::new(self.args.color);
         list_printer.begin(out)?;
         let last = fds.fdinfo.len() - 1;
@@ -378,6 +695,43 @@ impl Printer {
     Ok(())
   }
 
+  /// Print an exec event as NUL terminated fields: pid, result, filename, argc and then each argument.
+  ///
+  /// argc is included so that the end of a record is unambiguous even if some arguments are empty.
+  fn print_exec_record0(
+    &self,
+    out: &mut dyn Write,
+    state: &ProcessState,
+    result: i64,
+  ) -> io::Result<()> {
+    let exec_data = state.exec_data.as_ref().unwrap();
+    write!(out, "{}\0{}\0", state.pid, result)?;
+    out.write_all(
+      exec_data
+        .filename
+        .as_ref()
+        .map(|f| f.as_os_str().as_bytes())
+        .unwrap_or_default(),
+    )?;
+    out.write_all(b"\0")?;
+    let argv = exec_data.argv.as_deref().unwrap_or_default();
+    write!(out, "{}\0", argv.len())?;
+    for arg in argv {
+      out.write_all(arg.as_bytes())?;
+      out.write_all(b"\0")?;
+    }
+    Ok(())
+  }
+
+  /// Number the exec for --show-exit, the exit line of the process refers to its last successful exec
+  fn next_seq(&self, state: &ProcessState, result: i64) -> u64 {
+    let seq = self.next_exec_seq.fetch_add(1, Ordering::Relaxed);
+    if result == 0 {
+      self.exec_seqs.lock().unwrap().insert(state.pid, seq);
+    }
+    seq
+  }
+
   pub fn print_exec_trace(
     &self,
     state: &ProcessState,
@@ -389,6 +743,10 @@ impl Printer {
     // 1. execve syscall exit, which leads to 2
     // 2. state.exec_data is Some
 
+    if self.args.summary {
+      self.summary.lock().unwrap().on_exec(state, result);
+    }
+
     // Defer the warnings so that they are printed after the main message
     let mut _deferred_warnings = vec![];
 
@@ -396,34 +754,77 @@ impl Printer {
       let Some(out) = out else {
         return Ok(());
       };
+      if self.args.print0 {
+        self.print_exec_record0(out, state, result)?;
+        out.flush()?;
+        return Ok(());
+      }
+      if self.args.fold && self.fold_exec(out, state, result)? {
+        return Ok(());
+      }
+      let seq = self.args.show_exit.then(|| self.next_seq(state, result));
+      self.print_time(out)?;
+      self.print_indent(out, state)?;
+      if let Some(template) = &self.args.template {
+        if let Some(seq) = seq {
+          write!(out, "{} ", format!("#{seq}").style(palette().label))?;
+        }
+        template.render(
+          out,
+          state,
+          result,
+          env,
+          &self.baseline.secrets,
+          self.args.argv_escape.unwrap_or(ArgvEscape::Shell),
+        )?;
+        // The template decides where the pid goes, so the hierarchy goes after it
+        self.print_hierarchy(out, state)?;
+        writeln!(out)?;
+        out.flush()?;
+        return Ok(());
+      }
       let exec_data = state.exec_data.as_ref().unwrap();
       let list_printer = ListPrinter::new(self.args.color);
+      let palette = palette();
+      let pid = self.args.pid_display.format(state.pid, state.ns_pid);
       if result == 0 {
-        write!(out, "{}", state.pid.bright_green())?;
+        write!(out, "{}", pid.style(palette.pid_success))?;
       } else if result == -ENOENT as i64 {
-        write!(out, "{}", state.pid.bright_yellow())?;
+        write!(out, "{}", pid.style(palette.pid_enoent))?;
       } else {
-        write!(out, "{}", state.pid.bright_red())?;
+        write!(out, "{}", pid.style(palette.pid_failure))?;
       }
       if self.args.trace_comm {
-        write!(out, "<{}>", state.comm.cyan())?;
+        write!(out, "<{}>", state.comm.style(palette.comm))?;
+      }
+      self.print_hierarchy(out, state)?;
+      if let Some(seq) = seq {
+        write!(out, " {}", format!("#{seq}").style(palette.label))?;
       }
       write!(out, ":")?;
 
       match exec_data.filename.as_ref() {
         Ok(filename) => {
           if self.args.trace_filename {
-            write!(out, " {:?}", filename)?;
+            match exec_data.resolved_filename.as_ref() {
+              Some(resolved) if resolved != filename => {
+                write!(
+                  out,
+                  " {:?} {} {:?}",
+                  resolved.style(palette.filename),
+                  "resolved from".style(palette.label),
+                  filename
+                )?;
+              }
+              _ => write!(out, " {:?}", filename.style(palette.filename))?,
+            }
           }
         }
         Err(e) => {
           write!(
             out,
             " {}",
-            format!("[Failed to read filename: {e}]")
-              .bright_red()
-              .blink()
-              .bold()
+            format!("[Failed to read filename: {e}]").style(palette.error.blink())
           )?;
           _deferred_warnings.push(DeferredWarnings {
             warning: DeferredWarningKind::FailedReadingFilename(*e),
@@ -442,7 +843,12 @@ impl Printer {
         Ok(argv) => {
           if self.args.trace_argv {
             write!(out, " ")?;
-            list_printer.print_string_list(out, argv)?;
+            match self.args.argv_escape {
+              Some(escape) => {
+                list_printer.print_escaped_string_list(out, argv, palette.argv, escape)?
+              }
+              None => list_printer.print_styled_string_list(out, argv, palette.argv)?,
+            }
           }
         }
       }
@@ -450,14 +856,19 @@ impl Printer {
       // CWD
 
       if self.args.trace_cwd {
-        write!(out, " {} {:?}", "at".purple(), exec_data.cwd)?;
+        write!(
+          out,
+          " {} {:?}",
+          "at".style(palette.label),
+          exec_data.cwd.style(palette.cwd)
+        )?;
       }
 
       // Interpreter
 
       if self.args.trace_interpreter && result == 0 {
         // FIXME: show interpreter for errnos other than ENOENT
-        write!(out, " {} ", "interpreter".purple(),)?;
+        write!(out, " {} ", "interpreter".style(palette.label),)?;
         match exec_data.interpreters.len() {
           0 => {
             write!(out, "{}", Interpreter::None)?;
@@ -490,7 +901,7 @@ impl Printer {
             EnvPrintFormat::Diff => {
               // TODO: make it faster
               //       This is mostly a proof of concept
-              write!(out, " {} ", "with".purple())?;
+              write!(out, " {} ", "with".style(palette.label))?;
               list_printer.begin(out)?;
               let env = env.clone();
               let mut first_item_written = false;
@@ -503,16 +914,20 @@ impl Printer {
                 Ok(())
               };
 
-              let diff = diff_env(&env, envp);
+              let mut diff = diff_env(&env, envp);
+              self
+                .baseline
+                .secrets
+                .add_changes(&exec_data.secrets, &mut diff);
               for (k, v) in diff.added.into_iter() {
                 write_separator(out)?;
                 write!(
                   out,
                   "{}{:?}{}{:?}",
-                  "+".bright_green().bold(),
-                  k.green(),
-                  "=".bright_green().bold(),
-                  v.green()
+                  "+".style(palette.added_env.bold()),
+                  k.style(palette.added_env),
+                  "=".style(palette.added_env.bold()),
+                  v.style(palette.added_env)
                 )?;
               }
               for (k, v) in diff.modified.into_iter() {
@@ -520,10 +935,10 @@ impl Printer {
                 write!(
                   out,
                   "{}{:?}{}{:?}",
-                  "M".bright_yellow().bold(),
-                  k.yellow(),
-                  "=".bright_yellow().bold(),
-                  v.bright_blue()
+                  "M".style(palette.modified_env.bold()),
+                  k.style(palette.modified_env),
+                  "=".style(palette.modified_env.bold()),
+                  v.style(palette.modified_env_value)
                 )?;
               }
               // Now we have the tracee removed entries in env
@@ -532,10 +947,13 @@ impl Printer {
                 write!(
                   out,
                   "{}{:?}{}{:?}",
-                  "-".bright_red().bold(),
-                  k.bright_red().strikethrough(),
-                  "=".bright_red().strikethrough(),
-                  env.get(&k).unwrap().bright_red().strikethrough()
+                  "-".style(palette.removed_env.bold()),
+                  k.style(palette.removed_env.strikethrough()),
+                  "=".style(palette.removed_env.strikethrough()),
+                  env
+                    .get(&k)
+                    .unwrap()
+                    .style(palette.removed_env.strikethrough())
                 )?;
               }
               list_printer.end(out)?;
@@ -546,7 +964,7 @@ impl Printer {
               }
             }
             EnvPrintFormat::Raw => {
-              write!(out, " {} ", "with".purple())?;
+              write!(out, " {} ", "with".style(palette.label))?;
               list_printer.print_string_list(out, envp)?;
             }
             EnvPrintFormat::None => (),
@@ -558,11 +976,8 @@ impl Printer {
               write!(
                 out,
                 " {} {}",
-                "with".purple(),
-                format!("[Failed to read envp: {e}]")
-                  .bright_red()
-                  .blink()
-                  .bold()
+                "with".style(palette.label),
+                format!("[Failed to read envp: {e}]").style(palette.error.blink())
               )?;
             }
             EnvPrintFormat::None => {}
@@ -577,7 +992,7 @@ impl Printer {
       // Command line
 
       if self.args.print_cmdline {
-        write!(out, " {}", "cmdline".purple())?;
+        write!(out, " {}", "cmdline".style(palette.label))?;
         write!(out, " env")?;
 
         if self.args.stdio_in_cmdline {
@@ -686,15 +1101,19 @@ impl Printer {
             }
             // envp warning is already handled
             if let Ok(envp) = exec_data.envp.as_ref() {
-              let diff = diff_env(env, envp);
+              let mut diff = diff_env(env, envp);
+              self
+                .baseline
+                .secrets
+                .add_changes(&exec_data.secrets, &mut diff);
               // Now we have the tracee removed entries in env
               for k in diff.removed.into_iter() {
                 if self.args.color >= ColorLevel::Normal {
                   write!(
                     out,
                     " {}{}",
-                    "-u ".bright_red(),
-                    escape_str_for_bash!(&k).bright_red()
+                    "-u ".style(palette.removed_env),
+                    escape_str_for_bash!(&k).style(palette.removed_env)
                   )?;
                 } else {
                   write!(out, " -u={}", escape_str_for_bash!(&k))?;
@@ -705,9 +1124,9 @@ impl Printer {
                   write!(
                     out,
                     " {}{}{}",
-                    escape_str_for_bash!(&k).green(),
-                    "=".green().bold(),
-                    escape_str_for_bash!(&v).green()
+                    escape_str_for_bash!(&k).style(palette.added_env),
+                    "=".style(palette.added_env.bold()),
+                    escape_str_for_bash!(&v).style(palette.added_env)
                   )?;
                 }
                 for (k, v) in diff.modified.into_iter() {
@@ -715,8 +1134,8 @@ impl Printer {
                     out,
                     " {}{}{}",
                     escape_str_for_bash!(&k),
-                    "=".bright_yellow().bold(),
-                    escape_str_for_bash!(&v).bright_blue()
+                    "=".style(palette.modified_env.bold()),
+                    escape_str_for_bash!(&v).style(palette.modified_env_value)
                   )?;
                 }
               } else {
@@ -753,16 +1172,16 @@ impl Printer {
       if result == 0 {
         writeln!(out)?;
       } else {
-        write!(out, " {} ", "=".purple())?;
+        write!(out, " {} ", "=".style(palette.label))?;
         if self.args.decode_errno {
           writeln!(
             out,
             "{} ({})",
-            result.bright_red().bold(),
-            nix::errno::Errno::from_raw(-result as i32).red()
+            result.style(palette.error),
+            nix::errno::Errno::from_raw(-result as i32).style(palette.errno)
           )?;
         } else {
-          writeln!(out, "{}", result.bright_red().bold())?;
+          writeln!(out, "{}", result.style(palette.error))?;
         }
       }
       // It is critical to call [flush] before BufWriter<W> is dropped.
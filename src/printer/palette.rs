@@ -0,0 +1,106 @@
+//! Colors used by the log mode printer, which can be customized in the config file.
+
+use std::sync::OnceLock;
+
+use owo_colors::Style;
+
+use crate::cli::config::PaletteConfig;
+
+#[derive(Debug, Clone)]
+pub struct Palette {
+  pub pid: Style,
+  pub pid_success: Style,
+  pub pid_failure: Style,
+  pub pid_enoent: Style,
+  pub comm: Style,
+  /// Labels like `with`, `at` and `new child`
+  pub label: Style,
+  pub filename: Style,
+  pub argv: Style,
+  pub cwd: Style,
+  pub added_env: Style,
+  pub modified_env: Style,
+  pub modified_env_value: Style,
+  pub removed_env: Style,
+  pub error: Style,
+  pub errno: Style,
+  pub warning: Style,
+  pub timestamp: Style,
+}
+
+impl Default for Palette {
+  fn default() -> Self {
+    Self {
+      pid: Style::new().bright_yellow(),
+      pid_success: Style::new().bright_green(),
+      pid_failure: Style::new().bright_red(),
+      pid_enoent: Style::new().bright_yellow(),
+      comm: Style::new().cyan(),
+      label: Style::new().purple(),
+      filename: Style::new(),
+      argv: Style::new(),
+      cwd: Style::new(),
+      added_env: Style::new().green(),
+      modified_env: Style::new().yellow(),
+      modified_env_value: Style::new().bright_blue(),
+      removed_env: Style::new().bright_red(),
+      error: Style::new().bright_red().bold(),
+      errno: Style::new().red(),
+      warning: Style::new().bright_yellow(),
+      timestamp: Style::new().dimmed(),
+    }
+  }
+}
+
+macro_rules! override_styles {
+  ($palette:expr, $config:expr, [$($field:ident),* $(,)?]) => {
+    $(
+      if let Some(style) = $config.$field {
+        $palette.$field = style.into();
+      }
+    )*
+  };
+}
+
+impl Palette {
+  pub fn from_config(config: &PaletteConfig) -> Self {
+    let mut palette = Self::default();
+    override_styles!(
+      palette,
+      config,
+      [
+        pid,
+        pid_success,
+        pid_failure,
+        pid_enoent,
+        comm,
+        label,
+        filename,
+        argv,
+        cwd,
+        added_env,
+        modified_env,
+        modified_env_value,
+        removed_env,
+        error,
+        errno,
+        warning,
+        timestamp,
+      ]
+    );
+    palette
+  }
+}
+
+static PALETTE: OnceLock<Palette> = OnceLock::new();
+
+/// Set the palette for log mode. This should be called at most once, before any printing happens.
+pub fn set_palette(palette: Palette) {
+  if PALETTE.set(palette).is_err() {
+    panic!("The palette has already been set!");
+  }
+}
+
+pub fn palette() -> &'static Palette {
+  PALETTE.get_or_init(Palette::default)
+}
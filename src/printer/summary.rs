@@ -0,0 +1,102 @@
+//! Statistics printed at the end of the trace with `--summary`
+
+use std::{
+  collections::{HashMap, HashSet},
+  io::{self, Write},
+  time::Duration,
+};
+
+use nix::unistd::Pid;
+use owo_colors::OwoColorize;
+
+use crate::{event::ProcessExitStatus, tracer::state::ProcessState};
+
+use super::palette;
+
+#[derive(Debug, Default)]
+struct ProgramStats {
+  execs: u64,
+  failed_execs: u64,
+  cpu_time: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct Summary {
+  processes: HashSet<Pid>,
+  failed_processes: u64,
+  cpu_time: Duration,
+  programs: HashMap<String, ProgramStats>,
+  /// The program that each alive process is currently running
+  running: HashMap<Pid, String>,
+}
+
+impl Summary {
+  pub fn on_exec(&mut self, state: &ProcessState, result: i64) {
+    let Some(Ok(filename)) = state.exec_data.as_ref().map(|data| data.filename.as_ref()) else {
+      return;
+    };
+    let program = filename.to_string_lossy().into_owned();
+    self.processes.insert(state.pid);
+    let stats = self.programs.entry(program.clone()).or_default();
+    stats.execs += 1;
+    if result == 0 {
+      self.running.insert(state.pid, program);
+    } else {
+      stats.failed_execs += 1;
+    }
+  }
+
+  pub fn on_exit(&mut self, state: &ProcessState, status: ProcessExitStatus) {
+    self.processes.insert(state.pid);
+    if !status.is_success() {
+      self.failed_processes += 1;
+    }
+    let cpu_time = state.cpu_time.unwrap_or_default();
+    self.cpu_time += cpu_time;
+    // CPU time of processes that never exec'd is attributed to nothing
+    if let Some(program) = self.running.remove(&state.pid) {
+      if let Some(stats) = self.programs.get_mut(&program) {
+        stats.cpu_time += cpu_time;
+      }
+    }
+  }
+
+  pub fn print(&self, out: &mut dyn Write) -> io::Result<()> {
+    let palette = palette();
+    let execs: u64 = self.programs.values().map(|s| s.execs).sum();
+    let failed_execs: u64 = self.programs.values().map(|s| s.failed_execs).sum();
+    writeln!(out)?;
+    writeln!(
+      out,
+      "{} {} processes, {} execs ({} failed), {} processes exited unsuccessfully, {:.3}s CPU time",
+      "Summary:".style(palette.label),
+      self.processes.len(),
+      execs,
+      failed_execs,
+      self.failed_processes,
+      self.cpu_time.as_secs_f64()
+    )?;
+    let mut programs: Vec<_> = self.programs.iter().collect();
+    programs.sort_by(|(a_name, a), (b_name, b)| b.execs.cmp(&a.execs).then(a_name.cmp(b_name)));
+    writeln!(
+      out,
+      "{}",
+      format!(
+        "{:>8} {:>8} {:>12}  {}",
+        "EXECS", "FAILED", "CPU TIME", "PROGRAM"
+      )
+      .style(palette.label)
+    )?;
+    for (program, stats) in programs {
+      writeln!(
+        out,
+        "{:>8} {:>8} {:>11.3}s  {}",
+        stats.execs,
+        stats.failed_execs,
+        stats.cpu_time.as_secs_f64(),
+        program
+      )?;
+    }
+    out.flush()
+  }
+}
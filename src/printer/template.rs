@@ -0,0 +1,193 @@
+//! User defined log line templates, e.g. `{timestamp} {pid} {comm}: {argv}`
+
+use std::{collections::BTreeMap, io::Write, str::FromStr};
+
+use chrono::{DateTime, Local};
+use itertools::Itertools;
+use serde::Deserialize;
+use strum::{Display, EnumString};
+
+use crate::{
+  cli::options::ArgvEscape, event::TracerEvent, proc::diff_env, redact::Secrets,
+  tracer::state::ProcessState,
+};
+
+use super::{escape_arg, escape_str_for_bash};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum TemplateField {
+  /// When the exec happened, also for recorded traces
+  Timestamp,
+  Pid,
+  /// The pid inside the PID namespace of the process
+  NsPid,
+  Ppid,
+  Depth,
+  Comm,
+  Filename,
+  Argv,
+  Cwd,
+  Env,
+  Result,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TemplatePart {
+  Literal(String),
+  Field(TemplateField),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct LogTemplate {
+  parts: Vec<TemplatePart>,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ParseTemplateError {
+  #[error("Unknown field {{{0}}} in template")]
+  UnknownField(String),
+  #[error("Unclosed {{ in template, use {{{{ for a literal {{")]
+  Unclosed,
+  #[error("Unmatched }} in template, use }}}} for a literal }}")]
+  Unmatched,
+}
+
+impl TryFrom<String> for LogTemplate {
+  type Error = ParseTemplateError;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+impl FromStr for LogTemplate {
+  type Err = ParseTemplateError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parts = vec![];
+    let mut literal = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+      match c {
+        '{' if chars.peek() == Some(&'{') => {
+          chars.next();
+          literal.push('{');
+        }
+        '}' if chars.peek() == Some(&'}') => {
+          chars.next();
+          literal.push('}');
+        }
+        '{' => {
+          let mut name = String::new();
+          loop {
+            match chars.next() {
+              Some('}') => break,
+              Some(c) => name.push(c),
+              None => return Err(ParseTemplateError::Unclosed),
+            }
+          }
+          let field = TemplateField::from_str(name.trim())
+            .map_err(|_| ParseTemplateError::UnknownField(name))?;
+          if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+          }
+          parts.push(TemplatePart::Field(field));
+        }
+        '}' => return Err(ParseTemplateError::Unmatched),
+        c => literal.push(c),
+      }
+    }
+    if !literal.is_empty() {
+      parts.push(TemplatePart::Literal(literal));
+    }
+    Ok(Self { parts })
+  }
+}
+
+impl LogTemplate {
+  /// The template used by `--compact`: `{pid}<{comm}>: {argv}`
+  pub fn compact() -> Self {
+    Self {
+      parts: vec![
+        TemplatePart::Field(TemplateField::Pid),
+        TemplatePart::Literal("<".to_string()),
+        TemplatePart::Field(TemplateField::Comm),
+        TemplatePart::Literal(">: ".to_string()),
+        TemplatePart::Field(TemplateField::Argv),
+      ],
+    }
+  }
+
+  /// Render an exec event without the trailing newline
+  pub fn render(
+    &self,
+    out: &mut dyn Write,
+    state: &ProcessState,
+    result: i64,
+    env: &BTreeMap<String, String>,
+    secrets: &Secrets,
+    argv_escape: ArgvEscape,
+  ) -> std::io::Result<()> {
+    let exec_data = state.exec_data.as_ref().unwrap();
+    for part in self.parts.iter() {
+      let field = match part {
+        TemplatePart::Literal(s) => {
+          write!(out, "{s}")?;
+          continue;
+        }
+        TemplatePart::Field(field) => field,
+      };
+      match field {
+        TemplateField::Timestamp => {
+          let timestamp = DateTime::<Local>::from(exec_data.timestamp);
+          write!(out, "{}", timestamp.format("%Y-%m-%dT%H:%M:%S%.3f%:z"))?
+        }
+        TemplateField::Pid => write!(out, "{}", state.pid)?,
+        TemplateField::NsPid => write!(out, "{}", state.ns_pid.unwrap_or(state.pid))?,
+        TemplateField::Ppid => {
+          if let Some(ppid) = state.ppid {
+            write!(out, "{ppid}")?;
+          }
+        }
+        TemplateField::Depth => write!(out, "{}", state.depth)?,
+        TemplateField::Comm => write!(out, "{}", state.comm)?,
+        TemplateField::Filename => {
+          write!(out, "{}", TracerEvent::filename_to_cow(&exec_data.filename))?
+        }
+        TemplateField::Argv => match exec_data.argv.as_ref() {
+          Ok(argv) => write!(
+            out,
+            "{}",
+            argv
+              .iter()
+              .map(|arg| escape_arg(arg, argv_escape))
+              .join(" ")
+          )?,
+          Err(e) => write!(out, "[failed to read argv: {e}]")?,
+        },
+        TemplateField::Cwd => write!(out, "{}", escape_str_for_bash!(&exec_data.cwd))?,
+        TemplateField::Env => match exec_data.envp.as_ref() {
+          Ok(envp) => {
+            let mut diff = diff_env(env, envp);
+            secrets.add_changes(&exec_data.secrets, &mut diff);
+            let removed = diff
+              .removed
+              .iter()
+              .map(|k| format!("-u {}", escape_str_for_bash!(k)));
+            let set = diff
+              .added
+              .iter()
+              .chain(diff.modified.iter())
+              .map(|(k, v)| format!("{}={}", escape_str_for_bash!(k), escape_str_for_bash!(v)));
+            write!(out, "{}", removed.chain(set).join(" "))?
+          }
+          Err(e) => write!(out, "[failed to read envp: {e}]")?,
+        },
+        TemplateField::Result => write!(out, "{result}")?,
+      }
+    }
+    Ok(())
+  }
+}
@@ -7,21 +7,33 @@ use std::{
   ffi::CString,
   fmt::{Display, Formatter},
   io::{self, BufRead, BufReader, Read},
-  os::raw::c_int,
+  os::{
+    raw::c_int,
+    unix::{ffi::OsStrExt, fs::MetadataExt},
+  },
   path::{Path, PathBuf},
+  time::Duration,
 };
 
 use filedescriptor::AsRawFileDescriptor;
 use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
 
 use nix::{
   fcntl::OFlag,
-  libc::AT_FDCWD,
-  unistd::{getpid, Pid},
+  libc::{pid_t, AT_FDCWD},
+  unistd::{getpid, sysconf, Pid, SysconfVar, Uid},
 };
 use tracing::{trace, warn};
 
-use crate::pty::UnixSlavePty;
+use crate::{
+  event::nix_serde,
+  pty::UnixSlavePty,
+  redact::{redactor, Secrets},
+};
+
+#[cfg(test)]
+mod test;
 
 pub fn read_argv(pid: Pid) -> color_eyre::Result<Vec<CString>> {
   let filename = format!("/proc/{pid}/cmdline");
@@ -47,13 +59,94 @@ pub fn read_cwd(pid: Pid) -> std::io::Result<PathBuf> {
   Ok(buf)
 }
 
+/// The effective user of a process owns its `/proc/<pid>` directory
+pub fn read_uid(pid: Pid) -> std::io::Result<Uid> {
+  let metadata = std::fs::metadata(format!("/proc/{pid}"))?;
+  Ok(Uid::from_raw(metadata.uid()))
+}
+
+/// Read the pid of a process in its own PID namespace from `/proc/<pid>/status`,
+/// which is None if it is in the same PID namespace as tracexec
+pub fn read_ns_pid(pid: Pid) -> std::io::Result<Option<Pid>> {
+  parse_ns_pid(&std::fs::read_to_string(format!("/proc/{pid}/status"))?)
+}
+
+fn parse_ns_pid(status: &str) -> std::io::Result<Option<Pid>> {
+  // NSpid lists the pid in each PID namespace, from the one of /proc to the innermost one
+  let Some(nspid) = status.lines().find_map(|line| line.strip_prefix("NSpid:")) else {
+    return Ok(None);
+  };
+  let pids: Vec<&str> = nspid.split_whitespace().collect();
+  if pids.len() < 2 {
+    return Ok(None);
+  }
+  pids
+    .last()
+    .unwrap()
+    .parse()
+    .map(|pid| Some(Pid::from_raw(pid)))
+    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid NSpid"))
+}
+
+/// Read the thread that traces a process from `/proc/<pid>/status`, None if it is not traced
+pub fn read_tracer_pid(pid: Pid) -> std::io::Result<Option<Pid>> {
+  let status = std::fs::read_to_string(format!("/proc/{pid}/status"))?;
+  let tracer: pid_t = status
+    .lines()
+    .find_map(|line| line.strip_prefix("TracerPid:"))
+    .and_then(|tracer| tracer.trim().parse().ok())
+    .unwrap_or(0);
+  Ok((tracer != 0).then(|| Pid::from_raw(tracer)))
+}
+
 pub fn read_exe(pid: Pid) -> std::io::Result<PathBuf> {
   let filename = format!("/proc/{pid}/exe");
   let buf = std::fs::read_link(filename)?;
   Ok(buf)
 }
 
-#[derive(Debug, Clone, Default, PartialEq)]
+/// Read the user and system CPU time of a process from `/proc/<pid>/stat`
+pub fn read_cpu_time(pid: Pid) -> std::io::Result<Duration> {
+  let stat = std::fs::read_to_string(format!("/proc/{pid}/stat"))?;
+  // comm may contain spaces and parentheses, so skip to the last ')'.
+  // The first field after it is the 3rd field(state). utime and stime are the 14th and 15th fields.
+  let mut fields = stat
+    .rsplit_once(')')
+    .map(|(_, rest)| rest)
+    .unwrap_or_default()
+    .split_ascii_whitespace()
+    .skip(11);
+  let mut next_ticks = || -> std::io::Result<u64> {
+    fields
+      .next()
+      .and_then(|x| x.parse().ok())
+      .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Malformed /proc/<pid>/stat"))
+  };
+  let ticks = next_ticks()? + next_ticks()?;
+  let ticks_per_sec = sysconf(SysconfVar::CLK_TCK)
+    .ok()
+    .flatten()
+    .filter(|&x| x > 0)
+    .unwrap_or(100) as u64;
+  Ok(Duration::from_nanos(ticks * 1_000_000_000 / ticks_per_sec))
+}
+
+/// Read when a process started, in clock ticks after boot, from `/proc/<pid>/stat`.
+/// Together with the pid, it tells apart the processes that got the same pid.
+pub fn read_start_time(pid: Pid) -> std::io::Result<u64> {
+  let stat = std::fs::read_to_string(format!("/proc/{pid}/stat"))?;
+  // starttime is the 22nd field, see read_cpu_time for skipping comm
+  stat
+    .rsplit_once(')')
+    .map(|(_, rest)| rest)
+    .unwrap_or_default()
+    .split_ascii_whitespace()
+    .nth(19)
+    .and_then(|x| x.parse().ok())
+    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Malformed /proc/<pid>/stat"))
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct FileDescriptorInfoCollection {
   pub fdinfo: BTreeMap<c_int, FileDescriptorInfo>,
 }
@@ -98,11 +191,12 @@ impl FileDescriptorInfoCollection {
   }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FileDescriptorInfo {
   pub fd: c_int,
   pub path: PathBuf,
   pub pos: usize,
+  #[serde(with = "nix_serde::raw")]
   pub flags: OFlag,
   pub mnt_id: c_int,
   pub ino: c_int,
@@ -182,7 +276,7 @@ pub fn get_mountinfo_by_mnt_id(pid: Pid, mnt_id: c_int) -> color_eyre::Result<St
   Ok("Not found. This is probably a pipe or something else.".to_string())
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Interpreter {
   None,
   Shebang(String),
@@ -267,6 +361,26 @@ pub fn read_interpreter(exe: &Path) -> Interpreter {
   Interpreter::Shebang(shebang.into_owned())
 }
 
+/// Resolve the filename passed to exec to an absolute path with all symlinks resolved.
+///
+/// Bare names that can't be found in cwd are looked up in the PATH of the tracee.
+pub fn resolve_executable(filename: &Path, cwd: &Path, envp: &[String]) -> Option<PathBuf> {
+  if let Ok(path) = cwd.join(filename).canonicalize() {
+    return Some(path);
+  }
+  if filename.as_os_str().as_bytes().contains(&b'/') {
+    return None;
+  }
+  let (_, path) = envp
+    .iter()
+    .map(|entry| parse_env_entry(entry))
+    .find(|(key, _)| *key == "PATH")?;
+  path
+    .split(':')
+    .filter(|dir| !dir.is_empty())
+    .find_map(|dir| cwd.join(dir).join(filename).canonicalize().ok())
+}
+
 pub fn parse_env_entry(item: &str) -> (&str, &str) {
   trace!("Parsing envp entry: {:?}", item);
   let Some(mut sep_loc) = item.as_bytes().iter().position(|&x| x == b'=') else {
@@ -295,7 +409,7 @@ pub fn parse_env_entry(item: &str) -> (&str, &str) {
   (head, &tail[1..])
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EnvDiff {
   pub added: BTreeMap<String, String>,
   pub removed: BTreeSet<String>,
@@ -333,25 +447,40 @@ pub fn diff_env(original: &BTreeMap<String, String>, envp: &[String]) -> EnvDiff
   }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BaselineInfo {
   pub cwd: PathBuf,
   pub env: BTreeMap<String, String>,
   pub fdinfo: FileDescriptorInfoCollection,
+  /// The original values of the redacted variables of `env`
+  #[serde(skip)]
+  pub secrets: Secrets,
 }
 
 impl BaselineInfo {
   pub fn new() -> color_eyre::Result<Self> {
     let cwd = std::env::current_dir()?;
-    let env = std::env::vars().collect();
+    let mut env = std::env::vars().collect();
+    let secrets = redactor().redact_env(&mut env);
     let fdinfo = FileDescriptorInfoCollection::new_baseline()?;
-    Ok(Self { cwd, env, fdinfo })
+    Ok(Self {
+      cwd,
+      env,
+      fdinfo,
+      secrets,
+    })
   }
 
   pub fn with_pts(pts: &UnixSlavePty) -> color_eyre::Result<Self> {
     let cwd = std::env::current_dir()?;
-    let env = std::env::vars().collect();
+    let mut env = std::env::vars().collect();
+    let secrets = redactor().redact_env(&mut env);
     let fdinfo = FileDescriptorInfoCollection::with_pts(pts)?;
-    Ok(Self { cwd, env, fdinfo })
+    Ok(Self {
+      cwd,
+      env,
+      fdinfo,
+      secrets,
+    })
   }
 }
@@ -0,0 +1,22 @@
+use nix::unistd::{getpid, Pid};
+
+use super::{parse_ns_pid, read_ns_pid};
+
+#[test]
+fn ns_pid_is_the_innermost_pid() {
+  let status = "Name:\tsleep\nPid:\t12345\nNSpid:\t12345\t42\t7\nPPid:\t1\n";
+  assert_eq!(parse_ns_pid(status).unwrap(), Some(Pid::from_raw(7)));
+}
+
+#[test]
+fn ns_pid_is_none_in_the_same_namespace() {
+  assert_eq!(parse_ns_pid("Pid:\t12345\nNSpid:\t12345\n").unwrap(), None);
+  // Kernels before 4.1 have no NSpid
+  assert_eq!(parse_ns_pid("Pid:\t12345\n").unwrap(), None);
+  assert_eq!(read_ns_pid(getpid()).unwrap(), None);
+}
+
+#[test]
+fn invalid_ns_pid_is_an_error() {
+  assert!(parse_ns_pid("NSpid:\t12345\tnope\n").is_err());
+}
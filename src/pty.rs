@@ -47,6 +47,10 @@ use std::io::Result as IoResult;
 
 use crate::cmdbuilder::CommandBuilder;
 
+mod relay;
+
+pub use relay::{terminal_pty_size, StdioRelay};
+
 /// Represents the size of the visible display area in the pty
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PtySize {
@@ -0,0 +1,112 @@
+//! Passing the stdio of tracexec through to a pseudo terminal, for `tracexec log --pty`
+
+use std::{
+  io::{stdin, stdout, IsTerminal, Read, Write},
+  thread::JoinHandle,
+};
+
+use nix::sys::termios::{tcgetattr, tcsetattr, InputFlags, LocalFlags, SetArg, Termios};
+use signal_hook::{consts::SIGWINCH, iterator::Signals};
+
+use super::{MasterPty, PtyFd, PtySize, UnixMasterPty};
+
+/// The size of tracexec's own terminal, 80x24 if it isn't a terminal
+pub fn terminal_pty_size() -> PtySize {
+  let (cols, rows) = crossterm::terminal::size().unwrap_or((80, 24));
+  PtySize {
+    rows,
+    cols,
+    pixel_width: 0,
+    pixel_height: 0,
+  }
+}
+
+pub struct StdioRelay {
+  output: Option<JoinHandle<()>>,
+  /// Stops following the size of the terminal
+  resize: Option<signal_hook::iterator::Handle>,
+  /// The original attributes of the terminal on stdin, which are restored when the relay is dropped
+  termios: Option<Termios>,
+}
+
+impl StdioRelay {
+  /// Copy the output of the pseudo terminal to stdout and stdin to the pseudo terminal.
+  ///
+  /// When stdin is a terminal, it stops handling the keys itself so that
+  /// line editing, echo and signals like Ctrl+C are left to the pseudo terminal.
+  /// With `follow_size`, the pseudo terminal is resized along with the terminal,
+  /// which sends SIGWINCH to its foreground process group.
+  pub fn start(master: &UnixMasterPty, follow_size: bool) -> color_eyre::Result<Self> {
+    let mut reader = master.try_clone_reader()?;
+    let mut writer = master.take_writer()?;
+    let termios = if stdin().is_terminal() {
+      let original = tcgetattr(stdin())?;
+      let mut raw = original.clone();
+      raw
+        .local_flags
+        .remove(LocalFlags::ICANON | LocalFlags::ECHO | LocalFlags::ISIG | LocalFlags::IEXTEN);
+      raw.input_flags.remove(InputFlags::ICRNL | InputFlags::IXON);
+      // The output processing is kept, which the log of tracexec on the same terminal relies on
+      tcsetattr(stdin(), SetArg::TCSANOW, &raw)?;
+      Some(original)
+    } else {
+      None
+    };
+    // This thread is blocked on stdin until tracexec exits.
+    // The writer sends EOF to the pseudo terminal when stdin is closed.
+    std::thread::spawn(move || {
+      let _ = std::io::copy(&mut stdin(), &mut writer);
+    });
+    let output = std::thread::spawn(move || {
+      let mut buf = [0u8; 4096];
+      // The reader reaches EOF once every process closed the pseudo terminal
+      while let Ok(n @ 1..) = reader.read(&mut buf) {
+        let mut stdout = stdout().lock();
+        // Flush right away because prompts don't end with a newline
+        if stdout
+          .write_all(&buf[..n])
+          .and_then(|_| stdout.flush())
+          .is_err()
+        {
+          break;
+        }
+      }
+    });
+    let resize = if follow_size {
+      let resizer = PtyFd(master.fd.try_clone()?);
+      let mut signals = Signals::new([SIGWINCH])?;
+      let handle = signals.handle();
+      std::thread::spawn(move || {
+        for _ in signals.forever() {
+          let _ = resizer.resize(terminal_pty_size());
+        }
+      });
+      Some(handle)
+    } else {
+      None
+    };
+    Ok(Self {
+      output: Some(output),
+      resize,
+      termios,
+    })
+  }
+
+  /// Wait for the remaining output, which requires the slave of the pseudo terminal to be closed
+  pub fn finish(mut self) {
+    if let Some(output) = self.output.take() {
+      let _ = output.join();
+    }
+  }
+}
+
+impl Drop for StdioRelay {
+  fn drop(&mut self) {
+    if let Some(resize) = self.resize.take() {
+      resize.close();
+    }
+    if let Some(termios) = self.termios.take() {
+      let _ = tcsetattr(stdin(), SetArg::TCSANOW, &termios);
+    }
+  }
+}
@@ -0,0 +1,249 @@
+//! Recorded traces, which store the complete event stream of a trace for analyzing it later.
+//!
+//! A recorded trace is a zstd compressed stream of JSON lines.
+//! The first line is the [`TraceHeader`] and every following line is a [`TracerEvent`].
+
+use std::{
+  collections::HashMap,
+  fs::File,
+  io::{BufRead, BufReader, Lines, Write},
+  path::Path,
+  sync::Arc,
+};
+
+use color_eyre::eyre::{bail, OptionExt, WrapErr};
+use enumflags2::BitFlags;
+use nix::unistd::Pid;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+  cli::options::Compression,
+  event::{TracerEvent, TracerEventKind},
+  export::Exporter,
+  filter::ExecFilter,
+  output::OutputFile,
+  printer::{Printer, PrinterArgs, PrinterOut},
+  proc::BaselineInfo,
+  tracer::state::{ExecData, ProcessState},
+};
+
+pub mod diff;
+
+/// Bumped when the format of recorded traces changes incompatibly
+pub const TRACE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceHeader {
+  pub version: u32,
+  /// The command that is traced
+  pub cmd: Vec<String>,
+  /// The environment, cwd and stdio the events are compared against
+  pub baseline: BaselineInfo,
+}
+
+pub struct TraceRecorder {
+  out: OutputFile,
+}
+
+impl TraceRecorder {
+  pub fn create(path: &Path, cmd: Vec<String>, baseline: BaselineInfo) -> color_eyre::Result<Self> {
+    let mut out = OutputFile::create(path, Compression::Zstd)?;
+    let header = TraceHeader {
+      version: TRACE_FORMAT_VERSION,
+      cmd,
+      baseline,
+    };
+    serde_json::to_writer(&mut out, &header)?;
+    writeln!(out)?;
+    Ok(Self { out })
+  }
+}
+
+impl Exporter for TraceRecorder {
+  fn on_event(&mut self, event: &TracerEvent) -> color_eyre::Result<()> {
+    serde_json::to_writer(&mut self.out, event)?;
+    writeln!(self.out)?;
+    Ok(())
+  }
+
+  /// The compressed stream is finished when the recorder is dropped
+  fn finish(&mut self) -> color_eyre::Result<()> {
+    self.out.flush()?;
+    Ok(())
+  }
+}
+
+/// Reads the events of a recorded trace one by one
+pub struct TraceReader {
+  pub header: TraceHeader,
+  lines: Lines<BufReader<zstd::Decoder<'static, BufReader<File>>>>,
+}
+
+impl TraceReader {
+  pub fn open(path: &Path) -> color_eyre::Result<Self> {
+    let file = File::open(path).wrap_err_with(|| format!("Failed to open trace {path:?}"))?;
+    let mut lines = BufReader::new(zstd::Decoder::new(file)?).lines();
+    let header = lines.next().ok_or_eyre("The trace is empty")??;
+    let header: TraceHeader =
+      serde_json::from_str(&header).wrap_err_with(|| format!("{path:?} is not a trace"))?;
+    if header.version != TRACE_FORMAT_VERSION {
+      bail!(
+        "The trace is recorded in format version {}, but only version {TRACE_FORMAT_VERSION} is supported",
+        header.version
+      );
+    }
+    Ok(Self { header, lines })
+  }
+}
+
+impl Iterator for TraceReader {
+  type Item = color_eyre::Result<TracerEvent>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    match self.lines.next()? {
+      Ok(line) => Some(serde_json::from_str(&line).map_err(Into::into)),
+      // The compressed stream is not finished if tracexec is killed while recording,
+      // the events before that are still usable.
+      Err(e) => {
+        warn!("The trace ends abruptly: {e}");
+        None
+      }
+    }
+  }
+}
+
+/// Prints the events of a recorded trace like log mode does while tracing
+pub struct LogReplay {
+  printer: Printer,
+  baseline: Arc<BaselineInfo>,
+  filter: BitFlags<TracerEventKind>,
+  exec_filter: ExecFilter,
+  /// The processes are rebuilt from the events, which is enough for the printer
+  processes: HashMap<Pid, ProcessState>,
+  root_comm: String,
+}
+
+impl LogReplay {
+  pub fn new(
+    args: PrinterArgs,
+    header: TraceHeader,
+    filter: BitFlags<TracerEventKind>,
+    output: Box<PrinterOut>,
+  ) -> Self {
+    let baseline = Arc::new(header.baseline);
+    let printer = Printer::new(args, baseline.clone());
+    printer.init_thread_local(Some(output));
+    Self {
+      printer,
+      baseline,
+      filter,
+      exec_filter: ExecFilter::default(),
+      processes: HashMap::new(),
+      root_comm: header
+        .cmd
+        .first()
+        .map(|cmd| comm_of(cmd))
+        .unwrap_or_default(),
+    }
+  }
+
+  /// Only print the exec events that pass the filter
+  pub fn with_exec_filter(mut self, exec_filter: ExecFilter) -> Self {
+    self.exec_filter = exec_filter;
+    self
+  }
+
+  fn process(&mut self, pid: Pid, comm: &str) -> &mut ProcessState {
+    self
+      .processes
+      .entry(pid)
+      .or_insert_with(|| ProcessState::recorded(pid, None, 0, comm.to_owned()))
+  }
+
+  pub fn on_event(&mut self, event: &TracerEvent) -> color_eyre::Result<()> {
+    match event {
+      TracerEvent::TraceeSpawn(pid) => {
+        let comm = self.root_comm.clone();
+        self.process(*pid, &comm);
+      }
+      TracerEvent::NewChild {
+        ppid, pcomm, pid, ..
+      } => {
+        let parent = self.process(*ppid, pcomm);
+        parent.comm.clone_from(pcomm);
+        let depth = parent.depth + 1;
+        if self.filter.contains(TracerEventKind::NewChild) {
+          self.printer.print_new_child(&self.processes[ppid], *pid)?;
+        }
+        self.processes.insert(
+          *pid,
+          ProcessState::recorded(*pid, Some(*ppid), depth, pcomm.clone()),
+        );
+      }
+      TracerEvent::Exec(exec) => {
+        let state = self.process(exec.pid, &exec.comm);
+        state.comm.clone_from(&exec.comm);
+        state.ns_pid = exec.ns_pid;
+        state.exec_data = Some(ExecData {
+          filename: exec.filename.clone(),
+          resolved_filename: exec.resolved_filename.clone(),
+          argv: exec.argv.clone(),
+          envp: exec.envp.clone(),
+          secrets: exec.secrets.clone(),
+          cwd: exec.cwd.clone(),
+          interpreters: exec.interpreter.clone(),
+          fdinfo: exec.fdinfo.clone(),
+          timestamp: exec.timestamp,
+        });
+        let skipped =
+          (self.printer.args.successful_only && exec.result != 0) || !self.exec_filter.keeps(event);
+        if self.filter.contains(TracerEventKind::Exec) && !skipped {
+          let state = &self.processes[&exec.pid];
+          self.printer.print_exec_trace(
+            state,
+            exec.result,
+            &self.baseline.env,
+            &self.baseline.cwd,
+          )?;
+        }
+        let state = self.processes.get_mut(&exec.pid).unwrap();
+        state.exec_data = None;
+        if exec.result == 0 {
+          // Like the kernel does, until a later event tells the real comm
+          if let Ok(filename) = exec.filename.as_ref() {
+            state.comm = comm_of(&filename.to_string_lossy());
+          }
+        }
+      }
+      TracerEvent::ProcessExit {
+        pid, comm, status, ..
+      } => {
+        let mut state = self
+          .processes
+          .remove(pid)
+          .unwrap_or_else(|| ProcessState::recorded(*pid, None, 0, comm.clone()));
+        state.comm.clone_from(comm);
+        self.printer.print_exit(&state, *status)?;
+      }
+      _ => {}
+    }
+    Ok(())
+  }
+
+  pub fn finish(&self) -> color_eyre::Result<()> {
+    self.printer.finish()
+  }
+}
+
+/// The comm that the kernel gives to a process executing the file
+fn comm_of(filename: &str) -> String {
+  let name = filename.rsplit('/').next().unwrap_or(filename);
+  // TASK_COMM_LEN is 16 bytes, including the NUL terminator.
+  // The kernel cuts the name at 15 bytes, which may be in the middle of a character.
+  let mut end = name.len().min(15);
+  while !name.is_char_boundary(end) {
+    end -= 1;
+  }
+  name[..end].to_string()
+}
@@ -0,0 +1,153 @@
+//! Comparing the execs of two recorded traces, e.g. of a good and a bad build
+
+use std::{collections::BTreeMap, io::Write, path::PathBuf};
+
+use owo_colors::OwoColorize;
+use similar::{capture_diff_slices, Algorithm, DiffTag};
+
+use crate::{
+  event::{ExecEvent, TracerEvent},
+  export::exec_summary,
+  printer::{escape_str_for_bash, palette},
+  proc::{diff_env, parse_env_entry},
+};
+
+use super::TraceReader;
+
+/// The execs are aligned by the executed file, so execs of the same program are compared to each other
+fn exec_key(exec: &ExecEvent) -> Option<PathBuf> {
+  exec.filename.as_ref().ok().cloned()
+}
+
+fn read_execs(reader: TraceReader) -> color_eyre::Result<Vec<Box<ExecEvent>>> {
+  let mut execs = vec![];
+  for event in reader {
+    if let TracerEvent::Exec(exec) = event? {
+      execs.push(exec);
+    }
+  }
+  Ok(execs)
+}
+
+/// Print the differences between the execs of two traces, returns whether there are any.
+///
+/// The removed, added and modified lines are styled like the environment in log mode.
+pub fn diff_traces(
+  old: TraceReader,
+  new: TraceReader,
+  ignore_env: bool,
+  out: &mut dyn Write,
+) -> color_eyre::Result<bool> {
+  let palette = palette();
+  let old = read_execs(old)?;
+  let new = read_execs(new)?;
+  let old_keys: Vec<_> = old.iter().map(|exec| exec_key(exec)).collect();
+  let new_keys: Vec<_> = new.iter().map(|exec| exec_key(exec)).collect();
+  let mut differs = false;
+  for op in capture_diff_slices(Algorithm::Patience, &old_keys, &new_keys) {
+    let (tag, old_range, new_range) = op.as_tag_tuple();
+    if tag == DiffTag::Equal {
+      for (old_exec, new_exec) in old[old_range].iter().zip(&new[new_range]) {
+        differs |= diff_exec(old_exec, new_exec, ignore_env, out)?;
+      }
+      continue;
+    }
+    differs = true;
+    for exec in &old[old_range] {
+      writeln!(
+        out,
+        "{}",
+        format!("- {}", exec_summary(exec)).style(palette.removed_env)
+      )?;
+    }
+    for exec in &new[new_range] {
+      writeln!(
+        out,
+        "{}",
+        format!("+ {}", exec_summary(exec)).style(palette.added_env)
+      )?;
+    }
+  }
+  Ok(differs)
+}
+
+/// Print the differences between two execs of the same file
+fn diff_exec(
+  old: &ExecEvent,
+  new: &ExecEvent,
+  ignore_env: bool,
+  out: &mut dyn Write,
+) -> color_eyre::Result<bool> {
+  let palette = palette();
+  let argv_differs = old.argv != new.argv;
+  let result_differs = old.result != new.result;
+  // The environment is compared as a whole, instead of the diffs against the baselines.
+  // Recordings only have the redacted values, so changed secrets can't be told apart here.
+  let env = match (ignore_env, old.envp.as_ref(), new.envp.as_ref()) {
+    (false, Ok(old_envp), Ok(new_envp)) => {
+      let old_env: BTreeMap<String, String> = old_envp
+        .iter()
+        .map(|entry| {
+          let (key, value) = parse_env_entry(entry);
+          (key.to_owned(), value.to_owned())
+        })
+        .collect();
+      let diff = diff_env(&old_env, new_envp);
+      Some((old_env, diff))
+    }
+    _ => None,
+  };
+  let env_differs = env.as_ref().is_some_and(|(_, diff)| {
+    !(diff.added.is_empty() && diff.removed.is_empty() && diff.modified.is_empty())
+  });
+  if !(argv_differs || result_differs || env_differs) {
+    return Ok(false);
+  }
+  writeln!(
+    out,
+    "{}",
+    format!("~ {}", TracerEvent::filename_to_cow(&new.filename)).style(palette.modified_env)
+  )?;
+  if argv_differs || result_differs {
+    writeln!(
+      out,
+      "  {}",
+      format!("- {}", exec_summary(old)).style(palette.removed_env)
+    )?;
+    writeln!(
+      out,
+      "  {}",
+      format!("+ {}", exec_summary(new)).style(palette.added_env)
+    )?;
+  }
+  if let Some((old_env, diff)) = env {
+    for (key, value) in diff.added.iter() {
+      let entry = format!("{key}={value}");
+      writeln!(
+        out,
+        "  {}",
+        format!("+ env {}", escape_str_for_bash!(entry.as_str())).style(palette.added_env)
+      )?;
+    }
+    for key in diff.removed.iter() {
+      writeln!(
+        out,
+        "  {}",
+        format!("- env {key}").style(palette.removed_env)
+      )?;
+    }
+    for (key, value) in diff.modified.iter() {
+      writeln!(
+        out,
+        "  {}",
+        format!(
+          "~ env {key}: {} -> {}",
+          escape_str_for_bash!(old_env[key].as_str()),
+          escape_str_for_bash!(value.as_str())
+        )
+        .yellow()
+      )?;
+    }
+  }
+  Ok(true)
+}
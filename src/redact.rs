@@ -0,0 +1,167 @@
+//! Masking of secrets in the environment variables of tracees, which is on by default.
+//!
+//! Redaction happens right after envp is read, so log output, copies from the TUI,
+//! exporters and recordings never see the original values. The original values are kept
+//! aside as [`Secrets`] so that a secret that changes still shows up in the diffs.
+
+use std::{collections::BTreeMap, sync::OnceLock};
+
+use crate::{
+  cli::{args::ModifierArgs, config::RedactConfig},
+  proc::{parse_env_entry, EnvDiff},
+};
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Environment variables whose values are redacted unless overridden in the config file
+pub const DEFAULT_PATTERNS: &[&str] = &[
+  "*TOKEN*",
+  "*SECRET*",
+  "*PASSWORD*",
+  "*PASSWD*",
+  "*API_KEY*",
+  "*APIKEY*",
+  "*PRIVATE_KEY*",
+  "*CREDENTIAL*",
+  "AWS_*",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+  /// Upper case glob patterns of environment variable names
+  patterns: Vec<String>,
+}
+
+impl Redactor {
+  pub fn new(patterns: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+    Self {
+      patterns: patterns
+        .into_iter()
+        .map(|p| p.as_ref().to_ascii_uppercase())
+        .collect(),
+    }
+  }
+
+  pub fn from_cli(modifier_args: &ModifierArgs, config: &RedactConfig) -> Self {
+    if modifier_args.no_redact {
+      return Self::default();
+    }
+    let patterns = match config.patterns.as_ref() {
+      Some(patterns) => patterns.iter().map(String::as_str).collect::<Vec<_>>(),
+      None => DEFAULT_PATTERNS.to_vec(),
+    };
+    Self::new(
+      patterns
+        .into_iter()
+        .chain(modifier_args.redact.iter().map(String::as_str)),
+    )
+  }
+
+  /// Whether the value of the environment variable `key` should be redacted.
+  /// Patterns are matched case-insensitively.
+  pub fn should_redact(&self, key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+    self
+      .patterns
+      .iter()
+      .any(|pattern| glob_match(pattern.as_bytes(), key.as_bytes()))
+  }
+
+  pub fn redact_env(&self, env: &mut BTreeMap<String, String>) -> Secrets {
+    let mut secrets = Secrets::default();
+    for (k, v) in env.iter_mut() {
+      if self.should_redact(k) {
+        secrets
+          .0
+          .insert(k.clone(), std::mem::replace(v, REDACTED.to_string()));
+      }
+    }
+    secrets
+  }
+
+  pub fn redact_envp(&self, envp: Vec<String>) -> (Vec<String>, Secrets) {
+    let mut secrets = Secrets::default();
+    if self.patterns.is_empty() {
+      return (envp, secrets);
+    }
+    let envp = envp
+      .into_iter()
+      .map(|entry| {
+        let (k, v) = parse_env_entry(&entry);
+        if self.should_redact(k) {
+          secrets.0.insert(k.to_owned(), v.to_owned());
+          format!("{k}={REDACTED}")
+        } else {
+          entry
+        }
+      })
+      .collect();
+    (envp, secrets)
+  }
+}
+
+/// The original values of the redacted environment variables.
+///
+/// They are only compared and matched against, never shown, exported or recorded,
+/// so the diffs of recorded traces can't tell whether a secret changed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Secrets(BTreeMap<String, String>);
+
+impl Secrets {
+  /// Add the redacted variables whose original values differ to the diff of the redacted environments,
+  /// which misses them because both sides are redacted the same
+  pub fn add_changes(&self, new: &Secrets, diff: &mut EnvDiff) {
+    for (key, value) in new.0.iter() {
+      if self.0.get(key).is_some_and(|old| old != value) {
+        diff.modified.insert(key.clone(), REDACTED.to_string());
+      }
+    }
+  }
+
+  /// The original entries of the redacted variables, like they are in envp
+  pub fn entries(&self) -> impl Iterator<Item = String> + '_ {
+    self.0.iter().map(|(key, value)| format!("{key}={value}"))
+  }
+}
+
+/// Match `text` against a glob `pattern` that supports `*` and `?`
+pub fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+  let (mut p, mut t) = (0, 0);
+  // Position of the last `*` in pattern and the text position it is currently matched up to
+  let mut backtrack = None;
+  while t < text.len() {
+    match pattern.get(p) {
+      Some(b'*') => {
+        backtrack = Some((p, t));
+        p += 1;
+      }
+      Some(&c) if c == b'?' || c == text[t] => {
+        p += 1;
+        t += 1;
+      }
+      _ => match backtrack {
+        Some((star_p, star_t)) => {
+          // Let the `*` consume one more byte
+          backtrack = Some((star_p, star_t + 1));
+          p = star_p + 1;
+          t = star_t + 1;
+        }
+        None => return false,
+      },
+    }
+  }
+  pattern[p..].iter().all(|&c| c == b'*')
+}
+
+static REDACTOR: OnceLock<Redactor> = OnceLock::new();
+
+/// Set the redactor before the tracer starts. Only the first call has an effect,
+/// so that the redaction can't change in the middle of a trace.
+pub fn set_redactor(redactor: Redactor) {
+  REDACTOR.set(redactor).ok();
+}
+
+/// Redaction is disabled if the redactor is never set
+pub fn redactor() -> &'static Redactor {
+  REDACTOR.get_or_init(Redactor::default)
+}
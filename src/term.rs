@@ -0,0 +1,241 @@
+//! What the terminal tracexec runs on is capable of, detected from the environment.
+//!
+//! Both the log mode and the TUI use it to degrade gracefully on less capable terminals,
+//! e.g. downgrading the RGB colors of the config file or avoiding unicode glyphs.
+
+use std::{ffi::OsStr, sync::OnceLock};
+
+#[cfg(test)]
+mod test;
+
+/// How many colors the terminal supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorLevel {
+  /// The 8 basic colors and their bright variants
+  Ansi16,
+  /// The 256 colors of xterm
+  Ansi256,
+  /// 24-bit RGB colors
+  TrueColor,
+}
+
+/// A color that the terminal supports, downgraded from an RGB color if necessary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermColor {
+  Rgb(u8, u8, u8),
+  /// An index into the 256 colors of xterm
+  Xterm(u8),
+  /// An index into the 16 basic colors, bright variants being 8 to 15
+  Ansi(u8),
+}
+
+/// The levels of the channels in the 6x6x6 color cube of xterm
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The usual RGB values of the 16 basic colors, as xterm shows them
+const ANSI_RGB: [(u8, u8, u8); 16] = [
+  (0, 0, 0),
+  (205, 0, 0),
+  (0, 205, 0),
+  (205, 205, 0),
+  (0, 0, 238),
+  (205, 0, 205),
+  (0, 205, 205),
+  (229, 229, 229),
+  (127, 127, 127),
+  (255, 0, 0),
+  (0, 255, 0),
+  (255, 255, 0),
+  (92, 92, 255),
+  (255, 0, 255),
+  (0, 255, 255),
+  (255, 255, 255),
+];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+  let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+  d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// The RGB value of one of the 256 colors of xterm
+fn xterm_to_rgb(index: u8) -> (u8, u8, u8) {
+  match index {
+    0..=15 => ANSI_RGB[index as usize],
+    16..=231 => {
+      let i = index - 16;
+      (
+        CUBE_LEVELS[(i / 36) as usize],
+        CUBE_LEVELS[(i / 6 % 6) as usize],
+        CUBE_LEVELS[(i % 6) as usize],
+      )
+    }
+    _ => {
+      let gray = 8 + 10 * (index - 232);
+      (gray, gray, gray)
+    }
+  }
+}
+
+/// The nearest of the 256 colors of xterm, from the color cube or the grayscale ramp
+fn rgb_to_xterm(rgb: (u8, u8, u8)) -> u8 {
+  let level = |c: u8| {
+    (0..6)
+      .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs())
+      .unwrap()
+  };
+  let cube = 16 + 36 * level(rgb.0) + 6 * level(rgb.1) + level(rgb.2);
+  let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+  let gray = 232 + ((average.saturating_sub(3) / 10).min(23)) as u8;
+  [cube as u8, gray]
+    .into_iter()
+    .min_by_key(|&index| distance(rgb, xterm_to_rgb(index)))
+    .unwrap()
+}
+
+/// The nearest of the 16 basic colors
+fn rgb_to_ansi(rgb: (u8, u8, u8)) -> u8 {
+  (0..16)
+    .min_by_key(|&index| distance(rgb, ANSI_RGB[index as usize]))
+    .unwrap()
+}
+
+impl ColorLevel {
+  /// Downgrade an RGB color to the nearest color of this level
+  pub fn rgb(self, r: u8, g: u8, b: u8) -> TermColor {
+    match self {
+      Self::TrueColor => TermColor::Rgb(r, g, b),
+      Self::Ansi256 => TermColor::Xterm(rgb_to_xterm((r, g, b))),
+      Self::Ansi16 => TermColor::Ansi(rgb_to_ansi((r, g, b))),
+    }
+  }
+
+  /// Downgrade one of the 256 colors of xterm to the nearest color of this level
+  pub fn xterm(self, index: u8) -> TermColor {
+    match (self, index) {
+      (_, 0..=15) => TermColor::Ansi(index),
+      (Self::Ansi16, _) => TermColor::Ansi(rgb_to_ansi(xterm_to_rgb(index))),
+      _ => TermColor::Xterm(index),
+    }
+  }
+}
+
+/// The glyphs used to draw the TUI, which have ASCII replacements for terminals without unicode
+#[derive(Debug)]
+pub struct Symbols {
+  /// The status of the running processes
+  pub running: &'static str,
+  pub ellipsis: &'static str,
+  pub separator: &'static str,
+  pub cursor: &'static str,
+  pub bar: &'static str,
+  pub tree_branch: &'static str,
+  pub tree_last: &'static str,
+  pub tree_vertical: &'static str,
+}
+
+static UNICODE_SYMBOLS: Symbols = Symbols {
+  running: "▶",
+  ellipsis: "…",
+  separator: " │ ",
+  cursor: "█",
+  bar: "█",
+  tree_branch: "├─",
+  tree_last: "└─",
+  tree_vertical: "│ ",
+};
+
+static ASCII_SYMBOLS: Symbols = Symbols {
+  running: ">",
+  ellipsis: "~",
+  separator: " | ",
+  cursor: "_",
+  bar: "#",
+  tree_branch: "|-",
+  tree_last: "`-",
+  tree_vertical: "| ",
+};
+
+#[derive(Debug)]
+pub struct TermCaps {
+  pub color_level: ColorLevel,
+  /// Whether glyphs beyond ASCII can be shown, i.e. the locale is not explicitly non-UTF-8
+  pub unicode: bool,
+  /// `TERM=dumb`, which can't move the cursor or show colors
+  pub dumb: bool,
+  /// Whether colors are forced on or off by `CLICOLOR_FORCE`, `NO_COLOR` or `CLICOLOR=0`,
+  /// which only matters when the color is `auto`
+  pub env_color: Option<bool>,
+}
+
+/// Whether the character set of a locale can show unicode glyphs.
+///
+/// An unset locale is assumed to be UTF-8, as most terminals are nowadays.
+fn locale_is_unicode(locale: &str) -> bool {
+  let locale = locale.to_ascii_lowercase();
+  locale.is_empty() || locale.contains("utf-8") || locale.contains("utf8")
+}
+
+/// Whether an environment variable is set to something other than an empty string
+fn env_is_set(name: &str) -> bool {
+  std::env::var_os(name).is_some_and(|value| !value.is_empty())
+}
+
+impl TermCaps {
+  pub fn detect() -> Self {
+    let term = std::env::var("TERM").unwrap_or_default();
+    let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+    let color_level =
+      if matches!(colorterm.as_str(), "truecolor" | "24bit") || term.ends_with("-direct") {
+        ColorLevel::TrueColor
+      } else if term.contains("256color") {
+        ColorLevel::Ansi256
+      } else {
+        ColorLevel::Ansi16
+      };
+    // The first of these that is set decides the character set, as in POSIX
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+      .into_iter()
+      .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+      .unwrap_or_default();
+    let dumb = term == "dumb";
+    let unicode = !dumb && locale_is_unicode(&locale);
+    // NO_COLOR wins over CLICOLOR_FORCE
+    let env_color = if env_is_set("NO_COLOR")
+      || std::env::var_os("CLICOLOR").as_deref() == Some(OsStr::new("0"))
+    {
+      Some(false)
+    } else if env_is_set("CLICOLOR_FORCE")
+      && std::env::var_os("CLICOLOR_FORCE").as_deref() != Some(OsStr::new("0"))
+    {
+      Some(true)
+    } else {
+      None
+    };
+    Self {
+      color_level,
+      unicode,
+      dumb,
+      env_color,
+    }
+  }
+
+  pub fn symbols(&self) -> &'static Symbols {
+    if self.unicode {
+      &UNICODE_SYMBOLS
+    } else {
+      &ASCII_SYMBOLS
+    }
+  }
+}
+
+static TERM_CAPS: OnceLock<TermCaps> = OnceLock::new();
+
+/// The capabilities of the terminal, which are detected once
+pub fn term_caps() -> &'static TermCaps {
+  TERM_CAPS.get_or_init(TermCaps::detect)
+}
+
+/// The glyphs that the terminal can show
+pub fn symbols() -> &'static Symbols {
+  term_caps().symbols()
+}
@@ -0,0 +1,28 @@
+use super::{locale_is_unicode, rgb_to_xterm, xterm_to_rgb};
+
+#[test]
+fn rgb_to_xterm_keeps_exact_colors() {
+  for index in 16..=255 {
+    assert_eq!(rgb_to_xterm(xterm_to_rgb(index)), index);
+  }
+}
+
+#[test]
+fn rgb_to_xterm_picks_the_nearest_color() {
+  assert_eq!(rgb_to_xterm((0, 0, 0)), 16);
+  assert_eq!(rgb_to_xterm((255, 255, 255)), 231);
+  assert_eq!(rgb_to_xterm((250, 5, 5)), 196);
+  // Grays between the levels of the color cube come from the grayscale ramp
+  assert_eq!(rgb_to_xterm((128, 128, 128)), 244);
+  assert_eq!(rgb_to_xterm((40, 40, 40)), 235);
+}
+
+#[test]
+fn unicode_unless_the_locale_is_not_utf8() {
+  assert!(locale_is_unicode(""));
+  assert!(locale_is_unicode("en_US.UTF-8"));
+  assert!(locale_is_unicode("C.utf8"));
+  assert!(!locale_is_unicode("C"));
+  assert!(!locale_is_unicode("POSIX"));
+  assert!(!locale_is_unicode("en_US.ISO-8859-1"));
+}
@@ -1,12 +1,17 @@
 use std::{
-  collections::BTreeMap,
+  collections::HashSet,
   ffi::CString,
   io::{self, stdin},
-  os::fd::AsRawFd,
-  path::PathBuf,
+  os::fd::{AsRawFd, OwnedFd},
+  path::{Path, PathBuf},
   process::exit,
-  sync::{Arc, RwLock},
+  sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    mpsc::{self, RecvTimeoutError},
+    Arc, Condvar, Mutex, OnceLock, RwLock,
+  },
   thread::{self, JoinHandle},
+  time::{Duration, SystemTime},
 };
 
 use cfg_if::cfg_if;
@@ -16,41 +21,58 @@ use nix::{
   libc::{
     self, dup2, pid_t, raise, SYS_clone, SYS_clone3, AT_EMPTY_PATH, SIGSTOP, S_ISGID, S_ISUID,
   },
+  sched::CloneFlags,
   sys::{
-    signal::Signal,
+    signal::{kill, Signal},
     stat::fstat,
     wait::{waitpid, WaitPidFlag, WaitStatus},
   },
   unistd::{
-    getpid, initgroups, setpgid, setresgid, setresuid, setsid, tcsetpgrp, Gid, Pid, Uid, User,
+    getpid, gettid, initgroups, setpgid, setresgid, setresuid, setsid, tcsetpgrp, Gid, Pid, Uid,
+    User,
   },
 };
 use tokio::sync::mpsc::UnboundedSender;
 use tracing::{debug, error, info, trace, warn};
 
 use crate::{
-  arch::{syscall_arg, syscall_no_from_regs, syscall_res_from_regs},
-  cli::args::{LogModeArgs, ModifierArgs, TracerEventArgs},
+  arch::{
+    stack_pointer_from_regs, syscall_arg, syscall_no_from_regs, syscall_res_from_regs,
+    PtraceRegisters,
+  },
+  cli::{
+    args::{LogModeArgs, ModifierArgs, TracerEventArgs},
+    options::MaxEventsAction,
+  },
   cmdbuilder::CommandBuilder,
-  event::{filterable_event, ExecEvent, TracerEvent, TracerEventKind, TracerMessage},
+  event::{
+    filterable_event, ExecEvent, ProcessExitStatus, TracerEvent, TracerEventKind, TracerMessage,
+  },
+  filter::ExecFilter,
   printer::{Printer, PrinterArgs, PrinterOut},
   proc::{
-    diff_env, read_comm, read_cwd, read_exe, read_fd, read_fds, read_interpreter_recursive,
+    diff_env, read_comm, read_cpu_time, read_cwd, read_exe, read_fd, read_fds,
+    read_interpreter_recursive, read_start_time, read_tracer_pid, read_uid, resolve_executable,
     BaselineInfo,
   },
   pty::{self, Child, UnixSlavePty},
+  redact::redactor,
 };
 
+use self::breakpoint::{unredact_envp, write_string_arrays};
 use self::inspect::{read_pathbuf, read_string, read_string_array};
 use self::ptrace::*;
 use self::state::{ExecData, ProcessState, ProcessStateStore, ProcessStatus};
 
+mod breakpoint;
 mod inspect;
+mod namespace;
 mod ptrace;
 pub mod state;
 #[cfg(test)]
 mod test;
 
+pub use breakpoint::{BreakpointDecision, BreakpointHit, Breakpoints};
 pub use inspect::InspectError;
 
 cfg_if! {
@@ -71,16 +93,71 @@ pub struct Tracer {
   printer: Printer,
   modifier_args: ModifierArgs,
   filter: BitFlags<TracerEventKind>,
+  /// Only the exec events that pass it are kept
+  exec_filter: ExecFilter,
   baseline: Arc<BaselineInfo>,
   #[cfg(feature = "seccomp-bpf")]
   seccomp_bpf: SeccompBpf,
   tx: UnboundedSender<TracerEvent>,
   user: Option<User>,
+  pause: Arc<TracerPause>,
+  breakpoints: Arc<Breakpoints>,
+  /// The traced processes get the signal if the command is still running after the duration
+  timeout: Option<(Duration, Signal)>,
+  timed_out: AtomicBool,
+  max_events: Option<(usize, MaxEventsAction)>,
+  nr_exec_events: AtomicUsize,
+  /// The number of problems that made the trace incomplete, only counted with --strict
+  nr_incomplete: AtomicUsize,
+  /// The number of lost events and the other problems that made the trace incomplete, always counted
+  nr_dropped: AtomicUsize,
+  /// Set when the limit is reached, until the tracer handles it
+  stop_pending: AtomicBool,
+  /// The tracer traces a process that it attached to rather than a command
+  attached: AtomicBool,
+  /// Set when the tracer is asked to detach from the processes it attached to
+  detach_pending: AtomicBool,
+  /// The command that is traced or the process that is attached to
+  root_pid: OnceLock<Pid>,
+  /// The exit code of the root process, 128 + the signal number if it is killed by a signal
+  root_exit_code: OnceLock<i32>,
+  /// Start the command of the user with a login environment
+  login: bool,
+  /// Replaces the stdin of the command
+  stdin: Option<OwnedFd>,
+}
+
+/// Lets the TUI pause the tracer, which then stops resuming the tracees
+#[derive(Debug, Default)]
+pub struct TracerPause {
+  paused: Mutex<bool>,
+  resumed: Condvar,
+}
+
+impl TracerPause {
+  pub fn is_paused(&self) -> bool {
+    *self.paused.lock().unwrap()
+  }
+
+  pub fn pause(&self) {
+    *self.paused.lock().unwrap() = true;
+  }
+
+  pub fn resume(&self) {
+    *self.paused.lock().unwrap() = false;
+    self.resumed.notify_all();
+  }
+
+  /// Blocks the calling thread until the tracer is resumed
+  fn wait_while_paused(&self) {
+    let paused = self.paused.lock().unwrap();
+    drop(self.resumed.wait_while(paused, |paused| *paused).unwrap());
+  }
 }
 
 pub enum TracerMode {
   Tui(Option<UnixSlavePty>),
-  Log,
+  Log(Option<UnixSlavePty>),
 }
 
 impl PartialEq for TracerMode {
@@ -88,7 +165,7 @@ impl PartialEq for TracerMode {
     // I think a plain match is more readable here
     #[allow(clippy::match_like_matches_macro)]
     match (self, other) {
-      (Self::Log, Self::Log) => true,
+      (Self::Log(_), Self::Log(_)) => true,
       _ => false,
     }
   }
@@ -105,10 +182,27 @@ impl Tracer {
     user: Option<User>,
   ) -> color_eyre::Result<Self> {
     let baseline = Arc::new(baseline);
+    // The seccomp filter is inherited by the untraced children, whose execs would fail without a tracer
+    #[cfg(feature = "seccomp-bpf")]
+    if modifier_args.no_follow_forks && modifier_args.seccomp_bpf == SeccompBpf::On {
+      color_eyre::eyre::bail!("seccomp-bpf can not be used with --no-follow-forks");
+    }
+    // The command runs in a child of the root child, which is not traced without following forks
+    if modifier_args.no_follow_forks && modifier_args.unshare_pid {
+      color_eyre::eyre::bail!("--unshare-pid can not be used with --no-follow-forks");
+    }
+    // The user has no mapping in the new user namespace
+    if user.is_some()
+      && modifier_args
+        .namespaces()
+        .contains(CloneFlags::CLONE_NEWUSER)
+    {
+      color_eyre::eyre::bail!("--user can not be used with --unshare-user or --map-root-user");
+    }
     Ok(Self {
       with_tty: match &mode {
         TracerMode::Tui(tty) => tty.is_some(),
-        TracerMode::Log => true,
+        TracerMode::Log(_) => true,
       },
       store: RwLock::new(ProcessStateStore::new()),
       #[cfg(feature = "seccomp-bpf")]
@@ -119,6 +213,8 @@ impl Tracer {
           // Seccomp-bpf enforces no-new-privs, so when using --user to trace set(u|g)id
           // binaries, we disable seccomp-bpf by default.
           SeccompBpf::Off
+        } else if modifier_args.no_follow_forks {
+          SeccompBpf::Off
         } else {
           SeccompBpf::On
         }
@@ -127,17 +223,37 @@ impl Tracer {
       },
       tx,
       user,
+      pause: Arc::default(),
+      breakpoints: Arc::default(),
+      timeout: None,
+      timed_out: AtomicBool::new(false),
+      max_events: None,
+      nr_exec_events: AtomicUsize::new(0),
+      nr_incomplete: AtomicUsize::new(0),
+      nr_dropped: AtomicUsize::new(0),
+      stop_pending: AtomicBool::new(false),
+      attached: AtomicBool::new(false),
+      detach_pending: AtomicBool::new(false),
+      root_pid: OnceLock::new(),
+      root_exit_code: OnceLock::new(),
+      login: false,
+      stdin: None,
       filter: {
         let mut filter = tracer_event_args.filter()?;
         trace!("Event filter: {:?}", filter);
-        if mode == TracerMode::Log {
-          // FIXME: In logging mode, we rely on root child exit event to exit the process
-          //        with the same exit code as the root child. It is not printed in logging mode.
-          //        Ideally we should use another channel to send the exit code to the main thread.
-          filter |= TracerEventKind::TraceeExit;
+        if let TracerMode::Log(_) = mode {
+          // Exporters rely on the exit events to know the lifetime of the root child and other processes.
+          // Signal events are only consumed by structured outputs, which record the full lifecycle.
+          filter |=
+            TracerEventKind::TraceeExit | TracerEventKind::ProcessExit | TracerEventKind::Signal;
+        } else {
+          // The TUI needs the lifetime of processes for its statistics and the process tree.
+          // Events that are not in the user's filter are not shown in the event list.
+          filter |= TracerEventKind::NewChild | TracerEventKind::ProcessExit;
         }
         filter
       },
+      exec_filter: tracer_event_args.exec_filter(),
       printer: Printer::new(
         PrinterArgs::from_cli(&tracing_args, &modifier_args),
         baseline.clone(),
@@ -153,6 +269,236 @@ impl Tracer {
     })
   }
 
+  /// The exit code of the root process once it exited
+  pub fn root_exit_code(&self) -> Option<i32> {
+    self.root_exit_code.get().copied()
+  }
+
+  /// The root process of the traced command while the tracer has not seen it exit,
+  /// so that its pid is not reused by another process yet
+  pub fn running_root(&self) -> Option<Pid> {
+    if self.attached.load(Ordering::Relaxed) || self.root_exit_code.get().is_some() {
+      return None;
+    }
+    self.root_pid.get().copied()
+  }
+
+  pub fn pause_handle(&self) -> Arc<TracerPause> {
+    self.pause.clone()
+  }
+
+  /// Share the breakpoints with the TUI and the tracers of the other tabs
+  pub fn with_breakpoints(mut self, breakpoints: Arc<Breakpoints>) -> Self {
+    self.breakpoints = breakpoints;
+    self
+  }
+
+  /// Start the command with a login environment of the user, like `su -l`
+  pub fn with_login(mut self, login: bool) -> Self {
+    self.login = login;
+    self
+  }
+
+  /// Connect the stdin of the command to the file descriptor instead
+  pub fn with_stdin(mut self, stdin: Option<OwnedFd>) -> Self {
+    self.stdin = stdin;
+    self
+  }
+
+  pub fn with_timeout(mut self, timeout: Option<(Duration, Signal)>) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  /// Whether the timeout fired before the command exited
+  pub fn timed_out(&self) -> bool {
+    self.timed_out.load(Ordering::Relaxed)
+  }
+
+  /// The number of problems that made the trace incomplete, which are only counted with --strict
+  pub fn incomplete(&self) -> usize {
+    self.nr_incomplete.load(Ordering::Relaxed)
+  }
+
+  /// The number of lost events and the other problems that made the trace incomplete, with or without --strict
+  pub fn dropped(&self) -> usize {
+    self.nr_dropped.load(Ordering::Relaxed)
+  }
+
+  /// Report a lost event or a stop that can't be handled, which is silently ignored without --strict
+  fn report_incomplete(&self, pid: Option<Pid>, msg: String) -> color_eyre::Result<()> {
+    self.nr_dropped.fetch_add(1, Ordering::Relaxed);
+    if !self.modifier_args.strict {
+      return Ok(());
+    }
+    self.nr_incomplete.fetch_add(1, Ordering::Relaxed);
+    self.printer.print_warning(pid, &msg)?;
+    self
+      .tx
+      .send(TracerEvent::Warning(TracerMessage { pid, msg }))?;
+    Ok(())
+  }
+
+  /// Stop tracing after the number of exec events
+  pub fn with_max_events(
+    mut self,
+    max_events: Option<(usize, MaxEventsAction)>,
+  ) -> color_eyre::Result<Self> {
+    // The seccomp filter stays in the detached processes, whose execs would fail without a tracer
+    #[cfg(feature = "seccomp-bpf")]
+    if let Some((_, MaxEventsAction::Detach)) = max_events {
+      if self.modifier_args.seccomp_bpf == SeccompBpf::On {
+        color_eyre::eyre::bail!("seccomp-bpf can not be used when detaching at --max-events");
+      }
+      self.seccomp_bpf = SeccompBpf::Off;
+    }
+    self.max_events = max_events;
+    Ok(self)
+  }
+
+  /// Whether tracing stopped because of --max-events
+  pub fn max_events_reached(&self) -> bool {
+    self.nr_exec_events.load(Ordering::Relaxed)
+      >= self.max_events.map_or(usize::MAX, |(max, _)| max)
+  }
+
+  fn count_exec_event(&self) {
+    if let Some((max, _)) = self.max_events {
+      if self.nr_exec_events.fetch_add(1, Ordering::Relaxed) + 1 == max {
+        self.stop_pending.store(true, Ordering::Relaxed);
+      }
+    }
+  }
+
+  /// Detach or kill the tracees once the limit of exec events is reached.
+  /// Returns true if the tracer detached and tracing is over.
+  fn check_max_events(&self) -> color_eyre::Result<bool> {
+    if !self.stop_pending.swap(false, Ordering::Relaxed) {
+      return Ok(false);
+    }
+    let Some((max, action)) = self.max_events else {
+      return Ok(false);
+    };
+    let msg = match action {
+      MaxEventsAction::Detach => {
+        self.detach_all(None)?;
+        self.printer.finish()?;
+        format!("Reached {max} exec events, detached from the traced processes")
+      }
+      MaxEventsAction::Kill => {
+        for pid in self.store.read().unwrap().running_pids() {
+          kill(pid, Signal::SIGKILL).ok();
+        }
+        format!("Reached {max} exec events, killed the traced processes")
+      }
+    };
+    filterable_event!(Warning(TracerMessage { msg, pid: None }))
+      .send_if_match(&self.tx, self.filter)?;
+    Ok(action == MaxEventsAction::Detach)
+  }
+
+  /// Detach from the processes that the tracer attached to, which keep running untraced,
+  /// e.g. when the TUI exits. The tracer thread finishes once it detached.
+  pub fn detach(&self) {
+    if !self.attached.load(Ordering::Relaxed) || self.detach_pending.swap(true, Ordering::Relaxed) {
+      return;
+    }
+    // The stops wake up the tracer thread, which then detaches from all the tracees
+    for pid in self.store.read().unwrap().running_pids() {
+      kill(pid, Signal::SIGSTOP).ok();
+    }
+  }
+
+  /// Detach from all the tracees, which keep running untraced.
+  /// `first` is a status that was already waited for.
+  fn detach_all(&self, mut first: Option<WaitStatus>) -> color_eyre::Result<()> {
+    let mut remaining: HashSet<Pid> = self
+      .store
+      .read()
+      .unwrap()
+      .running_pids()
+      .into_iter()
+      .collect();
+    // Tracees can only be detached in a ptrace stop
+    for pid in remaining.iter() {
+      kill(*pid, Signal::SIGSTOP).ok();
+    }
+    let mut detached = vec![];
+    while !remaining.is_empty() {
+      let status = match first.take().map_or_else(
+        || waitpid(None, Some(WaitPidFlag::__WALL | WaitPidFlag::__WNOTHREAD)),
+        Ok,
+      ) {
+        Ok(status) => status,
+        Err(Errno::ECHILD) => break,
+        Err(e) => Err(e)?,
+      };
+      let (pid, sig) = match status {
+        WaitStatus::Exited(pid, _) | WaitStatus::Signaled(pid, _, _) => {
+          remaining.remove(&pid);
+          continue;
+        }
+        WaitStatus::PtraceEvent(pid, _, evt) => {
+          if matches!(
+            evt,
+            nix::libc::PTRACE_EVENT_FORK
+              | nix::libc::PTRACE_EVENT_VFORK
+              | nix::libc::PTRACE_EVENT_CLONE
+          ) {
+            // The new child is traced as well and stops soon
+            remaining.insert(Pid::from_raw(ptrace::getevent(pid)? as pid_t));
+          }
+          (pid, None)
+        }
+        // Our SIGSTOP is suppressed, other signals are delivered
+        WaitStatus::Stopped(pid, sig) => (pid, (sig != Signal::SIGSTOP).then_some(sig)),
+        WaitStatus::PtraceSyscall(pid) => (pid, None),
+        _ => continue,
+      };
+      ptrace::detach(pid, sig).ok();
+      remaining.remove(&pid);
+      detached.push(pid);
+    }
+    // The SIGSTOPs that are still pending are discarded by SIGCONT
+    for pid in detached {
+      kill(pid, Signal::SIGCONT).ok();
+    }
+    Ok(())
+  }
+
+  /// Signal the traced processes once the timeout expires, unless the returned sender is dropped before
+  fn start_timeout(self: &Arc<Self>) -> color_eyre::Result<Option<mpsc::Sender<()>>> {
+    let Some((timeout, signal)) = self.timeout else {
+      return Ok(None);
+    };
+    let (done_tx, done_rx) = mpsc::channel::<()>();
+    let tracer = self.clone();
+    thread::Builder::new()
+      .name("timeout".to_string())
+      .spawn(move || {
+        if done_rx.recv_timeout(timeout) != Err(RecvTimeoutError::Timeout) {
+          return;
+        }
+        tracer.timed_out.store(true, Ordering::Relaxed);
+        // The processes that were attached to are left alone
+        let msg = if tracer.attached.load(Ordering::Relaxed) {
+          tracer.detach();
+          format!("Timed out after {timeout:?}, detached from the traced processes")
+        } else {
+          let pids = tracer.store.read().unwrap().running_pids();
+          for pid in pids {
+            // The process may have exited in the meantime
+            kill(pid, signal).ok();
+          }
+          format!("Timed out after {timeout:?}, sent {signal} to the traced processes")
+        };
+        filterable_event!(Warning(TracerMessage { msg, pid: None }))
+          .send_if_match(&tracer.tx, tracer.filter)
+          .ok();
+      })?;
+    Ok(Some(done_tx))
+  }
+
   pub fn spawn(
     self: Arc<Self>,
     args: Vec<String>,
@@ -168,6 +514,45 @@ impl Tracer {
     )
   }
 
+  /// Trace a running process and its threads, instead of a command.
+  /// Returns once the process is attached to, or with the error if that fails, e.g. EPERM.
+  pub fn spawn_attach(
+    self: Arc<Self>,
+    pid: Pid,
+    output: Option<Box<PrinterOut>>,
+  ) -> color_eyre::Result<JoinHandle<color_eyre::Result<()>>> {
+    // Only the thread that seizes the process can trace it, so the result is sent back from there
+    let (seized_tx, seized_rx) = mpsc::sync_channel(1);
+    let thread = thread::Builder::new()
+      .name("tracer".to_string())
+      .spawn(move || {
+        self.printer.init_thread_local(output);
+        let seized = self.seize_process(pid);
+        let failed = seized.is_err();
+        seized_tx.send(seized).ok();
+        if failed {
+          return Ok(());
+        }
+        self.root_pid.set(pid).ok();
+        filterable_event!(TraceeSpawn(pid)).send_if_match(&self.tx, self.filter)?;
+        // The timeout stops counting when this function returns
+        let _timeout = self.start_timeout()?;
+        self.trace_loop(pid)
+      })?;
+    match seized_rx.recv() {
+      Ok(Ok(())) => Ok(thread),
+      Ok(Err(e)) => {
+        thread.join().ok();
+        Err(e)
+      }
+      // The thread ended without sending the result, i.e. it panicked
+      Err(_) => {
+        thread.join().ok();
+        color_eyre::eyre::bail!("The tracer exited before attaching to {pid}")
+      }
+    }
+  }
+
   fn start_root_process(self: Arc<Self>, args: Vec<String>) -> color_eyre::Result<()> {
     trace!("start_root_process: {:?}", args);
 
@@ -178,17 +563,37 @@ impl Tracer {
     #[cfg(feature = "seccomp-bpf")]
     let seccomp_bpf = self.seccomp_bpf;
     let slave_pty = match &self.mode {
-      TracerMode::Tui(tty) => tty.as_ref(),
-      TracerMode::Log => None,
+      TracerMode::Tui(tty) | TracerMode::Log(tty) => tty.as_ref(),
     };
     let with_tty = self.with_tty;
     let use_pseudo_term = slave_pty.is_some();
+    let stdin_fd = self.stdin.as_ref().map(AsRawFd::as_raw_fd);
     let user = self.user.clone();
+    let namespaces = self.modifier_args.namespaces();
+    let id_maps = self
+      .modifier_args
+      .map_root_user
+      .then(namespace::root_id_maps);
+    let user_name = match &self.user {
+      Some(user) => Some(CString::new(user.name.as_str())?),
+      None => None,
+    };
+    let user_env = self.user.clone().map(|user| (user, self.login));
 
     let root_child = pty::spawn_command(
       slave_pty,
       cmd,
-      |_| Ok(()),
+      move |cmd| {
+        if use_pseudo_term {
+          // Shells may export the size of tracexec's terminal, which would take precedence
+          // over the size of the pseudo terminal for tools like tput
+          cmd.env_remove("LINES").env_remove("COLUMNS");
+        }
+        if let Some((user, login)) = user_env {
+          set_user_env(cmd, &user, login);
+        }
+        Ok(())
+      },
       move |program_path| {
         #[cfg(feature = "seccomp-bpf")]
         if seccomp_bpf == SeccompBpf::On {
@@ -216,10 +621,21 @@ impl Tracer {
           setpgid(me, me)?;
         }
 
+        // The pseudo terminal has to be the controlling terminal before stdin is replaced
+        if let Some(fd) = stdin_fd {
+          if unsafe { dup2(fd, 0) } == -1 {
+            Err(io::Error::last_os_error())?;
+          }
+        }
+
         traceme()?;
         trace!("traceme setup!");
 
-        if let Some(user) = &user {
+        if !namespaces.is_empty() {
+          namespace::unshare_namespaces(namespaces, id_maps.as_ref())?;
+        }
+
+        if let (Some(user), Some(user_name)) = (&user, &user_name) {
           // First, read set(u|g)id info from stat
           let file = std::fs::File::open(program_path)?;
           let stat = fstat(file.as_raw_fd())?;
@@ -236,7 +652,7 @@ impl Tracer {
           } else {
             user.gid
           };
-          initgroups(&CString::new(user.name.as_str())?[..], user.gid)?;
+          initgroups(user_name, user.gid)?;
           setresgid(user.gid, egid, Gid::from_raw(u32::MAX))?;
           setresuid(user.uid, euid, Uid::from_raw(u32::MAX))?;
         }
@@ -247,11 +663,19 @@ impl Tracer {
         }
         trace!("raise success!");
 
+        // The fork is traced now, so the command is traced as the init process of the namespace
+        if namespaces.contains(CloneFlags::CLONE_NEWPID) {
+          namespace::enter_pid_namespace(namespaces.contains(CloneFlags::CLONE_NEWNS))?;
+        }
+
         Ok(())
       },
     )?
     .process_id();
+    self.root_pid.set(root_child).ok();
     filterable_event!(TraceeSpawn(root_child)).send_if_match(&self.tx, self.filter)?;
+    // The timeout stops counting when this function returns
+    let _timeout = self.start_timeout()?;
     // wait for child to be stopped by SIGSTOP
     loop {
       let status = waitpid(root_child, Some(WaitPidFlag::WSTOPPED))?;
@@ -266,14 +690,14 @@ impl Tracer {
       }
     }
     trace!("child stopped");
-    let mut root_child_state = ProcessState::new(root_child, 0)?;
+    let mut root_child_state = ProcessState::new(root_child)?;
     root_child_state.ppid = Some(getpid());
     {
       self.store.write().unwrap().insert(root_child_state);
     }
-    // Set foreground process group of the terminal
+    // Set foreground process group of the terminal, unless the tracee has its own pseudo terminal
     #[cfg(not(test))]
-    if let TracerMode::Log = &self.mode {
+    if let TracerMode::Log(None) = &self.mode {
       if self.foreground {
         match tcsetpgrp(stdin(), root_child) {
           Ok(_) => {}
@@ -284,26 +708,103 @@ impl Tracer {
         }
       }
     }
-    let mut ptrace_opts = {
-      use nix::sys::ptrace::Options;
-      Options::PTRACE_O_TRACEEXEC
-        | Options::PTRACE_O_TRACEEXIT
-        | Options::PTRACE_O_EXITKILL
-        | Options::PTRACE_O_TRACESYSGOOD
-        | Options::PTRACE_O_TRACEFORK
-        | Options::PTRACE_O_TRACECLONE
-        | Options::PTRACE_O_TRACEVFORK
-    };
-    #[cfg(feature = "seccomp-bpf")]
-    if self.seccomp_bpf == SeccompBpf::On {
-      ptrace_opts |= ptrace::Options::PTRACE_O_TRACESECCOMP;
-    }
-    ptrace::setoptions(root_child, ptrace_opts)?;
+    ptrace::setoptions(
+      root_child,
+      self.ptrace_options() | ptrace::Options::PTRACE_O_EXITKILL,
+    )?;
     // restart child
     trace!("resuming child");
     self.seccomp_aware_cont(root_child)?;
+    self.trace_loop(root_child)
+  }
+
+  /// Seize all the threads of the process. The process keeps running when the tracer detaches or exits.
+  /// Nothing stays seized if seizing any of the threads fails.
+  fn seize_process(&self, root: Pid) -> color_eyre::Result<()> {
+    trace!("seize_process: {root}");
+    #[cfg(feature = "seccomp-bpf")]
+    if self.seccomp_bpf == SeccompBpf::On {
+      color_eyre::eyre::bail!("seccomp-bpf can not be used when attaching to a running process");
+    }
+    let mut seized = vec![];
+    if let Err(e) = self.seize_threads(root, &mut seized) {
+      for tid in seized {
+        // Tracees can only be detached in a ptrace stop
+        let sig = match ptrace::interrupt(tid).and_then(|_| waitpid(tid, Some(WaitPidFlag::__WALL)))
+        {
+          Ok(WaitStatus::Stopped(_, sig)) => Some(sig),
+          _ => None,
+        };
+        ptrace::detach(tid, sig).ok();
+      }
+      return Err(e);
+    }
+    self.attached.store(true, Ordering::Relaxed);
+    for tid in seized {
+      // The interrupt stop is where the tracing starts
+      ptrace::interrupt(tid)?;
+      self.store.write().unwrap().insert(ProcessState::new(tid)?);
+    }
+    Ok(())
+  }
+
+  /// Seize the threads of the process, scanning again until no new threads show up,
+  /// since the threads that are not seized yet may create new ones meanwhile
+  fn seize_threads(&self, root: Pid, seized: &mut Vec<Pid>) -> color_eyre::Result<()> {
     loop {
-      let status = waitpid(None, Some(WaitPidFlag::__WALL))?;
+      let mut found_new = false;
+      for task in std::fs::read_dir(format!("/proc/{root}/task"))? {
+        let tid = Pid::from_raw(task?.file_name().to_string_lossy().parse()?);
+        if seized.contains(&tid) {
+          continue;
+        }
+        match ptrace::seize(tid, self.ptrace_options()) {
+          Ok(()) => seized.push(tid),
+          // The thread exited in the meantime
+          Err(Errno::ESRCH) => continue,
+          // Threads that are created by seized threads are traced automatically
+          Err(Errno::EPERM) if read_tracer_pid(tid).ok().flatten() == Some(gettid()) => continue,
+          Err(e) => Err(e)?,
+        }
+        found_new = true;
+      }
+      if !found_new {
+        return Ok(());
+      }
+    }
+  }
+
+  fn ptrace_options(&self) -> ptrace::Options {
+    use nix::sys::ptrace::Options;
+    let mut ptrace_opts =
+      Options::PTRACE_O_TRACEEXEC | Options::PTRACE_O_TRACEEXIT | Options::PTRACE_O_TRACESYSGOOD;
+    if !self.modifier_args.no_follow_forks {
+      ptrace_opts |=
+        Options::PTRACE_O_TRACEFORK | Options::PTRACE_O_TRACECLONE | Options::PTRACE_O_TRACEVFORK;
+    }
+    #[cfg(feature = "seccomp-bpf")]
+    if self.seccomp_bpf == SeccompBpf::On {
+      return ptrace_opts | Options::PTRACE_O_TRACESECCOMP;
+    }
+    ptrace_opts
+  }
+
+  /// Handle the stops of the tracees until the root process exits
+  fn trace_loop(&self, root_child: Pid) -> color_eyre::Result<()> {
+    loop {
+      if self.check_max_events()? {
+        return Ok(());
+      }
+      // Tracees that stop while the tracer is paused stay stopped until it is resumed
+      self.pause.wait_while_paused();
+      // Only wait for the tracees of this thread, other tracer threads may trace other commands
+      let status = waitpid(None, Some(WaitPidFlag::__WALL | WaitPidFlag::__WNOTHREAD))?;
+      if self.detach_pending.load(Ordering::Relaxed) {
+        // The status is one of the stops that `detach` caused or any other one
+        self.detach_all(Some(status))?;
+        self.printer.finish()?;
+        return Ok(());
+      }
       // trace!("waitpid: {:?}", status);
       match status {
         WaitStatus::Stopped(pid, sig) => {
@@ -311,40 +812,10 @@ impl Tracer {
           match sig {
             Signal::SIGSTOP => {
               trace!("sigstop event, child: {pid}");
-              {
-                let mut store = self.store.write().unwrap();
-                if let Some(state) = store.get_current_mut(pid) {
-                  if state.status == ProcessStatus::PtraceForkEventReceived {
-                    trace!("sigstop event received after ptrace fork event, pid: {pid}");
-                    state.status = ProcessStatus::Running;
-                    self.seccomp_aware_cont(pid)?;
-                  } else if pid != root_child {
-                    error!("Unexpected SIGSTOP: {state:?}")
-                  } else {
-                    error!("Unexpected SIGSTOP: {state:?}")
-                    // let siginfo = ptrace::getsiginfo(pid)?;
-                    // trace!(
-                    //     "FIXME: this is weird, pid: {pid}, siginfo: {siginfo:?}"
-                    // );
-                    // let sender = siginfo._pad[1];
-                    // let tmp = format!("/proc/{sender}/status");
-                    // ptrace::detach(pid, Some(Signal::SIGSTOP))?;
-                    // trace_dbg!(process::Command::new("/bin/cat")
-                    //     .arg(tmp)
-                    //     .output()?);
-                    // self.seccomp_aware_cont(pid)?;
-                  }
-                } else {
-                  trace!("sigstop event received before ptrace fork event, pid: {pid}");
-                  let mut state = ProcessState::new(pid, 0)?;
-                  state.status = ProcessStatus::SigstopReceived;
-                  store.insert(state);
-                }
-                // https://stackoverflow.com/questions/29997244/occasionally-missing-ptrace-event-vfork-when-running-ptrace
-                // DO NOT send PTRACE_SYSCALL until we receive the PTRACE_EVENT_FORK, etc.
-              }
+              self.on_child_stopped(pid, root_child)?;
             }
             Signal::SIGCHLD => {
+              self.send_signal(pid, sig)?;
               // From lurk:
               //
               // The SIGCHLD signal is sent to a process when a child process terminates, interrupted, or resumes after being interrupted
@@ -354,6 +825,7 @@ impl Tracer {
               self.seccomp_aware_cont_with_signal(pid, Signal::SIGCHLD)?;
             }
             _ => {
+              self.send_signal(pid, sig)?;
               // Just deliver the signal to tracee
               self.seccomp_aware_cont_with_signal(pid, sig)?;
             }
@@ -361,14 +833,18 @@ impl Tracer {
         }
         WaitStatus::Exited(pid, code) => {
           trace!("exited: pid {}, code {:?}", pid, code);
-          self
-            .store
-            .write()
-            .unwrap()
-            .get_current_mut(pid)
-            .unwrap()
-            .status = ProcessStatus::Exited(code);
+          {
+            let mut store = self.store.write().unwrap();
+            let state = store.get_current_mut(pid).unwrap();
+            state.status = ProcessStatus::Exited(code);
+            self.send_process_exit(state, ProcessExitStatus::Exited(code))?;
+            self
+              .printer
+              .print_exit(state, ProcessExitStatus::Exited(code))?;
+          }
           if pid == root_child {
+            self.printer.finish()?;
+            self.root_exit_code.set(code).ok();
             filterable_event!(TraceeExit {
               signal: None,
               exit_code: code,
@@ -392,11 +868,15 @@ impl Tracer {
                   ppid: parent.pid,
                   pcomm: parent.comm.clone(),
                   pid: new_child,
+                  timestamp: SystemTime::now(),
+                  pstart_time: parent.start_time,
+                  start_time: read_start_time(new_child).unwrap_or_default(),
                 })?;
                 self.printer.print_new_child(parent, new_child)?;
               }
               {
                 let mut store = self.store.write().unwrap();
+                let depth = store.get_current(pid).map_or(0, |parent| parent.depth + 1);
                 if let Some(state) = store.get_current_mut(new_child) {
                   if state.status == ProcessStatus::SigstopReceived {
                     trace!(
@@ -404,6 +884,7 @@ impl Tracer {
                     );
                     state.status = ProcessStatus::Running;
                     state.ppid = Some(pid);
+                    state.depth = depth;
                     self.seccomp_aware_cont(new_child)?;
                   } else if new_child != root_child {
                     filterable_event!(Error(TracerMessage {
@@ -416,9 +897,10 @@ impl Tracer {
                   trace!(
                     "ptrace fork event received before sigstop, pid: {pid}, child: {new_child}"
                   );
-                  let mut state = ProcessState::new(new_child, 0)?;
+                  let mut state = ProcessState::new(new_child)?;
                   state.status = ProcessStatus::PtraceForkEventReceived;
                   state.ppid = Some(pid);
+                  state.depth = depth;
                   store.insert(state);
                 }
                 // Resume parent
@@ -440,21 +922,64 @@ impl Tracer {
             }
             nix::libc::PTRACE_EVENT_EXIT => {
               trace!("exit event");
+              // This is the last chance to read the CPU time before the process becomes a zombie
+              let cpu_time = read_cpu_time(pid);
+              if let Err(e) = &cpu_time {
+                self.report_incomplete(Some(pid), format!("Failed to read the CPU time: {e}"))?;
+              }
+              if let Some(state) = self.store.write().unwrap().get_current_mut(pid) {
+                state.cpu_time = cpu_time.ok();
+              }
               self.seccomp_aware_cont(pid)?;
             }
             nix::libc::PTRACE_EVENT_SECCOMP => {
               trace!("seccomp event");
               self.on_syscall_enter(pid)?;
             }
+            nix::libc::PTRACE_EVENT_STOP => {
+              trace!("stop event");
+              let is_new_child = self
+                .store
+                .read()
+                .unwrap()
+                .get_current(pid)
+                .map_or(true, |state| {
+                  state.status == ProcessStatus::PtraceForkEventReceived
+                });
+              if is_new_child {
+                // The children of attached processes start with this stop instead of SIGSTOP
+                self.on_child_stopped(pid, root_child)?;
+              } else {
+                // The interrupt stop right after attaching, group stops are reported the same way
+                self.seccomp_aware_cont(pid)?;
+              }
+            }
             _ => {
               trace!("other event");
+              self.report_incomplete(Some(pid), format!("Unexpected ptrace event {evt}"))?;
               self.seccomp_aware_cont(pid)?;
             }
           }
         }
         WaitStatus::Signaled(pid, sig, _) => {
           debug!("signaled: {pid}, {:?}", sig);
+          if let Some(state) = self.store.read().unwrap().get_current(pid) {
+            self.send_process_exit(state, ProcessExitStatus::Signaled(sig))?;
+            self
+              .printer
+              .print_exit(state, ProcessExitStatus::Signaled(sig))?;
+          } else {
+            self.report_incomplete(
+              Some(pid),
+              format!(
+                "Lost the exit of an unknown process, killed by {}",
+                sig.as_str()
+              ),
+            )?;
+          }
           if pid == root_child {
+            self.printer.finish()?;
+            self.root_exit_code.set(128 + (sig as i32)).ok();
             filterable_event!(TraceeExit {
               signal: Some(sig),
               exit_code: 128 + (sig as i32),
@@ -477,11 +1002,53 @@ impl Tracer {
             self.on_syscall_exit(pid)?;
           }
         }
-        _ => {}
+        status => {
+          self.report_incomplete(status.pid(), format!("Unexpected wait status: {status:?}"))?;
+        }
       }
     }
   }
 
+  /// The initial stop of a new child, which may arrive before or after the fork event of its parent
+  fn on_child_stopped(&self, pid: Pid, root_child: Pid) -> color_eyre::Result<()> {
+    let mut store = self.store.write().unwrap();
+    if let Some(state) = store.get_current_mut(pid) {
+      if state.status == ProcessStatus::PtraceForkEventReceived {
+        trace!("sigstop event received after ptrace fork event, pid: {pid}");
+        state.status = ProcessStatus::Running;
+        self.seccomp_aware_cont(pid)?;
+      } else if pid != root_child {
+        error!("Unexpected SIGSTOP: {state:?}");
+        self.report_incomplete(Some(pid), "Unexpected SIGSTOP".to_string())?;
+      } else {
+        error!("Unexpected SIGSTOP: {state:?}");
+        self.report_incomplete(
+          Some(pid),
+          "Unexpected SIGSTOP of the root process".to_string(),
+        )?;
+        // let siginfo = ptrace::getsiginfo(pid)?;
+        // trace!(
+        //     "FIXME: this is weird, pid: {pid}, siginfo: {siginfo:?}"
+        // );
+        // let sender = siginfo._pad[1];
+        // let tmp = format!("/proc/{sender}/status");
+        // ptrace::detach(pid, Some(Signal::SIGSTOP))?;
+        // trace_dbg!(process::Command::new("/bin/cat")
+        //     .arg(tmp)
+        //     .output()?);
+        // self.seccomp_aware_cont(pid)?;
+      }
+    } else {
+      trace!("sigstop event received before ptrace fork event, pid: {pid}");
+      let mut state = ProcessState::new(pid)?;
+      state.status = ProcessStatus::SigstopReceived;
+      store.insert(state);
+    }
+    // https://stackoverflow.com/questions/29997244/occasionally-missing-ptrace-event-vfork-when-running-ptrace
+    // DO NOT send PTRACE_SYSCALL until we receive the PTRACE_EVENT_FORK, etc.
+    Ok(())
+  }
+
   fn on_syscall_enter(&self, pid: Pid) -> color_eyre::Result<()> {
     let mut store = self.store.write().unwrap();
     let p = store.get_current_mut(pid).unwrap();
@@ -550,11 +1117,14 @@ impl Tracer {
       } else {
         vec![]
       };
+      let cwd = read_cwd(pid)?;
+      let resolved_filename = self.resolve_filename(&filename, &cwd, &envp);
       p.exec_data = Some(ExecData::new(
         filename,
+        resolved_filename,
         argv,
         envp,
-        read_cwd(pid)?,
+        cwd,
         interpreters,
         read_fds(pid)?,
       ));
@@ -572,20 +1142,109 @@ impl Tracer {
       } else {
         vec![]
       };
+      let cwd = read_cwd(pid)?;
+      let resolved_filename = self.resolve_filename(&filename, &cwd, &envp);
       p.exec_data = Some(ExecData::new(
         filename,
+        resolved_filename,
         argv,
         envp,
-        read_cwd(pid)?,
+        cwd,
         interpreters,
         read_fds(pid)?,
       ));
     } else if syscallno == SYS_clone || syscallno == SYS_clone3 {
     }
+    let hit = p
+      .exec_data
+      .as_ref()
+      .and_then(|exec_data| self.breakpoints.check(pid, exec_data));
+    if let Some(hit) = hit {
+      // Don't block the TUI, the timeout and --max-events, which read the store while we wait
+      drop(store);
+      if let Some(decision) = self.breakpoints.wait_for_decision(hit) {
+        let mut store = self.store.write().unwrap();
+        let exec_data = store
+          .get_current_mut(pid)
+          .unwrap()
+          .exec_data
+          .as_mut()
+          .unwrap();
+        if let Err(e) = Self::substitute_exec(pid, regs, exec_data, decision) {
+          // Shown regardless of the filter, since the changes were made in the TUI
+          self.tx.send(TracerEvent::Warning(TracerMessage {
+            pid: Some(pid),
+            msg: format!(
+              "Failed to change the exec at the breakpoint, it continues unchanged: {e}"
+            ),
+          }))?;
+        }
+      }
+    }
     self.syscall_enter_cont(pid)?;
     Ok(())
   }
 
+  /// Replace the arguments or the environment of the exec that is stopped at a breakpoint
+  fn substitute_exec(
+    pid: Pid,
+    mut regs: PtraceRegisters,
+    exec_data: &mut ExecData,
+    decision: BreakpointDecision,
+  ) -> color_eyre::Result<()> {
+    let execveat = syscall_no_from_regs!(regs) == nix::libc::SYS_execveat;
+    let envp = match decision.envp {
+      Some(envp) => {
+        let address = if execveat {
+          syscall_arg!(regs, 3)
+        } else {
+          syscall_arg!(regs, 2)
+        };
+        let original = read_string_array(pid, address as AddressType)?;
+        Some(unredact_envp(envp, &original))
+      }
+      None => None,
+    };
+    let argv = decision.argv;
+    if argv.is_none() && envp.is_none() {
+      return Ok(());
+    }
+    let addresses = write_string_arrays(
+      pid,
+      stack_pointer_from_regs!(regs) as u64,
+      &[
+        argv.as_deref().unwrap_or_default(),
+        envp.as_deref().unwrap_or_default(),
+      ],
+    )?;
+    if execveat {
+      if argv.is_some() {
+        syscall_arg!(regs, 2) = addresses[0] as _;
+      }
+      if envp.is_some() {
+        syscall_arg!(regs, 3) = addresses[1] as _;
+      }
+    } else {
+      if argv.is_some() {
+        syscall_arg!(regs, 1) = addresses[0] as _;
+      }
+      if envp.is_some() {
+        syscall_arg!(regs, 2) = addresses[1] as _;
+      }
+    }
+    ptrace_setregs(pid, regs)?;
+    // The exec event shows what is actually executed
+    if let Some(argv) = argv {
+      exec_data.argv = Arc::new(Ok(argv));
+    }
+    if let Some(envp) = envp {
+      let (envp, secrets) = redactor().redact_envp(envp);
+      exec_data.envp = Arc::new(Ok(envp));
+      exec_data.secrets = Arc::new(secrets);
+    }
+    Ok(())
+  }
+
   fn on_syscall_exit(&self, pid: Pid) -> color_eyre::Result<()> {
     // SYSCALL EXIT
     // trace!("post syscall {}", p.syscall);
@@ -597,6 +1256,12 @@ impl Tracer {
       Ok(regs) => regs,
       Err(Errno::ESRCH) => {
         info!("ptrace getregs failed: {pid}, ESRCH, child probably gone!");
+        if p.exec_data.is_some() {
+          self.report_incomplete(
+            Some(pid),
+            "Lost the exec event, the process is gone before the exec returned".to_string(),
+          )?;
+        }
         return Ok(());
       }
       e => e?,
@@ -612,17 +1277,7 @@ impl Tracer {
           self.seccomp_aware_cont(pid)?;
           return Ok(());
         }
-        if self.filter.intersects(TracerEventKind::Exec) {
-          // TODO: optimize, we don't need to collect exec event for log mode
-          self.tx.send(TracerEvent::Exec(Tracer::collect_exec_event(
-            &self.baseline.env,
-            p,
-            exec_result,
-          )))?;
-          self
-            .printer
-            .print_exec_trace(p, exec_result, &self.baseline.env, &self.baseline.cwd)?;
-        }
+        self.send_exec_event(p, exec_result)?;
         p.exec_data = None;
         p.is_exec_successful = false;
         // update comm
@@ -635,16 +1290,7 @@ impl Tracer {
           self.seccomp_aware_cont(pid)?;
           return Ok(());
         }
-        if self.filter.intersects(TracerEventKind::Exec) {
-          self.tx.send(TracerEvent::Exec(Tracer::collect_exec_event(
-            &self.baseline.env,
-            p,
-            exec_result,
-          )))?;
-          self
-            .printer
-            .print_exec_trace(p, exec_result, &self.baseline.env, &self.baseline.cwd)?;
-        }
+        self.send_exec_event(p, exec_result)?;
         p.exec_data = None;
         p.is_exec_successful = false;
         // update comm
@@ -656,6 +1302,32 @@ impl Tracer {
     Ok(())
   }
 
+  /// Send, print and count the exec event if it passes the filters
+  fn send_exec_event(&self, p: &ProcessState, exec_result: i64) -> color_eyre::Result<()> {
+    if !self.filter.intersects(TracerEventKind::Exec) {
+      return Ok(());
+    }
+    // TODO: optimize, we don't need to collect exec event for log mode
+    let event = TracerEvent::Exec(Tracer::collect_exec_event(&self.baseline, p, exec_result));
+    if !self.exec_filter.keeps(&event) {
+      return Ok(());
+    }
+    if let TracerEvent::Exec(exec) = &event {
+      if exec.uid.is_none() {
+        self.report_incomplete(
+          Some(p.pid),
+          "Failed to read the user of the process".to_string(),
+        )?;
+      }
+    }
+    self.tx.send(event)?;
+    self
+      .printer
+      .print_exec_trace(p, exec_result, &self.baseline.env, &self.baseline.cwd)?;
+    self.count_exec_event();
+    Ok(())
+  }
+
   fn syscall_enter_cont(&self, pid: Pid) -> Result<(), Errno> {
     ptrace_syscall(pid, None)
   }
@@ -697,28 +1369,38 @@ impl Tracer {
     })
   }
 
+  /// Resolve the filename to an absolute canonical path if requested.
+  fn resolve_filename(
+    &self,
+    filename: &Result<PathBuf, InspectError>,
+    cwd: &Path,
+    envp: &Result<Vec<String>, InspectError>,
+  ) -> Option<PathBuf> {
+    if !self.modifier_args.resolve_filename {
+      return None;
+    }
+    resolve_executable(
+      filename.as_ref().ok()?,
+      cwd,
+      envp.as_deref().unwrap_or_default(),
+    )
+  }
+
   fn warn_for_argv(
     &self,
     argv: &Result<Vec<String>, InspectError>,
     pid: Pid,
   ) -> color_eyre::Result<()> {
-    if self.filter.intersects(TracerEventKind::Warning) {
-      match argv.as_deref() {
-        Ok(argv) => {
-          if argv.is_empty() {
-            self.tx.send(TracerEvent::Warning(TracerMessage {
-              pid: Some(pid),
-              msg: "Empty argv, the printed cmdline is not accurate!".to_string(),
-            }))?;
-          }
-        }
-        Err(e) => {
+    match argv.as_deref() {
+      Ok(argv) => {
+        if argv.is_empty() && self.filter.intersects(TracerEventKind::Warning) {
           self.tx.send(TracerEvent::Warning(TracerMessage {
             pid: Some(pid),
-            msg: format!("Failed to read argv: {:?}", e),
+            msg: "Empty argv, the printed cmdline is not accurate!".to_string(),
           }))?;
         }
       }
+      Err(e) => self.warn_for_read_failure(pid, format!("Failed to read argv: {:?}", e))?,
     }
     Ok(())
   }
@@ -728,13 +1410,8 @@ impl Tracer {
     envp: &Result<Vec<String>, InspectError>,
     pid: Pid,
   ) -> color_eyre::Result<()> {
-    if self.filter.intersects(TracerEventKind::Warning) {
-      if let Err(e) = envp.as_deref() {
-        self.tx.send(TracerEvent::Warning(TracerMessage {
-          pid: Some(pid),
-          msg: format!("Failed to read envp: {:?}", e),
-        }))?;
-      }
+    if let Err(e) = envp.as_deref() {
+      self.warn_for_read_failure(pid, format!("Failed to read envp: {:?}", e))?;
     }
     Ok(())
   }
@@ -744,39 +1421,127 @@ impl Tracer {
     filename: &Result<PathBuf, InspectError>,
     pid: Pid,
   ) -> color_eyre::Result<()> {
-    if self.filter.intersects(TracerEventKind::Warning) {
-      if let Err(e) = filename.as_deref() {
-        self.tx.send(TracerEvent::Warning(TracerMessage {
-          pid: Some(pid),
-          msg: format!("Failed to read filename: {:?}", e),
-        }))?;
-      }
+    if let Err(e) = filename.as_deref() {
+      self.warn_for_read_failure(pid, format!("Failed to read filename: {:?}", e))?;
     }
     Ok(())
   }
 
+  /// The printer shows these failures along with the exec.
+  /// With --strict, they are counted and sent regardless of the filter.
+  fn warn_for_read_failure(&self, pid: Pid, msg: String) -> color_eyre::Result<()> {
+    if self.modifier_args.strict {
+      self.nr_incomplete.fetch_add(1, Ordering::Relaxed);
+    } else if !self.filter.intersects(TracerEventKind::Warning) {
+      return Ok(());
+    }
+    self.tx.send(TracerEvent::Warning(TracerMessage {
+      pid: Some(pid),
+      msg,
+    }))?;
+    Ok(())
+  }
+
+  fn send_signal(&self, pid: Pid, signal: Signal) -> color_eyre::Result<()> {
+    let comm = match self.store.read().unwrap().get_current(pid) {
+      Some(state) => state.comm.clone(),
+      None => {
+        return self.report_incomplete(
+          Some(pid),
+          format!(
+            "Lost {}, which is delivered to an unknown process",
+            signal.as_str()
+          ),
+        )
+      }
+    };
+    filterable_event!(Signal {
+      pid,
+      comm,
+      signal,
+      timestamp: SystemTime::now(),
+    })
+    .send_if_match(&self.tx, self.filter)
+  }
+
+  fn send_process_exit(
+    &self,
+    state: &ProcessState,
+    status: ProcessExitStatus,
+  ) -> color_eyre::Result<()> {
+    filterable_event!(ProcessExit {
+      pid: state.pid,
+      ppid: state.ppid,
+      comm: state.comm.clone(),
+      status,
+      timestamp: SystemTime::now(),
+      start_time: state.start_time,
+    })
+    .send_if_match(&self.tx, self.filter)
+  }
+
   // This function does not take self due to borrow checker
   fn collect_exec_event(
-    env: &BTreeMap<String, String>,
+    baseline: &BaselineInfo,
     state: &ProcessState,
     result: i64,
   ) -> Box<ExecEvent> {
     let exec_data = state.exec_data.as_ref().unwrap();
     Box::new(ExecEvent {
       pid: state.pid,
+      ns_pid: state.ns_pid,
+      ppid: state.ppid,
       cwd: exec_data.cwd.to_owned(),
+      // Read after the exec so that set-user-ID programs show their new user
+      uid: read_uid(state.pid).ok(),
       comm: state.comm.clone(),
       filename: exec_data.filename.clone(),
+      resolved_filename: exec_data.resolved_filename.clone(),
       argv: exec_data.argv.clone(),
       envp: exec_data.envp.clone(),
+      secrets: exec_data.secrets.clone(),
       interpreter: exec_data.interpreters.clone(),
       env_diff: exec_data
         .envp
         .as_deref()
-        .map(|envp| diff_env(env, envp))
+        .map(|envp| {
+          let mut diff = diff_env(&baseline.env, envp);
+          baseline.secrets.add_changes(&exec_data.secrets, &mut diff);
+          diff
+        })
         .map_err(|e| *e),
       result,
       fdinfo: exec_data.fdinfo.clone(),
+      timestamp: exec_data.timestamp,
+      start_time: state.start_time,
     })
   }
 }
+
+const USER_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+const ROOT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// Set up the environment of the command for the user like su does, or like su -l with `login`
+fn set_user_env(cmd: &mut std::process::Command, user: &User, login: bool) {
+  if login {
+    let term = std::env::var_os("TERM");
+    cmd.env_clear();
+    if let Some(term) = term {
+      cmd.env("TERM", term);
+    }
+    cmd.env(
+      "PATH",
+      if user.uid.is_root() {
+        ROOT_PATH
+      } else {
+        USER_PATH
+      },
+    );
+    cmd.current_dir(&user.dir);
+  }
+  cmd
+    .env("HOME", &user.dir)
+    .env("USER", &user.name)
+    .env("LOGNAME", &user.name)
+    .env("SHELL", &user.shell);
+}
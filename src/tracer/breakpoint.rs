@@ -0,0 +1,237 @@
+//! Exec breakpoints, which stop the tracer at a matching exec until the TUI decides how to continue it
+
+use std::{
+  path::PathBuf,
+  sync::{Condvar, Mutex, RwLock},
+};
+
+use nix::{
+  errno::Errno,
+  libc::c_long,
+  sys::ptrace::{self, AddressType},
+  unistd::Pid,
+};
+use regex::Regex;
+
+use crate::{proc::parse_env_entry, redact::REDACTED};
+
+use super::{state::ExecData, InspectError};
+
+/// The exec that is stopped at a breakpoint, before it is executed
+#[derive(Debug, Clone)]
+pub struct BreakpointHit {
+  pub pid: Pid,
+  pub filename: PathBuf,
+  pub argv: Vec<String>,
+  /// The environment with the secrets redacted
+  pub envp: Vec<String>,
+}
+
+/// How the exec stopped at a breakpoint continues, `None` keeps the original arguments or environment
+#[derive(Debug, Clone, Default)]
+pub struct BreakpointDecision {
+  pub argv: Option<Vec<String>>,
+  pub envp: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default)]
+struct BreakpointState {
+  hit: Option<BreakpointHit>,
+  decision: Option<BreakpointDecision>,
+  /// The TUI is exiting, the tracers must not stop anymore
+  released: bool,
+}
+
+/// The breakpoints are shared by the tracers of all the tabs and the TUI.
+///
+/// A tracer thread continues all the processes of its tab, so the whole process tree of the tab
+/// stalls while one of its execs waits at a breakpoint, not only the process that is stopped.
+#[derive(Debug, Default)]
+pub struct Breakpoints {
+  /// An exec stops when its command line matches any of the patterns
+  patterns: RwLock<Vec<Regex>>,
+  state: Mutex<BreakpointState>,
+  changed: Condvar,
+}
+
+impl Breakpoints {
+  pub fn add(&self, pattern: Regex) {
+    self.patterns.write().unwrap().push(pattern);
+  }
+
+  pub fn clear(&self) {
+    self.patterns.write().unwrap().clear();
+  }
+
+  pub fn nr_patterns(&self) -> usize {
+    self.patterns.read().unwrap().len()
+  }
+
+  /// Continue all the stopped execs unchanged and stop no more, e.g. when the TUI exits
+  pub fn release(&self) {
+    self.clear();
+    self.state.lock().unwrap().released = true;
+    self.changed.notify_all();
+  }
+
+  /// The exec that is waiting for a decision, if there is one
+  pub fn hit(&self) -> Option<BreakpointHit> {
+    let state = self.state.lock().unwrap();
+    match state.decision {
+      None => state.hit.clone(),
+      Some(_) => None,
+    }
+  }
+
+  /// Continue the exec that is stopped at the breakpoint
+  pub fn decide(&self, decision: BreakpointDecision) {
+    let mut state = self.state.lock().unwrap();
+    if state.hit.is_some() {
+      state.decision = Some(decision);
+      self.changed.notify_all();
+    }
+  }
+
+  /// The breakpoint hit of the exec, if it matches a breakpoint
+  pub(super) fn check(&self, pid: Pid, exec_data: &ExecData) -> Option<BreakpointHit> {
+    let (Ok(filename), Ok(argv), Ok(envp)) = (
+      exec_data.filename.as_ref(),
+      exec_data.argv.as_ref(),
+      exec_data.envp.as_ref(),
+    ) else {
+      return None;
+    };
+    let cmdline = argv.join(" ");
+    let filename_lossy = filename.to_string_lossy();
+    if !self
+      .patterns
+      .read()
+      .unwrap()
+      .iter()
+      .any(|pattern| pattern.is_match(&cmdline) || pattern.is_match(&filename_lossy))
+    {
+      return None;
+    }
+    Some(BreakpointHit {
+      pid,
+      filename: filename.clone(),
+      argv: argv.clone(),
+      envp: envp.clone(),
+    })
+  }
+
+  /// Blocks the calling tracer thread until the TUI decides how the exec of the hit continues.
+  ///
+  /// The caller must not hold the lock of the process state store, which the TUI,
+  /// the timeout and `--max-events` need while the tracer waits here.
+  pub(super) fn wait_for_decision(&self, hit: BreakpointHit) -> Option<BreakpointDecision> {
+    let mut state = self.state.lock().unwrap();
+    // Another tracer may be stopped at a breakpoint
+    state = self
+      .changed
+      .wait_while(state, |state| state.hit.is_some() && !state.released)
+      .unwrap();
+    if state.released {
+      return None;
+    }
+    state.hit = Some(hit);
+    state = self
+      .changed
+      .wait_while(state, |state| state.decision.is_none() && !state.released)
+      .unwrap();
+    state.hit = None;
+    let decision = state.decision.take();
+    self.changed.notify_all();
+    decision
+  }
+}
+
+/// Put back the original values of the redacted variables, which the TUI never sees
+pub(super) fn unredact_envp(envp: Vec<String>, original: &[String]) -> Vec<String> {
+  envp
+    .into_iter()
+    .map(|entry| {
+      let (key, value) = parse_env_entry(&entry);
+      if value != REDACTED {
+        return entry;
+      }
+      original
+        .iter()
+        .find(|other| parse_env_entry(other).0 == key)
+        .cloned()
+        .unwrap_or(entry)
+    })
+    .collect()
+}
+
+pub(super) const WORD_SIZE: usize = 8;
+/// The area right below the stack pointer that is left alone. x86_64 has a red zone of 128 bytes
+/// that leaf functions may use without moving the stack pointer, aarch64 and riscv64 have none.
+pub(super) const STACK_GAP: u64 = 256;
+
+/// String arrays like argv and envp, laid out to be written below the stack pointer
+#[derive(Debug)]
+pub(super) struct StringArraysLayout {
+  /// The lowest address, aligned to 16 bytes
+  pub start: u64,
+  /// The words to write from `start` on, the pointer arrays followed by the strings they point to
+  pub bytes: Vec<u8>,
+  /// The addresses of the null terminated pointer arrays
+  pub addresses: Vec<u64>,
+}
+
+impl StringArraysLayout {
+  /// Lay out the arrays so that they end at least `STACK_GAP` bytes below the stack pointer,
+  /// `None` if they don't fit below it
+  pub fn new(stack_pointer: u64, arrays: &[&[String]]) -> Option<Self> {
+    let nr_pointers: usize = arrays.iter().map(|array| array.len() + 1).sum();
+    let strings_len: usize = arrays
+      .iter()
+      .flat_map(|array| array.iter())
+      .map(|s| s.len() + 1)
+      .sum();
+    let size = (nr_pointers * WORD_SIZE + strings_len).next_multiple_of(WORD_SIZE);
+    let start = stack_pointer.checked_sub(STACK_GAP + size as u64)? & !0xf;
+    let mut pointers = Vec::with_capacity(nr_pointers);
+    let mut strings = Vec::with_capacity(strings_len);
+    let mut addresses = Vec::with_capacity(arrays.len());
+    let strings_start = start + (nr_pointers * WORD_SIZE) as u64;
+    for array in arrays {
+      addresses.push(start + (pointers.len() * WORD_SIZE) as u64);
+      for s in array.iter() {
+        pointers.push(strings_start + strings.len() as u64);
+        strings.extend_from_slice(s.as_bytes());
+        strings.push(0);
+      }
+      pointers.push(0u64);
+    }
+    let mut bytes: Vec<u8> = pointers.iter().flat_map(|p| p.to_ne_bytes()).collect();
+    bytes.extend(strings);
+    bytes.resize(size, 0);
+    Some(Self {
+      start,
+      bytes,
+      addresses,
+    })
+  }
+}
+
+/// Write the string arrays into the stack of the tracee, below the stack pointer.
+///
+/// The memory there is unused while the tracee is stopped at the exec syscall and is
+/// discarded when the exec succeeds. Writing fails if the arrays don't fit into the mapped
+/// stack, since ptrace doesn't grow it. Returns the addresses of the arrays.
+pub(super) fn write_string_arrays(
+  pid: Pid,
+  stack_pointer: u64,
+  arrays: &[&[String]],
+) -> Result<Vec<u64>, InspectError> {
+  let layout = StringArraysLayout::new(stack_pointer, arrays).ok_or(Errno::ENOMEM)?;
+  for (i, word) in layout.bytes.chunks_exact(WORD_SIZE).enumerate() {
+    let word = c_long::from_ne_bytes(word.try_into().unwrap());
+    let address = (layout.start + (i * WORD_SIZE) as u64) as AddressType;
+    // SAFETY: the word is written into the memory of the tracee, not ours
+    unsafe { ptrace::write(pid, address, word as AddressType) }?;
+  }
+  Ok(layout.addresses)
+}
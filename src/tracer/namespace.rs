@@ -0,0 +1,70 @@
+//! Launching the root child in fresh namespaces, like unshare(1)
+
+use nix::{
+  libc,
+  mount::{mount, MsFlags},
+  sched::{unshare, CloneFlags},
+  sys::wait::{waitpid, WaitStatus},
+  unistd::{fork, getgid, getuid, ForkResult},
+};
+
+/// The contents of uid_map and gid_map that map the current user to root in a new user namespace.
+/// They are prepared before forking, the forked child must not allocate.
+pub(super) fn root_id_maps() -> (String, String) {
+  (format!("0 {} 1", getuid()), format!("0 {} 1", getgid()))
+}
+
+/// Move the root child into new namespaces before it stops for the tracer.
+/// This comes before switching to `--user`, who usually can't create the namespaces.
+pub(super) fn unshare_namespaces(
+  flags: CloneFlags,
+  id_maps: Option<&(String, String)>,
+) -> color_eyre::Result<()> {
+  unshare(flags)?;
+  if let Some((uid_map, gid_map)) = id_maps {
+    std::fs::write("/proc/self/setgroups", "deny")?;
+    std::fs::write("/proc/self/uid_map", uid_map)?;
+    std::fs::write("/proc/self/gid_map", gid_map)?;
+  }
+  if flags.contains(CloneFlags::CLONE_NEWNS) {
+    // The mounts made in the new namespace must not propagate back
+    mount(
+      None::<&str>,
+      "/",
+      None::<&str>,
+      MsFlags::MS_REC | MsFlags::MS_PRIVATE,
+      None::<&str>,
+    )?;
+  }
+  Ok(())
+}
+
+/// Only the children of the root child are in its new PID namespace.
+/// The root child forks the process that becomes the init process of the namespace,
+/// then waits for it and exits with its status.
+pub(super) fn enter_pid_namespace(mount_proc: bool) -> color_eyre::Result<()> {
+  // SAFETY: the process only waits and exits in the parent
+  match unsafe { fork() }? {
+    ForkResult::Child => {
+      if mount_proc {
+        mount(
+          Some("proc"),
+          "/proc",
+          Some("proc"),
+          MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC,
+          None::<&str>,
+        )?;
+      }
+      Ok(())
+    }
+    ForkResult::Parent { child } => {
+      let code = match waitpid(child, None) {
+        Ok(WaitStatus::Exited(_, code)) => code,
+        Ok(WaitStatus::Signaled(_, signal, _)) => 128 + signal as i32,
+        _ => 1,
+      };
+      // SAFETY: exiting without running the destructors of tracexec in the forked process
+      unsafe { libc::_exit(code) }
+    }
+  }
+}
@@ -61,3 +61,30 @@ pub fn ptrace_getregs(pid: Pid) -> Result<PtraceRegisters, Errno> {
       }
   }
 }
+
+pub fn ptrace_setregs(pid: Pid, regs: PtraceRegisters) -> Result<(), Errno> {
+  cfg_if! {
+      if #[cfg(target_arch = "x86_64")] {
+          ptrace::setregs(pid, regs)
+      } else {
+          use nix::sys::ptrace::AddressType;
+
+          let iovec = nix::libc::iovec {
+              iov_base: &regs as *const _ as AddressType,
+              iov_len: std::mem::size_of::<PtraceRegisters>(),
+          };
+          let ptrace_result = unsafe {
+              nix::libc::ptrace(
+                  nix::libc::PTRACE_SETREGSET,
+                  pid.as_raw(),
+                  nix::libc::NT_PRSTATUS,
+                  &iovec as *const _ as *const nix::libc::c_void,
+              )
+          };
+          if -1 == ptrace_result {
+              return Err(nix::errno::Errno::last());
+          }
+          Ok(())
+      }
+  }
+}
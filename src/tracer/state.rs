@@ -1,9 +1,18 @@
-use std::{collections::HashMap, ffi::CString, path::PathBuf, sync::Arc};
+use std::{
+  collections::HashMap,
+  ffi::CString,
+  path::PathBuf,
+  sync::Arc,
+  time::{Duration, SystemTime},
+};
 
 use nix::unistd::Pid;
 
 use crate::{
-  proc::{read_argv, read_comm, FileDescriptorInfoCollection, Interpreter},
+  proc::{
+    read_argv, read_comm, read_ns_pid, read_start_time, FileDescriptorInfoCollection, Interpreter,
+  },
+  redact::{redactor, Secrets},
   tracer::InspectError,
 };
 
@@ -14,8 +23,13 @@ pub struct ProcessStateStore {
 #[derive(Debug)]
 pub struct ProcessState {
   pub pid: Pid,
+  /// The pid in the PID namespace of the process, if it differs from the one of tracexec
+  pub ns_pid: Option<Pid>,
   pub ppid: Option<Pid>,
+  /// How deep the process is in the process tree. The root child has a depth of 0.
+  pub depth: usize,
   pub status: ProcessStatus,
+  /// When the process started, see `read_start_time`. It is 0 if it is unknown.
   pub start_time: u64,
   pub argv: Vec<CString>,
   pub comm: String,
@@ -23,6 +37,8 @@ pub struct ProcessState {
   pub is_exec_successful: bool,
   pub syscall: i64,
   pub exec_data: Option<ExecData>,
+  /// User and system CPU time, read when the process is about to exit
+  pub cpu_time: Option<Duration>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -36,29 +52,46 @@ pub enum ProcessStatus {
 #[derive(Debug)]
 pub struct ExecData {
   pub filename: Result<PathBuf, InspectError>,
+  /// Canonical path of the executable, only available with --resolve-filename
+  pub resolved_filename: Option<PathBuf>,
   pub argv: Arc<Result<Vec<String>, InspectError>>,
+  /// The environment with the secrets redacted
   pub envp: Arc<Result<Vec<String>, InspectError>>,
+  pub secrets: Arc<Secrets>,
   pub cwd: PathBuf,
   pub interpreters: Vec<Interpreter>,
   pub fdinfo: Arc<FileDescriptorInfoCollection>,
+  /// When the exec syscall is entered
+  pub timestamp: SystemTime,
 }
 
 impl ExecData {
   pub fn new(
     filename: Result<PathBuf, InspectError>,
+    resolved_filename: Option<PathBuf>,
     argv: Result<Vec<String>, InspectError>,
     envp: Result<Vec<String>, InspectError>,
     cwd: PathBuf,
     interpreters: Vec<Interpreter>,
     fdinfo: FileDescriptorInfoCollection,
   ) -> Self {
+    let (envp, secrets) = match envp {
+      Ok(envp) => {
+        let (envp, secrets) = redactor().redact_envp(envp);
+        (Ok(envp), secrets)
+      }
+      Err(e) => (Err(e), Secrets::default()),
+    };
     Self {
       filename,
+      resolved_filename,
       argv: Arc::new(argv),
       envp: Arc::new(envp),
+      secrets: Arc::new(secrets),
       cwd,
       interpreters,
       fdinfo: Arc::new(fdinfo),
+      timestamp: SystemTime::now(),
     }
   }
 }
@@ -84,21 +117,57 @@ impl ProcessStateStore {
     // The last process in the vector is the current process
     self.processes.get(&pid)?.last()
   }
+
+  /// The processes that have not exited yet
+  pub fn running_pids(&self) -> Vec<Pid> {
+    self
+      .processes
+      .iter()
+      .filter(|(_, states)| {
+        states
+          .last()
+          .is_some_and(|state| !matches!(state.status, ProcessStatus::Exited(_)))
+      })
+      .map(|(pid, _)| *pid)
+      .collect()
+  }
 }
 
 impl ProcessState {
-  pub fn new(pid: Pid, start_time: u64) -> color_eyre::Result<Self> {
+  pub fn new(pid: Pid) -> color_eyre::Result<Self> {
     Ok(Self {
       pid,
+      ns_pid: read_ns_pid(pid).ok().flatten(),
       ppid: None,
+      depth: 0,
       status: ProcessStatus::Running,
       comm: read_comm(pid)?,
       argv: read_argv(pid)?,
-      start_time,
+      start_time: read_start_time(pid).unwrap_or_default(),
       presyscall: true,
       is_exec_successful: false,
       syscall: -1,
       exec_data: None,
+      cpu_time: None,
     })
   }
+
+  /// The state of a process in a recorded trace, which can't be read from /proc
+  pub fn recorded(pid: Pid, ppid: Option<Pid>, depth: usize, comm: String) -> Self {
+    Self {
+      pid,
+      ns_pid: None,
+      ppid,
+      depth,
+      status: ProcessStatus::Running,
+      comm,
+      argv: vec![],
+      start_time: 0,
+      presyscall: true,
+      is_exec_successful: false,
+      syscall: -1,
+      exec_data: None,
+      cpu_time: None,
+    }
+  }
 }
@@ -5,20 +5,36 @@ use tokio::sync::mpsc::UnboundedReceiver;
 use tracing::info;
 use tracing_test::traced_test;
 
+use nix::{
+  libc,
+  sys::{
+    ptrace::{self, AddressType},
+    signal::{kill, raise, Signal},
+    wait::{waitpid, WaitStatus},
+  },
+  unistd::{fork, ForkResult},
+};
+
 use crate::{
-  cli::args::{ModifierArgs, TracerEventArgs, LogModeArgs},
+  arch::stack_pointer_from_regs,
+  cli::args::{LogModeArgs, ModifierArgs, TracerEventArgs},
   event::TracerEvent,
   proc::{BaselineInfo, Interpreter},
   tracer::Tracer,
 };
 
-use super::TracerMode;
+use super::{
+  breakpoint::{write_string_arrays, StringArraysLayout, STACK_GAP, WORD_SIZE},
+  inspect::read_string_array,
+  ptrace::ptrace_getregs,
+  TracerMode,
+};
 
 #[fixture]
 fn tracer(
   #[default(Default::default())] modifier_args: ModifierArgs,
 ) -> (Arc<Tracer>, UnboundedReceiver<TracerEvent>) {
-  let tracer_mod = TracerMode::Log;
+  let tracer_mod = TracerMode::Log(None);
   let tracing_args = LogModeArgs::default();
   let tracer_event_args = TracerEventArgs {
     show_all_events: true,
@@ -151,3 +167,79 @@ async fn tracer_emits_exec_event(tracer: (Arc<Tracer>, UnboundedReceiver<TracerE
   }
   panic!("Corresponding exec event not found")
 }
+
+/// Read a null terminated array of strings out of the layout, like the tracee would
+fn read_layout_array(layout: &StringArraysLayout, mut address: u64) -> Vec<String> {
+  let offset = |address: u64| (address - layout.start) as usize;
+  let mut strings = vec![];
+  loop {
+    let pointer = u64::from_ne_bytes(
+      layout.bytes[offset(address)..offset(address) + WORD_SIZE]
+        .try_into()
+        .unwrap(),
+    );
+    if pointer == 0 {
+      return strings;
+    }
+    let string = &layout.bytes[offset(pointer)..];
+    let len = string.iter().position(|&b| b == 0).unwrap();
+    strings.push(String::from_utf8(string[..len].to_vec()).unwrap());
+    address += WORD_SIZE as u64;
+  }
+}
+
+#[test]
+fn string_arrays_are_laid_out_below_the_stack_pointer() {
+  let argv = vec![
+    "sh".to_string(),
+    "-c".to_string(),
+    "echo \"$A\"".to_string(),
+  ];
+  let envp = vec!["A=1".to_string()];
+  let empty: Vec<String> = vec![];
+  let stack_pointer = 0x7ffc_1234_5679;
+  let layout = StringArraysLayout::new(stack_pointer, &[&argv, &envp, &empty]).unwrap();
+  assert_eq!(layout.start % 16, 0);
+  assert_eq!(layout.bytes.len() % WORD_SIZE, 0);
+  assert!(layout.start + layout.bytes.len() as u64 <= stack_pointer - STACK_GAP);
+  assert_eq!(layout.addresses[0], layout.start);
+  assert_eq!(read_layout_array(&layout, layout.addresses[0]), argv);
+  assert_eq!(read_layout_array(&layout, layout.addresses[1]), envp);
+  assert_eq!(read_layout_array(&layout, layout.addresses[2]), empty);
+  // No underflow of a bogus stack pointer
+  assert!(StringArraysLayout::new(STACK_GAP, &[&argv]).is_none());
+}
+
+#[test]
+fn string_arrays_are_written_into_the_tracee() {
+  // SAFETY: the child only makes system calls and exits without running the destructors
+  match unsafe { fork() }.unwrap() {
+    ForkResult::Child => {
+      let _ = ptrace::traceme().and_then(|_| raise(Signal::SIGSTOP));
+      unsafe { libc::_exit(0) }
+    }
+    ForkResult::Parent { child } => {
+      assert_eq!(
+        waitpid(child, None).unwrap(),
+        WaitStatus::Stopped(child, Signal::SIGSTOP)
+      );
+      let argv = vec!["/bin/echo".to_string(), "hello world".to_string()];
+      let envp = vec!["A=1".to_string(), "B=".to_string()];
+      let regs = ptrace_getregs(child).unwrap();
+      let result = write_string_arrays(
+        child,
+        stack_pointer_from_regs!(regs) as u64,
+        &[&argv, &envp],
+      )
+      .and_then(|addresses| {
+        addresses
+          .into_iter()
+          .map(|address| read_string_array(child, address as AddressType))
+          .collect::<Result<Vec<_>, _>>()
+      });
+      kill(child, Signal::SIGKILL).unwrap();
+      waitpid(child, None).unwrap();
+      assert_eq!(result.unwrap(), vec![argv, envp]);
+    }
+  }
+}
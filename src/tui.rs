@@ -18,19 +18,24 @@
 
 use std::{
   ops::{Deref, DerefMut},
-  time::Duration,
+  sync::Arc,
+  time::{Duration, Instant},
 };
 
 use color_eyre::eyre::Result;
 use crossterm::{
   cursor,
-  event::{Event as CrosstermEvent, KeyEventKind},
+  event::{DisableMouseCapture, EnableMouseCapture, Event as CrosstermEvent, KeyEventKind},
+  style::Print,
   terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
 use futures::{FutureExt, StreamExt};
 use ratatui::{backend::CrosstermBackend as Backend, layout::Size};
 use tokio::{
-  sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+  sync::{
+    mpsc::{self, UnboundedReceiver, UnboundedSender},
+    Notify,
+  },
   task::JoinHandle,
 };
 use tokio_util::sync::CancellationToken;
@@ -39,15 +44,32 @@ use tracing::{error, trace};
 use crate::event::{Event, TracerEvent};
 
 pub mod app;
+pub mod breakpoint_popup;
+pub mod clipboard;
+pub mod columns_popup;
 pub mod copy_popup;
 pub mod details_popup;
-mod event_list;
+pub mod diff_popup;
+pub mod event_list;
+pub mod export_popup;
+mod file_info;
 pub mod help;
+pub mod keymap;
+pub mod kill_popup;
+pub mod new_trace_popup;
 mod partial_line;
+pub mod process_picker;
+mod process_tree;
 mod pseudo_term;
-mod sized_paragraph;
+mod search;
+pub mod sort_popup;
+pub mod state;
+mod stats;
+pub mod tabs;
 pub mod theme;
+mod timeline;
 mod ui;
+mod watch;
 
 pub struct Tui {
   pub terminal: ratatui::Terminal<Backend<std::io::Stderr>>,
@@ -56,16 +78,40 @@ pub struct Tui {
   pub event_rx: UnboundedReceiver<Event>,
   pub event_tx: UnboundedSender<Event>,
   pub frame_rate: f64,
+  pub mouse: bool,
+  /// Notified on tracer events and terminal output, which end the idle render rate
+  pub activity: Arc<Notify>,
 }
 
+/// The render rate is lowered when nothing has happened for this long
+const IDLE_AFTER: Duration = Duration::from_secs(1);
+/// The render rate while idle, which still keeps the clock in the status line going
+const IDLE_FRAME_RATE: f64 = 2.0;
+
+/// Saves the window title on the title stack of xterm compatible terminals
+const PUSH_TITLE: &str = "\x1b[22;0t";
+/// Restores the saved window title
+const POP_TITLE: &str = "\x1b[23;0t";
+
 pub fn init_tui() -> Result<()> {
   crossterm::terminal::enable_raw_mode()?;
-  crossterm::execute!(std::io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+  crossterm::execute!(
+    std::io::stdout(),
+    EnterAlternateScreen,
+    cursor::Hide,
+    Print(PUSH_TITLE)
+  )?;
   Ok(())
 }
 
 pub fn restore_tui() -> Result<()> {
-  crossterm::execute!(std::io::stdout(), LeaveAlternateScreen, cursor::Show)?;
+  crossterm::execute!(
+    std::io::stdout(),
+    DisableMouseCapture,
+    LeaveAlternateScreen,
+    cursor::Show,
+    Print(POP_TITLE)
+  )?;
   crossterm::terminal::disable_raw_mode()?;
   Ok(())
 }
@@ -84,6 +130,8 @@ impl Tui {
       event_rx,
       event_tx,
       frame_rate,
+      mouse: true,
+      activity: Arc::new(Notify::new()),
     })
   }
 
@@ -92,29 +140,72 @@ impl Tui {
     self
   }
 
-  pub fn start(&mut self, mut tracer_rx: UnboundedReceiver<TracerEvent>) {
-    let render_delay = std::time::Duration::from_secs_f64(1.0 / self.frame_rate);
+  pub fn mouse(mut self, mouse: bool) -> Self {
+    self.mouse = mouse;
+    self
+  }
+
+  pub fn activity(mut self, activity: Arc<Notify>) -> Self {
+    self.activity = activity;
+    self
+  }
+
+  /// Forward the tracer events to the event loop. Unlike the terminal events, they are still
+  /// received while the TUI is suspended.
+  fn forward_tracer_events(&self, mut tracer_rx: UnboundedReceiver<TracerEvent>) {
+    let event_tx = self.event_tx.clone();
+    let activity = self.activity.clone();
+    tokio::spawn(async move {
+      while let Some(tracer_event) = tracer_rx.recv().await {
+        trace!("TUI event: tracer event!");
+        if event_tx.send(Event::Tracer(tracer_event)).is_err() {
+          break;
+        }
+        activity.notify_one();
+      }
+    });
+  }
+
+  /// Start reading the terminal events and sending the render events.
+  ///
+  /// Nothing needs to be redrawn while nothing happens, so the render rate is lowered when idle
+  /// and goes back up as soon as there is input, a tracer event or output in the pseudo terminal.
+  pub fn start(&mut self) {
+    let active_delay = Duration::from_secs_f64(1.0 / self.frame_rate);
+    let idle_delay = Duration::from_secs_f64(1.0 / IDLE_FRAME_RATE.min(self.frame_rate));
     self.cancel();
     self.cancellation_token = CancellationToken::new();
     let _cancellation_token = self.cancellation_token.clone();
     let _event_tx = self.event_tx.clone();
+    let activity = self.activity.clone();
     self.task = tokio::spawn(async move {
       let mut reader = crossterm::event::EventStream::new();
-      let mut render_interval = tokio::time::interval(render_delay);
+      let mut render_interval = tokio::time::interval(active_delay);
+      let mut last_activity = Instant::now();
+      let mut idle = false;
       _event_tx.send(Event::Init).unwrap();
       loop {
         let render_delay = render_interval.tick();
         let crossterm_event = reader.next().fuse();
-        let tracer_event = tracer_rx.recv();
+        let woken = activity.notified();
         tokio::select! {
             _ = _cancellation_token.cancelled() => {
                 break;
             }
-            Some(tracer_event) = tracer_event => {
-              trace!("TUI event: tracer event!");
-              _event_tx.send(Event::Tracer(tracer_event)).unwrap();
+            _ = woken => {
+              last_activity = Instant::now();
+              if idle {
+                idle = false;
+                // The first tick is immediate
+                render_interval = tokio::time::interval(active_delay);
+              }
             }
             Some(event) = crossterm_event => {
+              last_activity = Instant::now();
+              if idle {
+                idle = false;
+                render_interval = tokio::time::interval(active_delay);
+              }
               trace!("TUI event: crossterm event {event:?}!");
                 match event {
                   Ok(evt) => {
@@ -124,6 +215,9 @@ impl Tui {
                               _event_tx.send(Event::Key(key)).unwrap();
                           }
                       },
+                      CrosstermEvent::Mouse(mouse) => {
+                          _event_tx.send(Event::Mouse(mouse)).unwrap();
+                      },
                       CrosstermEvent::Resize(cols, rows) => {
                           _event_tx.send(Event::Resize(Size {
                               width: cols,
@@ -141,6 +235,12 @@ impl Tui {
             _ = render_delay => {
               // log::trace!("TUI event: Render!");
               _event_tx.send(Event::Render).unwrap();
+              if !idle && last_activity.elapsed() >= IDLE_AFTER {
+                idle = true;
+                render_interval = tokio::time::interval(idle_delay);
+                // Skip the immediate first tick
+                render_interval.reset();
+              }
             },
         }
       }
@@ -166,7 +266,30 @@ impl Tui {
 
   pub fn enter(&mut self, tracer_rx: UnboundedReceiver<TracerEvent>) -> Result<()> {
     init_tui()?;
-    self.start(tracer_rx);
+    if self.mouse {
+      crossterm::execute!(std::io::stdout(), EnableMouseCapture)?;
+    }
+    self.forward_tracer_events(tracer_rx);
+    self.start();
+    Ok(())
+  }
+
+  /// Give the terminal back, e.g. to run an editor in it. The terminal events are not read until
+  /// the TUI is resumed, so that they go to the program running in the terminal.
+  pub fn suspend(&mut self) -> Result<()> {
+    self.stop()?;
+    restore_tui()?;
+    Ok(())
+  }
+
+  pub fn resume(&mut self) -> Result<()> {
+    init_tui()?;
+    if self.mouse {
+      crossterm::execute!(std::io::stdout(), EnableMouseCapture)?;
+    }
+    // The screen has been overwritten, so everything needs to be drawn again
+    self.terminal.clear()?;
+    self.start();
     Ok(())
   }
 
@@ -16,107 +16,292 @@
 // OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
 // CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
-use std::ops::ControlFlow;
+use std::{
+  fs::OpenOptions,
+  io::{BufWriter, Write},
+  mem,
+  ops::ControlFlow,
+  path::Path,
+  sync::Arc,
+  thread::JoinHandle,
+  time::{Duration, Instant},
+};
 
-use arboard::Clipboard;
 use clap::ValueEnum;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use color_eyre::eyre::bail;
+use crossterm::{
+  event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+  style::Print,
+  terminal::SetTitle,
+};
 
+use enumflags2::BitFlags;
 use itertools::chain;
-use nix::{sys::signal::Signal, unistd::Pid};
+use nix::{errno::Errno, sys::signal::Signal, unistd::Pid};
 use ratatui::{
   buffer::Buffer,
-  layout::{Constraint, Layout, Rect},
-  text::Line,
-  widgets::{Block, Paragraph, StatefulWidgetRef, Widget, Wrap},
+  layout::{Alignment, Constraint, Layout, Rect},
+  style::{Modifier, Styled},
+  text::{Line, Span},
+  widgets::{block::Title, Block, Paragraph, StatefulWidgetRef, Widget, Wrap},
 };
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use strum::Display;
-use tokio::sync::mpsc;
-use tracing::{debug, trace};
-use tui_popup::Popup;
+use tokio::sync::{
+  mpsc::{self, UnboundedSender},
+  Notify,
+};
+use tracing::{debug, trace, warn};
 
 use crate::{
-  action::{Action, ActivePopup},
+  action::{Action, ActivePopup, CopyTarget, SupportedShell},
   cli::{
-    args::{ModifierArgs, LogModeArgs},
-    options::ActivePane,
+    args::{LogModeArgs, ModifierArgs},
+    config::TuiModeConfig,
+    options::{ActivePane, ClipboardProvider},
   },
-  event::{Event, TracerEvent},
+  event::{Event, FilterableTracerEvent, TracerEvent, TracerEventKind},
+  export::event_to_json,
   printer::PrinterArgs,
   proc::BaselineInfo,
-  pty::{PtySize, UnixMasterPty},
+  pty::{MasterPty, PtySize, UnixMasterPty},
+  term::symbols,
+  tracer::{Breakpoints, Tracer, TracerPause},
 };
 
 use super::{
+  breakpoint_popup::{BreakpointPopup, BreakpointPopupState},
+  clipboard::Clipboard,
+  columns_popup::{ColumnsPopup, ColumnsPopupState},
   copy_popup::{CopyPopup, CopyPopupState},
   details_popup::{DetailsPopup, DetailsPopupState},
-  event_list::EventList,
-  help::{help, help_item},
+  diff_popup::{DiffPopup, DiffPopupState},
+  event_list::{EventColumn, EventList, ListFilter},
+  export_popup::{ExportFormat, ExportPopup, ExportPopupState, ExportScope},
+  help::{help_item, HelpPopup, HelpPopupState},
+  keymap::{KeyAction, Keymap},
+  kill_popup::{KillPopup, KillPopupState},
+  new_trace_popup::{CommandTarget, NewTracePopup, NewTracePopupState},
+  process_picker::{ProcessPicker, ProcessPickerState},
+  process_tree::ProcessTree,
   pseudo_term::PseudoTerminalPane,
+  search::{SearchInput, SearchPurpose},
+  sort_popup::{SortPopup, SortPopupState},
+  state::TuiState,
+  stats::{Stats, StatsOrder},
+  tabs::{title_of, NewTrace, TraceSpawner, TraceTab, TraceTarget},
   theme::THEME,
+  timeline::Timeline,
   ui::render_title,
+  watch::{Watch, WatchAlert, BELL_INTERVAL},
   Tui,
 };
 
-#[derive(Debug, Clone, PartialEq, Default, ValueEnum, Display)]
+#[derive(Debug, Clone, PartialEq, Default, ValueEnum, Display, Serialize, Deserialize)]
 #[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
 pub enum AppLayout {
   #[default]
   Horizontal,
   Vertical,
 }
 
+/// What is shown in the event pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EventsView {
+  #[default]
+  List,
+  Stats,
+  Timeline,
+}
+
 pub struct App {
   pub event_list: EventList,
   pub printer_args: PrinterArgs,
   pub term: Option<PseudoTerminalPane>,
-  pub root_pid: Option<Pid>,
   pub active_pane: ActivePane,
   pub clipboard: Option<Clipboard>,
   pub split_percentage: u16,
   pub layout: AppLayout,
   pub should_handle_internal_resize: bool,
   pub popup: Option<ActivePopup>,
+  pub search_input: Option<SearchInput>,
+  pub process_tree: ProcessTree,
+  pub show_process_tree: bool,
+  pub keymap: Keymap,
+  pub stats: Stats,
+  pub timeline: Timeline,
+  pub events_view: EventsView,
+  /// The events shown in the event list, the other events are only used for the other views
+  pub event_filter: BitFlags<TracerEventKind>,
+  /// Areas of the panes in the last render, for handling mouse events
+  areas: PaneAreas,
+  dragging_splitter: bool,
+  /// The active pane is maximized, the other panes are hidden
+  zoomed: bool,
+  /// Where the mouse was pressed in the pseudo terminal, text is selected by dragging from it
+  terminal_drag_start: Option<(u16, u16)>,
+  /// Pauses the tracer. This doesn't freeze the processes: each one keeps running until its next
+  /// traced stop, e.g. an exec, and is held there until the tracer is resumed.
+  pub tracer_pause: Option<Arc<TracerPause>>,
+  /// The process whose children are cycled through, so that cycling continues from a child
+  child_cycle_parent: Option<Pid>,
+  /// The exec event that is compared against when diffing
+  diff_base: Option<Arc<TracerEvent>>,
+  /// The traces in the tabs, the trace of the active tab is `None` because its state is in the app
+  tabs: Vec<Option<TraceTab>>,
+  tab_titles: Vec<String>,
+  /// How the root process of the trace in each tab exited, e.g. `exited with 1`
+  tab_exits: Vec<Option<String>>,
+  active_tab: usize,
+  watches: Vec<Watch>,
+  /// The notification of the last matched watch
+  watch_alert: Option<WatchAlert>,
+  /// When the bell was last rung for a watch
+  last_bell: Option<Instant>,
+  /// The exec breakpoints, shared with the tracers of all the tabs
+  pub breakpoints: Arc<Breakpoints>,
+  /// Wakes up the TUI from the lower render rate while idle, shared with the `Tui`
+  pub activity: Arc<Notify>,
+  /// The last title set for the host terminal window, it is only set when it changes
+  window_title: String,
+  /// Starts the traces of new tabs
+  pub trace_spawner: Option<TraceSpawner>,
+  /// The tracer threads of the traces started in new tabs, which are joined after the TUI exits
+  pub tracer_threads: Vec<JoinHandle<color_eyre::Result<()>>>,
+  /// The tracer of the trace in the active tab
+  pub tracer: Option<Arc<Tracer>>,
+  /// The filter of the event list restored from the last session, shown as such while it is in use
+  restored_filter: Option<String>,
+  /// Whether the setup of the TUI is remembered for the next session when it exits
+  save_state: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PaneAreas {
+  /// The area containing all panes
+  panes: Rect,
+  events: Rect,
+  event_list: Rect,
+  process_tree: Option<Rect>,
+  terminal: Option<Rect>,
 }
 
 impl App {
+  #[allow(clippy::too_many_arguments)]
   pub fn new(
     tracing_args: &LogModeArgs,
     modifier_args: &ModifierArgs,
     baseline: BaselineInfo,
     pty_master: Option<UnixMasterPty>,
-    active_pane: ActivePane,
-    layout: AppLayout,
-    follow: bool,
+    active_pane: Option<ActivePane>,
+    layout: Option<AppLayout>,
+    follow: Option<bool>,
+    keymap: Keymap,
+    event_filter: BitFlags<TracerEventKind>,
+    clipboard: ClipboardProvider,
+    config: &TuiModeConfig,
   ) -> color_eyre::Result<Self> {
-    let active_pane = if pty_master.is_some() {
-      active_pane
-    } else {
+    // The command line takes precedence over the config, which takes precedence over the last session
+    let state = TuiState::load();
+    let active_pane = active_pane
+      .or(config.active_pane)
+      .or(state.active_pane)
+      .unwrap_or_default();
+    let active_pane = if pty_master.is_none() && active_pane == ActivePane::Terminal {
       ActivePane::Events
+    } else {
+      active_pane
     };
+    let layout = layout
+      .or(config.layout)
+      .or(state.layout)
+      .unwrap_or_default();
+    let columns = config
+      .columns
+      .clone()
+      .or(state.columns)
+      .unwrap_or_else(EventColumn::default_columns);
+    let mut event_list = EventList::new(
+      baseline,
+      follow.or(config.follow).or(state.follow).unwrap_or(false),
+      modifier_args.to_owned(),
+      columns,
+      config.history.unwrap_or(1_000_000).max(1),
+    );
+    event_list.filter = match (state.filter, state.filter_expr) {
+      (Some(filter), _) => Regex::new(&filter)
+        .map(ListFilter::Regex)
+        .inspect_err(|e| warn!("Ignoring the last filter: {e}"))
+        .ok(),
+      (None, Some(expr)) => expr
+        .parse()
+        .map(ListFilter::Expr)
+        .inspect_err(|e| warn!("Ignoring the last filter: {e}"))
+        .ok(),
+      (None, None) => None,
+    };
+    let restored_filter = event_list
+      .filter
+      .as_ref()
+      .map(|filter| filter.as_str().to_owned());
+    let activity = Arc::new(Notify::new());
     Ok(Self {
-      event_list: EventList::new(baseline, follow, modifier_args.to_owned()),
+      event_list,
       printer_args: PrinterArgs::from_cli(tracing_args, modifier_args),
-      split_percentage: if pty_master.is_some() { 50 } else { 100 },
+      split_percentage: if pty_master.is_some() {
+        state
+          .split_percentage
+          .map_or(50, |percentage| percentage.clamp(10, 90))
+      } else {
+        100
+      },
       term: if let Some(pty_master) = pty_master {
         Some(PseudoTerminalPane::new(
-          PtySize {
-            rows: 24,
-            cols: 80,
-            pixel_width: 0,
-            pixel_height: 0,
-          },
+          pty_master.get_size().unwrap_or_default(),
           pty_master,
+          config.scrollback.unwrap_or(1000),
+          activity.clone(),
         )?)
       } else {
         None
       },
-      root_pid: None,
       active_pane,
-      clipboard: Clipboard::new().ok(),
+      clipboard: Clipboard::new(clipboard),
       layout,
       should_handle_internal_resize: true,
       popup: None,
+      search_input: None,
+      process_tree: ProcessTree::default(),
+      show_process_tree: active_pane == ActivePane::ProcessTree,
+      keymap,
+      stats: Stats::default(),
+      timeline: Timeline::default(),
+      events_view: EventsView::List,
+      event_filter,
+      areas: PaneAreas::default(),
+      dragging_splitter: false,
+      zoomed: false,
+      terminal_drag_start: None,
+      tracer_pause: None,
+      child_cycle_parent: None,
+      diff_base: None,
+      tabs: vec![None],
+      tab_titles: vec![String::new()],
+      tab_exits: vec![None],
+      watches: vec![],
+      breakpoints: Arc::default(),
+      activity,
+      watch_alert: None,
+      last_bell: None,
+      window_title: String::new(),
+      active_tab: 0,
+      trace_spawner: None,
+      tracer_threads: vec![],
+      tracer: None,
+      restored_filter,
+      save_state: true,
     })
   }
 
@@ -132,6 +317,259 @@ impl App {
     }
   }
 
+  /// Translate a command from the keymap into actions
+  fn key_actions(&self, key_action: KeyAction) -> Vec<Action> {
+    match key_action {
+      KeyAction::Quit => vec![Action::Quit],
+      KeyAction::NextItem => vec![Action::NextItem],
+      KeyAction::PrevItem => vec![Action::StopFollow, Action::PrevItem],
+      KeyAction::PageDown => vec![Action::PageDown],
+      KeyAction::PageUp => vec![Action::StopFollow, Action::PageUp],
+      KeyAction::PageLeft => vec![Action::PageLeft],
+      KeyAction::PageRight => vec![Action::PageRight],
+      KeyAction::ScrollLeft => vec![Action::ScrollLeft],
+      KeyAction::ScrollRight => vec![Action::ScrollRight],
+      KeyAction::ScrollToTop => vec![Action::StopFollow, Action::ScrollToTop],
+      KeyAction::ScrollToBottom => vec![Action::ScrollToBottom],
+      KeyAction::ScrollToStart => vec![Action::ScrollToStart],
+      KeyAction::ScrollToEnd => vec![Action::ScrollToEnd],
+      KeyAction::ToggleFollow => vec![Action::ToggleFollow],
+      KeyAction::TogglePin => vec![Action::TogglePin],
+      KeyAction::ToggleEnvDisplay => vec![Action::ToggleEnvDisplay],
+      KeyAction::ToggleWrap => vec![Action::ToggleWrap],
+      KeyAction::ToggleGroup => vec![Action::ToggleGroup],
+      KeyAction::ToggleCollapse => vec![Action::StopFollow, Action::ToggleCollapse],
+      KeyAction::ToggleSubtree => vec![Action::ToggleSubtree],
+      KeyAction::GrowPane => vec![Action::GrowPane],
+      KeyAction::ShrinkPane => vec![Action::ShrinkPane],
+      KeyAction::SwitchLayout => vec![Action::SwitchLayout],
+      KeyAction::ToggleZoom => vec![Action::ToggleZoom],
+      KeyAction::Copy => match self.event_list.selection() {
+        Some(selected) if self.clipboard.is_some() => vec![Action::ShowCopyDialog(selected)],
+        _ => vec![],
+      },
+      KeyAction::ViewDetails => match self.event_list.selection() {
+        Some(selected) => vec![Action::SetActivePopup(ActivePopup::ViewDetails(
+          DetailsPopupState::new(
+            selected.clone(),
+            self.event_list.baseline.clone(),
+            self.event_list.parent_exec(&selected),
+          ),
+        ))],
+        None => vec![],
+      },
+      KeyAction::Help => vec![Action::SetActivePopup(ActivePopup::Help(
+        HelpPopupState::default(),
+      ))],
+      KeyAction::Search => vec![Action::BeginSearch],
+      KeyAction::NextMatch => vec![Action::StopFollow, Action::NextMatch],
+      KeyAction::PrevMatch => vec![Action::StopFollow, Action::PrevMatch],
+      KeyAction::Filter => vec![Action::BeginFilter],
+      KeyAction::Watch => vec![Action::BeginWatch],
+      KeyAction::GoTo => vec![Action::BeginGoTo],
+      KeyAction::Breakpoint => vec![Action::BeginBreakpoint],
+      KeyAction::ToggleProcessTree => vec![Action::ToggleProcessTree],
+      KeyAction::ToggleStats => vec![Action::ToggleStats],
+      KeyAction::ToggleTimeline => vec![Action::ToggleTimeline],
+      KeyAction::ToggleBookmark => vec![Action::ToggleBookmark],
+      KeyAction::NextBookmark => vec![Action::StopFollow, Action::NextBookmark],
+      KeyAction::NextFailure => vec![Action::StopFollow, Action::NextFailure],
+      KeyAction::PrevFailure => vec![Action::StopFollow, Action::PrevFailure],
+      KeyAction::MarkDiffBase => vec![Action::MarkDiffBase],
+      KeyAction::Diff => match (self.diff_base.as_deref(), self.event_list.selection()) {
+        (Some(TracerEvent::Exec(base)), Some(selected)) => match selected.as_ref() {
+          TracerEvent::Exec(target) => vec![Action::SetActivePopup(ActivePopup::Diff(
+            DiffPopupState::new(base, target),
+          ))],
+          _ => vec![],
+        },
+        _ => vec![],
+      },
+      KeyAction::TogglePause => vec![Action::TogglePause],
+      KeyAction::JumpToParent => vec![Action::StopFollow, Action::JumpToParent],
+      KeyAction::NextChild => vec![Action::StopFollow, Action::NextChild],
+      KeyAction::Export => vec![Action::SetActivePopup(ActivePopup::Export(
+        ExportPopupState::new(self.event_list.filter.is_some()),
+      ))],
+      KeyAction::Columns => vec![Action::SetActivePopup(ActivePopup::Columns(
+        ColumnsPopupState::new(self.event_list.columns()),
+      ))],
+      KeyAction::Sort => vec![Action::SetActivePopup(ActivePopup::Sort(
+        SortPopupState::new(self.event_list.sort),
+      ))],
+      KeyAction::NewTrace if self.trace_spawner.is_some() => vec![Action::SetActivePopup(
+        ActivePopup::NewTrace(NewTracePopupState::default()),
+      )],
+      KeyAction::NewTrace => vec![],
+      KeyAction::Attach if self.trace_spawner.is_some() => vec![Action::SetActivePopup(
+        ActivePopup::ProcessPicker(ProcessPickerState::default()),
+      )],
+      KeyAction::Attach => vec![],
+      KeyAction::Rerun => match self.event_list.selection() {
+        Some(selected) if self.term.is_some() && matches!(*selected, TracerEvent::Exec(_)) => {
+          let command = selected.text_for_copy(
+            &self.event_list.baseline,
+            CopyTarget::Commandline(SupportedShell::Bash),
+            &self.event_list.modifier_args,
+            self.event_list.env_in_cmdline,
+          );
+          vec![Action::SetActivePopup(ActivePopup::NewTrace(
+            NewTracePopupState::rerun(command.into_owned()),
+          ))]
+        }
+        _ => vec![],
+      },
+      KeyAction::KillProcess => self
+        .event_list
+        .selection()
+        .and_then(|selected| KillPopupState::new(&selected))
+        // Only the live processes of the trace in the active tab
+        .filter(|state| self.tracer_pause.is_some() && self.process_tree.is_running(state.pid))
+        .map(|state| Action::SetActivePopup(ActivePopup::KillConfirmation(state)))
+        .into_iter()
+        .collect(),
+    }
+  }
+
+  /// Whether the position is on the border between the event list and the terminal
+  fn is_on_splitter(&self, column: u16, row: u16) -> bool {
+    let Some(term_area) = self.areas.terminal.filter(|_| !self.zoomed) else {
+      return false;
+    };
+    let events = self.areas.events;
+    match self.layout {
+      AppLayout::Horizontal => {
+        (column == events.right().saturating_sub(1) || column == term_area.x)
+          && (events.y..events.bottom()).contains(&row)
+      }
+      AppLayout::Vertical => {
+        (row == events.bottom().saturating_sub(1) || row == term_area.y)
+          && (events.x..events.right()).contains(&column)
+      }
+    }
+  }
+
+  /// The position on the screen of the pseudo terminal, clamped to it
+  fn terminal_position(&self, column: u16, row: u16) -> (u16, u16) {
+    let Some(area) = self.areas.terminal else {
+      return (0, 0);
+    };
+    // Skip the border
+    (
+      row
+        .saturating_sub(area.y + 1)
+        .min(area.height.saturating_sub(3)),
+      column
+        .saturating_sub(area.x + 1)
+        .min(area.width.saturating_sub(3)),
+    )
+  }
+
+  /// Translate a mouse event into actions
+  fn mouse_actions(&mut self, me: MouseEvent) -> Vec<Action> {
+    let contains = |area: Rect| {
+      (area.x..area.right()).contains(&me.column) && (area.y..area.bottom()).contains(&me.row)
+    };
+    let in_events = contains(self.areas.events);
+    let in_tree = self.areas.process_tree.is_some_and(contains);
+    let in_terminal = self.areas.terminal.is_some_and(contains);
+    match me.kind {
+      MouseEventKind::Down(MouseButton::Left) if self.is_on_splitter(me.column, me.row) => {
+        self.dragging_splitter = true;
+        vec![]
+      }
+      MouseEventKind::Drag(MouseButton::Left) if self.dragging_splitter => {
+        let panes = self.areas.panes;
+        let (offset, len) = match self.layout {
+          AppLayout::Horizontal => (me.column.saturating_sub(panes.x), panes.width),
+          AppLayout::Vertical => (me.row.saturating_sub(panes.y), panes.height),
+        };
+        if len == 0 {
+          return vec![];
+        }
+        let percentage = (u32::from(offset + 1) * 100 / u32::from(len)) as u16;
+        vec![Action::ResizePane(percentage.clamp(10, 90))]
+      }
+      MouseEventKind::Drag(MouseButton::Left) if self.terminal_drag_start.is_some() => {
+        let from = self.terminal_drag_start.unwrap();
+        vec![Action::SelectTerminalText {
+          from,
+          to: self.terminal_position(me.column, me.row),
+        }]
+      }
+      MouseEventKind::Up(MouseButton::Left) => {
+        self.dragging_splitter = false;
+        if self.terminal_drag_start.take().is_some()
+          && self.term.as_ref().is_some_and(|t| t.has_selection())
+        {
+          vec![Action::YankTerminalSelection]
+        } else {
+          vec![]
+        }
+      }
+      MouseEventKind::Down(MouseButton::Left) if in_events => {
+        let mut actions = vec![Action::SetActivePane(ActivePane::Events)];
+        let list = self.areas.event_list;
+        if contains(list) && self.events_view == EventsView::List {
+          actions.extend([
+            Action::StopFollow,
+            Action::SelectItem((me.row - list.y) as usize),
+          ]);
+        }
+        actions
+      }
+      MouseEventKind::Down(MouseButton::Left) if in_tree => {
+        let tree = self.areas.process_tree.unwrap();
+        let mut actions = vec![Action::SetActivePane(ActivePane::ProcessTree)];
+        // Skip the border
+        if me.row > tree.y && me.row < tree.bottom().saturating_sub(1) {
+          actions.push(Action::SelectProcess((me.row - tree.y - 1) as usize));
+        }
+        actions
+      }
+      MouseEventKind::Down(MouseButton::Left) if in_terminal => {
+        self.terminal_drag_start = Some(self.terminal_position(me.column, me.row));
+        vec![
+          Action::SetActivePane(ActivePane::Terminal),
+          Action::ExitTerminalCopyMode,
+        ]
+      }
+      MouseEventKind::ScrollUp if in_terminal => vec![Action::ScrollTerminal(3)],
+      MouseEventKind::ScrollDown if in_terminal => vec![Action::ScrollTerminal(-3)],
+      MouseEventKind::ScrollDown if in_events && self.events_view == EventsView::Stats => {
+        vec![Action::NextStatsRow]
+      }
+      MouseEventKind::ScrollUp if in_events && self.events_view == EventsView::Stats => {
+        vec![Action::PrevStatsRow]
+      }
+      MouseEventKind::ScrollDown if in_events && self.events_view == EventsView::Timeline => {
+        vec![Action::ScrollTimelineDown]
+      }
+      MouseEventKind::ScrollUp if in_events && self.events_view == EventsView::Timeline => {
+        vec![Action::ScrollTimelineUp]
+      }
+      MouseEventKind::ScrollDown if in_events => vec![Action::NextItem],
+      MouseEventKind::ScrollUp if in_events => vec![Action::StopFollow, Action::PrevItem],
+      MouseEventKind::ScrollDown if in_tree => vec![Action::NextProcess],
+      MouseEventKind::ScrollUp if in_tree => vec![Action::PrevProcess],
+      _ => vec![],
+    }
+  }
+
+  /// Don't remember the setup of the TUI for the next session, e.g. when browsing a recorded trace
+  pub fn without_saving_state(mut self) -> Self {
+    self.save_state = false;
+    self
+  }
+
+  /// Keep the size of the pseudo terminal as it is opened instead of fitting it to the pane
+  pub fn with_fixed_pty_size(mut self, fixed: bool) -> Self {
+    if let Some(term) = self.term.as_mut() {
+      term.fixed_size = fixed;
+    }
+    self
+  }
+
   pub async fn run(&mut self, tui: &mut Tui) -> color_eyre::Result<()> {
     let (action_tx, mut action_rx) = mpsc::unbounded_channel();
 
@@ -151,21 +589,48 @@ impl App {
               // Cancel all popups
               self.popup = None;
               // action_tx.send(Action::Render)?;
+            } else if self.popup.is_none()
+              && self.search_input.is_none()
+              && self.keymap.action(&ke) == Some(KeyAction::ToggleZoom)
+            {
+              // Works in all panes, including the terminal
+              action_tx.send(Action::ToggleZoom)?;
             } else {
               trace!("TUI: Active pane: {}", self.active_pane);
-              if self.active_pane == ActivePane::Events {
+              if self.active_pane == ActivePane::ProcessTree {
+                match self.keymap.action(&ke) {
+                  Some(KeyAction::Quit) => {
+                    action_tx.send(Action::Quit)?;
+                  }
+                  Some(KeyAction::NextItem) => {
+                    action_tx.send(Action::NextProcess)?;
+                  }
+                  Some(KeyAction::PrevItem) => {
+                    action_tx.send(Action::PrevProcess)?;
+                  }
+                  Some(KeyAction::ToggleProcessTree) => {
+                    action_tx.send(Action::ToggleProcessTree)?;
+                  }
+                  _ if ke.code == KeyCode::Enter => {
+                    if let Some(pid) = self.process_tree.selection() {
+                      action_tx.send(Action::JumpToProcess(pid))?;
+                    }
+                  }
+                  _ => {}
+                }
+              } else if self.active_pane == ActivePane::Events {
                 // Handle popups
                 // TODO: do this in a separate function
                 if let Some(popup) = &mut self.popup {
                   match popup {
-                    ActivePopup::Help => {
-                      self.popup = None;
+                    ActivePopup::Help(state) => {
+                      if let Some(action) = state.handle_key_event(ke) {
+                        action_tx.send(action)?;
+                      }
                     }
                     ActivePopup::ViewDetails(state) => {
-                      if let ControlFlow::Break(()) =
-                        state.handle_key_event(ke, self.clipboard.as_mut())?
-                      {
-                        self.popup = None;
+                      if let Some(action) = state.handle_key_event(ke, self.clipboard.as_mut())? {
+                        action_tx.send(action)?;
                       }
                     }
                     ActivePopup::CopyTargetSelection(state) => {
@@ -173,120 +638,138 @@ impl App {
                         action_tx.send(action)?;
                       }
                     }
+                    ActivePopup::KillConfirmation(state) => {
+                      if let Some(action) = state.handle_key_event(ke) {
+                        action_tx.send(action)?;
+                      }
+                    }
+                    ActivePopup::Export(state) => {
+                      if let Some(action) = state.handle_key_event(ke) {
+                        action_tx.send(action)?;
+                      }
+                    }
+                    ActivePopup::Columns(state) => {
+                      if let Some(action) = state.handle_key_event(ke) {
+                        action_tx.send(action)?;
+                      }
+                    }
+                    ActivePopup::Sort(state) => {
+                      if let Some(action) = state.handle_key_event(ke) {
+                        action_tx.send(action)?;
+                      }
+                    }
+                    ActivePopup::NewTrace(state) => {
+                      if let Some(action) = state.handle_key_event(ke) {
+                        action_tx.send(action)?;
+                      }
+                    }
+                    ActivePopup::Diff(state) => {
+                      if let ControlFlow::Break(()) = state.handle_key_event(ke) {
+                        self.popup = None;
+                      }
+                    }
+                    ActivePopup::Breakpoint(state) => {
+                      if let Some(action) = state.handle_key_event(ke) {
+                        action_tx.send(action)?;
+                      }
+                    }
+                    ActivePopup::ProcessPicker(state) => {
+                      if let Some(action) = state.handle_key_event(ke) {
+                        action_tx.send(action)?;
+                      }
+                    }
                   }
                   continue;
                 }
 
-                match ke.code {
-                  KeyCode::Char('q') if ke.modifiers == KeyModifiers::NONE => {
-                    if self.popup.is_some() {
-                      self.popup = None;
-                    } else {
+                if let Some(search_input) = &mut self.search_input {
+                  if let Some(action) = search_input.handle_key_event(ke) {
+                    action_tx.send(action)?;
+                  }
+                  continue;
+                }
+
+                if self.events_view == EventsView::Stats {
+                  match self.keymap.action(&ke) {
+                    Some(KeyAction::Quit) => {
                       action_tx.send(Action::Quit)?;
                     }
-                  }
-                  KeyCode::Down | KeyCode::Char('j') => {
-                    if ke.modifiers == KeyModifiers::CONTROL {
-                      action_tx.send(Action::PageDown)?;
-                    } else if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::NextItem)?;
+                    Some(KeyAction::NextItem) => {
+                      action_tx.send(Action::NextStatsRow)?;
                     }
-                    // action_tx.send(Action::Render)?;
-                  }
-                  KeyCode::Up | KeyCode::Char('k') => {
-                    if ke.modifiers == KeyModifiers::CONTROL {
-                      action_tx.send(Action::StopFollow)?;
-                      action_tx.send(Action::PageUp)?;
-                    } else if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::StopFollow)?;
-                      action_tx.send(Action::PrevItem)?;
+                    Some(KeyAction::PrevItem) => {
+                      action_tx.send(Action::PrevStatsRow)?;
                     }
-                    // action_tx.send(Action::Render)?;
-                  }
-                  KeyCode::Left | KeyCode::Char('h') => {
-                    if ke.modifiers == KeyModifiers::CONTROL {
-                      action_tx.send(Action::PageLeft)?;
-                    } else if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::ScrollLeft)?;
+                    Some(KeyAction::ToggleStats) => {
+                      action_tx.send(Action::ToggleStats)?;
                     }
-                    // action_tx.send(Action::Render)?;
-                  }
-                  KeyCode::Right | KeyCode::Char('l') if ke.modifiers != KeyModifiers::ALT => {
-                    if ke.modifiers == KeyModifiers::CONTROL {
-                      action_tx.send(Action::PageRight)?;
-                    } else if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::ScrollRight)?;
+                    Some(KeyAction::ToggleTimeline) => {
+                      action_tx.send(Action::ToggleTimeline)?;
                     }
-                    // action_tx.send(Action::Render)?;
-                  }
-                  KeyCode::PageDown if ke.modifiers == KeyModifiers::NONE => {
-                    action_tx.send(Action::PageDown)?;
-                    // action_tx.send(Action::Render)?;
-                  }
-                  KeyCode::PageUp if ke.modifiers == KeyModifiers::NONE => {
-                    action_tx.send(Action::StopFollow)?;
-                    action_tx.send(Action::PageUp)?;
-                    // action_tx.send(Action::Render)?;
-                  }
-                  KeyCode::Home => {
-                    if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::StopFollow)?;
-                      action_tx.send(Action::ScrollToTop)?;
-                    } else if ke.modifiers == KeyModifiers::SHIFT {
-                      action_tx.send(Action::ScrollToStart)?;
+                    Some(KeyAction::Help) => {
+                      action_tx.send(Action::SetActivePopup(ActivePopup::Help(
+                        HelpPopupState::default(),
+                      )))?;
                     }
-                    // action_tx.send(Action::Render)?;
-                  }
-                  KeyCode::End => {
-                    if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::ScrollToBottom)?;
-                    } else if ke.modifiers == KeyModifiers::SHIFT {
-                      action_tx.send(Action::ScrollToEnd)?;
+                    _ if ke.code == KeyCode::Tab => {
+                      action_tx.send(Action::ToggleStatsOrder)?;
                     }
-                    // action_tx.send(Action::Render)?;
+                    _ => {}
                   }
-                  KeyCode::Char('g') if ke.modifiers == KeyModifiers::NONE => {
-                    action_tx.send(Action::GrowPane)?;
-                    // action_tx.send(Action::Render)?;
-                  }
-                  KeyCode::Char('s') => {
-                    if ke.modifiers == KeyModifiers::NONE {
-                      action_tx.send(Action::ShrinkPane)?;
-                    } else if ke.modifiers == KeyModifiers::ALT {
-                      action_tx.send(Action::HandleTerminalKeyPress(KeyEvent::new(
-                        KeyCode::Char('s'),
-                        KeyModifiers::CONTROL,
-                      )))?;
+                  continue;
+                }
+
+                if self.events_view == EventsView::Timeline {
+                  match self.keymap.action(&ke) {
+                    Some(KeyAction::Quit) => {
+                      action_tx.send(Action::Quit)?;
                     }
-                    // action_tx.send(Action::Render)?;
-                  }
-                  KeyCode::Char('c')
-                    if ke.modifiers == KeyModifiers::NONE && self.clipboard.is_some() =>
-                  {
-                    if let Some(selected) = self.event_list.selection() {
-                      action_tx.send(Action::ShowCopyDialog(selected))?;
+                    Some(KeyAction::NextItem) => {
+                      action_tx.send(Action::ScrollTimelineDown)?;
                     }
+                    Some(KeyAction::PrevItem) => {
+                      action_tx.send(Action::ScrollTimelineUp)?;
+                    }
+                    Some(KeyAction::ToggleStats) => {
+                      action_tx.send(Action::ToggleStats)?;
+                    }
+                    Some(KeyAction::ToggleTimeline) => {
+                      action_tx.send(Action::ToggleTimeline)?;
+                    }
+                    Some(KeyAction::Help) => {
+                      action_tx.send(Action::SetActivePopup(ActivePopup::Help(
+                        HelpPopupState::default(),
+                      )))?;
+                    }
+                    _ => {}
                   }
-                  KeyCode::Char('l') if ke.modifiers == KeyModifiers::ALT => {
-                    action_tx.send(Action::SwitchLayout)?;
-                  }
-                  KeyCode::Char('f') if ke.modifiers == KeyModifiers::NONE => {
-                    action_tx.send(Action::ToggleFollow)?;
+                  continue;
+                }
+
+                match ke.code {
+                  KeyCode::Char('s') if ke.modifiers == KeyModifiers::ALT => {
+                    action_tx.send(Action::HandleTerminalKeyPress(KeyEvent::new(
+                      KeyCode::Char('s'),
+                      KeyModifiers::CONTROL,
+                    )))?;
                   }
-                  KeyCode::Char('e') if ke.modifiers == KeyModifiers::NONE => {
-                    action_tx.send(Action::ToggleEnvDisplay)?;
+                  KeyCode::Esc if self.event_list.search.is_some() => {
+                    action_tx.send(Action::ClearSearch)?;
                   }
-                  KeyCode::F(1) if ke.modifiers == KeyModifiers::NONE => {
-                    action_tx.send(Action::SetActivePopup(ActivePopup::Help))?;
+                  KeyCode::Esc if self.event_list.filter.is_some() => {
+                    action_tx.send(Action::ClearFilter)?;
                   }
-                  KeyCode::Char('v') if ke.modifiers == KeyModifiers::NONE => {
-                    if let Some(selected) = self.event_list.selection() {
-                      action_tx.send(Action::SetActivePopup(ActivePopup::ViewDetails(
-                        DetailsPopupState::new(selected, self.event_list.baseline.clone()),
-                      )))?;
+                  _ => {
+                    if let Some(key_action) = self.keymap.action(&ke) {
+                      for action in self.key_actions(key_action) {
+                        action_tx.send(action)?;
+                      }
+                    } else if let KeyCode::Char(c @ '1'..='9') = ke.code {
+                      if ke.modifiers == KeyModifiers::NONE {
+                        action_tx.send(Action::SwitchTab(c as usize - '1' as usize))?;
+                      }
                     }
                   }
-                  _ => {}
                 }
               } else {
                 action_tx.send(Action::HandleTerminalKeyPress(ke))?;
@@ -294,17 +777,28 @@ impl App {
               }
             }
           }
-          Event::Tracer(te) => {
-            if let TracerEvent::TraceeSpawn(pid) = te {
-              self.root_pid = Some(pid);
-            }
-            self.event_list.events.push(te.into());
-            if self.event_list.follow {
-              action_tx.send(Action::ScrollToBottom)?;
+          Event::Mouse(me) => {
+            if self.popup.is_none() && self.search_input.is_none() {
+              for action in self.mouse_actions(me) {
+                action_tx.send(action)?;
+              }
             }
+          }
+          Event::Tracer(te) => {
+            self.on_tracer_event(0, te, &action_tx)?;
             // action_tx.send(Action::Render)?;
           }
+          Event::TabTracer(tab, te) => {
+            self.on_tracer_event(tab, te, &action_tx)?;
+          }
           Event::Render => {
+            if let Some(filter) = self
+              .search_input
+              .as_mut()
+              .and_then(SearchInput::poll_filter)
+            {
+              action_tx.send(Action::SetFilter(filter))?;
+            }
             action_tx.send(Action::Render)?;
           }
           Event::Resize(size) => {
@@ -317,6 +811,14 @@ impl App {
             // action_tx.send(Action::Render)?;
           }
           Event::Error => {}
+          Event::ExternalProgramExited { program, error } => {
+            tui.resume()?;
+            // The program may have changed the title
+            self.window_title.clear();
+            if let Some(e) = error {
+              warn!("Failed to run {program}: {e}");
+            }
+          }
         }
       }
 
@@ -330,8 +832,13 @@ impl App {
             return Ok(());
           }
           Action::Render => {
+            self.check_breakpoint();
+            self.update_window_title()?;
             tui.draw(|f| self.render(f.size(), f.buffer_mut()))?;
           }
+          Action::SelectItem(index) => {
+            self.event_list.select_row(index);
+          }
           Action::NextItem => {
             self.event_list.next();
           }
@@ -352,7 +859,11 @@ impl App {
           }
           Action::HandleTerminalKeyPress(ke) => {
             if let Some(term) = self.term.as_mut() {
-              term.handle_key_event(&ke).await;
+              if let Some(text) = term.handle_key_event(&ke).await {
+                if let Some(clipboard) = self.clipboard.as_mut() {
+                  clipboard.set_text(text)?;
+                }
+              }
             }
           }
           Action::Resize(_size) => {
@@ -379,9 +890,31 @@ impl App {
           Action::ToggleFollow => {
             self.event_list.toggle_follow();
           }
+          Action::TogglePin => {
+            if self.event_list.pinned_pid.is_some() {
+              self.event_list.stop_follow();
+            } else if let Some(pid) = self.event_list.selection().and_then(|event| event.pid()) {
+              self.event_list.pin(pid);
+            }
+          }
           Action::ToggleEnvDisplay => {
             self.event_list.toggle_env_display();
           }
+          Action::ToggleWrap => {
+            self.event_list.toggle_wrap();
+          }
+          Action::ToggleGroup => {
+            self.event_list.toggle_group();
+          }
+          Action::ToggleCollapse => {
+            self.event_list.toggle_collapse();
+          }
+          Action::ToggleSubtree => {
+            if let Some(pid) = self.event_list.selection().and_then(|event| event.pid()) {
+              let descendants = self.process_tree.descendants(pid);
+              self.event_list.toggle_subtree(pid, descendants);
+            }
+          }
           Action::StopFollow => {
             self.event_list.stop_follow();
           }
@@ -400,9 +933,16 @@ impl App {
             };
             self.should_handle_internal_resize = true;
           }
+          Action::ToggleZoom => {
+            self.zoomed = !self.zoomed;
+            self.should_handle_internal_resize = true;
+          }
           Action::SwitchActivePane => {
             self.active_pane = match self.active_pane {
+              ActivePane::Events if self.show_process_tree => ActivePane::ProcessTree,
               ActivePane::Events => ActivePane::Terminal,
+              ActivePane::ProcessTree if self.term.is_some() => ActivePane::Terminal,
+              ActivePane::ProcessTree => ActivePane::Events,
               ActivePane::Terminal => ActivePane::Events,
             }
           }
@@ -429,29 +969,679 @@ impl App {
           Action::CancelCurrentPopup => {
             self.popup = None;
           }
+          Action::OpenInEditor(path) => {
+            let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+            self.open_externally(tui, &editor, &path)?;
+          }
+          Action::OpenInPager(path) => {
+            let pager = std::env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+            self.open_externally(tui, &pager, &path)?;
+          }
+          Action::KillProcess { pid, signal } => {
+            // The process might have exited while the popup was open
+            if !self.process_tree.is_running(pid) {
+              warn!("Not sending {signal} to {pid}, which exited");
+            } else if let Err(e) = nix::sys::signal::kill(pid, signal) {
+              warn!("Failed to send {signal} to {pid}: {e}");
+            }
+            self.popup = None;
+          }
+          Action::BeginSearch => {
+            self.search_input = Some(SearchInput::new(SearchPurpose::Search));
+          }
+          Action::CancelSearchInput => {
+            // Enter in the filter prompt applies the filter right away
+            if let Some(filter) = self
+              .search_input
+              .take()
+              .and_then(|mut input| input.take_pending_filter())
+            {
+              self.event_list.set_filter(filter);
+            }
+          }
+          Action::Search(regex) => {
+            self.search_input = None;
+            self.event_list.set_search(Some(regex));
+            self.event_list.stop_follow();
+            self.event_list.next_match();
+          }
+          Action::ClearSearch => {
+            self.search_input = None;
+            self.event_list.set_search(None);
+          }
+          Action::NextMatch => {
+            self.event_list.next_match();
+          }
+          Action::PrevMatch => {
+            self.event_list.prev_match();
+          }
+          Action::BeginFilter => {
+            self.search_input = Some(SearchInput::new(SearchPurpose::Filter));
+          }
+          Action::SetFilter(filter) => {
+            self.event_list.set_filter(filter);
+          }
+          Action::ClearFilter => {
+            self.search_input = None;
+            self.event_list.set_filter(None);
+          }
+          Action::BeginWatch => {
+            self.search_input = Some(SearchInput::new(SearchPurpose::Watch));
+          }
+          Action::AddWatch { regex, select } => {
+            self.search_input = None;
+            self.watches.push(Watch { regex, select });
+          }
+          Action::ClearWatches => {
+            self.search_input = None;
+            self.watches.clear();
+            self.watch_alert = None;
+          }
+          Action::BeginBreakpoint => {
+            self.search_input = Some(SearchInput::new(SearchPurpose::Breakpoint));
+          }
+          Action::AddBreakpoint(regex) => {
+            self.search_input = None;
+            self.breakpoints.add(regex);
+          }
+          Action::ClearBreakpoints => {
+            self.search_input = None;
+            self.breakpoints.clear();
+          }
+          Action::ResumeBreakpoint(decision) => {
+            self.breakpoints.decide(decision);
+            self.popup = None;
+          }
+          Action::BeginGoTo => {
+            self.search_input = Some(SearchInput::new(SearchPurpose::GoTo));
+          }
+          Action::GoTo(target) => match self.event_list.go_to(target) {
+            Ok(()) => {
+              self.search_input = None;
+              self.event_list.stop_follow();
+            }
+            Err(e) => {
+              if let Some(search_input) = self.search_input.as_mut() {
+                search_input.set_error(e);
+              }
+            }
+          },
+          Action::ToggleBookmark => {
+            self.event_list.toggle_bookmark();
+          }
+          Action::NextBookmark => {
+            self.event_list.next_bookmark();
+          }
+          Action::NextFailure => {
+            self.event_list.next_failure();
+          }
+          Action::PrevFailure => {
+            self.event_list.prev_failure();
+          }
+          Action::MarkDiffBase => {
+            if let Some(selected) = self.event_list.selection() {
+              if let TracerEvent::Exec(_) = selected.as_ref() {
+                self.diff_base = Some(selected);
+              }
+            }
+          }
+          Action::ToggleProcessTree => {
+            self.show_process_tree = !self.show_process_tree;
+            self.active_pane = if self.show_process_tree {
+              ActivePane::ProcessTree
+            } else {
+              ActivePane::Events
+            };
+            self.should_handle_internal_resize = true;
+          }
+          Action::NextProcess => {
+            self.process_tree.next();
+          }
+          Action::PrevProcess => {
+            self.process_tree.previous();
+          }
+          Action::SelectProcess(row) => {
+            self.process_tree.select_row(row);
+          }
+          Action::ToggleStats => {
+            self.events_view = match self.events_view {
+              EventsView::Stats => EventsView::List,
+              _ => EventsView::Stats,
+            };
+          }
+          Action::ToggleStatsOrder => {
+            self.stats.toggle_order();
+          }
+          Action::NextStatsRow => {
+            self.stats.next();
+          }
+          Action::PrevStatsRow => {
+            self.stats.previous();
+          }
+          Action::ToggleTimeline => {
+            self.events_view = match self.events_view {
+              EventsView::Timeline => EventsView::List,
+              _ => EventsView::Timeline,
+            };
+          }
+          Action::ScrollTimelineDown => {
+            self.timeline.scroll_down();
+          }
+          Action::ScrollTimelineUp => {
+            self.timeline.scroll_up();
+          }
+          Action::SetActivePane(pane) => {
+            self.active_pane = pane;
+          }
+          Action::ScrollTerminal(lines) => {
+            if let Some(term) = self.term.as_ref() {
+              term.scroll(lines);
+            }
+          }
+          Action::SelectTerminalText { from, to } => {
+            if let Some(term) = self.term.as_mut() {
+              term.select(from, to);
+            }
+          }
+          Action::YankTerminalSelection => {
+            if let Some(text) = self.term.as_mut().and_then(|term| term.yank()) {
+              if let Some(clipboard) = self.clipboard.as_mut() {
+                clipboard.set_text(text)?;
+              }
+            }
+          }
+          Action::ExitTerminalCopyMode => {
+            if let Some(term) = self.term.as_mut() {
+              term.exit_copy_mode();
+            }
+          }
+          Action::ResizePane(percentage) => {
+            if self.term.is_some() {
+              self.split_percentage = percentage;
+              self.should_handle_internal_resize = true;
+            }
+          }
+          Action::JumpToProcess(pid) => {
+            self.event_list.stop_follow();
+            self.event_list.next_exec_of(pid);
+          }
+          Action::JumpToParent => {
+            if let Some(TracerEvent::Exec(exec)) = self.event_list.selection().as_deref() {
+              if let Some(ppid) = exec.ppid {
+                self.event_list.prev_exec_of(ppid);
+              }
+            }
+          }
+          Action::NextChild => {
+            if let Some(TracerEvent::Exec(exec)) = self.event_list.selection().as_deref() {
+              let parent = match self.child_cycle_parent {
+                Some(parent) if self.process_tree.children(parent).contains(&exec.pid) => parent,
+                _ => exec.pid,
+              };
+              self.child_cycle_parent = Some(parent);
+              let children = self.process_tree.children(parent).to_vec();
+              self.event_list.next_exec_of_any(&children);
+            }
+          }
+          Action::ExportEvents {
+            format,
+            scope,
+            path,
+          } => match self.export_events(format, scope, &path) {
+            Ok(()) => self.popup = None,
+            Err(e) => {
+              if let Some(ActivePopup::Export(state)) = self.popup.as_mut() {
+                state.error = Some(format!("Failed to export to {}: {e}", path.display()));
+              }
+            }
+          },
+          Action::SwitchTab(tab) => {
+            self.switch_tab(tab);
+          }
+          Action::NewTrace(command) => {
+            if let Err(e) = self.new_trace(command, &tui.event_tx) {
+              if let Some(ActivePopup::NewTrace(state)) = self.popup.as_mut() {
+                state.error = Some(format!("Failed to start the trace: {e}"));
+              }
+            }
+          }
+          Action::Attach(pid, title) => {
+            if let Err(e) = self.start_trace(TraceTarget::Process(pid), title, &tui.event_tx) {
+              if let Some(ActivePopup::ProcessPicker(state)) = self.popup.as_mut() {
+                state.error = Some(format!("Failed to attach to {pid}: {e}"));
+              }
+            }
+          }
+          Action::RunInTerminal(command) => {
+            if let Some(term) = self.term.as_ref() {
+              term.send_text(&format!("{command}\r")).await;
+              self.active_pane = ActivePane::Terminal;
+            }
+            self.popup = None;
+          }
+          Action::SetColumns(columns) => {
+            self.event_list.set_columns(columns);
+            self.popup = None;
+          }
+          Action::SetSort(sort) => {
+            self.event_list.set_sort(sort);
+            self.popup = None;
+          }
+          Action::TogglePause => {
+            if let Some(pause) = self.tracer_pause.as_ref() {
+              if pause.is_paused() {
+                pause.resume();
+              } else {
+                pause.pause();
+              }
+            }
+          }
         }
       }
     }
   }
 
-  pub fn exit(&self, terminate_on_exit: bool, kill_on_exit: bool) -> color_eyre::Result<()> {
+  /// Remember how the TUI is set up for the next session
+  fn save_state(&self) {
+    let mut state = TuiState::load();
+    if self.term.is_some() {
+      // These only apply when there is a pseudo terminal
+      state.split_percentage = Some(self.split_percentage);
+      state.layout = Some(self.layout.clone());
+      state.active_pane = Some(self.active_pane);
+    }
+    state.follow = Some(self.event_list.follow);
+    state.columns = Some(self.event_list.columns().to_vec());
+    (state.filter, state.filter_expr) = match self.event_list.filter.as_ref() {
+      Some(ListFilter::Regex(regex)) => (Some(regex.as_str().to_owned()), None),
+      Some(ListFilter::Expr(expr)) => (None, Some(expr.as_str().to_owned())),
+      None => (None, None),
+    };
+    if let Err(e) = state.save() {
+      warn!("Failed to save the TUI state: {e}");
+    }
+  }
+
+  /// Clean up when the TUI exits. The root processes get the exit signal if there is one,
+  /// and SIGKILL if they are still running after the grace period.
+  pub fn exit(
+    &self,
+    exit_signal: Option<Signal>,
+    grace_period: Option<Duration>,
+  ) -> color_eyre::Result<()> {
     // Close pty master
     self.term.as_ref().inspect(|t| t.exit());
-    // Terminate root process
-    if terminate_on_exit {
-      self.signal_root_process(Signal::SIGTERM)?;
-    } else if kill_on_exit {
-      self.signal_root_process(Signal::SIGKILL)?;
+    if self.save_state {
+      self.save_state();
+    }
+    // The tracer threads are joined after the TUI exits, so they must not stay paused
+    self.breakpoints.release();
+    let pauses = self
+      .tabs
+      .iter()
+      .flatten()
+      .map(|tab| tab.tracer_pause.as_ref());
+    for pause in chain!([self.tracer_pause.as_ref()], pauses).flatten() {
+      pause.resume();
+    }
+    // The processes that were attached to keep running, the tracers finish once they detached
+    for tracer in self.tracers() {
+      tracer.detach();
+    }
+    let Some(signal) = exit_signal else {
+      return Ok(());
+    };
+    let signaled = self.signal_root_process(signal);
+    if let Some(grace_period) = grace_period.filter(|_| signal != Signal::SIGKILL) {
+      // Exiting is not held up, the tracers are joined while the root processes shut down
+      let tracers: Vec<_> = self.tracers().cloned().collect();
+      std::thread::spawn(move || {
+        std::thread::sleep(grace_period);
+        // Only the root processes that the tracers haven't seen exit, their pids may be reused otherwise
+        for pid in tracers.iter().filter_map(|tracer| tracer.running_root()) {
+          nix::sys::signal::kill(pid, Signal::SIGKILL).ok();
+        }
+      });
+    }
+    signaled
+  }
+
+  fn export_events(
+    &self,
+    format: ExportFormat,
+    scope: ExportScope,
+    path: &Path,
+  ) -> color_eyre::Result<()> {
+    let events = match scope {
+      ExportScope::All => self.event_list.all_events().to_vec(),
+      ExportScope::Shown => self.event_list.events.clone(),
+      ExportScope::Selected => self.event_list.selection().into_iter().collect(),
+    };
+    // Never overwrite an existing file, the path is typed in a hurry
+    let file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    let mut file = BufWriter::new(file);
+    for event in events.iter() {
+      match format {
+        ExportFormat::Text => writeln!(
+          file,
+          "{}",
+          event.to_tui_line(
+            &self.event_list.baseline,
+            false,
+            &self.event_list.modifier_args,
+            self.event_list.env_in_cmdline,
+          )
+        )?,
+        ExportFormat::Jsonl => {
+          serde_json::to_writer(&mut file, &event_to_json(event))?;
+          writeln!(file)?;
+        }
+      }
     }
+    file.flush()?;
     Ok(())
   }
 
+  pub fn is_paused(&self) -> bool {
+    self
+      .tracer_pause
+      .as_ref()
+      .is_some_and(|pause| pause.is_paused())
+  }
+
+  /// Run the program on the file, suspending the TUI until it exits.
+  /// The program can have arguments, e.g. `$EDITOR` is often set to something like `code --wait`.
+  /// It is waited for in the background, so that the tracer events are still handled meanwhile.
+  fn open_externally(
+    &mut self,
+    tui: &mut Tui,
+    program: &str,
+    path: &Path,
+  ) -> color_eyre::Result<()> {
+    let mut args = program.split_whitespace();
+    let Some(program) = args.next() else {
+      return Ok(());
+    };
+    tui.suspend()?;
+    let child = tokio::process::Command::new(program)
+      .args(args)
+      .arg(path)
+      .spawn();
+    let program = program.to_string();
+    let event_tx = tui.event_tx.clone();
+    tokio::spawn(async move {
+      let result = match child {
+        Ok(mut child) => child.wait().await.map(|_| ()),
+        Err(e) => Err(e),
+      };
+      event_tx
+        .send(Event::ExternalProgramExited {
+          program,
+          error: result.err().map(|e| e.to_string()),
+        })
+        .ok();
+    });
+    Ok(())
+  }
+
+  /// Show the exec that is stopped at a breakpoint, its tracer waits until it is continued
+  fn check_breakpoint(&mut self) {
+    let Some(hit) = self.breakpoints.hit() else {
+      return;
+    };
+    if !matches!(&self.popup, Some(ActivePopup::Breakpoint(state)) if state.is_for(&hit)) {
+      self.popup = Some(ActivePopup::Breakpoint(BreakpointPopupState::new(hit)));
+      // The popups only get the keys when the event list is active
+      self.active_pane = ActivePane::Events;
+    }
+  }
+
+  /// Show the command and the status of the trace in the active tab in the title of the host terminal
+  fn update_window_title(&mut self) -> color_eyre::Result<()> {
+    let command = &self.tab_titles[self.active_tab];
+    let title = if command.is_empty() {
+      "tracexec".to_string()
+    } else {
+      let status = if let Some(exit) = self.tab_exits[self.active_tab].as_deref() {
+        exit
+      } else if self.is_paused() {
+        "tracer paused"
+      } else {
+        "running"
+      };
+      // The arguments of the tracee must not end the title and inject escape sequences
+      format!("{} ({status}) - tracexec", escape_control_chars(command))
+    };
+    if title != self.window_title {
+      crossterm::execute!(std::io::stdout(), SetTitle(&title))?;
+      self.window_title = title;
+    }
+    Ok(())
+  }
+
+  /// The tracers of all tabs. The commands they trace are signaled on exit,
+  /// the processes they attached to are detached from
+  fn tracers(&self) -> impl Iterator<Item = &Arc<Tracer>> {
+    let tracers = self.tabs.iter().flatten().map(|tab| tab.tracer.as_ref());
+    chain!([self.tracer.as_ref()], tracers).flatten()
+  }
+
+  /// Send the signal to the root processes of the traces in all tabs,
+  /// failing after all of them are signaled if any of them can't be
   pub fn signal_root_process(&self, sig: Signal) -> color_eyre::Result<()> {
-    if let Some(root_pid) = self.root_pid {
-      nix::sys::signal::kill(root_pid, sig)?;
+    let mut result = Ok(());
+    for root_pid in self.tracers().filter_map(|tracer| tracer.running_root()) {
+      match nix::sys::signal::kill(root_pid, sig) {
+        // It exited since the tracer last saw it
+        Ok(()) | Err(Errno::ESRCH) => {}
+        Err(e) => result = Err(e.into()),
+      }
+    }
+    result
+  }
+
+  /// Handle an event of the trace in the tab, the events of inactive tabs are handled later
+  fn on_tracer_event(
+    &mut self,
+    tab: usize,
+    te: TracerEvent,
+    action_tx: &UnboundedSender<Action>,
+  ) -> color_eyre::Result<()> {
+    if self.tab_titles[tab].is_empty() {
+      if let Some(title) = title_of(&te) {
+        self.tab_titles[tab] = title;
+      }
+    }
+    let watch = match &te {
+      TracerEvent::Exec(exec) => self.watches.iter().find(|watch| watch.matches(exec)),
+      _ => None,
+    };
+    // Only the events of the active tab can be selected right away
+    let select_pid = match watch {
+      Some(watch) => {
+        let command = title_of(&te).unwrap_or_default();
+        self.watch_alert = Some(WatchAlert::new(if tab == self.active_tab {
+          format!("Watch matched: {command}")
+        } else {
+          format!("Watch matched in tab {}: {command}", tab + 1)
+        }));
+        // Ring the bell of the host terminal
+        if self
+          .last_bell
+          .map_or(true, |last_bell| last_bell.elapsed() >= BELL_INTERVAL)
+        {
+          self.last_bell = Some(Instant::now());
+          crossterm::execute!(std::io::stdout(), Print("\x07"))?;
+        }
+        te.pid().filter(|_| watch.select && tab == self.active_tab)
+      }
+      None => None,
+    };
+    if let TracerEvent::TraceeExit { signal, exit_code } = &te {
+      self.tab_exits[tab] = Some(match signal {
+        Some(signal) => format!("killed by {signal}"),
+        None => format!("exited with {exit_code}"),
+      });
+    }
+    if tab != self.active_tab {
+      if let Some(Some(tab)) = self.tabs.get_mut(tab) {
+        tab.pending_events.push(te);
+      }
+      return Ok(());
+    }
+    self.handle_tracer_event(te);
+    if let Some(pid) = select_pid {
+      self.event_list.stop_follow();
+      self.event_list.select_latest_of(pid);
+    } else if self.event_list.follow {
+      action_tx.send(Action::ScrollToBottom)?;
     }
     Ok(())
   }
+
+  fn handle_tracer_event(&mut self, te: TracerEvent) {
+    if let TracerEvent::ProcessExit {
+      pid,
+      status,
+      timestamp,
+      start_time,
+      ..
+    } = &te
+    {
+      self
+        .event_list
+        .set_exit_status(*pid, *start_time, *status, *timestamp);
+    }
+    if let TracerEvent::NewChild {
+      ppid,
+      pid,
+      timestamp,
+      pstart_time,
+      start_time,
+      ..
+    } = &te
+    {
+      self.event_list.on_new_child(*ppid, *pid);
+      self
+        .event_list
+        .on_spawn((*ppid, *pstart_time), (*pid, *start_time), *timestamp);
+    }
+    self.process_tree.on_event(&te);
+    self.stats.on_event(&te);
+    self.timeline.on_event(&te);
+    if let Some(te) = FilterableTracerEvent::from(te).filter_and_take(self.event_filter) {
+      self.event_list.push(te.into());
+    }
+  }
+
+  fn switch_tab(&mut self, index: usize) {
+    // The entry of the active tab is None, so switching to it does nothing
+    let Some(mut tab) = self.tabs.get_mut(index).and_then(Option::take) else {
+      return;
+    };
+    let pending_events = mem::take(&mut tab.pending_events);
+    mem::swap(&mut self.event_list, &mut tab.event_list);
+    mem::swap(&mut self.process_tree, &mut tab.process_tree);
+    mem::swap(&mut self.stats, &mut tab.stats);
+    mem::swap(&mut self.timeline, &mut tab.timeline);
+    mem::swap(&mut self.tracer_pause, &mut tab.tracer_pause);
+    mem::swap(&mut self.tracer, &mut tab.tracer);
+    mem::swap(&mut self.child_cycle_parent, &mut tab.child_cycle_parent);
+    self.tabs[self.active_tab] = Some(tab);
+    self.active_tab = index;
+    for te in pending_events {
+      self.handle_tracer_event(te);
+    }
+    if self.event_list.follow {
+      self.event_list.scroll_to_bottom();
+    }
+    self.popup = None;
+    self.search_input = None;
+    // The event list of the tab may not know the size of the pane yet
+    self.should_handle_internal_resize = true;
+  }
+
+  /// Trace the command in a new tab. It is run by `sh` so that it can be typed like in a shell.
+  fn new_trace(
+    &mut self,
+    command: String,
+    event_tx: &UnboundedSender<Event>,
+  ) -> color_eyre::Result<()> {
+    self.start_trace(
+      TraceTarget::Command(vec!["sh".to_string(), "-c".to_string(), command.clone()]),
+      command,
+      event_tx,
+    )
+  }
+
+  /// Open the process picker, e.g. when the TUI is launched without a command
+  pub fn pick_process(&mut self) {
+    self.popup = Some(ActivePopup::ProcessPicker(ProcessPickerState::default()));
+  }
+
+  fn start_trace(
+    &mut self,
+    target: TraceTarget,
+    title: String,
+    event_tx: &UnboundedSender<Event>,
+  ) -> color_eyre::Result<()> {
+    let Some(spawner) = self.trace_spawner.as_ref() else {
+      bail!("Tracing in a new tab is not supported");
+    };
+    let NewTrace {
+      baseline,
+      mut tracer_rx,
+      pause,
+      thread,
+      tracer,
+    } = spawner(target)?;
+    // Nothing is traced in the active tab when the TUI is launched without a command
+    let reuse_active_tab = self.tracer_pause.is_none();
+    let index = if reuse_active_tab {
+      self.active_tab
+    } else {
+      self.tabs.len()
+    };
+    let event_tx = event_tx.clone();
+    let activity = self.activity.clone();
+    tokio::spawn(async move {
+      while let Some(te) = tracer_rx.recv().await {
+        if event_tx.send(Event::TabTracer(index, te)).is_err() {
+          break;
+        }
+        activity.notify_one();
+      }
+    });
+    self.tracer_threads.push(thread);
+    if reuse_active_tab {
+      self.event_list.baseline = Arc::new(baseline);
+      self.tracer_pause = Some(pause);
+      self.tracer = Some(tracer);
+      self.tab_titles[index] = title;
+      self.popup = None;
+      return Ok(());
+    }
+    self.tabs.push(Some(TraceTab {
+      event_list: EventList::new(
+        baseline,
+        self.event_list.follow,
+        self.event_list.modifier_args.clone(),
+        self.event_list.columns().to_vec(),
+        self.event_list.history,
+      ),
+      process_tree: ProcessTree::default(),
+      stats: Stats::default(),
+      timeline: Timeline::default(),
+      tracer_pause: Some(pause),
+      tracer: Some(tracer),
+      child_cycle_parent: None,
+      pending_events: vec![],
+    }));
+    self.tab_titles.push(title);
+    self.tab_exits.push(None);
+    self.switch_tab(index);
+    Ok(())
+  }
 }
 
 impl Widget for &mut App {
@@ -460,12 +1650,23 @@ impl Widget for &mut App {
     let vertical = Layout::vertical([
       Constraint::Length(2),
       Constraint::Min(0),
+      Constraint::Length(1),
       Constraint::Length(2),
     ]);
-    let [header_area, rest_area, footer_area] = vertical.areas(area);
+    let [header_area, rest_area, status_area, footer_area] = vertical.areas(area);
+    let zoomed_terminal = self.zoomed && self.active_pane == ActivePane::Terminal;
+    let show_events = !zoomed_terminal;
+    let show_term = self.term.is_some() && (zoomed_terminal || !self.zoomed);
+    let split_percentage = if !show_term {
+      100
+    } else if !show_events {
+      0
+    } else {
+      self.split_percentage
+    };
     let horizontal_constraints = [
-      Constraint::Percentage(self.split_percentage),
-      Constraint::Percentage(100 - self.split_percentage),
+      Constraint::Percentage(split_percentage),
+      Constraint::Percentage(100 - split_percentage),
     ];
     let [event_area, term_area] = (if self.layout == AppLayout::Horizontal {
       Layout::horizontal
@@ -473,19 +1674,58 @@ impl Widget for &mut App {
       Layout::vertical
     })(horizontal_constraints)
     .areas(rest_area);
-    render_title(
-      header_area,
-      buf,
-      format!(" tracexec {}", env!("CARGO_PKG_VERSION")),
-    );
-    self.render_help(footer_area, buf);
+    let (tree_area, event_area) = if self.show_process_tree {
+      let [tree_area, event_area] =
+        Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+          .areas(event_area);
+      (Some(tree_area), event_area)
+    } else {
+      (None, event_area)
+    };
+    let mut title = vec![format!(" tracexec {}", env!("CARGO_PKG_VERSION")).into()];
+    if self.is_paused() {
+      title.extend([" ".into(), " TRACER PAUSED ".set_style(THEME.paused)]);
+    }
+    if let Some(alert) = self.watch_alert.as_ref().filter(|alert| alert.is_active()) {
+      title.extend([
+        " ".into(),
+        format!(" {} ", alert.message).set_style(if alert.is_lit() {
+          THEME.watch_alert
+        } else {
+          THEME.watch_alert.add_modifier(Modifier::REVERSED)
+        }),
+      ]);
+    }
+    if self.tabs.len() > 1 {
+      for (i, tab_title) in self.tab_titles.iter().enumerate() {
+        let tab_title: String = tab_title.chars().take(24).collect();
+        title.extend([
+          " ".into(),
+          Span::styled(
+            format!(" {}:{} ", i + 1, tab_title),
+            if i == self.active_tab {
+              THEME.active_tab
+            } else {
+              THEME.inactive_tab
+            },
+          ),
+        ]);
+      }
+    }
+    render_title(header_area, buf, Line::from(title));
+    if let Some(search_input) = self.search_input.as_ref() {
+      search_input.render(footer_area, buf);
+    } else {
+      self.render_help(footer_area, buf);
+    }
+    self.render_status(status_area, buf);
 
-    if event_area.width < 4 || (self.term.is_some() && term_area.width < 4) {
+    if (show_events && event_area.width < 4) || (show_term && term_area.width < 4) {
       Paragraph::new("Terminal\nor\npane\ntoo\nsmall").render(rest_area, buf);
       return;
     }
 
-    if event_area.height < 4 || (self.term.is_some() && term_area.height < 4) {
+    if (show_events && event_area.height < 4) || (show_term && term_area.height < 4) {
       Paragraph::new("Terminal or pane too small").render(rest_area, buf);
       return;
     }
@@ -494,12 +1734,14 @@ impl Widget for &mut App {
     if self.should_handle_internal_resize {
       self.should_handle_internal_resize = false;
       // Set the window size of the event list
-      self.event_list.max_window_len = event_area.height as usize - 2;
-      self.event_list.set_window((
-        self.event_list.get_window().0,
-        self.event_list.get_window().0 + self.event_list.max_window_len,
-      ));
-      if let Some(term) = self.term.as_mut() {
+      if show_events {
+        self.event_list.max_window_len = event_area.height as usize - 2;
+        self.event_list.set_window((
+          self.event_list.get_window().0,
+          self.event_list.get_window().0 + self.event_list.max_window_len,
+        ));
+      }
+      if let Some(term) = self.term.as_mut().filter(|_| show_term) {
         term
           .resize(PtySize {
             rows: term_area.height - 2,
@@ -512,19 +1754,50 @@ impl Widget for &mut App {
     }
 
     let block = Block::default()
-      .title("Events")
       .borders(ratatui::widgets::Borders::ALL)
       .border_style(if self.active_pane == ActivePane::Events {
         THEME.active_border
       } else {
         THEME.inactive_border
-      })
-      .title(self.event_list.statistics());
+      });
+    let block = match self.events_view {
+      EventsView::List => block.title("Events").title(self.event_list.statistics()),
+      EventsView::Stats => block.title(match self.stats.order {
+        StatsOrder::Execs => "Statistics (by execs)",
+        StatsOrder::Runtime => "Statistics (by runtime)",
+      }),
+      EventsView::Timeline => block.title("Timeline"),
+    };
     let inner = block.inner(event_area);
-    block.render(event_area, buf);
-    self.event_list.render(inner, buf);
-    if let Some(term) = self.term.as_mut() {
+    if show_events {
+      block.render(event_area, buf);
+      match self.events_view {
+        EventsView::List => self.event_list.render(inner, buf),
+        EventsView::Stats => self.stats.render(inner, buf),
+        EventsView::Timeline => self.timeline.render(inner, buf),
+      }
+    }
+    self.areas = PaneAreas {
+      panes: rest_area,
+      events: event_area,
+      event_list: inner,
+      process_tree: tree_area,
+      terminal: show_term.then_some(term_area),
+    };
+    if let Some(tree_area) = tree_area.filter(|_| show_events) {
       let block = Block::default()
+        .title("Processes")
+        .borders(ratatui::widgets::Borders::ALL)
+        .border_style(if self.active_pane == ActivePane::ProcessTree {
+          THEME.active_border
+        } else {
+          THEME.inactive_border
+        });
+      self.process_tree.render(block.inner(tree_area), buf);
+      block.render(tree_area, buf);
+    }
+    if let Some(term) = self.term.as_mut().filter(|_| show_term) {
+      let mut block = Block::default()
         .title("Terminal")
         .borders(ratatui::widgets::Borders::ALL)
         .border_style(if self.active_pane == ActivePane::Terminal {
@@ -532,6 +1805,17 @@ impl Widget for &mut App {
         } else {
           THEME.inactive_border
         });
+      if term.is_in_copy_mode() {
+        block = block.title(" Copy Mode ".set_style(THEME.scrollback_indicator));
+      }
+      let offset = term.scrollback_offset();
+      if offset > 0 {
+        // Not at the bottom, the latest output is not visible
+        block = block.title(
+          Title::from(format!(" ↑ {offset} lines ").set_style(THEME.scrollback_indicator))
+            .alignment(Alignment::Right),
+        );
+      }
       term.render(block.inner(term_area), buf);
       block.render(term_area, buf);
     }
@@ -539,13 +1823,33 @@ impl Widget for &mut App {
     // popups
     if let Some(popup) = self.popup.as_mut() {
       match popup {
-        ActivePopup::Help => {
-          let popup = Popup::new("Help", help(rest_area)).style(THEME.help_popup);
-          popup.render(area, buf);
+        ActivePopup::Help(state) => {
+          HelpPopup::new(&self.keymap).render_ref(area, buf, state);
         }
         ActivePopup::CopyTargetSelection(state) => {
           CopyPopup.render_ref(area, buf, state);
         }
+        ActivePopup::KillConfirmation(state) => {
+          KillPopup.render_ref(area, buf, state);
+        }
+        ActivePopup::Export(state) => {
+          ExportPopup.render_ref(area, buf, state);
+        }
+        ActivePopup::Columns(state) => {
+          ColumnsPopup.render_ref(area, buf, state);
+        }
+        ActivePopup::Sort(state) => {
+          SortPopup.render_ref(area, buf, state);
+        }
+        ActivePopup::NewTrace(state) => {
+          NewTracePopup.render_ref(area, buf, state);
+        }
+        ActivePopup::Breakpoint(state) => {
+          BreakpointPopup.render_ref(area, buf, state);
+        }
+        ActivePopup::ProcessPicker(state) => {
+          ProcessPicker.render_ref(area, buf, state);
+        }
         _ => {}
       }
     }
@@ -554,6 +1858,9 @@ impl Widget for &mut App {
       // Handled separately to pass borrow checker
       self.render_details_popup(rest_area, buf);
     }
+    if let Some(ActivePopup::Diff(state)) = self.popup.as_mut() {
+      DiffPopup.render_ref(rest_area, buf, state);
+    }
   }
 }
 
@@ -567,33 +1874,221 @@ impl App {
     DetailsPopup::new(self.clipboard.is_some()).render_ref(area, buf, state);
   }
 
+  /// The counters of the trace in the active tab and the state of the event list
+  fn render_status(&self, area: Rect, buf: &mut Buffer) {
+    let separator = || symbols().separator.into();
+    let mut spans = vec![
+      format!(" {} events", self.event_list.all_events().len()).into(),
+      separator(),
+      format!(
+        "{} running, {} exited",
+        self.timeline.nr_alive(),
+        self.timeline.nr_exited()
+      )
+      .into(),
+    ];
+    let dropped = self.tracer.as_ref().map_or(0, |tracer| tracer.dropped());
+    if dropped > 0 {
+      spans.extend([separator(), format!("{dropped} dropped").into()]);
+    }
+    if let Some(filter) = self.event_list.filter.as_ref() {
+      let filter = filter.as_str();
+      if self.restored_filter.as_deref() == Some(filter) {
+        spans.extend([
+          separator(),
+          format!("filter of the last session: {filter}").into(),
+        ]);
+      } else {
+        spans.extend([separator(), format!("filter: {filter}").into()]);
+      }
+    }
+    if let Some(sort) = self.event_list.sort {
+      spans.extend([separator(), format!("sorted by {sort}").into()]);
+    }
+    if let Some(pid) = self.event_list.pinned_pid {
+      spans.extend([separator(), format!("pinned: {pid}").into()]);
+    } else if self.event_list.follow {
+      spans.extend([separator(), "following".into()]);
+    }
+    if let Some(duration) = self.timeline.duration() {
+      let secs = duration.as_secs();
+      spans.extend([
+        separator(),
+        format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60).into(),
+      ]);
+    }
+    Paragraph::new(Line::from(spans))
+      .style(THEME.status_bar)
+      .render(area, buf);
+  }
+
   fn render_help(&self, area: Rect, buf: &mut Buffer) {
     let mut items = Vec::from_iter(help_item!("Ctrl+S", "Switch\u{00a0}Pane"));
 
     if let Some(popup) = &self.popup {
-      items.extend(help_item!("Q", "Close Popup"));
+      // The popups with a text field can only be closed with Esc
+      items.extend(help_item!(
+        if matches!(
+          popup,
+          ActivePopup::Help(_)
+            | ActivePopup::Export(_)
+            | ActivePopup::NewTrace(_)
+            | ActivePopup::ProcessPicker(_)
+        ) {
+          "Esc"
+        } else {
+          "Q"
+        },
+        "Close Popup"
+      ));
       match popup {
         ActivePopup::ViewDetails(state) => {
           if state.active_tab() == "Info" {
             items.extend(help_item!("W/S", "Move Focus"));
+          } else if state.active_tab() == "Environment" {
+            if state.can_switch_env_diff() {
+              items.extend(help_item!("P", "Parent/Baseline"));
+            }
+            items.extend(help_item!("U", "Show/Hide\u{00a0}Unchanged"));
           }
           items.extend(help_item!("←/Tab/→", "Switch Tab"));
+          if state.can_open_file() {
+            items.extend(help_item!("E", "Edit"));
+            items.extend(help_item!("V", "View"));
+            items.extend(help_item!("Shift+H", "SHA-256"));
+          }
+          items.extend(help_item!("/", "Search"));
+          if state.is_searching() {
+            items.extend(help_item!("N/Shift+N", "Next/Prev\u{00a0}Match"));
+          }
         }
         ActivePopup::CopyTargetSelection(state) => {
           items.extend(help_item!("Enter", "Choose"));
+          if let TracerEvent::Exec(_) = state.event.as_ref() {
+            items.extend(help_item!("Tab", "Switch\u{00a0}Shell"));
+          }
           items.extend(state.help_items())
         }
-        _ => {}
+        ActivePopup::KillConfirmation(_) => {
+          items.extend(help_item!("Enter", "Send\u{00a0}Signal"));
+        }
+        ActivePopup::Export(_) => {
+          items.extend(chain!(
+            help_item!("↑/↓", "Move\u{00a0}Focus"),
+            help_item!("←/→", "Change"),
+            help_item!("Enter", "Export"),
+          ));
+        }
+        ActivePopup::NewTrace(state) => {
+          items.extend(chain!(
+            help_item!(
+              "Enter",
+              match state.target {
+                CommandTarget::NewTab => "Start\u{00a0}Tracing",
+                CommandTarget::Terminal => "Run",
+              }
+            ),
+            help_item!("Ctrl+U", "Clear"),
+          ));
+        }
+        ActivePopup::Diff(_) => {
+          items.extend(help_item!("U", "Show/Hide\u{00a0}Unchanged"));
+        }
+        ActivePopup::ProcessPicker(_) => {
+          items.extend(chain!(
+            help_item!("Type", "Filter"),
+            help_item!("↑/↓", "Move\u{00a0}Focus"),
+            help_item!("Enter", "Attach"),
+            help_item!("Ctrl+R", "Refresh"),
+            help_item!("Ctrl+U", "Clear"),
+          ));
+        }
+        ActivePopup::Breakpoint(_) => {
+          items.extend(chain!(
+            help_item!("Tab", "Args/Env"),
+            help_item!("E/Enter", "Edit"),
+            help_item!("A", "Add"),
+            help_item!("D", "Delete"),
+            help_item!("R", "Reset"),
+            help_item!("C", "Continue"),
+            help_item!("Esc", "Continue\u{00a0}Unchanged"),
+          ));
+        }
+        ActivePopup::Help(_) => {
+          items.extend(chain!(
+            help_item!("Type", "Filter"),
+            help_item!("↑/↓/PgUp/PgDn", "Scroll"),
+          ));
+        }
+        ActivePopup::Columns(_) => {
+          items.extend(chain!(
+            help_item!("Space", "Show/Hide"),
+            help_item!("Shift+↑/↓", "Move"),
+            help_item!("Enter", "Apply"),
+          ));
+        }
+        ActivePopup::Sort(_) => {
+          items.extend(chain!(
+            help_item!("Space", "Ascending/Descending"),
+            help_item!("Enter", "Apply"),
+          ));
+        }
       }
+    } else if self.active_pane == ActivePane::Events && self.events_view == EventsView::Stats {
+      items.extend(chain!(
+        help_item!(
+          format!(
+            "{}/{}",
+            self.keymap.keys(KeyAction::PrevItem),
+            self.keymap.keys(KeyAction::NextItem)
+          ),
+          "Select"
+        ),
+        help_item!(
+          "Tab",
+          match self.stats.order {
+            StatsOrder::Execs => "Sort\u{00a0}by\u{00a0}Runtime",
+            StatsOrder::Runtime => "Sort\u{00a0}by\u{00a0}Execs",
+          }
+        ),
+        help_item!(self.keymap.keys(KeyAction::ToggleStats), "Events"),
+        help_item!(self.keymap.keys(KeyAction::ToggleTimeline), "Timeline"),
+        help_item!(self.keymap.keys(KeyAction::Quit), "Quit"),
+      ))
+    } else if self.active_pane == ActivePane::Events && self.events_view == EventsView::Timeline {
+      items.extend(chain!(
+        help_item!(
+          format!(
+            "{}/{}",
+            self.keymap.keys(KeyAction::PrevItem),
+            self.keymap.keys(KeyAction::NextItem)
+          ),
+          "Scroll"
+        ),
+        help_item!(self.keymap.keys(KeyAction::ToggleTimeline), "Events"),
+        help_item!(self.keymap.keys(KeyAction::ToggleStats), "Stats"),
+        help_item!(self.keymap.keys(KeyAction::Quit), "Quit"),
+      ))
     } else if self.active_pane == ActivePane::Events {
       if self.clipboard.is_some() {
-        items.extend(help_item!("C", "Copy"));
+        items.extend(help_item!(self.keymap.keys(KeyAction::Copy), "Copy"));
       }
       items.extend(chain!(
-        help_item!("G/S", "Grow/Shrink\u{00a0}Pane"),
-        help_item!("Alt+L", "Layout"),
         help_item!(
-          "F",
+          format!(
+            "{}/{}",
+            self.keymap.keys(KeyAction::GrowPane),
+            self.keymap.keys(KeyAction::ShrinkPane)
+          ),
+          "Grow/Shrink\u{00a0}Pane"
+        ),
+        help_item!(self.keymap.keys(KeyAction::SwitchLayout), "Layout"),
+        help_item!(
+          self.keymap.keys(KeyAction::ToggleZoom),
+          if self.zoomed { "Unzoom" } else { "Zoom" }
+        ),
+        help_item!(
+          self.keymap.keys(KeyAction::ToggleFollow),
           if self.event_list.follow {
             "Unfollow"
           } else {
@@ -601,19 +2096,200 @@ impl App {
           }
         ),
         help_item!(
-          "E",
+          self.keymap.keys(KeyAction::TogglePin),
+          if self.event_list.pinned_pid.is_some() {
+            "Unpin"
+          } else {
+            "Pin"
+          }
+        ),
+        help_item!(
+          self.keymap.keys(KeyAction::ToggleEnvDisplay),
           if self.event_list.env_in_cmdline {
             "Hide Env"
           } else {
             "Show Env"
           }
         ),
-        help_item!("V", "View"),
-        help_item!("Q", "Quit"),
-        help_item!("F1", "Help"),
+        help_item!(
+          self.keymap.keys(KeyAction::ToggleWrap),
+          if self.event_list.wrap {
+            "No\u{00a0}Wrap"
+          } else {
+            "Wrap"
+          }
+        ),
+        help_item!(
+          self.keymap.keys(KeyAction::ToggleGroup),
+          if self.event_list.group {
+            "Ungroup"
+          } else {
+            "Group"
+          }
+        ),
+        help_item!(self.keymap.keys(KeyAction::ViewDetails), "View"),
+        help_item!(self.keymap.keys(KeyAction::Search), "Search"),
+        help_item!(self.keymap.keys(KeyAction::Filter), "Filter"),
+        help_item!(self.keymap.keys(KeyAction::ToggleBookmark), "Bookmark"),
+        help_item!(
+          self.keymap.keys(KeyAction::MarkDiffBase),
+          "Diff\u{00a0}Base"
+        ),
+        help_item!(self.keymap.keys(KeyAction::KillProcess), "Signal"),
+        help_item!(self.keymap.keys(KeyAction::Export), "Export"),
+        help_item!(self.keymap.keys(KeyAction::Columns), "Columns"),
+        help_item!(self.keymap.keys(KeyAction::Sort), "Sort"),
+        help_item!(self.keymap.keys(KeyAction::NewTrace), "New\u{00a0}Trace"),
+        help_item!(self.keymap.keys(KeyAction::Attach), "Attach"),
+        help_item!(
+          format!(
+            "{}/{}",
+            self.keymap.keys(KeyAction::JumpToParent),
+            self.keymap.keys(KeyAction::NextChild)
+          ),
+          "Parent/Child"
+        ),
+        help_item!(
+          self.keymap.keys(KeyAction::TogglePause),
+          if self.is_paused() {
+            "Resume\u{00a0}Tracer"
+          } else {
+            "Pause\u{00a0}Tracer"
+          }
+        ),
+        help_item!(
+          self.keymap.keys(KeyAction::ToggleProcessTree),
+          if self.show_process_tree {
+            "Hide\u{00a0}Processes"
+          } else {
+            "Show\u{00a0}Processes"
+          }
+        ),
+        help_item!(self.keymap.keys(KeyAction::ToggleStats), "Stats"),
+        help_item!(self.keymap.keys(KeyAction::ToggleTimeline), "Timeline"),
+      ));
+      if self.term.is_some() {
+        items.extend(help_item!(self.keymap.keys(KeyAction::Rerun), "Re-run"));
+      }
+      if self.event_list.search.is_some() {
+        items.extend(chain!(
+          help_item!(
+            format!(
+              "{}/{}",
+              self.keymap.keys(KeyAction::NextMatch),
+              self.keymap.keys(KeyAction::PrevMatch)
+            ),
+            "Next/Prev\u{00a0}Match"
+          ),
+          help_item!("Esc", "Clear\u{00a0}Search"),
+        ));
+      } else if self.event_list.filter.is_some() {
+        items.extend(help_item!("Esc", "Clear\u{00a0}Filter"));
+      }
+      items.extend(help_item!(
+        self.keymap.keys(KeyAction::ToggleSubtree),
+        if self
+          .event_list
+          .selection()
+          .and_then(|event| event.pid())
+          .is_some_and(|pid| self.event_list.is_subtree_collapsed(pid))
+        {
+          "Expand\u{00a0}Subtree"
+        } else {
+          "Collapse\u{00a0}Subtree"
+        }
+      ));
+      if self.event_list.group {
+        items.extend(help_item!(
+          self.keymap.keys(KeyAction::ToggleCollapse),
+          "Collapse/Expand"
+        ));
+      }
+      if self.tabs.len() > 1 {
+        items.extend(help_item!("1-9", "Switch\u{00a0}Tab"));
+      }
+      if !self.event_list.bookmarks.is_empty() {
+        items.extend(help_item!(
+          self.keymap.keys(KeyAction::NextBookmark),
+          "Next\u{00a0}Bookmark"
+        ));
+      }
+      if self.event_list.nr_failures > 0 {
+        items.extend(help_item!(
+          format!(
+            "{}/{}",
+            self.keymap.keys(KeyAction::NextFailure),
+            self.keymap.keys(KeyAction::PrevFailure)
+          ),
+          "Next/Prev\u{00a0}Failure"
+        ));
+      }
+      if self.diff_base.is_some() {
+        items.extend(help_item!(
+          self.keymap.keys(KeyAction::Diff),
+          "Diff\u{00a0}With\u{00a0}Base"
+        ));
+      }
+      if !self.watches.is_empty() {
+        items.extend(help_item!(
+          self.keymap.keys(KeyAction::Watch),
+          format!("Watches\u{00a0}({})", self.watches.len())
+        ));
+      }
+      let nr_breakpoints = self.breakpoints.nr_patterns();
+      if nr_breakpoints > 0 {
+        items.extend(help_item!(
+          self.keymap.keys(KeyAction::Breakpoint),
+          format!("Breakpoints\u{00a0}({nr_breakpoints})")
+        ));
+      }
+      items.extend(chain!(
+        help_item!(self.keymap.keys(KeyAction::Quit), "Quit"),
+        help_item!(self.keymap.keys(KeyAction::Help), "Help")
+      ))
+    } else if self.active_pane == ActivePane::ProcessTree {
+      items.extend(chain!(
+        help_item!(
+          format!(
+            "{}/{}",
+            self.keymap.keys(KeyAction::PrevItem),
+            self.keymap.keys(KeyAction::NextItem)
+          ),
+          "Select"
+        ),
+        help_item!("Enter", "Jump\u{00a0}to\u{00a0}Execs"),
+        help_item!(
+          self.keymap.keys(KeyAction::ToggleProcessTree),
+          "Hide\u{00a0}Processes"
+        ),
+        help_item!(self.keymap.keys(KeyAction::Quit), "Quit"),
       ))
     } else {
       // Terminal
+      if self.term.as_ref().is_some_and(|t| t.is_in_copy_mode()) {
+        items.extend(chain!(
+          help_item!("←/↓/↑/→", "Move"),
+          help_item!("V", "Select"),
+          help_item!("Y", "Copy"),
+          help_item!("Q", "Exit\u{00a0}Copy\u{00a0}Mode"),
+        ));
+      } else if self.clipboard.is_some() {
+        items.extend(help_item!("Alt+C", "Copy\u{00a0}Mode"));
+      }
+      items.extend(chain!(
+        help_item!("Shift+PgUp/PgDn", "Scroll\u{00a0}History"),
+        help_item!(
+          self.keymap.keys(KeyAction::ToggleZoom),
+          if self.zoomed { "Unzoom" } else { "Zoom" }
+        ),
+      ));
+      if self
+        .term
+        .as_ref()
+        .is_some_and(|t| t.scrollback_offset() > 0)
+      {
+        items.extend(help_item!("Shift+End", "Back\u{00a0}to\u{00a0}Bottom"));
+      }
     };
 
     let line = Line::default().spans(items);
@@ -623,3 +2299,16 @@ impl App {
       .render(area, buf);
   }
 }
+
+/// Escape the C0 and C1 control characters, e.g. ESC, in text that is sent to the terminal as is
+fn escape_control_chars(s: &str) -> String {
+  let mut escaped = String::with_capacity(s.len());
+  for c in s.chars() {
+    if c.is_control() {
+      escaped.extend(c.escape_default());
+    } else {
+      escaped.push(c);
+    }
+  }
+  escaped
+}
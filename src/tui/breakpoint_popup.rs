@@ -0,0 +1,219 @@
+//! The exec that is stopped at a breakpoint, whose arguments and environment can be edited before it continues
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  buffer::Buffer,
+  layout::{Alignment::Center, Rect},
+  style::Styled,
+  text::{Line, Span},
+  widgets::{Block, Borders, Clear, Paragraph, StatefulWidgetRef, Widget},
+};
+
+use crate::{
+  action::Action,
+  term::symbols,
+  tracer::{BreakpointDecision, BreakpointHit},
+};
+
+use super::theme::THEME;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+  Argv,
+  Env,
+}
+
+#[derive(Debug, Clone)]
+pub struct BreakpointPopup;
+
+#[derive(Debug, Clone)]
+pub struct BreakpointPopupState {
+  hit: BreakpointHit,
+  argv: Vec<String>,
+  envp: Vec<String>,
+  section: Section,
+  selected: usize,
+  /// The text of the entry that is being edited
+  editing: Option<String>,
+  /// The first line shown, which follows the selection
+  scroll: u16,
+}
+
+impl BreakpointPopupState {
+  pub fn new(hit: BreakpointHit) -> Self {
+    Self {
+      argv: hit.argv.clone(),
+      envp: hit.envp.clone(),
+      hit,
+      section: Section::Argv,
+      selected: 0,
+      editing: None,
+      scroll: 0,
+    }
+  }
+
+  pub fn is_for(&self, hit: &BreakpointHit) -> bool {
+    self.hit.pid == hit.pid && self.hit.argv == hit.argv
+  }
+
+  fn entries(&mut self) -> &mut Vec<String> {
+    match self.section {
+      Section::Argv => &mut self.argv,
+      Section::Env => &mut self.envp,
+    }
+  }
+
+  /// Only the edited parts are substituted
+  fn decision(&self) -> BreakpointDecision {
+    BreakpointDecision {
+      argv: (self.argv != self.hit.argv).then(|| self.argv.clone()),
+      envp: (self.envp != self.hit.envp).then(|| self.envp.clone()),
+    }
+  }
+
+  pub fn handle_key_event(&mut self, ke: KeyEvent) -> Option<Action> {
+    if let Some(text) = self.editing.as_mut() {
+      match ke.code {
+        KeyCode::Esc => self.editing = None,
+        KeyCode::Enter => {
+          let text = self.editing.take().unwrap();
+          let selected = self.selected;
+          if let Some(entry) = self.entries().get_mut(selected) {
+            *entry = text;
+          }
+        }
+        KeyCode::Backspace => {
+          text.pop();
+        }
+        KeyCode::Char(c) if (ke.modifiers - KeyModifiers::SHIFT).is_empty() => text.push(c),
+        _ => {}
+      }
+      return None;
+    }
+    if ke.modifiers != KeyModifiers::NONE {
+      return None;
+    }
+    let len = self.entries().len();
+    match ke.code {
+      KeyCode::Char('c') => return Some(Action::ResumeBreakpoint(self.decision())),
+      KeyCode::Esc => return Some(Action::ResumeBreakpoint(BreakpointDecision::default())),
+      KeyCode::Tab => {
+        self.section = match self.section {
+          Section::Argv => Section::Env,
+          Section::Env => Section::Argv,
+        };
+        self.selected = 0;
+      }
+      KeyCode::Down | KeyCode::Char('j') => {
+        self.selected = (self.selected + 1).min(len.saturating_sub(1));
+      }
+      KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+      KeyCode::Enter | KeyCode::Char('e') if len > 0 => {
+        self.editing = Some(self.entries()[self.selected].clone());
+      }
+      KeyCode::Char('a') => {
+        let index = if len == 0 { 0 } else { self.selected + 1 };
+        self.entries().insert(index, String::new());
+        self.selected = index;
+        self.editing = Some(String::new());
+      }
+      KeyCode::Char('d') if len > 0 => {
+        let selected = self.selected;
+        self.entries().remove(selected);
+        self.selected = selected.min(len.saturating_sub(2));
+      }
+      KeyCode::Char('r') => {
+        self.argv = self.hit.argv.clone();
+        self.envp = self.hit.envp.clone();
+        self.selected = 0;
+      }
+      _ => {}
+    }
+    None
+  }
+
+  fn section_lines(&self, section: Section, lines: &mut Vec<Line<'static>>) -> Option<usize> {
+    let (label, entries) = match section {
+      Section::Argv => (" Arguments ", &self.argv),
+      Section::Env => (" Environment ", &self.envp),
+    };
+    let active = section == self.section;
+    lines.push(
+      label
+        .set_style(if active {
+          THEME.selected_label
+        } else {
+          THEME.label
+        })
+        .into(),
+    );
+    let mut selected_line = None;
+    for (i, entry) in entries.iter().enumerate() {
+      let is_selected = active && i == self.selected;
+      if is_selected {
+        selected_line = Some(lines.len());
+      }
+      let content = match self.editing.as_ref() {
+        Some(text) if is_selected => {
+          Line::from(vec![Span::raw(text.clone()), symbols().cursor.into()])
+        }
+        _ => Line::raw(entry.clone()),
+      };
+      let mut spans =
+        vec![if is_selected { ">" } else { " " }.set_style(THEME.selection_indicator)];
+      spans.extend(content.spans);
+      let line = Line::from(spans);
+      lines.push(if is_selected {
+        line.style(THEME.selection)
+      } else {
+        line
+      });
+    }
+    selected_line
+  }
+}
+
+impl StatefulWidgetRef for BreakpointPopup {
+  fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut BreakpointPopupState) {
+    let popup_area = Rect {
+      x: area.x + area.width / 10,
+      y: area.y + area.height / 10,
+      width: area.width - area.width / 5,
+      height: area.height - area.height / 5,
+    };
+    Clear.render(popup_area, buf);
+    let mut lines = vec![Line::from(vec![
+      state.hit.pid.to_string().set_style(THEME.pid_in_msg),
+      " is about to execute ".into(),
+      state
+        .hit
+        .filename
+        .display()
+        .to_string()
+        .set_style(THEME.filename),
+    ])];
+    let argv_selection = state.section_lines(Section::Argv, &mut lines);
+    let env_selection = state.section_lines(Section::Env, &mut lines);
+    let block = Block::default()
+      .title(" Breakpoint ")
+      .title_alignment(Center)
+      .borders(Borders::ALL)
+      .border_style(THEME.popup_border);
+    let height = block.inner(popup_area).height;
+    // Keep the selected entry in view
+    if let Some(selected) = argv_selection.or(env_selection) {
+      let selected = selected as u16;
+      if selected < state.scroll {
+        state.scroll = selected.saturating_sub(1);
+      } else if selected >= state.scroll + height {
+        state.scroll = selected + 1 - height;
+      }
+    }
+    Paragraph::new(lines)
+      .block(block)
+      .scroll((state.scroll, 0))
+      .render(popup_area, buf);
+  }
+
+  type State = BreakpointPopupState;
+}
@@ -0,0 +1,61 @@
+//! Copying to the system clipboard, or to the clipboard of the terminal with OSC 52
+
+use std::{
+  borrow::Cow,
+  env,
+  io::{stderr, Write},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::cli::options::ClipboardProvider;
+
+pub enum Clipboard {
+  System(arboard::Clipboard),
+  /// The terminal sets its clipboard when it receives the OSC 52 escape sequence,
+  /// which works over SSH where there is no system clipboard to talk to
+  Osc52,
+}
+
+impl Clipboard {
+  pub fn new(provider: ClipboardProvider) -> Option<Self> {
+    match provider {
+      ClipboardProvider::System => arboard::Clipboard::new().ok().map(Self::System),
+      ClipboardProvider::Osc52 => Some(Self::Osc52),
+      ClipboardProvider::Auto => {
+        // The system clipboard of a remote machine is useless even if there is one
+        if env::var_os("SSH_TTY").is_some() || env::var_os("SSH_CONNECTION").is_some() {
+          return Some(Self::Osc52);
+        }
+        Some(
+          arboard::Clipboard::new()
+            .map(Self::System)
+            .unwrap_or(Self::Osc52),
+        )
+      }
+    }
+  }
+
+  pub fn set_text<'a>(&mut self, text: impl Into<Cow<'a, str>>) -> color_eyre::Result<()> {
+    match self {
+      Self::System(clipboard) => clipboard.set_text(text)?,
+      Self::Osc52 => {
+        let text = text.into();
+        let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text.as_bytes()));
+        let mut stderr = stderr().lock();
+        if env::var_os("TMUX").is_some() {
+          // tmux only passes the sequence through to the outer terminal if it is wrapped in DCS
+          write!(
+            stderr,
+            "\x1bPtmux;{}\x1b\\",
+            sequence.replace('\x1b', "\x1b\x1b")
+          )?;
+        } else {
+          write!(stderr, "{sequence}")?;
+        }
+        stderr.flush()?;
+      }
+    }
+    Ok(())
+  }
+}
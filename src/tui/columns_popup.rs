@@ -0,0 +1,136 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  buffer::Buffer,
+  layout::{Alignment::Center, Rect},
+  widgets::{Block, Borders, Clear, HighlightSpacing, List, ListState, StatefulWidgetRef, Widget},
+};
+
+use crate::action::Action;
+
+use super::{copy_popup::centered_popup_rect, event_list::EventColumn, theme::THEME};
+
+#[derive(Debug, Clone)]
+pub struct ColumnsPopup;
+
+#[derive(Debug, Clone)]
+pub struct ColumnsPopupState {
+  /// All the columns in the order they are shown, and whether they are shown
+  columns: Vec<(EventColumn, bool)>,
+  state: ListState,
+}
+
+impl ColumnsPopupState {
+  pub fn new(shown: &[EventColumn]) -> Self {
+    // The hidden columns go after the shown ones
+    let columns = shown
+      .iter()
+      .map(|column| (*column, true))
+      .chain(
+        EventColumn::ALL
+          .iter()
+          .filter(|column| !shown.contains(column))
+          .map(|column| (*column, false)),
+      )
+      .collect();
+    let mut state = ListState::default();
+    state.select(Some(0));
+    Self { columns, state }
+  }
+
+  fn selected(&self) -> usize {
+    self.state.selected().unwrap_or(0)
+  }
+
+  pub fn next(&mut self) {
+    self
+      .state
+      .select(Some((self.selected() + 1).min(self.columns.len() - 1)))
+  }
+
+  pub fn prev(&mut self) {
+    self.state.select(Some(self.selected().saturating_sub(1)))
+  }
+
+  /// Move the selected column one place down, i.e. after the next column
+  fn move_down(&mut self) {
+    let i = self.selected();
+    if i + 1 < self.columns.len() {
+      self.columns.swap(i, i + 1);
+      self.state.select(Some(i + 1));
+    }
+  }
+
+  fn move_up(&mut self) {
+    let i = self.selected();
+    if i > 0 {
+      self.columns.swap(i, i - 1);
+      self.state.select(Some(i - 1));
+    }
+  }
+
+  pub fn handle_key_event(&mut self, ke: KeyEvent) -> Option<Action> {
+    match (ke.code, ke.modifiers - KeyModifiers::SHIFT) {
+      (KeyCode::Char('q') | KeyCode::Esc, KeyModifiers::NONE) => {
+        return Some(Action::CancelCurrentPopup);
+      }
+      (KeyCode::Down, KeyModifiers::NONE) if ke.modifiers.contains(KeyModifiers::SHIFT) => {
+        self.move_down();
+      }
+      (KeyCode::Up, KeyModifiers::NONE) if ke.modifiers.contains(KeyModifiers::SHIFT) => {
+        self.move_up();
+      }
+      (KeyCode::Char('J'), KeyModifiers::NONE) => self.move_down(),
+      (KeyCode::Char('K'), KeyModifiers::NONE) => self.move_up(),
+      (KeyCode::Down | KeyCode::Char('j'), KeyModifiers::NONE) => self.next(),
+      (KeyCode::Up | KeyCode::Char('k'), KeyModifiers::NONE) => self.prev(),
+      (KeyCode::Char(' '), KeyModifiers::NONE) => {
+        let i = self.selected();
+        self.columns[i].1 = !self.columns[i].1;
+      }
+      (KeyCode::Enter, KeyModifiers::NONE) => {
+        return Some(Action::SetColumns(
+          self
+            .columns
+            .iter()
+            .filter(|(_, shown)| *shown)
+            .map(|(column, _)| *column)
+            .collect(),
+        ));
+      }
+      _ => {}
+    }
+    None
+  }
+}
+
+impl StatefulWidgetRef for ColumnsPopup {
+  fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut ColumnsPopupState) {
+    let items: Vec<String> = state
+      .columns
+      .iter()
+      .map(|(column, shown)| format!("[{}] {}", if *shown { "x" } else { " " }, column.name()))
+      .collect();
+    let width = items
+      .iter()
+      .map(|item| item.len() + 1)
+      .max()
+      .unwrap_or_default()
+      .max(9);
+    let list = List::new(items)
+      .block(
+        Block::default()
+          .title("Columns")
+          .title_alignment(Center)
+          .borders(Borders::ALL)
+          .border_style(THEME.popup_border),
+      )
+      .highlight_style(THEME.selection)
+      .highlight_symbol(">")
+      .highlight_spacing(HighlightSpacing::Always);
+    let popup_area = centered_popup_rect(width as u16, list.len() as u16, area);
+    Clear.render(popup_area, buf);
+    StatefulWidgetRef::render_ref(&list, popup_area, buf, &mut state.state);
+  }
+
+  type State = ColumnsPopupState;
+}
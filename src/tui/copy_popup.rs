@@ -11,7 +11,7 @@ use ratatui::{
 };
 
 use crate::{
-  action::{Action, CopyTarget, SupportedShell::Bash},
+  action::{Action, CopyTarget, SupportedShell},
   event::TracerEvent,
 };
 
@@ -25,6 +25,8 @@ pub struct CopyPopupState {
   pub event: Arc<TracerEvent>,
   pub state: ListState,
   pub available_targets: Vec<char>,
+  /// The shell that the command lines are quoted for
+  pub shell: SupportedShell,
 }
 
 lazy_static! {
@@ -35,10 +37,18 @@ lazy_static! {
       'f',
       ("Command line with (F)ile descriptors", "Cmdline with Fds")
     ),
+    (
+      'i',
+      (
+        "Command line with full env (env -(I))",
+        "Cmdline with full env"
+      )
+    ),
     ('e', ("(E)nvironment variables", "Env")),
     ('d', ("(D)iff of environment variables", "Diff of Env")),
     ('a', ("(A)rguments", "Argv")),
     ('n', ("File(N)ame", "Filename")),
+    ('o', ("JS(O)N object", "JSON")),
     ('r', ("Syscall (R)esult", "Result")),
     ('l', ("Current (L)ine", "Line")),
   ]
@@ -50,18 +60,47 @@ impl CopyPopupState {
   pub fn new(event: Arc<TracerEvent>) -> Self {
     let mut state = ListState::default();
     state.select(Some(0));
-    let available_targets = if let TracerEvent::Exec(_) = &event.as_ref() {
-      KEY_MAP.keys().copied().collect()
-    } else {
-      vec!['l']
-    };
+    let shell = SupportedShell::Bash;
     Self {
+      available_targets: Self::targets_for(&event, shell),
       event,
       state,
-      available_targets,
+      shell,
+    }
+  }
+
+  fn targets_for(event: &TracerEvent, shell: SupportedShell) -> Vec<char> {
+    if let TracerEvent::Exec(_) = event {
+      KEY_MAP
+        .keys()
+        .copied()
+        // Only some shells can redirect the file descriptors
+        .filter(|key| shell.supports_redirections() || !matches!(key, 's' | 'f'))
+        .collect()
+    } else {
+      vec!['l', 'o']
     }
   }
 
+  /// Switch to the next shell for quoting the command lines
+  pub fn next_shell(&mut self) {
+    let selected = self.available_targets[self.state.selected().unwrap_or(0)];
+    let i = SupportedShell::ALL
+      .iter()
+      .position(|&shell| shell == self.shell)
+      .unwrap();
+    self.shell = SupportedShell::ALL[(i + 1) % SupportedShell::ALL.len()];
+    self.available_targets = Self::targets_for(&self.event, self.shell);
+    // Keep the selected target selected if it is still available
+    self.state.select(Some(
+      self
+        .available_targets
+        .iter()
+        .position(|&key| key == selected)
+        .unwrap_or(0),
+    ));
+  }
+
   pub fn next(&mut self) {
     self.state.select(Some(
       (self.state.selected().unwrap() + 1).min(self.available_targets.len() - 1),
@@ -78,15 +117,17 @@ impl CopyPopupState {
     let id = self.state.selected().unwrap_or(0);
     let key = self.available_targets[id];
     match key {
-      'c' => CopyTarget::Commandline(Bash),
-      's' => CopyTarget::CommandlineWithStdio(Bash),
-      'f' => CopyTarget::CommandlineWithFds(Bash),
+      'c' => CopyTarget::Commandline(self.shell),
+      's' => CopyTarget::CommandlineWithStdio(self.shell),
+      'f' => CopyTarget::CommandlineWithFds(self.shell),
+      'i' => CopyTarget::CommandlineWithFullEnv(self.shell),
       'e' => CopyTarget::Env,
       'd' => CopyTarget::EnvDiff,
       'a' => CopyTarget::Argv,
       'n' => CopyTarget::Filename,
       'r' => CopyTarget::SyscallResult,
       'l' => CopyTarget::Line,
+      'o' => CopyTarget::Json,
       _ => unreachable!(),
     }
   }
@@ -121,6 +162,9 @@ impl CopyPopupState {
         KeyCode::Up | KeyCode::Char('k') => {
           self.prev();
         }
+        KeyCode::Tab if matches!(self.event.as_ref(), TracerEvent::Exec(_)) => {
+          self.next_shell();
+        }
         KeyCode::Enter => {
           return Ok(Some(Action::CopyToClipboard {
             event: self.event.clone(),
@@ -152,7 +196,11 @@ impl StatefulWidgetRef for CopyPopup {
     )
     .block(
       Block::default()
-        .title("Copy")
+        .title(if let TracerEvent::Exec(_) = state.event.as_ref() {
+          format!("Copy ({})", state.shell)
+        } else {
+          "Copy".to_string()
+        })
         .title_alignment(Center)
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::LightGreen)),
@@ -198,7 +246,7 @@ impl StatefulWidgetRef for CopyPopup {
 // SOFTWARE.
 
 /// Create a rectangle centered in the given area.
-fn centered_popup_rect(width: u16, height: u16, area: Rect) -> Rect {
+pub(super) fn centered_popup_rect(width: u16, height: u16, area: Rect) -> Rect {
   let height = height.saturating_add(2).min(area.height);
   let width = width.saturating_add(2).min(area.width);
   Rect {
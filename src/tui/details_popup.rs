@@ -1,28 +1,41 @@
 use std::{
-  ops::{ControlFlow, Deref, DerefMut},
-  sync::Arc,
+  collections::BTreeMap,
+  io,
+  ops::{Deref, DerefMut},
+  path::PathBuf,
+  sync::{Arc, OnceLock},
 };
 
-use arboard::Clipboard;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use itertools::{chain, Itertools};
 use nix::{errno::Errno, fcntl::OFlag};
 use ratatui::{
   buffer::Buffer,
-  layout::{Alignment::Center, Rect, Size},
-  style::Styled,
-  text::{Line, Span},
+  layout::{
+    Alignment::{Center, Right},
+    Position, Rect, Size,
+  },
+  style::{Style, Styled},
+  text::{Line, Span, Text},
   widgets::{
     Block, Borders, Clear, Paragraph, StatefulWidget, StatefulWidgetRef, Tabs, Widget, WidgetRef,
     Wrap,
   },
 };
+use regex::Regex;
 use tui_scrollview::{ScrollView, ScrollViewState};
 
-use crate::{event::TracerEvent, proc::BaselineInfo};
+use crate::{
+  action::Action,
+  event::TracerEvent,
+  proc::{diff_env, parse_env_entry, BaselineInfo, EnvDiff},
+};
 
 use super::{
+  clipboard::Clipboard,
+  file_info::{file_info, sha256_file, Sha256Progress},
   help::{help_desc, help_key},
+  search::{highlight_matches, SearchInput, SearchPurpose},
   theme::THEME,
 };
 
@@ -36,19 +49,138 @@ impl DetailsPopup {
   }
 }
 
+/// The environment shown as a diff against another environment
+#[derive(Debug, Clone)]
+pub(super) struct EnvView {
+  /// What the environment is compared against
+  against: String,
+  /// The sections of the added, removed and changed variables, followed by the inherited ones
+  pub(super) lines: Vec<Line<'static>>,
+  pub(super) nr_changed: usize,
+}
+
+impl EnvView {
+  pub(super) fn new(
+    against: String,
+    original: &BTreeMap<String, String>,
+    env_diff: &EnvDiff,
+  ) -> Self {
+    let entry = |sign: Span<'static>, key: &str, key_style: Style, value: Vec<Span<'static>>| {
+      let mut spans = vec![
+        sign,
+        key.to_string().set_style(key_style),
+        "=".set_style(THEME.equal_sign),
+      ];
+      spans.extend(value);
+      Line::from(spans)
+    };
+    let header =
+      |name: &str, count: usize| Line::from(format!("{name} ({count})").set_style(THEME.sublabel));
+    let mut lines = vec![];
+    // Each kind of change has its own section, the variables are sorted by name in each of them
+    if !env_diff.added.is_empty() {
+      lines.push(header("Added", env_diff.added.len()));
+      lines.extend(env_diff.added.iter().map(|(key, value)| {
+        entry(
+          "+".set_style(THEME.plus_sign),
+          key,
+          THEME.added_env_key,
+          vec![value.to_string().set_style(THEME.added_env_val)],
+        )
+      }));
+    }
+    if !env_diff.removed.is_empty() {
+      lines.push(header("Removed", env_diff.removed.len()));
+      lines.extend(env_diff.removed.iter().map(|key| {
+        entry(
+          "-".set_style(THEME.minus_sign),
+          key,
+          THEME.removed_env_key,
+          vec![original[key].to_string().set_style(THEME.removed_env_val)],
+        )
+      }));
+    }
+    if !env_diff.modified.is_empty() {
+      lines.push(header("Changed", env_diff.modified.len()));
+      lines.extend(env_diff.modified.iter().map(|(key, new)| {
+        entry(
+          "~".set_style(THEME.changed_env_key),
+          key,
+          THEME.changed_env_key,
+          vec![
+            original[key].to_string().set_style(THEME.removed_env_val),
+            " → ".set_style(THEME.equal_sign),
+            new.to_string().set_style(THEME.changed_env_val),
+          ],
+        )
+      }));
+    }
+    let nr_changed = lines.len();
+    let inherited = original
+      .iter()
+      .filter(|(key, _)| !env_diff.is_modified_or_removed(key))
+      .collect_vec();
+    if !inherited.is_empty() {
+      lines.push(header("Inherited", inherited.len()));
+      lines.extend(inherited.into_iter().map(|(key, value)| {
+        entry(
+          " ".into(),
+          key,
+          THEME.unchanged_env_key,
+          vec![value.to_string().set_style(THEME.unchanged_env_val)],
+        )
+      }));
+    }
+    Self {
+      against,
+      lines,
+      nr_changed,
+    }
+  }
+
+  pub(super) fn error(e: String) -> Self {
+    Self {
+      against: "the baseline".to_string(),
+      lines: vec![Line::from(format!("Failed to read envp: {}", e))],
+      nr_changed: 1,
+    }
+  }
+}
+
 #[derive(Debug, Clone)]
 pub struct DetailsPopupState {
-  details: Vec<(&'static str, Line<'static>)>,
+  details: Vec<(&'static str, Text<'static>)>,
+  /// The executed file, which can be opened in an editor or a pager
+  filename: Option<PathBuf>,
+  /// The SHA-256 of the file, set by the thread that computes it
+  sha256: Option<Arc<OnceLock<io::Result<String>>>>,
+  /// Whether the computed SHA-256 is shown
+  sha256_shown: bool,
   active_index: usize,
   scroll: ScrollViewState,
-  env: Option<Vec<Line<'static>>>,
+  env: Option<Vec<EnvView>>,
+  env_index: usize,
+  show_unchanged_env: bool,
   fdinfo: Option<Vec<Line<'static>>>,
   available_tabs: Vec<&'static str>,
   tab_index: usize,
+  /// The search prompt, shown while the search text is typed
+  search_input: Option<SearchInput>,
+  search: Option<Regex>,
+  /// Index of the current match among the lines of the active tab that match the search
+  current_match: usize,
+  nr_matches: usize,
+  /// Scroll to the current match when rendering, where the positions of the lines are known
+  scroll_to_match: bool,
 }
 
 impl DetailsPopupState {
-  pub fn new(event: Arc<TracerEvent>, baseline: Arc<BaselineInfo>) -> Self {
+  /// `parent` is the exec event whose environment the event inherits, if it is known
+  pub fn new(
+    event: Arc<TracerEvent>,
+    baseline: Arc<BaselineInfo>,
+    parent: Option<Arc<TracerEvent>>,
+  ) -> Self {
     let mut modifier_args = Default::default();
     let mut details = vec![(
       if matches!(event.as_ref(), TracerEvent::Exec(_)) {
@@ -56,20 +188,31 @@ impl DetailsPopupState {
       } else {
         " Details "
       },
-      event.to_tui_line(&baseline, true, &modifier_args, true),
+      event
+        .to_tui_line(&baseline, true, &modifier_args, true)
+        .into(),
     )];
     let event_cloned = event.clone();
     let (env, fdinfo, available_tabs) = if let TracerEvent::Exec(exec) = event_cloned.as_ref() {
       details.extend([
         (" Cmdline with stdio ", {
           modifier_args.stdio_in_cmdline = true;
-          event.to_tui_line(&baseline, true, &modifier_args, true)
+          event
+            .to_tui_line(&baseline, true, &modifier_args, true)
+            .into()
         }),
         (" Cmdline with file descriptors ", {
           modifier_args.fd_in_cmdline = true;
-          event.to_tui_line(&baseline, true, &modifier_args, true)
+          event
+            .to_tui_line(&baseline, true, &modifier_args, true)
+            .into()
         }),
-        (" Pid ", Line::from(exec.pid.to_string())),
+        (" Pid ", exec.pid.to_string().into()),
+      ]);
+      if let Some(ns_pid) = exec.ns_pid {
+        details.push((" Pid in Namespace ", ns_pid.to_string().into()));
+      }
+      details.extend([
         (" Result ", {
           if exec.result == 0 {
             "0 (Success)".set_style(THEME.exec_result_success).into()
@@ -88,7 +231,25 @@ impl DetailsPopupState {
           " Filename ",
           Span::from(TracerEvent::filename_to_cow(&exec.filename).into_owned()).into(),
         ),
-        (" Argv ", TracerEvent::argv_to_string(&exec.argv).into()),
+      ]);
+      if let Some(resolved) = exec.resolved_filename.as_ref() {
+        details.push((
+          " Resolved Filename ",
+          Span::from(resolved.to_string_lossy().to_string()).into(),
+        ));
+      }
+      if let Ok(filename) = exec.filename.as_ref() {
+        details.push((" File ", file_info(filename, Sha256Progress::NotStarted)));
+      }
+      details.extend([
+        // One argument per line, so that long argument lists can be read
+        (
+          " Argv ",
+          match exec.argv.as_ref() {
+            Ok(argv) => Text::from(argv.iter().map(|arg| Line::from(arg.clone())).collect_vec()),
+            Err(_) => TracerEvent::argv_to_string(&exec.argv).into(),
+          },
+        ),
         (
           " Interpreters ",
           TracerEvent::interpreters_to_string(&exec.interpreter).into(),
@@ -118,84 +279,32 @@ impl DetailsPopupState {
           },
         ),
       ]);
-      let env = match exec.env_diff.as_ref() {
-        Ok(env_diff) => {
-          let mut env = env_diff
-            .added
+      let mut env = vec![match exec.env_diff.as_ref() {
+        Ok(env_diff) => EnvView::new("the baseline".to_string(), &baseline.env, env_diff),
+        Err(e) => EnvView::error(e.to_string()),
+      }];
+      if let Some(TracerEvent::Exec(parent)) = parent.as_deref() {
+        if let (Ok(parent_envp), Ok(envp)) = (parent.envp.as_ref(), exec.envp.as_ref()) {
+          let parent_env: BTreeMap<String, String> = parent_envp
             .iter()
-            .map(|(key, value)| {
-              let spans = vec![
-                "+".set_style(THEME.plus_sign),
-                key.to_string().set_style(THEME.added_env_key),
-                "=".set_style(THEME.equal_sign),
-                value.to_string().set_style(THEME.added_env_val),
-              ];
-              Line::default().spans(spans)
+            .map(|entry| {
+              let (key, value) = parse_env_entry(entry);
+              (key.to_owned(), value.to_owned())
             })
-            .collect_vec();
-          env.extend(
-            env_diff
-              .removed
-              .iter()
-              .map(|key| {
-                let value = baseline.env.get(key).unwrap();
-                let spans = vec![
-                  "-".set_style(THEME.minus_sign),
-                  key.to_string().set_style(THEME.removed_env_key),
-                  "=".set_style(THEME.equal_sign),
-                  value.to_string().set_style(THEME.removed_env_val),
-                ];
-                Line::default().spans(spans)
-              })
-              .collect_vec(),
-          );
-          env.extend(
-            env_diff
-              .modified
-              .iter()
-              .flat_map(|(key, new)| {
-                let old = baseline.env.get(key).unwrap();
-                let spans_old = vec![
-                  "-".set_style(THEME.minus_sign),
-                  key.to_string().set_style(THEME.removed_env_key),
-                  "=".set_style(THEME.equal_sign),
-                  old.to_string().set_style(THEME.removed_env_val),
-                ];
-                let spans_new = vec![
-                  "+".set_style(THEME.plus_sign),
-                  key.to_string().set_style(THEME.added_env_key),
-                  "=".set_style(THEME.equal_sign),
-                  new.to_string().set_style(THEME.added_env_val),
-                ];
-                vec![
-                  Line::default().spans(spans_old),
-                  Line::default().spans(spans_new),
-                ]
-              })
-              .collect_vec(),
-          );
-          env.extend(
-            // Unchanged env
-            baseline
-              .env
-              .iter()
-              .filter(|(key, _)| !env_diff.is_modified_or_removed(key))
-              .map(|(key, value)| {
-                let spans = vec![
-                  " ".into(),
-                  key.to_string().set_style(THEME.unchanged_env_key),
-                  "=".set_style(THEME.equal_sign),
-                  value.to_string().set_style(THEME.unchanged_env_val),
-                ];
-                Line::default().spans(spans)
-              }),
-          );
-          env
-        }
-        Err(e) => {
-          vec![Line::from(format!("Failed to read envp: {}", e))]
+            .collect();
+          let mut diff = diff_env(&parent_env, envp);
+          parent.secrets.add_changes(&exec.secrets, &mut diff);
+          env.push(EnvView::new(
+            format!(
+              "the parent exec ({} {})",
+              parent.pid,
+              TracerEvent::filename_to_cow(&parent.filename)
+            ),
+            &parent_env,
+            &diff,
+          ));
         }
-      };
+      }
       let mut fdinfo = vec![];
       for (&fd, info) in exec.fdinfo.fdinfo.iter() {
         fdinfo.push(
@@ -303,14 +412,28 @@ impl DetailsPopupState {
     } else {
       (None, None, vec!["Info"])
     };
+    let filename = match event.as_ref() {
+      TracerEvent::Exec(exec) => exec.filename.as_ref().ok().cloned(),
+      _ => None,
+    };
     Self {
       details,
+      filename,
+      sha256: None,
+      sha256_shown: false,
       fdinfo,
       active_index: 0,
       scroll: Default::default(),
       env,
+      env_index: 0,
+      show_unchanged_env: false,
       available_tabs,
       tab_index: 0,
+      search_input: None,
+      search: None,
+      current_match: 0,
+      nr_matches: 0,
+      scroll_to_match: false,
     }
   }
 
@@ -323,7 +446,78 @@ impl DetailsPopupState {
   }
 
   pub fn selected(&self) -> String {
-    self.details[self.active_index].1.to_string()
+    self.details[self.active_index]
+      .1
+      .lines
+      .iter()
+      .map(ToString::to_string)
+      .join("\n")
+  }
+
+  /// Hashing can take a while for large files, so it is only done when asked for, in the background
+  fn compute_sha256(&mut self) {
+    let Some(filename) = self.filename.clone() else {
+      return;
+    };
+    if self.sha256.is_some() {
+      return;
+    }
+    let sha256 = Arc::new(OnceLock::new());
+    self.sha256 = Some(sha256.clone());
+    std::thread::spawn(move || {
+      sha256.set(sha256_file(&filename)).ok();
+    });
+    self.update_file_info(Sha256Progress::Computing);
+  }
+
+  /// Show the SHA-256 once the background thread computed it
+  fn poll_sha256(&mut self) {
+    if self.sha256_shown {
+      return;
+    }
+    let Some(sha256) = self.sha256.clone() else {
+      return;
+    };
+    if let Some(result) = sha256.get() {
+      self.update_file_info(Sha256Progress::Done(result));
+      self.sha256_shown = true;
+    }
+  }
+
+  fn update_file_info(&mut self, sha256: Sha256Progress) {
+    let Some(filename) = self.filename.as_ref() else {
+      return;
+    };
+    if let Some((_, text)) = self
+      .details
+      .iter_mut()
+      .find(|(label, _)| *label == " File ")
+    {
+      *text = file_info(filename, sha256);
+    }
+  }
+
+  /// Whether the executed file is known, so that it can be opened
+  pub fn can_open_file(&self) -> bool {
+    self.filename.is_some()
+  }
+
+  pub fn is_searching(&self) -> bool {
+    self.search.is_some()
+  }
+
+  fn next_match(&mut self) {
+    if self.nr_matches > 0 {
+      self.current_match = (self.current_match + 1) % self.nr_matches;
+      self.scroll_to_match = true;
+    }
+  }
+
+  fn prev_match(&mut self) {
+    if self.nr_matches > 0 {
+      self.current_match = (self.current_match + self.nr_matches - 1) % self.nr_matches;
+      self.scroll_to_match = true;
+    }
   }
 
   pub fn next_tab(&mut self) {
@@ -354,11 +548,51 @@ impl DetailsPopupState {
     self.available_tabs[self.tab_index]
   }
 
+  /// Whether the environment can be compared against more than one environment
+  pub fn can_switch_env_diff(&self) -> bool {
+    self.env.as_ref().is_some_and(|env| env.len() > 1)
+  }
+
+  fn switch_env_diff(&mut self) {
+    if let Some(env) = self.env.as_ref() {
+      self.env_index = (self.env_index + 1) % env.len();
+      self.scroll.scroll_to_top();
+    }
+  }
+
   pub fn handle_key_event(
     &mut self,
     ke: KeyEvent,
     clipboard: Option<&mut Clipboard>,
-  ) -> color_eyre::Result<ControlFlow<()>> {
+  ) -> color_eyre::Result<Option<Action>> {
+    if let Some(input) = self.search_input.as_mut() {
+      match input.handle_key_event(ke) {
+        Some(Action::Search(regex)) => {
+          self.search = Some(regex);
+          self.search_input = None;
+          self.current_match = 0;
+          self.scroll_to_match = true;
+        }
+        Some(Action::ClearSearch) => {
+          self.search = None;
+          self.search_input = None;
+        }
+        Some(Action::CancelSearchInput) => {
+          self.search_input = None;
+        }
+        _ => {}
+      }
+      return Ok(None);
+    }
+    // Whether shift is reported for upper case characters depends on the terminal
+    if ke.code == KeyCode::Char('N') {
+      self.prev_match();
+      return Ok(None);
+    }
+    if ke.code == KeyCode::Char('H') {
+      self.compute_sha256();
+      return Ok(None);
+    }
     if ke.modifiers == KeyModifiers::NONE {
       match ke.code {
         KeyCode::Down | KeyCode::Char('j') => {
@@ -404,7 +638,16 @@ impl DetailsPopupState {
           }
         }
         KeyCode::Char('q') => {
-          return Ok(ControlFlow::Break(()));
+          return Ok(Some(Action::CancelCurrentPopup));
+        }
+        KeyCode::Char('/') => {
+          self.search_input = Some(SearchInput::new(SearchPurpose::Search));
+        }
+        KeyCode::Char('n') => {
+          self.next_match();
+        }
+        KeyCode::Esc => {
+          self.search = None;
         }
         KeyCode::Char('c') => {
           if self.active_tab() == "Info" {
@@ -416,10 +659,26 @@ impl DetailsPopupState {
         KeyCode::Tab => {
           self.circle_tab();
         }
+        KeyCode::Char('p') => {
+          if self.active_tab() == "Environment" {
+            self.switch_env_diff();
+          }
+        }
+        KeyCode::Char('u') => {
+          if self.active_tab() == "Environment" {
+            self.show_unchanged_env = !self.show_unchanged_env;
+          }
+        }
+        KeyCode::Char('e') => {
+          return Ok(self.filename.clone().map(Action::OpenInEditor));
+        }
+        KeyCode::Char('v') => {
+          return Ok(self.filename.clone().map(Action::OpenInPager));
+        }
         _ => {}
       }
     }
-    Ok(ControlFlow::Continue(()))
+    Ok(None)
   }
 }
 
@@ -439,6 +698,7 @@ impl DerefMut for DetailsPopupState {
 
 impl StatefulWidgetRef for DetailsPopup {
   fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut DetailsPopupState) {
+    state.poll_sha256();
     Clear.render(area, buf);
     let block = Block::new()
       .title(" Details ")
@@ -464,19 +724,43 @@ impl StatefulWidgetRef for DetailsPopup {
     tabs.render_ref(Rect::new(start, 0, tabs_width, 1), buf);
 
     // Tab Info
-    let paragraph = match state.tab_index {
-      0 => self.info_paragraph(state),
-      1 => self.env_paragraph(state),
-      2 => self.fd_paragraph(state),
+    let mut lines = match state.tab_index {
+      0 => self.info_lines(state),
+      1 => self.env_lines(state),
+      2 => self.fd_lines(state),
       _ => unreachable!(),
     };
+    let width = area.width - 1;
+    if let Some(regex) = state.search.as_ref() {
+      let matches = lines
+        .iter()
+        .positions(|line| regex.is_match(&line.to_string()))
+        .collect_vec();
+      state.nr_matches = matches.len();
+      state.current_match = state.current_match.min(matches.len().saturating_sub(1));
+      lines = lines
+        .into_iter()
+        .map(|line| highlight_matches(line, regex))
+        .collect();
+      if state.scroll_to_match {
+        state.scroll_to_match = false;
+        if let Some(&i) = matches.get(state.current_match) {
+          // The lines before the match can be wrapped
+          let y = Paragraph::new(lines[..i].to_vec())
+            .wrap(Wrap { trim: false })
+            .line_count(width);
+          state.scroll.set_offset(Position {
+            x: 0,
+            y: y.try_into().unwrap_or(u16::MAX),
+          });
+        }
+      }
+    }
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
 
     let size = Size {
-      width: area.width - 1,
-      height: paragraph
-        .line_count(area.width - 1)
-        .try_into()
-        .unwrap_or(u16::MAX),
+      width,
+      height: paragraph.line_count(width).try_into().unwrap_or(u16::MAX),
     };
     let mut scrollview = ScrollView::new(size);
     scrollview.render_widget(
@@ -489,13 +773,35 @@ impl StatefulWidgetRef for DetailsPopup {
       },
     );
     scrollview.render(inner, buf, &mut state.scroll);
+
+    // The search prompt and the matches are shown on the bottom border
+    let bottom = Rect {
+      y: area.bottom().saturating_sub(1),
+      height: 1,
+      ..area
+    };
+    if let Some(input) = state.search_input.as_ref() {
+      Clear.render(bottom, buf);
+      input.render(bottom, buf);
+    } else if state.search.is_some() {
+      Paragraph::new(
+        if state.nr_matches == 0 {
+          " No matches ".to_string()
+        } else {
+          format!(" Match {}/{} ", state.current_match + 1, state.nr_matches)
+        }
+        .set_style(THEME.search_prompt),
+      )
+      .alignment(Right)
+      .render(bottom, buf);
+    }
   }
 
   type State = DetailsPopupState;
 }
 
 impl DetailsPopup {
-  fn label<'a>(&self, content: &'a str, active: bool) -> Line<'a> {
+  fn label(&self, content: &'static str, active: bool) -> Line<'static> {
     if !active {
       content.set_style(THEME.label).into()
     } else {
@@ -511,23 +817,38 @@ impl DetailsPopup {
     }
   }
 
-  fn info_paragraph(&self, state: &DetailsPopupState) -> Paragraph {
-    let text = state
+  fn info_lines(&self, state: &DetailsPopupState) -> Vec<Line<'static>> {
+    state
       .details
       .iter()
       .enumerate()
-      .flat_map(|(idx, (label, line))| [self.label(label, idx == state.active_index), line.clone()])
-      .collect_vec();
-    Paragraph::new(text).wrap(Wrap { trim: false })
+      .flat_map(|(idx, (label, text))| {
+        chain!(
+          [self.label(label, idx == state.active_index)],
+          text.lines.iter().cloned()
+        )
+      })
+      .collect_vec()
   }
 
-  fn env_paragraph(&self, state: &DetailsPopupState) -> Paragraph {
-    let text = state.env.clone().unwrap();
-    Paragraph::new(text).wrap(Wrap { trim: false })
+  fn env_lines(&self, state: &DetailsPopupState) -> Vec<Line<'static>> {
+    let view = &state.env.as_ref().unwrap()[state.env_index];
+    let mut text: Vec<Line> = vec![format!("Compared against {}", view.against)
+      .set_style(THEME.sublabel)
+      .into()];
+    let lines = if state.show_unchanged_env {
+      &view.lines[..]
+    } else {
+      &view.lines[..view.nr_changed]
+    };
+    if view.nr_changed == 0 {
+      text.push("No changes".set_style(THEME.unchanged_env_val).into());
+    }
+    text.extend(lines.iter().cloned());
+    text
   }
 
-  fn fd_paragraph(&self, state: &DetailsPopupState) -> Paragraph {
-    let text = state.fdinfo.clone().unwrap();
-    Paragraph::new(text).wrap(Wrap { trim: false })
+  fn fd_lines(&self, state: &DetailsPopupState) -> Vec<Line<'static>> {
+    state.fdinfo.clone().unwrap()
   }
 }
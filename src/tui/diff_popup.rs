@@ -0,0 +1,177 @@
+//! Comparing two exec events, e.g. the passing and the failing invocation of a compiler
+
+use std::{collections::BTreeMap, ops::ControlFlow};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use itertools::chain;
+use ratatui::{
+  buffer::Buffer,
+  layout::{Alignment::Center, Rect, Size},
+  style::Styled,
+  text::Line,
+  widgets::{Block, Borders, Clear, Paragraph, StatefulWidget, StatefulWidgetRef, Widget, Wrap},
+};
+use similar::{ChangeTag, TextDiff};
+use tui_scrollview::{ScrollView, ScrollViewState};
+
+use crate::{
+  event::ExecEvent,
+  proc::{diff_env, parse_env_entry},
+};
+
+use super::{details_popup::EnvView, theme::THEME};
+
+pub struct DiffPopup;
+
+#[derive(Debug, Clone)]
+pub struct DiffPopupState {
+  /// The lines that compare the programs, the working directories and the arguments
+  lines: Vec<Line<'static>>,
+  env: EnvView,
+  show_unchanged_env: bool,
+  scroll: ScrollViewState,
+}
+
+impl DiffPopupState {
+  pub fn new(base: &ExecEvent, target: &ExecEvent) -> Self {
+    let mut lines = vec![];
+    lines.push(" Base ".set_style(THEME.label).into());
+    lines.push(Self::summary(base));
+    lines.push(" Target ".set_style(THEME.label).into());
+    lines.push(Self::summary(target));
+    lines.push(" Cwd ".set_style(THEME.label).into());
+    lines.extend(diff_lines(
+      &[base.cwd.display().to_string()],
+      &[target.cwd.display().to_string()],
+    ));
+    lines.push(" Argv ".set_style(THEME.label).into());
+    match (base.argv.as_ref(), target.argv.as_ref()) {
+      (Ok(old), Ok(new)) => lines.extend(diff_lines(old, new)),
+      (Err(e), _) | (_, Err(e)) => lines.push(
+        format!("Failed to read argv: {e}")
+          .set_style(THEME.inline_tracer_error)
+          .into(),
+      ),
+    }
+    let env = match (base.envp.as_ref(), target.envp.as_ref()) {
+      (Ok(old), Ok(new)) => {
+        let original: BTreeMap<String, String> = old
+          .iter()
+          .map(|entry| {
+            let (key, value) = parse_env_entry(entry);
+            (key.to_owned(), value.to_owned())
+          })
+          .collect();
+        let mut diff = diff_env(&original, new);
+        base.secrets.add_changes(&target.secrets, &mut diff);
+        EnvView::new("the base".to_string(), &original, &diff)
+      }
+      (Err(e), _) | (_, Err(e)) => EnvView::error(e.to_string()),
+    };
+    Self {
+      lines,
+      env,
+      show_unchanged_env: false,
+      scroll: Default::default(),
+    }
+  }
+
+  fn summary(exec: &ExecEvent) -> Line<'static> {
+    Line::from(vec![
+      exec.pid.to_string().set_style(THEME.pid_in_msg),
+      ": ".into(),
+      match exec.filename.as_ref() {
+        Ok(filename) => filename.display().to_string().set_style(THEME.filename),
+        Err(e) => format!("[failed to read filename: {e}]").set_style(THEME.inline_tracer_error),
+      },
+    ])
+  }
+
+  pub fn handle_key_event(&mut self, ke: KeyEvent) -> ControlFlow<()> {
+    match (ke.code, ke.modifiers) {
+      (KeyCode::Char('q') | KeyCode::Esc, KeyModifiers::NONE) => return ControlFlow::Break(()),
+      (KeyCode::Down | KeyCode::Char('j'), KeyModifiers::NONE) => self.scroll.scroll_down(),
+      (KeyCode::Up | KeyCode::Char('k'), KeyModifiers::NONE) => self.scroll.scroll_up(),
+      (KeyCode::Down | KeyCode::Char('j'), KeyModifiers::CONTROL) | (KeyCode::PageDown, _) => {
+        self.scroll.scroll_page_down()
+      }
+      (KeyCode::Up | KeyCode::Char('k'), KeyModifiers::CONTROL) | (KeyCode::PageUp, _) => {
+        self.scroll.scroll_page_up()
+      }
+      (KeyCode::Home, _) => self.scroll.scroll_to_top(),
+      (KeyCode::End, _) => self.scroll.scroll_to_bottom(),
+      (KeyCode::Char('u'), KeyModifiers::NONE) => {
+        self.show_unchanged_env = !self.show_unchanged_env;
+      }
+      _ => {}
+    }
+    ControlFlow::Continue(())
+  }
+}
+
+/// The lines of a diff of the items
+fn diff_lines(old: &[String], new: &[String]) -> Vec<Line<'static>> {
+  let old: Vec<&str> = old.iter().map(String::as_str).collect();
+  let new: Vec<&str> = new.iter().map(String::as_str).collect();
+  TextDiff::from_slices(&old, &new)
+    .iter_all_changes()
+    .map(|change| {
+      let value = change.value().to_string();
+      Line::from(match change.tag() {
+        ChangeTag::Equal => vec![" ".into(), value.set_style(THEME.unchanged_env_val)],
+        ChangeTag::Delete => vec![
+          "-".set_style(THEME.minus_sign),
+          value.set_style(THEME.removed_env_val),
+        ],
+        ChangeTag::Insert => vec![
+          "+".set_style(THEME.plus_sign),
+          value.set_style(THEME.added_env_val),
+        ],
+      })
+    })
+    .collect()
+}
+
+impl StatefulWidgetRef for DiffPopup {
+  fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut DiffPopupState) {
+    Clear.render(area, buf);
+    let block = Block::new()
+      .title(" Diff ")
+      .borders(Borders::TOP | Borders::BOTTOM)
+      .title_alignment(Center);
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    let env = if state.show_unchanged_env {
+      &state.env.lines[..]
+    } else {
+      &state.env.lines[..state.env.nr_changed]
+    };
+    let lines: Vec<Line> = chain!(
+      state.lines.iter().cloned(),
+      [" Environment ".set_style(THEME.label).into()],
+      (state.env.nr_changed == 0).then(|| "No changes".set_style(THEME.unchanged_env_val).into()),
+      env.iter().cloned()
+    )
+    .collect();
+    let width = area.width.saturating_sub(1);
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
+    let size = Size {
+      width,
+      height: paragraph.line_count(width).try_into().unwrap_or(u16::MAX),
+    };
+    let mut scrollview = ScrollView::new(size);
+    scrollview.render_widget(
+      paragraph,
+      Rect {
+        x: 0,
+        y: 0,
+        width: size.width,
+        height: size.height,
+      },
+    );
+    scrollview.render(inner, buf, &mut state.scroll);
+  }
+
+  type State = DiffPopupState;
+}
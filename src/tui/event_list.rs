@@ -16,30 +16,240 @@
 // OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
 // CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
-use std::{collections::VecDeque, sync::Arc};
+use std::{
+  cmp::Ordering,
+  collections::{HashMap, HashSet, VecDeque},
+  sync::Arc,
+  time::{Duration, Instant, SystemTime},
+};
 
+use chrono::{DateTime, Local};
 use ratatui::{
   layout::Alignment::Right,
   prelude::{Buffer, Rect},
-  style::{Color, Modifier, Style},
-  text::Line,
+  style::Styled,
+  text::{Line, Span, Text},
   widgets::{
     block::Title, HighlightSpacing, List, ListItem, ListState, Scrollbar, ScrollbarOrientation,
     ScrollbarState, StatefulWidget, StatefulWidgetRef, Widget,
   },
 };
 
-use crate::{cli::args::ModifierArgs, event::TracerEvent, proc::BaselineInfo};
+use nix::unistd::{Pid, Uid, User};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{
+  action::{GoToTarget, SupportedShell},
+  cli::args::ModifierArgs,
+  event::{ExecEvent, ProcessExitStatus, TracerEvent},
+  filter::FilterExpr,
+  proc::BaselineInfo,
+  term::symbols,
+};
+
+use super::{partial_line::PartialLine, search::highlight_matches, theme::THEME};
+
+/// The indentation of the events under the header of their group
+const GROUP_INDENT: &str = "  ";
+
+/// The filter of the event list
+#[derive(Debug, Clone)]
+pub enum ListFilter {
+  /// Matches the lines of the events as they are shown
+  Regex(Regex),
+  /// Matches the fields of the events, see [`FilterExpr`]
+  Expr(FilterExpr),
+}
+
+impl ListFilter {
+  pub fn as_str(&self) -> &str {
+    match self {
+      Self::Regex(regex) => regex.as_str(),
+      Self::Expr(expr) => expr.as_str(),
+    }
+  }
+}
+
+/// The columns shown before the line of each event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventColumn {
+  /// Whether the process of an exec event is still running, or how it exited
+  Status,
+  Pid,
+  Ppid,
+  Timestamp,
+  /// How long after the latest exec of its parent the process executed
+  Latency,
+  /// How long the program ran, until the process executed another program or exited
+  Duration,
+  Cwd,
+  User,
+  Comm,
+  Argv,
+}
+
+impl EventColumn {
+  pub const ALL: [Self; 10] = [
+    Self::Status,
+    Self::Pid,
+    Self::Ppid,
+    Self::Timestamp,
+    Self::Latency,
+    Self::Duration,
+    Self::Cwd,
+    Self::User,
+    Self::Comm,
+    Self::Argv,
+  ];
+
+  pub fn default_columns() -> Vec<Self> {
+    vec![Self::Status]
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::Status => "Status",
+      Self::Pid => "PID",
+      Self::Ppid => "Parent PID",
+      Self::Timestamp => "Timestamp",
+      Self::Latency => "Spawn Latency",
+      Self::Duration => "Duration",
+      Self::Cwd => "Working Directory",
+      Self::User => "User",
+      Self::Comm => "Comm",
+      Self::Argv => "Arguments",
+    }
+  }
+
+  /// The columns are as wide as their widest cell in the window, up to this width
+  fn max_width(self) -> usize {
+    match self {
+      Self::Status => 6,
+      Self::Pid | Self::Ppid => 7,
+      Self::Timestamp => 12,
+      Self::Latency | Self::Duration => 10,
+      Self::Cwd => 30,
+      Self::User => 12,
+      Self::Comm => 15,
+      Self::Argv => 40,
+    }
+  }
+
+  /// Pad or truncate the cell to the width of the column, in terminal columns
+  fn fit(self, cell: Span<'static>, width: usize) -> Span<'static> {
+    let len = cell.width();
+    let ellipsis = symbols().ellipsis;
+    // Takes the characters that fit in the width, wide characters can leave a column for padding
+    let take = |chars: &mut dyn Iterator<Item = char>| {
+      let mut taken = 0;
+      chars
+        .take_while(|c| {
+          taken += c.width().unwrap_or(0);
+          taken < width
+        })
+        .collect::<String>()
+    };
+    let content = if len > width {
+      match self {
+        // The end of a path is more interesting than its start
+        Self::Cwd => format!(
+          "{ellipsis}{}",
+          take(&mut cell.content.chars().rev())
+            .chars()
+            .rev()
+            .collect::<String>()
+        ),
+        _ => format!("{}{ellipsis}", take(&mut cell.content.chars())),
+      }
+    } else {
+      cell.content.to_string()
+    };
+    // The padding of format! counts characters instead of columns
+    let padding = " ".repeat(width.saturating_sub(content.width()));
+    let content = match self {
+      Self::Status | Self::Pid | Self::Ppid | Self::Latency | Self::Duration => {
+        format!("{padding}{content}")
+      }
+      _ => format!("{content}{padding}"),
+    };
+    Span::styled(content, cell.style)
+  }
+}
+
+/// What the event list can be sorted by, instead of the order the events arrived in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+  Timestamp,
+  Pid,
+  Comm,
+  /// How long the program of an exec ran
+  Duration,
+}
+
+impl SortKey {
+  pub const ALL: [Self; 4] = [Self::Timestamp, Self::Pid, Self::Comm, Self::Duration];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::Timestamp => "Timestamp",
+      Self::Pid => "PID",
+      Self::Comm => "Comm",
+      Self::Duration => "Duration",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventSort {
+  pub key: SortKey,
+  pub descending: bool,
+}
+
+impl std::fmt::Display for EventSort {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let arrow = if self.descending { "↓" } else { "↑" };
+    write!(f, "{} {arrow}", self.key.name())
+  }
+}
+
+/// Compare the keys, the events without the key go last in both directions
+fn compare_keys<T: Ord>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+  match (a, b) {
+    (Some(a), Some(b)) if descending => b.cmp(&a),
+    (Some(a), Some(b)) => a.cmp(&b),
+    (a, b) => a.is_none().cmp(&b.is_none()),
+  }
+}
 
-use super::partial_line::PartialLine;
+fn format_timestamp(timestamp: SystemTime) -> Span<'static> {
+  DateTime::<Local>::from(timestamp)
+    .format("%H:%M:%S%.3f")
+    .to_string()
+    .set_style(THEME.timestamp)
+}
+
+fn user_name(uid: Uid) -> String {
+  User::from_uid(uid)
+    .ok()
+    .flatten()
+    .map(|user| user.name)
+    .unwrap_or_else(|| uid.to_string())
+}
 
 pub struct EventList {
   pub state: ListState,
+  /// The events that are shown, i.e. the events that match the filter
   pub events: Vec<Arc<TracerEvent>>,
+  /// All the events, regardless of the filter
+  all_events: Vec<Arc<TracerEvent>>,
   /// Current window of the event list, [start, end)
   window: (usize, usize),
-  /// Cache of the lines in the window
-  lines_cache: VecDeque<Line<'static>>,
+  /// Cache of the lines in the window and their widths, it can lag behind the end of the window,
+  /// the missing lines are only created when the list is rendered
+  lines_cache: VecDeque<(Line<'static>, usize)>,
   should_refresh_lines_cache: bool,
   /// Cache of the list items in the view
   list_cache: List<'static>,
@@ -54,15 +264,67 @@ pub struct EventList {
   pub max_window_len: usize,
   pub baseline: Arc<BaselineInfo>,
   pub follow: bool,
+  /// The process whose latest event is kept selected, like follow mode but for a single process
+  pub pinned_pid: Option<Pid>,
   pub modifier_args: ModifierArgs,
   pub env_in_cmdline: bool,
+  /// The active search, whose matches are highlighted
+  pub search: Option<Regex>,
+  /// The active filter, only the matching events are shown
+  pub filter: Option<ListFilter>,
+  /// The bookmarked events, in the order they are bookmarked
+  pub bookmarks: Vec<Arc<TracerEvent>>,
+  /// How the processes that have exited exited, the others are still running.
+  /// The processes are keyed by their pids and start times, as pids are reused.
+  exit_status: HashMap<(Pid, u64), ProcessExitStatus>,
+  /// When the processes that have exited exited
+  exit_times: HashMap<(Pid, u64), SystemTime>,
+  /// The times of the successful execs of each process, in order
+  exec_times: HashMap<(Pid, u64), Vec<SystemTime>>,
+  /// The parents of the processes and when the processes were created
+  spawns: HashMap<(Pid, u64), ((Pid, u64), SystemTime)>,
+  /// The number of failed execs and processes that exited with a nonzero status
+  pub nr_failures: usize,
+  /// The columns shown before the lines, in order
+  columns: Vec<EventColumn>,
+  /// Total width of the columns in the current window, including the separators
+  columns_width: u16,
+  /// Cache of the names of the users in the user column
+  user_names: HashMap<Uid, String>,
+  /// Wrap long lines instead of scrolling horizontally
+  pub wrap: bool,
+  /// How many rows each item in the window takes
+  item_heights: Vec<usize>,
+  /// Nest the consecutive events of a process under a header row with its pid and comm
+  pub group: bool,
+  /// The first events of the collapsed groups, the other events of these groups are hidden
+  collapsed_groups: Vec<Arc<TracerEvent>>,
+  /// The processes whose descendants are collapsed, with the descendants whose events are hidden
+  collapsed_subtrees: HashMap<Pid, HashSet<Pid>>,
+  /// How the shown events are sorted, they are in the order they arrived in if it is `None`
+  pub sort: Option<EventSort>,
+  /// Whether the durations the events are sorted by changed since they were sorted
+  should_resort: bool,
+  /// When the durations of the running programs were last updated
+  durations_updated_at: Instant,
+  /// The number of events that are kept, the oldest ones are dropped beyond it
+  pub history: usize,
+  /// The number of the oldest events that are dropped because of the history limit
+  nr_forgotten: usize,
 }
 
 impl EventList {
-  pub fn new(baseline: BaselineInfo, follow: bool, modifier_args: ModifierArgs) -> Self {
+  pub fn new(
+    baseline: BaselineInfo,
+    follow: bool,
+    modifier_args: ModifierArgs,
+    columns: Vec<EventColumn>,
+    history: usize,
+  ) -> Self {
     Self {
       state: ListState::default(),
       events: vec![],
+      all_events: vec![],
       window: (0, 0),
       nr_items_in_window: 0,
       horizontal_offset: 0,
@@ -71,26 +333,375 @@ impl EventList {
       max_window_len: 0,
       baseline: Arc::new(baseline),
       follow,
+      pinned_pid: None,
       lines_cache: VecDeque::new(),
       should_refresh_lines_cache: true,
       should_refresh_list_cache: true,
       list_cache: List::default(),
       modifier_args,
       env_in_cmdline: true,
+      search: None,
+      filter: None,
+      bookmarks: vec![],
+      exit_status: HashMap::new(),
+      exit_times: HashMap::new(),
+      exec_times: HashMap::new(),
+      spawns: HashMap::new(),
+      nr_failures: 0,
+      columns,
+      columns_width: 0,
+      user_names: HashMap::new(),
+      wrap: false,
+      item_heights: vec![],
+      group: false,
+      collapsed_groups: vec![],
+      collapsed_subtrees: HashMap::new(),
+      sort: None,
+      should_resort: false,
+      durations_updated_at: Instant::now(),
+      history,
+      nr_forgotten: 0,
     }
   }
 
+  pub fn toggle_wrap(&mut self) {
+    self.wrap = !self.wrap;
+    self.horizontal_offset = 0;
+    self.should_refresh_list_cache = true;
+  }
+
+  pub fn columns(&self) -> &[EventColumn] {
+    &self.columns
+  }
+
+  pub fn set_columns(&mut self, columns: Vec<EventColumn>) {
+    self.columns = columns;
+    self.should_refresh_list_cache = true;
+  }
+
   pub fn toggle_follow(&mut self) {
     self.follow = !self.follow;
+    if self.follow {
+      self.pinned_pid = None;
+    }
   }
 
+  /// Stop following the bottom or the pinned process, e.g. when the user scrolls
   pub fn stop_follow(&mut self) {
     self.follow = false;
+    self.pinned_pid = None;
+  }
+
+  /// Pin the process, selecting its latest event and every new event of it
+  pub fn pin(&mut self, pid: Pid) {
+    self.follow = false;
+    self.pinned_pid = Some(pid);
+    self.select_latest_of_pinned();
+  }
+
+  fn select_latest_of_pinned(&mut self) {
+    if let Some(pid) = self.pinned_pid {
+      self.select_latest_of(pid);
+    }
+  }
+
+  /// Select the latest event of the process, if it is shown
+  pub fn select_latest_of(&mut self, pid: Pid) {
+    if let Some(index) = self
+      .events
+      .iter()
+      .rposition(|event| event.pid() == Some(pid))
+    {
+      self.select_absolute(index);
+    }
+  }
+
+  /// Select the event the go-to prompt points to, or explain why it can't be selected
+  pub fn go_to(&mut self, target: GoToTarget) -> Result<(), String> {
+    let index = match target {
+      GoToTarget::Event(number) => {
+        let event = self
+          .all_events
+          .get(number - 1)
+          .ok_or_else(|| format!("There are only {} events", self.all_events.len()))?;
+        self
+          .events
+          .iter()
+          .position(|other| Arc::ptr_eq(other, event))
+          .ok_or_else(|| format!("Event #{number} is hidden"))?
+      }
+      GoToTarget::FirstOf(pid) => self
+        .events
+        .iter()
+        .position(|event| event.pid() == Some(pid))
+        .ok_or_else(|| format!("No event of {pid} is shown"))?,
+      GoToTarget::LastOf(pid) => self
+        .events
+        .iter()
+        .rposition(|event| event.pid() == Some(pid))
+        .ok_or_else(|| format!("No event of {pid} is shown"))?,
+    };
+    self.select_absolute(index);
+    Ok(())
   }
 
   pub fn toggle_env_display(&mut self) {
     self.env_in_cmdline = !self.env_in_cmdline;
     self.should_refresh_lines_cache = true;
+    // The filter matches against the lines, which have just changed
+    if self.filter.is_some() {
+      self.set_filter(self.filter.clone());
+    }
+  }
+
+  /// Returns the exec event whose environment the exec event inherits, i.e. the previous exec
+  /// of the same process or, if there is none, the last exec of its parent before it.
+  pub fn parent_exec(&self, event: &Arc<TracerEvent>) -> Option<Arc<TracerEvent>> {
+    let TracerEvent::Exec(exec) = event.as_ref() else {
+      return None;
+    };
+    let index = self
+      .all_events
+      .iter()
+      .rposition(|other| Arc::ptr_eq(other, event))?;
+    let previous_exec_of = |pid| {
+      self.all_events[..index]
+        .iter()
+        .rev()
+        .find(|other| matches!(other.as_ref(), TracerEvent::Exec(other) if other.pid == pid))
+        .cloned()
+    };
+    previous_exec_of(exec.pid).or_else(|| exec.ppid.and_then(previous_exec_of))
+  }
+
+  pub fn push(&mut self, event: Arc<TracerEvent>) {
+    if let TracerEvent::Exec(exec) = event.as_ref() {
+      if exec.result != 0 {
+        self.nr_failures += 1;
+      } else {
+        let sorted_by_duration = self.is_sorted_by_duration();
+        let exec_times = self
+          .exec_times
+          .entry((exec.pid, exec.start_time))
+          .or_default();
+        // The new exec ends the program of the previous one
+        if !exec_times.is_empty() && sorted_by_duration {
+          self.should_resort = true;
+        }
+        exec_times.push(exec.timestamp);
+      }
+    }
+    if self
+      .filter
+      .as_ref()
+      .map_or(true, |filter| self.filter_matches(filter, &event))
+      && !self.continues_collapsed_group(&event)
+      && !self.is_in_collapsed_subtree(&event)
+    {
+      let index = match self.sort {
+        Some(sort) => {
+          // The new event goes after the events that are equal to it
+          let index = self
+            .events
+            .partition_point(|other| self.compare_events(other, &event, sort) != Ordering::Greater);
+          self.events.insert(index, event.clone());
+          self.should_refresh_lines_cache = true;
+          index
+        }
+        None => {
+          self.events.push(event.clone());
+          self.events.len() - 1
+        }
+      };
+      if self.pinned_pid.is_some() && event.pid() == self.pinned_pid {
+        self.select_absolute(index);
+      }
+    }
+    self.all_events.push(event);
+    self.forget_oldest_events();
+  }
+
+  /// Drop the oldest events beyond the history limit. A tenth of the history is dropped at once,
+  /// so that the shown events are rarely rebuilt.
+  fn forget_oldest_events(&mut self) {
+    if self.all_events.len() <= self.history {
+      return;
+    }
+    let nr_forgotten = self.all_events.len() - self.history + self.history / 10;
+    let forgotten: HashSet<*const TracerEvent> = self
+      .all_events
+      .drain(..nr_forgotten)
+      .map(|event| Arc::as_ptr(&event))
+      .collect();
+    self.nr_forgotten += forgotten.len();
+    let is_kept = |event: &Arc<TracerEvent>| !forgotten.contains(&Arc::as_ptr(event));
+    self.bookmarks.retain(is_kept);
+    self.collapsed_groups.retain(is_kept);
+    if self.selection().is_some_and(|selected| !is_kept(&selected)) {
+      self.state.select(None);
+    }
+    self.refresh_events();
+  }
+
+  /// All the events, regardless of the filter
+  pub fn all_events(&self) -> &[Arc<TracerEvent>] {
+    &self.all_events
+  }
+
+  pub fn set_exit_status(
+    &mut self,
+    pid: Pid,
+    start_time: u64,
+    status: ProcessExitStatus,
+    timestamp: SystemTime,
+  ) {
+    if !status.is_success() {
+      self.nr_failures += 1;
+    }
+    self.exit_status.insert((pid, start_time), status);
+    self.exit_times.insert((pid, start_time), timestamp);
+    self.should_refresh_list_cache = true;
+    if self.is_sorted_by_duration() {
+      self.should_resort = true;
+    }
+  }
+
+  /// Whether the event is a successful exec of a process that is still running
+  fn is_running(&self, event: &TracerEvent) -> bool {
+    matches!(event, TracerEvent::Exec(exec) if exec.result == 0 && !self.exit_status.contains_key(&(exec.pid, exec.start_time)))
+  }
+
+  /// The status cell of the row, which shows whether the process of an exec event is still running
+  fn status_span(&self, event: &TracerEvent) -> Span<'static> {
+    let TracerEvent::Exec(exec) = event else {
+      return Span::default();
+    };
+    if exec.result != 0 {
+      // The process keeps running its old program
+      return Span::default();
+    }
+    match self.exit_status.get(&(exec.pid, exec.start_time)) {
+      None => symbols().running.set_style(THEME.status_running),
+      Some(ProcessExitStatus::Exited(code)) => code.to_string().set_style(if *code == 0 {
+        THEME.pid_success
+      } else {
+        THEME.pid_failure
+      }),
+      Some(ProcessExitStatus::Signaled(signal)) => signal
+        .as_str()
+        .trim_start_matches("SIG")
+        .to_string()
+        .set_style(THEME.pid_failure),
+    }
+  }
+
+  /// How long after the latest exec of its parent the process of the exec was created
+  fn spawn_latency(&self, exec: &ExecEvent) -> Option<Duration> {
+    let (parent, spawned_at) = self.spawns.get(&(exec.pid, exec.start_time))?;
+    let parent_execs = self.exec_times.get(parent)?;
+    let i = parent_execs.partition_point(|time| time <= spawned_at);
+    let parent_exec = parent_execs.get(i.checked_sub(1)?)?;
+    spawned_at.duration_since(*parent_exec).ok()
+  }
+
+  /// How long the program of a successful exec ran, it is still running if there is no end
+  fn run_time(&self, exec: &ExecEvent) -> Option<(Duration, bool)> {
+    if exec.result != 0 {
+      return None;
+    }
+    let next_exec = self
+      .exec_times
+      .get(&(exec.pid, exec.start_time))
+      .and_then(|execs| {
+        let i = execs.partition_point(|time| *time <= exec.timestamp);
+        execs.get(i).copied()
+      });
+    let end = next_exec.or_else(|| self.exit_times.get(&(exec.pid, exec.start_time)).copied());
+    let duration = end
+      .unwrap_or_else(SystemTime::now)
+      .duration_since(exec.timestamp)
+      .ok()?;
+    Some((duration, end.is_none()))
+  }
+
+  /// The cell of the event in the column, before it is fitted to the width of the column
+  fn cell(&self, column: EventColumn, event: &TracerEvent) -> Span<'static> {
+    match (column, event) {
+      (EventColumn::Status, _) => self.status_span(event),
+      (EventColumn::Pid, TracerEvent::Exec(exec)) => self
+        .modifier_args
+        .pid_display
+        .unwrap_or_default()
+        .format(exec.pid, exec.ns_pid)
+        .set_style(THEME.pid_in_msg),
+      (
+        EventColumn::Pid,
+        TracerEvent::NewChild { pid, .. }
+        | TracerEvent::ProcessExit { pid, .. }
+        | TracerEvent::Signal { pid, .. }
+        | TracerEvent::TraceeSpawn(pid),
+      ) => pid.to_string().set_style(THEME.pid_in_msg),
+      (
+        EventColumn::Pid,
+        TracerEvent::Info(msg) | TracerEvent::Warning(msg) | TracerEvent::Error(msg),
+      ) => msg
+        .pid
+        .map(|pid| pid.to_string().set_style(THEME.pid_in_msg))
+        .unwrap_or_default(),
+      (EventColumn::Ppid, TracerEvent::Exec(exec)) => exec
+        .ppid
+        .map(|ppid| ppid.to_string().set_style(THEME.pid_in_msg))
+        .unwrap_or_default(),
+      (EventColumn::Ppid, TracerEvent::NewChild { ppid, .. }) => {
+        ppid.to_string().set_style(THEME.pid_in_msg)
+      }
+      (EventColumn::Timestamp, TracerEvent::Exec(exec)) => format_timestamp(exec.timestamp),
+      (
+        EventColumn::Timestamp,
+        TracerEvent::NewChild { timestamp, .. }
+        | TracerEvent::ProcessExit { timestamp, .. }
+        | TracerEvent::Signal { timestamp, .. },
+      ) => format_timestamp(*timestamp),
+      (EventColumn::Latency, TracerEvent::Exec(exec)) => self
+        .spawn_latency(exec)
+        .map(|latency| format!("+{:.3}s", latency.as_secs_f64()).set_style(THEME.timestamp))
+        .unwrap_or_default(),
+      (EventColumn::Duration, TracerEvent::Exec(exec)) => self
+        .run_time(exec)
+        .map(|(duration, running)| {
+          format!("{:.3}s", duration.as_secs_f64()).set_style(if running {
+            THEME.status_running
+          } else {
+            THEME.timestamp
+          })
+        })
+        .unwrap_or_default(),
+      (EventColumn::Cwd, TracerEvent::Exec(exec)) => {
+        exec.cwd.to_string_lossy().into_owned().set_style(THEME.cwd)
+      }
+      (EventColumn::User, TracerEvent::Exec(exec)) => exec
+        .uid
+        .and_then(|uid| self.user_names.get(&uid))
+        .map(|name| name.clone().set_style(THEME.user))
+        .unwrap_or_default(),
+      (EventColumn::Comm, TracerEvent::Exec(exec)) => exec.comm.clone().set_style(THEME.comm),
+      (
+        EventColumn::Comm,
+        TracerEvent::NewChild { pcomm: comm, .. }
+        | TracerEvent::ProcessExit { comm, .. }
+        | TracerEvent::Signal { comm, .. },
+      ) => comm.clone().set_style(THEME.comm),
+      (EventColumn::Argv, TracerEvent::Exec(exec)) => match exec.argv.as_deref() {
+        Ok(argv) => argv
+          .iter()
+          .map(|arg| SupportedShell::Bash.quote(arg))
+          .collect::<Vec<_>>()
+          .join(" ")
+          .set_style(THEME.argv),
+        Err(_) => "[failed to read argv]".set_style(THEME.inline_tracer_error),
+      },
+      _ => Span::default(),
+    }
   }
 
   /// returns the index of the selected item if there is any
@@ -120,13 +731,34 @@ impl EventList {
 
   pub fn statistics(&self) -> Title {
     let id = self.selection_index().unwrap_or(0);
-    Title::default()
-      .content(format!(
-        "{}/{}──",
-        (id + 1).min(self.events.len()),
-        self.events.len()
-      ))
-      .alignment(Right)
+    let position = format!("{}/{}", (id + 1).min(self.events.len()), self.events.len());
+    let mut spans = vec![];
+    if self.nr_failures > 0 {
+      spans.extend([
+        format!("{} failed", self.nr_failures).set_style(THEME.pid_failure),
+        "──".into(),
+      ]);
+    }
+    if self.nr_forgotten > 0 {
+      spans.extend([
+        format!("{} oldest forgotten", self.nr_forgotten).set_style(THEME.tracer_warning),
+        "──".into(),
+      ]);
+    }
+    // The events can also be hidden by collapsing groups or subtrees
+    spans.push(
+      if self.filter.is_some() || self.events.len() < self.all_events.len() {
+        format!(
+          "{position} (showing {}/{})──",
+          self.events.len(),
+          self.all_events.len()
+        )
+      } else {
+        format!("{position}──")
+      }
+      .into(),
+    );
+    Title::default().content(Line::from(spans)).alignment(Right)
   }
 }
 
@@ -135,8 +767,11 @@ impl Widget for &mut EventList {
   where
     Self: Sized,
   {
-    self.inner_width = area.width - 1; // 1 for the selection indicator
-    let mut max_len = area.width as usize - 1;
+    if self.should_resort {
+      self.resort();
+    }
+    // 1 for the selection indicator
+    self.inner_width = area.width.saturating_sub(1 + self.columns_width);
     // Iterate through all elements in the `items` and stylize them.
     let events_in_window = EventList::window(&self.events, self.window);
     // tracing::debug!(
@@ -147,58 +782,154 @@ impl Widget for &mut EventList {
       self.should_refresh_lines_cache = false;
       self.should_refresh_list_cache = true;
       // Initialize the line cache, which will be kept in sync by the navigation methods
-      self.lines_cache = events_in_window
+      let lines = events_in_window
         .iter()
-        .map(|evt| {
-          evt.to_tui_line(
-            &self.baseline,
-            false,
-            &self.modifier_args,
-            self.env_in_cmdline,
-          )
-        })
+        .map(|evt| self.cached_line(evt))
         .collect();
+      self.lines_cache = lines;
     }
     self.nr_items_in_window = events_in_window.len();
     if self.nr_items_in_window > self.lines_cache.len() {
       // Push the new items to the cache
       self.should_refresh_list_cache = true;
-      for evt in events_in_window.iter().skip(self.lines_cache.len()) {
-        tracing::debug!("Pushing new item to line cache");
-        self.lines_cache.push_back(evt.to_tui_line(
-          &self.baseline,
-          false,
-          &self.modifier_args,
-          self.env_in_cmdline,
-        ));
-      }
+      let lines: Vec<_> = events_in_window
+        .iter()
+        .skip(self.lines_cache.len())
+        .map(|evt| self.cached_line(evt))
+        .collect();
+      self.lines_cache.extend(lines);
     }
     // tracing::debug!(
     //   "Should refresh list cache: {}",
     //   self.should_refresh_list_cache
     // );
+    // The statuses are refreshed by the exit events, but the durations of the running programs
+    // keep growing, which is shown often enough if they are updated every second.
+    if self.columns.contains(&EventColumn::Duration)
+      && self.durations_updated_at.elapsed() >= Duration::from_secs(1)
+      && events_in_window.iter().any(|evt| self.is_running(evt))
+    {
+      self.durations_updated_at = Instant::now();
+      self.should_refresh_list_cache = true;
+    }
     if self.should_refresh_list_cache {
       self.should_refresh_list_cache = false;
-      let items = self.lines_cache.iter().map(|full_line| {
-        max_len = max_len.max(full_line.width());
-        ListItem::from(
-          full_line
-            .clone()
-            .substring(self.horizontal_offset, area.width),
-        )
-      });
+      if self.columns.contains(&EventColumn::User) {
+        for evt in events_in_window.iter() {
+          if let TracerEvent::Exec(exec) = evt.as_ref() {
+            if let Some(uid) = exec.uid {
+              self.user_names.entry(uid).or_insert_with(|| user_name(uid));
+            }
+          }
+        }
+      }
+      let cells: Vec<Vec<Span>> = events_in_window
+        .iter()
+        .map(|evt| {
+          self
+            .columns
+            .iter()
+            .map(|column| self.cell(*column, evt))
+            .collect()
+        })
+        .collect();
+      // The columns adapt to the cells in the window, empty columns take no space
+      let widths: Vec<usize> = self
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+          cells
+            .iter()
+            .map(|row| row[i].width())
+            .max()
+            .unwrap_or_default()
+            .min(column.max_width())
+        })
+        .collect();
+      self.columns_width = widths
+        .iter()
+        .filter(|width| **width > 0)
+        .map(|width| width + 1)
+        .sum::<usize>() as u16
+        + if self.group {
+          GROUP_INDENT.len() as u16
+        } else {
+          0
+        };
+      self.inner_width = area.width.saturating_sub(1 + self.columns_width);
+      // The max width has to be known before the lines are cut at the horizontal offset,
+      // which is kept in range when the longest lines scroll out of the window
+      self.max_width = if self.wrap {
+        self.inner_width as usize
+      } else {
+        self
+          .lines_cache
+          .iter()
+          .map(|(_, width)| *width)
+          .max()
+          .unwrap_or_default()
+          .max(self.inner_width as usize)
+      };
+      self.horizontal_offset = self
+        .horizontal_offset
+        .min(self.max_width - self.inner_width as usize);
+      let group_starts: Vec<bool> = (self.window.0..self.window.0 + events_in_window.len())
+        .map(|i| self.group && self.is_group_start(i))
+        .collect();
+      let items = self
+        .lines_cache
+        .iter()
+        .zip(events_in_window.iter())
+        .zip(cells)
+        .zip(group_starts)
+        .map(|((((full_line, _), event), row), group_start)| {
+          let line = match self.search.as_ref() {
+            Some(regex) => highlight_matches(full_line.clone(), regex),
+            None => full_line.clone(),
+          };
+          let cells = self
+            .columns
+            .iter()
+            .zip(row)
+            .zip(widths.iter())
+            .filter(|(_, width)| **width > 0)
+            .flat_map(|((column, cell), width)| [column.fit(cell, *width), " ".into()]);
+          let mut lines = if self.wrap {
+            let mut lines = line.wrap(self.inner_width);
+            lines[0].spans.splice(0..0, cells);
+            // Align the continuation lines with the start of the line
+            let indent = " ".repeat(self.columns_width as usize);
+            for line in lines.iter_mut().skip(1) {
+              line.spans.insert(0, indent.clone().into());
+            }
+            lines
+          } else {
+            let mut line = line.substring(self.horizontal_offset, self.inner_width);
+            line.spans.splice(0..0, cells);
+            vec![line]
+          };
+          if self.group {
+            // The continuation lines of wrapped lines are already indented with the columns
+            lines[0].spans.insert(0, GROUP_INDENT.into());
+            if group_start {
+              lines.insert(0, self.group_header(event));
+            }
+          }
+          let item = ListItem::new(Text::from(lines));
+          if self.is_bookmarked(event) {
+            item.style(THEME.bookmark)
+          } else {
+            item
+          }
+        })
+        .collect::<Vec<_>>();
+      self.item_heights = items.iter().map(ListItem::height).collect();
       // Create a List from all list items and highlight the currently selected one
       let list = List::new(items)
-        .highlight_style(
-          Style::default()
-            .add_modifier(Modifier::BOLD)
-            .bg(Color::DarkGray),
-        )
+        .highlight_style(THEME.selection)
         .highlight_symbol(">")
         .highlight_spacing(HighlightSpacing::Always);
-      // FIXME: It's a little late to set the max width here. The max width is already used
-      //        Though this should only affect the first render.
-      self.max_width = max_len;
       self.list_cache = list;
     }
 
@@ -245,20 +976,6 @@ impl Widget for &mut EventList {
 
 /// Scrolling implementation for the EventList
 impl EventList {
-  /// Returns the index(absolute) of the last item in the window
-  fn last_item_in_window_absolute(&self) -> Option<usize> {
-    if self.events.is_empty() {
-      return None;
-    }
-    Some(
-      self
-        .window
-        .1
-        .saturating_sub(1)
-        .min(self.events.len().saturating_sub(1)),
-    )
-  }
-
   /// Returns the index(relative) of the last item in the window
   fn last_item_in_window_relative(&self) -> Option<usize> {
     if !self.events.is_empty() {
@@ -296,15 +1013,11 @@ impl EventList {
     if self.window.1 < self.events.len() {
       self.window.0 += 1;
       self.window.1 += 1;
+      // The list scrolls by itself when the wrapped items don't fit,
+      // keep showing the same items after they are shifted up in the window
+      *self.state.offset_mut() = self.state.offset().saturating_sub(1);
+      // The line of the new last item is created when it is rendered
       self.lines_cache.pop_front();
-      self.lines_cache.push_back(
-        self.events[self.last_item_in_window_absolute().unwrap()].to_tui_line(
-          &self.baseline,
-          false,
-          &self.modifier_args,
-          self.env_in_cmdline,
-        ),
-      );
       self.should_refresh_list_cache = true;
       true
     } else {
@@ -316,15 +1029,12 @@ impl EventList {
     if self.window.0 > 0 {
       self.window.0 -= 1;
       self.window.1 -= 1;
-      self.lines_cache.pop_back();
+      // The cache may lag behind the end of the window, so the last line may not be there
       self
         .lines_cache
-        .push_front(self.events[self.window.0].to_tui_line(
-          &self.baseline,
-          false,
-          &self.modifier_args,
-          self.env_in_cmdline,
-        ));
+        .truncate((self.window.1 - self.window.0).saturating_sub(1));
+      let line = self.cached_line(&self.events[self.window.0]);
+      self.lines_cache.push_front(line);
       self.should_refresh_list_cache = true;
       true
     } else {
@@ -478,26 +1188,21 @@ impl EventList {
     self.window.0 = self.events.len().saturating_sub(self.max_window_len);
     self.window.1 = self.window.0 + self.max_window_len;
     self.select_last();
-    if self.window.0.saturating_sub(old_window.0) == 1
-      && self.window.1.saturating_sub(old_window.1) == 1
-    {
-      // Special optimization for follow mode where scroll to bottom is called continuously
-      self.lines_cache.pop_front();
-      self.lines_cache.push_back(
-        self.events[self.last_item_in_window_absolute().unwrap()].to_tui_line(
-          &self.baseline,
-          false,
-          &self.modifier_args,
-          self.env_in_cmdline,
-        ),
-      );
-      self.should_refresh_list_cache = true;
-    } else {
-      self.should_refresh_lines_cache = old_window != self.window;
-      tracing::trace!(
-        "bottom: should_refresh_lines_cache = {}",
-        self.should_refresh_lines_cache
-      );
+    match self.window.0.checked_sub(old_window.0) {
+      Some(shift) if shift < self.max_window_len && !self.should_refresh_lines_cache => {
+        // Special optimization for follow mode where scroll to bottom is called continuously.
+        // Only the lines that scrolled out are dropped, the new lines are created when rendering,
+        // so the lines of the events that arrive and scroll out between two frames are never created.
+        self.lines_cache.drain(..shift.min(self.lines_cache.len()));
+        self.should_refresh_list_cache = true;
+      }
+      _ => {
+        self.should_refresh_lines_cache |= old_window != self.window;
+        tracing::trace!(
+          "bottom: should_refresh_lines_cache = {}",
+          self.should_refresh_lines_cache
+        );
+      }
     }
   }
 
@@ -523,4 +1228,500 @@ impl EventList {
       );
     }
   }
+
+  /// Select the item shown at the row of the list if there is one,
+  /// an item takes more than one row if its line is wrapped
+  pub fn select_row(&mut self, row: usize) {
+    let mut top = 0;
+    for (i, height) in self
+      .item_heights
+      .iter()
+      .enumerate()
+      .skip(self.state.offset())
+    {
+      if row < top + height {
+        self.state.select(Some(i));
+        return;
+      }
+      top += height;
+    }
+  }
+
+  /// Select the item at the absolute index, moving the window if it is out of view
+  pub fn select_absolute(&mut self, index: usize) {
+    if index < self.window.0 || index >= self.window.1 {
+      // Put the item in the middle of the window if possible
+      let start = index
+        .saturating_sub(self.max_window_len / 2)
+        .min(self.events.len().saturating_sub(self.max_window_len));
+      self.window = (start, start + self.max_window_len);
+      self.should_refresh_lines_cache = true;
+    }
+    self.state.select(Some(index - self.window.0));
+  }
+}
+
+/// Search implementation for the EventList
+impl EventList {
+  pub fn set_search(&mut self, search: Option<Regex>) {
+    self.search = search;
+    self.should_refresh_list_cache = true;
+  }
+
+  /// Select the next matching event after the selected one, wrapping around at the end.
+  pub fn next_match(&mut self) {
+    let len = self.events.len();
+    let current = self.selection_index();
+    self.select_match((1..=len).map(|offset| match current {
+      Some(current) => (current + offset) % len,
+      None => offset - 1,
+    }));
+  }
+
+  /// Select the previous matching event before the selected one, wrapping around at the start.
+  pub fn prev_match(&mut self) {
+    let len = self.events.len();
+    let current = self.selection_index().unwrap_or(0);
+    self.select_match((1..=len).map(|offset| (current + len - offset) % len));
+  }
+
+  fn select_match(&mut self, candidates: impl Iterator<Item = usize>) {
+    let Some(regex) = self.search.clone() else {
+      return;
+    };
+    self.select_first_where(candidates, |list, event| list.is_match(&regex, event));
+    self.reveal_match(&regex);
+  }
+
+  /// Scroll horizontally so that the first match in the selected event is in view
+  fn reveal_match(&mut self, regex: &Regex) {
+    if self.wrap {
+      return;
+    }
+    let Some(event) = self.selection() else {
+      return;
+    };
+    let text = self.cached_line(&event).0.to_string();
+    let Some(m) = regex.find(&text) else {
+      return;
+    };
+    let width = self.inner_width as usize;
+    if m.start() < self.horizontal_offset || m.end() > self.horizontal_offset + width {
+      // Leave some context before the match, the offset is kept in range when rendering
+      self.horizontal_offset = m.start().saturating_sub(width / 4);
+      self.should_refresh_list_cache = true;
+    }
+  }
+
+  /// Select the next exec event of the process after the selected one, wrapping around at the end.
+  pub fn next_exec_of(&mut self, pid: Pid) {
+    self.next_exec_of_any(&[pid]);
+  }
+
+  /// Select the next exec event of any of the processes after the selected one, wrapping around at the end.
+  pub fn next_exec_of_any(&mut self, pids: &[Pid]) {
+    let len = self.events.len();
+    let current = self.selection_index();
+    self.select_first_where(
+      (1..=len).map(|offset| match current {
+        Some(current) => (current + offset) % len,
+        None => offset - 1,
+      }),
+      |_, event| matches!(event, TracerEvent::Exec(exec) if pids.contains(&exec.pid)),
+    );
+  }
+
+  /// Select the previous exec event of the process before the selected one, wrapping around at the start.
+  pub fn prev_exec_of(&mut self, pid: Pid) {
+    let len = self.events.len();
+    let current = self.selection_index().unwrap_or(0);
+    self.select_first_where(
+      (1..=len).map(|offset| (current + len - offset) % len),
+      |_, event| matches!(event, TracerEvent::Exec(exec) if exec.pid == pid),
+    );
+  }
+
+  fn select_first_where(
+    &mut self,
+    mut candidates: impl Iterator<Item = usize>,
+    predicate: impl Fn(&Self, &TracerEvent) -> bool,
+  ) {
+    let found = candidates.find(|&i| predicate(self, &self.events[i]));
+    if let Some(index) = found {
+      self.select_absolute(index);
+    }
+  }
+
+  /// The line of the event for the line cache along with its width, which is costly to compute
+  fn cached_line(&self, event: &TracerEvent) -> (Line<'static>, usize) {
+    let line = event.to_tui_line(
+      &self.baseline,
+      false,
+      &self.modifier_args,
+      self.env_in_cmdline,
+    );
+    let width = line.width();
+    (line, width)
+  }
+
+  /// Whether the line of the event, as it is shown in the list, matches the regex
+  fn is_match(&self, regex: &Regex, event: &TracerEvent) -> bool {
+    let line = event.to_tui_line(
+      &self.baseline,
+      false,
+      &self.modifier_args,
+      self.env_in_cmdline,
+    );
+    regex.is_match(&line.to_string())
+  }
+
+  fn filter_matches(&self, filter: &ListFilter, event: &TracerEvent) -> bool {
+    match filter {
+      ListFilter::Regex(regex) => self.is_match(regex, event),
+      ListFilter::Expr(expr) => expr.matches(event),
+    }
+  }
+}
+
+/// Grouping implementation for the EventList
+impl EventList {
+  pub fn toggle_group(&mut self) {
+    self.group = !self.group;
+    self.refresh_events();
+  }
+
+  /// Collapse the group of the selected event, or expand it if it is collapsed.
+  /// The first event of the group is selected, as the others are hidden when it is collapsed.
+  pub fn toggle_collapse(&mut self) {
+    let Some(index) = self.selection_index() else {
+      return;
+    };
+    if !self.group {
+      return;
+    }
+    let start = self.group_start(index);
+    let head = self.events[start].clone();
+    if head.pid().is_none() {
+      return;
+    }
+    self.select_absolute(start);
+    match self
+      .collapsed_groups
+      .iter()
+      .position(|collapsed| Arc::ptr_eq(collapsed, &head))
+    {
+      Some(i) => {
+        self.collapsed_groups.remove(i);
+      }
+      None => self.collapsed_groups.push(head),
+    }
+    self.refresh_events();
+  }
+
+  fn is_collapsed(&self, event: &TracerEvent) -> bool {
+    self
+      .collapsed_groups
+      .iter()
+      .any(|collapsed| std::ptr::eq(collapsed.as_ref(), event))
+  }
+
+  /// Whether the shown event at the index starts a group, the events without a pid are not grouped
+  fn is_group_start(&self, index: usize) -> bool {
+    let pid = self.events[index].pid();
+    pid.is_some() && (index == 0 || self.events[index - 1].pid() != pid)
+  }
+
+  fn group_start(&self, mut index: usize) -> usize {
+    while index > 0 && !self.is_group_start(index) && self.events[index].pid().is_some() {
+      index -= 1;
+    }
+    index
+  }
+
+  /// Whether the new event belongs to the last group, which is collapsed
+  fn continues_collapsed_group(&self, event: &TracerEvent) -> bool {
+    // The other events of a collapsed group are hidden, so its first event is the last shown event
+    self.group
+      && event.pid().is_some()
+      && self
+        .events
+        .last()
+        .is_some_and(|last| last.pid() == event.pid() && self.is_collapsed(last))
+  }
+
+  fn group_header(&self, event: &TracerEvent) -> Line<'static> {
+    let marker = if self.is_collapsed(event) {
+      "▸ "
+    } else {
+      "▾ "
+    };
+    let comm = comm_of(event).unwrap_or_default();
+    Line::from(vec![
+      marker.into(),
+      event
+        .pid()
+        .map(|pid| pid.to_string())
+        .unwrap_or_default()
+        .set_style(THEME.pid_in_msg),
+      " ".into(),
+      comm.to_string().set_style(THEME.comm),
+    ])
+    .patch_style(THEME.group_header)
+  }
+}
+
+/// Subtree implementation for the EventList
+impl EventList {
+  /// Hide the events of the descendants of the process, or show them if they are hidden
+  pub fn toggle_subtree(&mut self, pid: Pid, descendants: HashSet<Pid>) {
+    if self.collapsed_subtrees.remove(&pid).is_none() {
+      self.collapsed_subtrees.insert(pid, descendants);
+    }
+    self.refresh_events();
+  }
+
+  pub fn is_subtree_collapsed(&self, pid: Pid) -> bool {
+    self.collapsed_subtrees.contains_key(&pid)
+  }
+
+  /// Remember when the process was created by its parent, for the spawn latency
+  pub fn on_spawn(&mut self, parent: (Pid, u64), child: (Pid, u64), timestamp: SystemTime) {
+    self.spawns.insert(child, (parent, timestamp));
+  }
+
+  /// Keep track of the descendants of the collapsed processes as new children are created
+  pub fn on_new_child(&mut self, ppid: Pid, pid: Pid) {
+    for (root, descendants) in self.collapsed_subtrees.iter_mut() {
+      if *root == ppid || descendants.contains(&ppid) {
+        descendants.insert(pid);
+      }
+    }
+  }
+
+  fn is_in_collapsed_subtree(&self, event: &TracerEvent) -> bool {
+    event.pid().is_some_and(|pid| {
+      self
+        .collapsed_subtrees
+        .values()
+        .any(|descendants| descendants.contains(&pid))
+    })
+  }
+}
+
+/// Failure navigation implementation for the EventList
+impl EventList {
+  /// Whether the exec failed, or the process exited with a nonzero status
+  fn is_failure(&self, event: &TracerEvent) -> bool {
+    match event {
+      TracerEvent::Exec(exec) => {
+        exec.result != 0
+          || self
+            .exit_status
+            .get(&(exec.pid, exec.start_time))
+            .is_some_and(|status| !status.is_success())
+      }
+      TracerEvent::ProcessExit { status, .. } => !status.is_success(),
+      _ => false,
+    }
+  }
+
+  /// Select the next failure after the selected event, wrapping around at the end.
+  pub fn next_failure(&mut self) {
+    let len = self.events.len();
+    let current = self.selection_index();
+    self.select_first_where(
+      (1..=len).map(|offset| match current {
+        Some(current) => (current + offset) % len,
+        None => offset - 1,
+      }),
+      |list, event| list.is_failure(event),
+    );
+  }
+
+  /// Select the previous failure before the selected event, wrapping around at the start.
+  pub fn prev_failure(&mut self) {
+    let len = self.events.len();
+    let current = self.selection_index().unwrap_or(0);
+    self.select_first_where(
+      (1..=len).map(|offset| (current + len - offset) % len),
+      |list, event| list.is_failure(event),
+    );
+  }
+}
+
+/// Bookmark implementation for the EventList
+impl EventList {
+  /// Bookmark the selected event, or remove its bookmark if it is already bookmarked
+  pub fn toggle_bookmark(&mut self) {
+    let Some(selected) = self.selection() else {
+      return;
+    };
+    match self
+      .bookmarks
+      .iter()
+      .position(|bookmark| Arc::ptr_eq(bookmark, &selected))
+    {
+      Some(index) => {
+        self.bookmarks.remove(index);
+      }
+      None => self.bookmarks.push(selected),
+    }
+    self.should_refresh_list_cache = true;
+  }
+
+  fn is_bookmarked(&self, event: &TracerEvent) -> bool {
+    self
+      .bookmarks
+      .iter()
+      .any(|bookmark| std::ptr::eq(bookmark.as_ref(), event))
+  }
+
+  /// Select the next bookmarked event after the selected one, wrapping around at the end.
+  pub fn next_bookmark(&mut self) {
+    let len = self.events.len();
+    let current = self.selection_index();
+    self.select_first_where(
+      (1..=len).map(|offset| match current {
+        Some(current) => (current + offset) % len,
+        None => offset - 1,
+      }),
+      |list, event| list.is_bookmarked(event),
+    );
+  }
+}
+
+/// Filter implementation for the EventList
+impl EventList {
+  /// Only show the events matching the filter, keeping the selected event selected if it still matches
+  pub fn set_filter(&mut self, filter: Option<ListFilter>) {
+    self.filter = filter;
+    self.refresh_events();
+  }
+
+  /// Rebuild the shown events, keeping the selected event selected if it is still shown
+  fn refresh_events(&mut self) {
+    let selected = self.selection();
+    let mut events: Vec<Arc<TracerEvent>> = vec![];
+    // Whether the rest of the current group is hidden because it is collapsed
+    let mut hiding = false;
+    for event in self.all_events.iter() {
+      if let Some(filter) = self.filter.as_ref() {
+        if !self.filter_matches(filter, event) {
+          continue;
+        }
+      }
+      if self.is_in_collapsed_subtree(event) {
+        continue;
+      }
+      let continues_group = self.group
+        && event.pid().is_some()
+        && events.last().map(|last| last.pid()) == Some(event.pid());
+      if !continues_group {
+        hiding = self.group && self.is_collapsed(event);
+      } else if hiding {
+        continue;
+      }
+      events.push(event.clone());
+    }
+    if let Some(sort) = self.sort {
+      // The sort is stable, so the equal events stay in the order they arrived in
+      events.sort_by(|a, b| self.compare_events(a, b, sort));
+    }
+    self.events = events;
+    self.should_resort = false;
+    let position = selected.and_then(|selected| {
+      self
+        .events
+        .iter()
+        .position(|event| Arc::ptr_eq(event, &selected))
+    });
+    self.window = (0, self.max_window_len);
+    self.state.select(None);
+    match position {
+      _ if self.follow => self.scroll_to_bottom(),
+      _ if self.pinned_pid.is_some() => self.select_latest_of_pinned(),
+      Some(index) => self.select_absolute(index),
+      None => self.select_first(),
+    }
+    self.should_refresh_lines_cache = true;
+  }
+}
+
+/// Sort implementation for the EventList
+impl EventList {
+  pub fn set_sort(&mut self, sort: Option<EventSort>) {
+    self.sort = sort;
+    self.refresh_events();
+  }
+
+  fn compare_events(&self, a: &TracerEvent, b: &TracerEvent, sort: EventSort) -> Ordering {
+    let descending = sort.descending;
+    match sort.key {
+      SortKey::Timestamp => compare_keys(timestamp_of(a), timestamp_of(b), descending),
+      SortKey::Pid => compare_keys(a.pid(), b.pid(), descending),
+      SortKey::Comm => compare_keys(comm_of(a), comm_of(b), descending),
+      SortKey::Duration => compare_keys(self.duration_of(a), self.duration_of(b), descending),
+    }
+  }
+
+  fn is_sorted_by_duration(&self) -> bool {
+    self.sort.is_some_and(|sort| sort.key == SortKey::Duration)
+  }
+
+  /// Sort the shown events again after the durations changed, keeping the selected event selected
+  fn resort(&mut self) {
+    self.should_resort = false;
+    let Some(sort) = self.sort else {
+      return;
+    };
+    let selected = self.selection();
+    let mut events = std::mem::take(&mut self.events);
+    events.sort_by(|a, b| self.compare_events(a, b, sort));
+    self.events = events;
+    self.should_refresh_lines_cache = true;
+    if self.follow {
+      return;
+    }
+    if let Some(index) = selected.and_then(|selected| {
+      self
+        .events
+        .iter()
+        .position(|event| Arc::ptr_eq(event, &selected))
+    }) {
+      self.select_absolute(index);
+    }
+  }
+
+  /// The programs that are still running are sorted as the longest ones,
+  /// so that the order only changes when they end and not on every frame
+  fn duration_of(&self, event: &TracerEvent) -> Option<Duration> {
+    match event {
+      TracerEvent::Exec(exec) => {
+        self
+          .run_time(exec)
+          .map(|(duration, running)| if running { Duration::MAX } else { duration })
+      }
+      _ => None,
+    }
+  }
+}
+
+fn timestamp_of(event: &TracerEvent) -> Option<SystemTime> {
+  match event {
+    TracerEvent::Exec(exec) => Some(exec.timestamp),
+    TracerEvent::NewChild { timestamp, .. }
+    | TracerEvent::ProcessExit { timestamp, .. }
+    | TracerEvent::Signal { timestamp, .. } => Some(*timestamp),
+    _ => None,
+  }
+}
+
+fn comm_of(event: &TracerEvent) -> Option<&str> {
+  match event {
+    TracerEvent::Exec(exec) => Some(exec.comm.as_str()),
+    TracerEvent::ProcessExit { comm, .. } | TracerEvent::Signal { comm, .. } => Some(comm.as_str()),
+    // The child has the comm of its parent until it execs
+    TracerEvent::NewChild { pcomm, .. } => Some(pcomm.as_str()),
+    _ => None,
+  }
 }
@@ -0,0 +1,196 @@
+use std::path::PathBuf;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  buffer::Buffer,
+  layout::{Alignment::Center, Rect},
+  style::Styled,
+  text::{Line, Span},
+  widgets::{Block, Borders, Clear, Paragraph, StatefulWidgetRef, Widget},
+};
+
+use crate::action::Action;
+
+use super::{copy_popup::centered_popup_rect, theme::THEME};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+  /// The lines as they are shown in the event list
+  Text,
+  /// One JSON object per event, the same as the JSONL output of the log mode
+  Jsonl,
+}
+
+impl ExportFormat {
+  fn extension(self) -> &'static str {
+    match self {
+      Self::Text => "txt",
+      Self::Jsonl => "jsonl",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportScope {
+  All,
+  /// The events that match the filter
+  Shown,
+  Selected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportField {
+  Format,
+  Scope,
+  File,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportPopup;
+
+#[derive(Debug, Clone)]
+pub struct ExportPopupState {
+  format: ExportFormat,
+  scope: ExportScope,
+  file: String,
+  focus: ExportField,
+  /// The error of the last export, the popup stays open so that it can be retried
+  pub error: Option<String>,
+}
+
+impl ExportPopupState {
+  pub fn new(filtered: bool) -> Self {
+    Self {
+      format: ExportFormat::Text,
+      scope: if filtered {
+        ExportScope::Shown
+      } else {
+        ExportScope::All
+      },
+      file: "tracexec-events.txt".to_string(),
+      focus: ExportField::File,
+      error: None,
+    }
+  }
+
+  fn toggle_format(&mut self) {
+    let old = self.format;
+    self.format = match self.format {
+      ExportFormat::Text => ExportFormat::Jsonl,
+      ExportFormat::Jsonl => ExportFormat::Text,
+    };
+    // Keep the extension in sync with the format unless the user has chosen another one
+    if let Some(stem) = self.file.strip_suffix(old.extension()) {
+      self.file = format!("{stem}{}", self.format.extension());
+    }
+  }
+
+  fn cycle_scope(&mut self, forward: bool) {
+    const SCOPES: [ExportScope; 3] = [ExportScope::All, ExportScope::Shown, ExportScope::Selected];
+    let i = SCOPES.iter().position(|s| *s == self.scope).unwrap();
+    self.scope = if forward {
+      SCOPES[(i + 1) % SCOPES.len()]
+    } else {
+      SCOPES[(i + SCOPES.len() - 1) % SCOPES.len()]
+    };
+  }
+
+  pub fn handle_key_event(&mut self, ke: KeyEvent) -> Option<Action> {
+    match ke.code {
+      KeyCode::Esc => return Some(Action::CancelCurrentPopup),
+      KeyCode::Enter if !self.file.is_empty() => {
+        return Some(Action::ExportEvents {
+          format: self.format,
+          scope: self.scope,
+          path: PathBuf::from(&self.file),
+        })
+      }
+      KeyCode::Up => {
+        self.focus = match self.focus {
+          ExportField::Format | ExportField::Scope => ExportField::Format,
+          ExportField::File => ExportField::Scope,
+        };
+      }
+      KeyCode::Down | KeyCode::Tab => {
+        self.focus = match self.focus {
+          ExportField::Format => ExportField::Scope,
+          ExportField::Scope | ExportField::File => ExportField::File,
+        };
+      }
+      KeyCode::Left | KeyCode::Right if self.focus == ExportField::Format => {
+        self.toggle_format();
+      }
+      KeyCode::Left | KeyCode::Right if self.focus == ExportField::Scope => {
+        self.cycle_scope(ke.code == KeyCode::Right);
+      }
+      KeyCode::Backspace if self.focus == ExportField::File => {
+        self.file.pop();
+      }
+      KeyCode::Char(c)
+        if self.focus == ExportField::File && (ke.modifiers - KeyModifiers::SHIFT).is_empty() =>
+      {
+        self.file.push(c);
+      }
+      _ => return None,
+    }
+    self.error = None;
+    None
+  }
+}
+
+impl StatefulWidgetRef for ExportPopup {
+  fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut ExportPopupState) {
+    let field = |field: ExportField, name: &'static str, value: String| {
+      let line = Line::from(vec![
+        Span::raw(format!("{name:>7}: ")),
+        match field {
+          ExportField::File => value.into(),
+          _ => format!("< {value} >").into(),
+        },
+      ]);
+      if state.focus == field {
+        line.patch_style(THEME.selection)
+      } else {
+        line
+      }
+    };
+    let mut lines = vec![
+      field(
+        ExportField::Format,
+        "Format",
+        match state.format {
+          ExportFormat::Text => "Text",
+          ExportFormat::Jsonl => "JSON Lines",
+        }
+        .to_string(),
+      ),
+      field(
+        ExportField::Scope,
+        "Events",
+        match state.scope {
+          ExportScope::All => "All",
+          ExportScope::Shown => "Shown",
+          ExportScope::Selected => "Selected",
+        }
+        .to_string(),
+      ),
+      field(ExportField::File, "File", state.file.clone()),
+    ];
+    if let Some(error) = state.error.as_ref() {
+      lines.push(Line::from(error.as_str().set_style(THEME.tracer_error)));
+    }
+    let popup_area = centered_popup_rect(60, lines.len() as u16, area);
+    Clear.render(popup_area, buf);
+    Paragraph::new(lines)
+      .block(
+        Block::default()
+          .title("Export")
+          .title_alignment(Center)
+          .borders(Borders::ALL)
+          .border_style(THEME.popup_border),
+      )
+      .render(popup_area, buf);
+  }
+
+  type State = ExportPopupState;
+}
@@ -0,0 +1,95 @@
+//! Information about the executed file, to verify exactly which file was run
+
+use std::{
+  fs::File,
+  io::{self, Read},
+  path::Path,
+};
+
+use chrono::{DateTime, Local};
+use ratatui::{
+  style::Styled,
+  text::{Line, Text},
+};
+use sha2::{Digest, Sha256};
+
+use super::theme::THEME;
+
+/// How far the SHA-256 of the file is computed, which is done in the background when asked for
+#[derive(Debug)]
+pub enum Sha256Progress<'a> {
+  NotStarted,
+  Computing,
+  Done(&'a io::Result<String>),
+}
+
+/// The type, size and modification time of the file, and its SHA-256 if it has been computed
+pub fn file_info(path: &Path, sha256: Sha256Progress) -> Text<'static> {
+  let line = |label: &'static str, value: String| -> Line<'static> {
+    vec![label.set_style(THEME.sublabel), ": ".into(), value.into()].into()
+  };
+  let mut lines = vec![];
+  match path.metadata() {
+    Ok(metadata) => {
+      lines.push(line(
+        "Type",
+        file_type(path).unwrap_or_else(|e| format!("[failed to read the file: {e}]")),
+      ));
+      lines.push(line("Size", format!("{} bytes", metadata.len())));
+      if let Ok(mtime) = metadata.modified() {
+        lines.push(line(
+          "Modified",
+          DateTime::<Local>::from(mtime)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        ));
+      }
+    }
+    Err(e) => lines.push(
+      format!("Failed to read the metadata: {e}")
+        .set_style(THEME.inline_tracer_error)
+        .into(),
+    ),
+  }
+  lines.push(line(
+    "SHA-256",
+    match sha256 {
+      Sha256Progress::NotStarted => "press H to compute".to_string(),
+      Sha256Progress::Computing => "computing...".to_string(),
+      Sha256Progress::Done(Ok(hash)) => hash.clone(),
+      Sha256Progress::Done(Err(e)) => format!("[failed to read the file: {e}]"),
+    },
+  ));
+  lines.into()
+}
+
+/// What kind of executable the file is, judging by its first bytes
+fn file_type(path: &Path) -> io::Result<String> {
+  let mut head = Vec::with_capacity(256);
+  File::open(path)?.take(256).read_to_end(&mut head)?;
+  Ok(if head.starts_with(b"\x7fELF") {
+    match head.get(4) {
+      Some(1) => "ELF 32-bit".to_string(),
+      Some(2) => "ELF 64-bit".to_string(),
+      _ => "ELF".to_string(),
+    }
+  } else if let Some(shebang) = head.strip_prefix(b"#!") {
+    let line = shebang.split(|&c| c == b'\n').next().unwrap_or_default();
+    format!("Script for {}", String::from_utf8_lossy(line).trim())
+  } else {
+    "Unknown".to_string()
+  })
+}
+
+/// The SHA-256 of the file in hex
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+  let mut hasher = Sha256::new();
+  io::copy(&mut File::open(path)?, &mut hasher)?;
+  Ok(
+    hasher
+      .finalize()
+      .iter()
+      .map(|byte| format!("{byte:02x}"))
+      .collect(),
+  )
+}
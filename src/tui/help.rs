@@ -1,10 +1,20 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
-  layout::Rect,
+  buffer::Buffer,
+  layout::{Alignment::Center, Constraint, Layout, Rect, Size},
   text::{Line, Text},
-  widgets::{Paragraph, Wrap},
+  widgets::{Block, Borders, Clear, Paragraph, StatefulWidget, StatefulWidgetRef, Widget, Wrap},
 };
+use strum::IntoEnumIterator;
+use tui_scrollview::{ScrollView, ScrollViewState};
 
-use super::{sized_paragraph::SizedParagraph, theme::THEME};
+use crate::{action::Action, term::symbols};
+
+use super::{
+  copy_popup::centered_popup_rect,
+  keymap::{KeyAction, KeyCategory, Keymap},
+  theme::THEME,
+};
 
 use std::borrow::Cow;
 
@@ -51,57 +61,228 @@ macro_rules! help_item {
 
 pub(crate) use help_item;
 
-pub fn help<'a>(area: Rect) -> SizedParagraph<'a> {
-  let line1 = Line::default().spans(vec![
-      "Welcome to tracexec! The TUI consists of at most two panes: the event list and optionally the pseudo terminal if ".into(),
-      cli_flag("--tty/-t"),
-      " is enabled. The event list displays the events emitted by the tracer. \
-       The active pane's border is highlighted in cyan. \
-       To switch active pane, press ".into(),
-      help_key("Ctrl+S"),
-      ". To send ".into(),
-      help_key("Ctrl+S"),
-      " to the pseudo terminal, press ".into(),
-      help_key("Alt+S"),
-      " when event list is active. The keybinding list at the bottom of the screen shows the available keys for currently active pane or popup.".into(),
-    ]);
-  let line2 = Line::default().spans(vec![
-    "You can navigate the event list using the arrow keys or ".into(),
-    help_key("H/J/K/L"),
-    ". To scroll faster, use ".into(),
-    help_key("Ctrl+↑/↓/←/→/H/J/K/L"),
-    " or ".into(),
-    help_key("PgUp/PgDn"),
-    ". Use ".into(),
-    help_key("(Shift +) Home/End"),
-    " to scroll to the (line start/line end)/top/bottom. Press ".into(),
-    help_key("F"),
-    " to toggle follow mode, which will keep the list scrolled to bottom. ".into(),
-    "To change pane size, press ".into(),
-    help_key("G/S"),
-    " when the active pane is event list. ".into(),
-    "To switch between horizontal and vertical layout, press ".into(),
-    help_key("Alt+L"),
-    ". To view the details of the selected event, press ".into(),
-    help_key("V"),
-    ". To copy the selected event to the clipboard, press ".into(),
-    help_key("C"),
-    " then select what to copy. To quit, press ".into(),
-    help_key("Q"),
-    " while the event list is active.".into(),
-  ]);
-  let line3 = Line::default().spans(vec![
-    "When the pseudo terminal is active, you can interact with the terminal using the keyboard.",
-  ]);
-  let line4 = Line::default()
-    .spans(vec![
-      "Press ".into(),
-      help_key("Any Key"),
-      " to close this help popup.".into(),
-    ])
-    .centered();
-  let paragraph =
-    Paragraph::new(Text::from_iter([line1, line2, line3, line4])).wrap(Wrap { trim: false });
-  let perhaps_a_suitable_width = area.width.saturating_sub(6) as usize;
-  SizedParagraph::new(paragraph, perhaps_a_suitable_width)
+/// The keys that are not in the keymap, e.g. the keys of the search prompt
+const FIXED_KEYS: &[(KeyCategory, &str, &str)] = &[
+  (
+    KeyCategory::SearchAndFilter,
+    "Enter",
+    "Search, in the search prompt",
+  ),
+  (
+    KeyCategory::SearchAndFilter,
+    "Ctrl+R",
+    "Search with a regex, in the search prompt",
+  ),
+  (
+    KeyCategory::SearchAndFilter,
+    "Ctrl+E",
+    "Filter with an expression like comm == \"rustc\", in the filter prompt",
+  ),
+  (
+    KeyCategory::SearchAndFilter,
+    "Esc",
+    "Clear the search or the filter",
+  ),
+  (
+    KeyCategory::SearchAndFilter,
+    "Ctrl+A",
+    "Also select the matching events, in the watch prompt",
+  ),
+  (
+    KeyCategory::Events,
+    "E/V",
+    "Open the executed file in $EDITOR/$PAGER, in the details",
+  ),
+  (
+    KeyCategory::Processes,
+    "Enter",
+    "Jump to the execs of the selected process, in the process tree",
+  ),
+  (
+    KeyCategory::Panes,
+    "Ctrl+S",
+    "Switch between the event list and the terminal",
+  ),
+  (
+    KeyCategory::Panes,
+    "Alt+S",
+    "Send Ctrl+S to the terminal, when the event list is active",
+  ),
+  (KeyCategory::Panes, "1-9", "Switch to the tab"),
+  (
+    KeyCategory::Panes,
+    "Shift+PgUp/PgDn",
+    "Scroll the history of the terminal",
+  ),
+  (
+    KeyCategory::Panes,
+    "Alt+C",
+    "Select text in the terminal and copy it, like in tmux",
+  ),
+];
+
+/// The help popup, listing the keys of the keymap in use by category
+pub struct HelpPopup<'a> {
+  keymap: &'a Keymap,
+}
+
+impl<'a> HelpPopup<'a> {
+  pub fn new(keymap: &'a Keymap) -> Self {
+    Self { keymap }
+  }
+
+  /// The keys and descriptions of the category that contain the filter
+  fn rows(&self, category: KeyCategory, filter: &str) -> Vec<(String, &'static str)> {
+    KeyAction::iter()
+      .filter(|action| action.category() == category)
+      .map(|action| (self.keymap.keys(action), action.description()))
+      .chain(
+        FIXED_KEYS
+          .iter()
+          .filter(|(c, _, _)| *c == category)
+          .map(|(_, keys, description)| (keys.to_string(), *description)),
+      )
+      .filter(|(keys, description)| {
+        keys.to_lowercase().contains(filter) || description.to_lowercase().contains(filter)
+      })
+      .collect()
+  }
+
+  fn intro(&self) -> Vec<Line<'static>> {
+    vec![
+      Line::default().spans(vec![
+        "Welcome to tracexec! The TUI consists of at most two panes: the event list and optionally the pseudo terminal if ".into(),
+        cli_flag("--tty/-t"),
+        " is enabled. The event list displays the events emitted by the tracer. \
+         The active pane's border is highlighted in cyan. \
+         When the pseudo terminal is active, you can interact with the terminal using the keyboard. \
+         The keybinding list at the bottom of the screen shows the available keys for currently active pane or popup.".into(),
+      ]),
+      Line::default().spans(vec![
+        "The keys can be changed in the ".into(),
+        cli_flag("[tui]"),
+        " section of the config file. \
+         The mouse can be used to select events, scroll the lists, activate a pane and drag the border between the panes to resize them, unless ".into(),
+        cli_flag("--no-mouse"),
+        " is given.".into(),
+      ]),
+    ]
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HelpPopupState {
+  /// Only the keys whose keys or descriptions contain it are shown
+  filter: String,
+  scroll: ScrollViewState,
+}
+
+impl HelpPopupState {
+  pub fn handle_key_event(&mut self, ke: KeyEvent) -> Option<Action> {
+    match ke.code {
+      KeyCode::Esc if self.filter.is_empty() => return Some(Action::CancelCurrentPopup),
+      KeyCode::Esc => {
+        self.filter.clear();
+        self.scroll.scroll_to_top();
+      }
+      KeyCode::Backspace => {
+        self.filter.pop();
+        self.scroll.scroll_to_top();
+      }
+      KeyCode::Char(c) if (ke.modifiers - KeyModifiers::SHIFT).is_empty() => {
+        self.filter.push(c);
+        self.scroll.scroll_to_top();
+      }
+      KeyCode::Down => self.scroll.scroll_down(),
+      KeyCode::Up => self.scroll.scroll_up(),
+      KeyCode::PageDown => self.scroll.scroll_page_down(),
+      KeyCode::PageUp => self.scroll.scroll_page_up(),
+      KeyCode::Home => self.scroll.scroll_to_top(),
+      KeyCode::End => self.scroll.scroll_to_bottom(),
+      _ => {}
+    }
+    None
+  }
+}
+
+impl StatefulWidgetRef for HelpPopup<'_> {
+  fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut HelpPopupState) {
+    let popup_area = centered_popup_rect(area.width.saturating_sub(8).min(100), area.height, area);
+    Clear.render(popup_area, buf);
+    let block = Block::default()
+      .title("Help")
+      .title_alignment(Center)
+      .borders(Borders::ALL)
+      .border_style(THEME.popup_border)
+      .style(THEME.help_popup);
+    let inner = block.inner(popup_area);
+    block.render(popup_area, buf);
+    let [filter_area, list_area] =
+      Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+
+    let filter_line = if state.filter.is_empty() {
+      Line::from(vec![
+        "Type to filter the keys, ".into(),
+        help_key("Esc"),
+        " to close".into(),
+      ])
+    } else {
+      Line::from(vec![
+        "Filter: ".into(),
+        Span::raw(state.filter.as_str()),
+        symbols().cursor.into(),
+      ])
+    };
+    filter_line.render(filter_area, buf);
+
+    let filter = state.filter.to_lowercase();
+    let sections: Vec<_> = KeyCategory::iter()
+      .map(|category| (category, self.rows(category, &filter)))
+      .filter(|(_, rows)| !rows.is_empty())
+      .collect();
+    let key_width = sections
+      .iter()
+      .flat_map(|(_, rows)| rows.iter().map(|(keys, _)| keys.chars().count()))
+      .max()
+      .unwrap_or_default();
+    let mut lines = if filter.is_empty() {
+      self.intro()
+    } else {
+      vec![]
+    };
+    for (category, rows) in sections.iter() {
+      lines.push(Line::default());
+      lines.push(Line::from(category.to_string().set_style(THEME.label)));
+      for (keys, description) in rows {
+        lines.push(Line::from(vec![
+          help_key(format!("{keys:key_width$}")),
+          " ".into(),
+          (*description).into(),
+        ]));
+      }
+    }
+    if sections.is_empty() {
+      lines.push(Line::from("No matching keys"));
+    }
+
+    let width = list_area.width.saturating_sub(1);
+    let paragraph = Paragraph::new(Text::from(lines)).wrap(Wrap { trim: false });
+    let size = Size {
+      width,
+      height: paragraph.line_count(width).try_into().unwrap_or(u16::MAX),
+    };
+    let mut scrollview = ScrollView::new(size);
+    scrollview.render_widget(
+      paragraph,
+      Rect {
+        x: 0,
+        y: 0,
+        width: size.width,
+        height: size.height,
+      },
+    );
+    scrollview.render(list_area, buf, &mut state.scroll);
+  }
+
+  type State = HelpPopupState;
 }
@@ -0,0 +1,495 @@
+//! Key bindings of the event list, which can be customized in the `[tui]` section of the config file
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use itertools::Itertools;
+use serde::Deserialize;
+use strum::{Display, EnumIter};
+
+/// The commands that can be bound to keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAction {
+  Quit,
+  NextItem,
+  PrevItem,
+  PageDown,
+  PageUp,
+  PageLeft,
+  PageRight,
+  ScrollLeft,
+  ScrollRight,
+  ScrollToTop,
+  ScrollToBottom,
+  ScrollToStart,
+  ScrollToEnd,
+  ToggleFollow,
+  TogglePin,
+  ToggleEnvDisplay,
+  ToggleWrap,
+  ToggleGroup,
+  ToggleCollapse,
+  ToggleSubtree,
+  GrowPane,
+  ShrinkPane,
+  SwitchLayout,
+  ToggleZoom,
+  Copy,
+  ViewDetails,
+  Help,
+  Search,
+  NextMatch,
+  PrevMatch,
+  Filter,
+  Watch,
+  Breakpoint,
+  GoTo,
+  ToggleProcessTree,
+  ToggleStats,
+  ToggleTimeline,
+  ToggleBookmark,
+  NextBookmark,
+  NextFailure,
+  PrevFailure,
+  MarkDiffBase,
+  Diff,
+  KillProcess,
+  TogglePause,
+  Export,
+  Columns,
+  Sort,
+  NewTrace,
+  Attach,
+  Rerun,
+  JumpToParent,
+  NextChild,
+}
+
+/// The sections of the help popup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum KeyCategory {
+  Navigation,
+  View,
+  #[strum(serialize = "Search and Filter")]
+  SearchAndFilter,
+  Events,
+  Processes,
+  #[strum(serialize = "Panes and Tabs")]
+  Panes,
+  Other,
+}
+
+impl KeyAction {
+  pub fn category(self) -> KeyCategory {
+    match self {
+      Self::NextItem
+      | Self::PrevItem
+      | Self::PageDown
+      | Self::PageUp
+      | Self::PageLeft
+      | Self::PageRight
+      | Self::ScrollLeft
+      | Self::ScrollRight
+      | Self::ScrollToTop
+      | Self::ScrollToBottom
+      | Self::ScrollToStart
+      | Self::ScrollToEnd
+      | Self::GoTo
+      | Self::ToggleFollow
+      | Self::TogglePin => KeyCategory::Navigation,
+      Self::ToggleEnvDisplay
+      | Self::ToggleWrap
+      | Self::ToggleGroup
+      | Self::ToggleCollapse
+      | Self::ToggleSubtree
+      | Self::Columns
+      | Self::Sort
+      | Self::ToggleStats
+      | Self::ToggleTimeline => KeyCategory::View,
+      Self::Search | Self::NextMatch | Self::PrevMatch | Self::Filter | Self::Watch => {
+        KeyCategory::SearchAndFilter
+      }
+      Self::ViewDetails
+      | Self::Copy
+      | Self::ToggleBookmark
+      | Self::NextBookmark
+      | Self::NextFailure
+      | Self::PrevFailure
+      | Self::MarkDiffBase
+      | Self::Diff
+      | Self::Export
+      | Self::Rerun => KeyCategory::Events,
+      Self::ToggleProcessTree
+      | Self::Breakpoint
+      | Self::JumpToParent
+      | Self::NextChild
+      | Self::KillProcess
+      | Self::TogglePause => KeyCategory::Processes,
+      Self::GrowPane
+      | Self::ShrinkPane
+      | Self::SwitchLayout
+      | Self::ToggleZoom
+      | Self::NewTrace
+      | Self::Attach => KeyCategory::Panes,
+      Self::Help | Self::Quit => KeyCategory::Other,
+    }
+  }
+
+  /// What the action does, as shown in the help popup
+  pub fn description(self) -> &'static str {
+    match self {
+      Self::Quit => "Quit, when the event list is active",
+      Self::NextItem => "Select the next item",
+      Self::PrevItem => "Select the previous item",
+      Self::PageDown => "Scroll down by a page",
+      Self::PageUp => "Scroll up by a page",
+      Self::PageLeft => "Scroll left by a page",
+      Self::PageRight => "Scroll right by a page",
+      Self::ScrollLeft => "Scroll left",
+      Self::ScrollRight => "Scroll right",
+      Self::ScrollToTop => "Scroll to the top",
+      Self::ScrollToBottom => "Scroll to the bottom",
+      Self::ScrollToStart => "Scroll to the start of the lines",
+      Self::ScrollToEnd => "Scroll to the end of the lines",
+      Self::ToggleFollow => "Follow mode, which keeps the list scrolled to the bottom",
+      Self::TogglePin => "Pin the process of the selected event, keeping its latest event selected",
+      Self::ToggleEnvDisplay => "Show or hide the environment in the command lines",
+      Self::ToggleWrap => "Wrap the long lines",
+      Self::ToggleGroup => "Group the consecutive events of each process under a header",
+      Self::ToggleCollapse => "Collapse or expand the group of the selected event",
+      Self::ToggleSubtree => "Hide or show the events of the descendants of the selected process",
+      Self::GrowPane => "Grow the event list pane",
+      Self::ShrinkPane => "Shrink the event list pane",
+      Self::SwitchLayout => "Switch between horizontal and vertical layout",
+      Self::ToggleZoom => "Maximize the active pane to the full screen, or restore it",
+      Self::Copy => "Copy the selected event to the clipboard",
+      Self::ViewDetails => "View the details of the selected event",
+      Self::Help => "Show this help",
+      Self::Search => "Search the event list",
+      Self::NextMatch => "Jump to the next match",
+      Self::PrevMatch => "Jump to the previous match",
+      Self::Filter => "Only show the events matching a pattern",
+      Self::Watch => "Notify when an exec matching a pattern occurs, or clear the watches",
+      Self::Breakpoint => {
+        "Stop at the execs matching a pattern to edit them, or clear the breakpoints"
+      }
+      Self::GoTo => "Jump to an event by its number, or to the first or last event of a pid",
+      Self::ToggleProcessTree => "Show or hide the process tree",
+      Self::ToggleStats => "Switch to the statistics of the executed programs",
+      Self::ToggleTimeline => "Switch to the timeline of the processes",
+      Self::ToggleBookmark => "Bookmark the selected event",
+      Self::NextBookmark => "Jump to the next bookmark",
+      Self::NextFailure => "Jump to the next failed exec or nonzero exit",
+      Self::PrevFailure => "Jump to the previous failed exec or nonzero exit",
+      Self::MarkDiffBase => "Mark the selected exec as the base of a diff",
+      Self::Diff => "Diff the selected exec with the base",
+      Self::KillProcess => {
+        "Send a signal to the process of the selected event if it is still running"
+      }
+      Self::TogglePause => {
+        "Pause the tracer, or resume it. Processes keep running until their next traced stop, e.g. an exec"
+      }
+      Self::Export => "Save the events to a file",
+      Self::Columns => "Choose the columns shown before each event",
+      Self::Sort => "Sort the event list by timestamp, pid, comm or duration",
+      Self::NewTrace => "Trace another command in a new tab",
+      Self::Attach => "Pick a running process and trace it in a new tab",
+      Self::Rerun => "Edit the command line of the selected exec and run it in the terminal",
+      Self::JumpToParent => "Jump to the exec of the parent process",
+      Self::NextChild => "Cycle through the execs of the child processes",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeymapPreset {
+  #[default]
+  Default,
+  Vim,
+}
+
+const DEFAULT_PRESET: &[(KeyAction, &[&str])] = &[
+  (KeyAction::Quit, &["q"]),
+  (KeyAction::NextItem, &["down", "j"]),
+  (KeyAction::PrevItem, &["up", "k"]),
+  (KeyAction::PageDown, &["ctrl+down", "ctrl+j", "pagedown"]),
+  (KeyAction::PageUp, &["ctrl+up", "ctrl+k", "pageup"]),
+  (KeyAction::PageLeft, &["ctrl+left", "ctrl+h"]),
+  (KeyAction::PageRight, &["ctrl+right", "ctrl+l"]),
+  (KeyAction::ScrollLeft, &["left", "h"]),
+  (KeyAction::ScrollRight, &["right", "l"]),
+  (KeyAction::ScrollToTop, &["home"]),
+  (KeyAction::ScrollToBottom, &["end"]),
+  (KeyAction::ScrollToStart, &["shift+home"]),
+  (KeyAction::ScrollToEnd, &["shift+end"]),
+  (KeyAction::ToggleFollow, &["F"]),
+  (KeyAction::TogglePin, &["i"]),
+  (KeyAction::ToggleEnvDisplay, &["e"]),
+  (KeyAction::ToggleWrap, &["w"]),
+  (KeyAction::ToggleGroup, &["Z"]),
+  (KeyAction::ToggleCollapse, &["z"]),
+  (KeyAction::ToggleSubtree, &["D"]),
+  (KeyAction::GrowPane, &["g"]),
+  (KeyAction::ShrinkPane, &["s"]),
+  (KeyAction::SwitchLayout, &["alt+l"]),
+  (KeyAction::ToggleZoom, &["alt+z"]),
+  (KeyAction::Copy, &["c"]),
+  (KeyAction::ViewDetails, &["v"]),
+  (KeyAction::Help, &["f1"]),
+  (KeyAction::Search, &["/"]),
+  (KeyAction::NextMatch, &["n"]),
+  (KeyAction::PrevMatch, &["N"]),
+  (KeyAction::Filter, &["f"]),
+  (KeyAction::Watch, &["W"]),
+  (KeyAction::Breakpoint, &["X"]),
+  (KeyAction::GoTo, &[":"]),
+  (KeyAction::ToggleProcessTree, &["t"]),
+  (KeyAction::ToggleStats, &["S"]),
+  (KeyAction::ToggleTimeline, &["T"]),
+  (KeyAction::ToggleBookmark, &["m"]),
+  (KeyAction::NextBookmark, &["'"]),
+  (KeyAction::NextFailure, &["]"]),
+  (KeyAction::PrevFailure, &["["]),
+  (KeyAction::MarkDiffBase, &["b"]),
+  (KeyAction::Diff, &["B"]),
+  (KeyAction::KillProcess, &["K"]),
+  (KeyAction::TogglePause, &["p"]),
+  (KeyAction::Export, &["x"]),
+  (KeyAction::Columns, &["o"]),
+  (KeyAction::Sort, &["O"]),
+  (KeyAction::NewTrace, &["alt+n"]),
+  (KeyAction::Attach, &["A"]),
+  (KeyAction::Rerun, &["R"]),
+  (KeyAction::JumpToParent, &["P"]),
+  (KeyAction::NextChild, &["C"]),
+];
+
+const VIM_PRESET: &[(KeyAction, &[&str])] = &[
+  (KeyAction::Quit, &["q"]),
+  (KeyAction::NextItem, &["down", "j"]),
+  (KeyAction::PrevItem, &["up", "k"]),
+  (KeyAction::PageDown, &["ctrl+f", "ctrl+d", "pagedown"]),
+  (KeyAction::PageUp, &["ctrl+b", "ctrl+u", "pageup"]),
+  (KeyAction::PageLeft, &["H"]),
+  (KeyAction::PageRight, &["L"]),
+  (KeyAction::ScrollLeft, &["left", "h"]),
+  (KeyAction::ScrollRight, &["right", "l"]),
+  (KeyAction::ScrollToTop, &["g", "home"]),
+  (KeyAction::ScrollToBottom, &["G", "end"]),
+  (KeyAction::ScrollToStart, &["0"]),
+  (KeyAction::ScrollToEnd, &["$"]),
+  (KeyAction::ToggleFollow, &["F"]),
+  (KeyAction::TogglePin, &["i"]),
+  (KeyAction::ToggleEnvDisplay, &["e"]),
+  (KeyAction::ToggleWrap, &["w"]),
+  (KeyAction::ToggleGroup, &["Z"]),
+  (KeyAction::ToggleCollapse, &["z"]),
+  (KeyAction::ToggleSubtree, &["D"]),
+  (KeyAction::GrowPane, &["+"]),
+  (KeyAction::ShrinkPane, &["-"]),
+  (KeyAction::SwitchLayout, &["alt+l"]),
+  (KeyAction::ToggleZoom, &["alt+z"]),
+  (KeyAction::Copy, &["y"]),
+  (KeyAction::ViewDetails, &["enter", "v"]),
+  (KeyAction::Help, &["?", "f1"]),
+  (KeyAction::Search, &["/"]),
+  (KeyAction::NextMatch, &["n"]),
+  (KeyAction::PrevMatch, &["N"]),
+  (KeyAction::Filter, &["f"]),
+  (KeyAction::Watch, &["W"]),
+  (KeyAction::Breakpoint, &["X"]),
+  (KeyAction::GoTo, &[":"]),
+  (KeyAction::ToggleProcessTree, &["t"]),
+  (KeyAction::ToggleStats, &["S"]),
+  (KeyAction::ToggleTimeline, &["T"]),
+  (KeyAction::ToggleBookmark, &["m"]),
+  (KeyAction::NextBookmark, &["'"]),
+  (KeyAction::NextFailure, &["]"]),
+  (KeyAction::PrevFailure, &["["]),
+  (KeyAction::MarkDiffBase, &["b"]),
+  (KeyAction::Diff, &["B"]),
+  (KeyAction::KillProcess, &["K"]),
+  (KeyAction::TogglePause, &["p"]),
+  (KeyAction::Export, &["x"]),
+  (KeyAction::Columns, &["o"]),
+  (KeyAction::Sort, &["O"]),
+  (KeyAction::NewTrace, &["alt+n"]),
+  (KeyAction::Attach, &["A"]),
+  (KeyAction::Rerun, &["R"]),
+  (KeyAction::JumpToParent, &["P"]),
+  (KeyAction::NextChild, &["C"]),
+];
+
+/// A key with modifiers, written as e.g. `ctrl+d`, `shift+home`, `N` or `f1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyBinding {
+  code: KeyCode,
+  /// Shift is never set for characters, upper case characters are used instead
+  modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+  pub fn matches(&self, ke: &KeyEvent) -> bool {
+    match ke.code {
+      // Whether shift is reported for shifted characters, e.g. `&`, depends on the terminal
+      KeyCode::Char(_) => {
+        self.code == ke.code && self.modifiers == ke.modifiers - KeyModifiers::SHIFT
+      }
+      _ => self.code == ke.code && self.modifiers == ke.modifiers,
+    }
+  }
+}
+
+impl TryFrom<String> for KeyBinding {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.as_str().try_into()
+  }
+}
+
+impl TryFrom<&str> for KeyBinding {
+  type Error = String;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    let mut modifiers = KeyModifiers::NONE;
+    // Split off the modifiers, taking care of `+` and `ctrl++`
+    let mut rest = value;
+    while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+      modifiers |= match modifier.to_ascii_lowercase().as_str() {
+        "ctrl" => KeyModifiers::CONTROL,
+        "alt" => KeyModifiers::ALT,
+        "shift" => KeyModifiers::SHIFT,
+        _ => return Err(format!("Unknown modifier {modifier:?} in key {value:?}")),
+      };
+      rest = key;
+    }
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+      (Some(c), None) => {
+        if modifiers.contains(KeyModifiers::SHIFT) {
+          modifiers.remove(KeyModifiers::SHIFT);
+          KeyCode::Char(c.to_ascii_uppercase())
+        } else {
+          KeyCode::Char(c)
+        }
+      }
+      _ => match rest.to_ascii_lowercase().as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "enter" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "space" => KeyCode::Char(' '),
+        key => match key.strip_prefix('f').and_then(|n| n.parse().ok()) {
+          Some(n @ 1..=12) => KeyCode::F(n),
+          _ => return Err(format!("Unknown key {rest:?} in {value:?}")),
+        },
+      },
+    };
+    Ok(Self { code, modifiers })
+  }
+}
+
+impl Display for KeyBinding {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    if self.modifiers.contains(KeyModifiers::CONTROL) {
+      write!(f, "Ctrl+")?;
+    }
+    if self.modifiers.contains(KeyModifiers::ALT) {
+      write!(f, "Alt+")?;
+    }
+    if self.modifiers.contains(KeyModifiers::SHIFT) {
+      write!(f, "Shift+")?;
+    }
+    match self.code {
+      KeyCode::Char(' ') => write!(f, "Space"),
+      KeyCode::Char(c) if c.is_ascii_uppercase() => write!(f, "Shift+{c}"),
+      KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+      KeyCode::Up => write!(f, "↑"),
+      KeyCode::Down => write!(f, "↓"),
+      KeyCode::Left => write!(f, "←"),
+      KeyCode::Right => write!(f, "→"),
+      KeyCode::PageUp => write!(f, "PgUp"),
+      KeyCode::PageDown => write!(f, "PgDn"),
+      KeyCode::F(n) => write!(f, "F{n}"),
+      code => write!(f, "{code:?}"),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+  bindings: Vec<(KeyBinding, KeyAction)>,
+}
+
+impl Keymap {
+  pub fn preset(preset: KeymapPreset) -> Self {
+    let table = match preset {
+      KeymapPreset::Default => DEFAULT_PRESET,
+      KeymapPreset::Vim => VIM_PRESET,
+    };
+    Self {
+      bindings: table
+        .iter()
+        .flat_map(|(action, keys)| {
+          keys
+            .iter()
+            .map(|key| (KeyBinding::try_from(*key).unwrap(), *action))
+        })
+        .collect(),
+    }
+  }
+
+  /// Start from the preset and replace the keys of the actions in `keys`.
+  /// The keys bound in `keys` are taken away from the actions of the preset they were bound to.
+  pub fn new(preset: KeymapPreset, keys: &BTreeMap<KeyAction, Vec<KeyBinding>>) -> Self {
+    let mut keymap = Self::preset(preset);
+    let overrides: Vec<(KeyBinding, KeyAction)> = keys
+      .iter()
+      .flat_map(|(action, keys)| keys.iter().map(|key| (*key, *action)))
+      .collect();
+    keymap.bindings.retain(|(key, action)| {
+      !keys.contains_key(action) && !overrides.iter().any(|(bound, _)| bound == key)
+    });
+    keymap.bindings.splice(0..0, overrides);
+    keymap
+  }
+
+  pub fn action(&self, ke: &KeyEvent) -> Option<KeyAction> {
+    self
+      .bindings
+      .iter()
+      .find(|(key, _)| key.matches(ke))
+      .map(|(_, action)| *action)
+  }
+
+  /// The keys bound to the action for showing in help, e.g. `↓/J`
+  pub fn keys(&self, action: KeyAction) -> String {
+    let keys = self
+      .bindings
+      .iter()
+      .filter(|(_, a)| *a == action)
+      .map(|(key, _)| key)
+      .join("/");
+    if keys.is_empty() {
+      "Unbound".to_string()
+    } else {
+      keys
+    }
+  }
+}
+
+#[cfg(test)]
+mod test;
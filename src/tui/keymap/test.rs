@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::{KeyAction, KeyBinding, Keymap, KeymapPreset};
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+  KeyEvent::new(code, modifiers)
+}
+
+#[test]
+fn key_bindings_are_parsed() {
+  let binding = KeyBinding::try_from("ctrl+d").unwrap();
+  assert!(binding.matches(&key(KeyCode::Char('d'), KeyModifiers::CONTROL)));
+  assert!(!binding.matches(&key(KeyCode::Char('d'), KeyModifiers::NONE)));
+  // Shift is written as an upper case character, which terminals may report with or without shift
+  let binding = KeyBinding::try_from("shift+n").unwrap();
+  assert_eq!(binding, KeyBinding::try_from("N").unwrap());
+  assert!(binding.matches(&key(KeyCode::Char('N'), KeyModifiers::SHIFT)));
+  assert!(binding.matches(&key(KeyCode::Char('N'), KeyModifiers::NONE)));
+  let binding = KeyBinding::try_from("shift+home").unwrap();
+  assert!(binding.matches(&key(KeyCode::Home, KeyModifiers::SHIFT)));
+  assert!(!binding.matches(&key(KeyCode::Home, KeyModifiers::NONE)));
+  assert!(KeyBinding::try_from("f12")
+    .unwrap()
+    .matches(&key(KeyCode::F(12), KeyModifiers::NONE)));
+  assert!(KeyBinding::try_from("ctrl++")
+    .unwrap()
+    .matches(&key(KeyCode::Char('+'), KeyModifiers::CONTROL)));
+  assert!(KeyBinding::try_from("+")
+    .unwrap()
+    .matches(&key(KeyCode::Char('+'), KeyModifiers::NONE)));
+}
+
+#[test]
+fn bad_key_bindings_are_rejected() {
+  assert!(KeyBinding::try_from("hyper+d")
+    .unwrap_err()
+    .contains("Unknown modifier"));
+  assert!(KeyBinding::try_from("f13")
+    .unwrap_err()
+    .contains("Unknown key"));
+  assert!(KeyBinding::try_from("ctrl+nope")
+    .unwrap_err()
+    .contains("Unknown key"));
+}
+
+#[test]
+fn presets_are_valid() {
+  // The preset tables are parsed with unwrap
+  Keymap::preset(KeymapPreset::Default);
+  Keymap::preset(KeymapPreset::Vim);
+}
+
+#[test]
+fn overrides_replace_the_keys_of_the_action() {
+  let keys = BTreeMap::from([(
+    KeyAction::Quit,
+    vec![KeyBinding::try_from("ctrl+q").unwrap()],
+  )]);
+  let keymap = Keymap::new(KeymapPreset::Default, &keys);
+  assert_eq!(
+    keymap.action(&key(KeyCode::Char('q'), KeyModifiers::CONTROL)),
+    Some(KeyAction::Quit)
+  );
+  assert_eq!(
+    keymap.action(&key(KeyCode::Char('q'), KeyModifiers::NONE)),
+    None
+  );
+}
+
+#[test]
+fn overrides_take_the_keys_of_the_preset() {
+  // `j` selects the next item in the preset
+  let keys = BTreeMap::from([(KeyAction::Quit, vec![KeyBinding::try_from("j").unwrap()])]);
+  let keymap = Keymap::new(KeymapPreset::Default, &keys);
+  assert_eq!(
+    keymap.action(&key(KeyCode::Char('j'), KeyModifiers::NONE)),
+    Some(KeyAction::Quit)
+  );
+  assert_eq!(keymap.keys(KeyAction::Quit), "J");
+  assert_eq!(keymap.keys(KeyAction::NextItem), "↓");
+}
+
+#[test]
+fn keys_in_config() {
+  let keys: BTreeMap<KeyAction, Vec<KeyBinding>> =
+    toml::from_str("next_item = [\"ctrl+n\"]\nquit = []").unwrap();
+  let keymap = Keymap::new(KeymapPreset::Vim, &keys);
+  assert_eq!(keymap.keys(KeyAction::NextItem), "Ctrl+N");
+  assert_eq!(keymap.keys(KeyAction::Quit), "Unbound");
+  assert!(toml::from_str::<BTreeMap<KeyAction, Vec<KeyBinding>>>("quit = [\"hyper+q\"]").is_err());
+  assert!(toml::from_str::<BTreeMap<KeyAction, Vec<KeyBinding>>>("nope = []").is_err());
+}
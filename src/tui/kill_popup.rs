@@ -0,0 +1,116 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use nix::{sys::signal::Signal, unistd::Pid};
+use ratatui::{
+  buffer::Buffer,
+  layout::{Alignment::Center, Rect},
+  widgets::{Block, Borders, Clear, HighlightSpacing, List, ListState, StatefulWidgetRef, Widget},
+};
+
+use crate::{action::Action, event::TracerEvent};
+
+use super::{copy_popup::centered_popup_rect, theme::THEME};
+
+const SIGNALS: [(Signal, &str); 10] = [
+  (Signal::SIGTERM, "Terminate (SIGTERM)"),
+  (Signal::SIGKILL, "Kill (SIGKILL)"),
+  (Signal::SIGINT, "Interrupt (SIGINT)"),
+  (Signal::SIGQUIT, "Quit (SIGQUIT)"),
+  (Signal::SIGHUP, "Hang up (SIGHUP)"),
+  (Signal::SIGSTOP, "Stop (SIGSTOP)"),
+  (Signal::SIGCONT, "Continue (SIGCONT)"),
+  (Signal::SIGUSR1, "User signal 1 (SIGUSR1)"),
+  (Signal::SIGUSR2, "User signal 2 (SIGUSR2)"),
+  (Signal::SIGWINCH, "Window changed (SIGWINCH)"),
+];
+
+#[derive(Debug, Clone)]
+pub struct KillPopup;
+
+#[derive(Debug, Clone)]
+pub struct KillPopupState {
+  pub pid: Pid,
+  pub comm: String,
+  pub state: ListState,
+}
+
+impl KillPopupState {
+  /// Returns `None` if there is no process behind the event that could still be alive
+  pub fn new(event: &TracerEvent) -> Option<Self> {
+    let (pid, comm) = match event {
+      TracerEvent::Exec(exec) => (exec.pid, exec.comm.clone()),
+      TracerEvent::NewChild { pid, pcomm, .. } => (*pid, pcomm.clone()),
+      _ => return None,
+    };
+    let mut state = ListState::default();
+    state.select(Some(0));
+    Some(Self { pid, comm, state })
+  }
+
+  pub fn next(&mut self) {
+    self.state.select(Some(
+      (self.state.selected().unwrap() + 1).min(SIGNALS.len() - 1),
+    ))
+  }
+
+  pub fn prev(&mut self) {
+    self
+      .state
+      .select(Some(self.state.selected().unwrap().saturating_sub(1)))
+  }
+
+  pub fn selected(&self) -> Signal {
+    SIGNALS[self.state.selected().unwrap_or(0)].0
+  }
+
+  pub fn handle_key_event(&mut self, ke: KeyEvent) -> Option<Action> {
+    if ke.modifiers == KeyModifiers::NONE {
+      match ke.code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+          return Some(Action::CancelCurrentPopup);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+          self.next();
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+          self.prev();
+        }
+        KeyCode::Enter => {
+          return Some(Action::KillProcess {
+            pid: self.pid,
+            signal: self.selected(),
+          });
+        }
+        _ => {}
+      }
+    }
+    None
+  }
+}
+
+impl StatefulWidgetRef for KillPopup {
+  fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut KillPopupState) {
+    let title = format!("Send signal to {} ({})", state.pid, state.comm);
+    let list = List::from_iter(SIGNALS.iter().map(|(_, desc)| *desc))
+      .block(
+        Block::default()
+          .title(title.as_str())
+          .title_alignment(Center)
+          .borders(Borders::ALL)
+          .border_style(THEME.popup_border),
+      )
+      .highlight_style(THEME.selection)
+      .highlight_symbol(">")
+      .highlight_spacing(HighlightSpacing::Always);
+    let width = SIGNALS
+      .iter()
+      .map(|(_, desc)| desc.len() + 1)
+      .chain([title.len() + 2])
+      .max()
+      .unwrap_or_default();
+    let popup_area = centered_popup_rect(width as u16, list.len() as u16, area);
+    Clear.render(popup_area, buf);
+    StatefulWidgetRef::render_ref(&list, popup_area, buf, &mut state.state);
+  }
+
+  type State = KillPopupState;
+}
@@ -0,0 +1,97 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  buffer::Buffer,
+  layout::{Alignment::Center, Rect},
+  style::Styled,
+  text::{Line, Span},
+  widgets::{Block, Borders, Clear, Paragraph, StatefulWidgetRef, Widget},
+};
+
+use crate::{action::Action, term::symbols};
+
+use super::{copy_popup::centered_popup_rect, theme::THEME};
+
+#[derive(Debug, Clone)]
+pub struct NewTracePopup;
+
+/// Where the command typed in the popup is run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CommandTarget {
+  /// Traced in a new tab
+  #[default]
+  NewTab,
+  /// Typed into the pseudo terminal, e.g. for the shell that runs in it
+  Terminal,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NewTracePopupState {
+  command: String,
+  pub target: CommandTarget,
+  /// The error of the last attempt, the popup stays open so that it can be retried
+  pub error: Option<String>,
+}
+
+impl NewTracePopupState {
+  /// Edit the command line of an exec before it is run again in the terminal
+  pub fn rerun(command: String) -> Self {
+    Self {
+      command,
+      target: CommandTarget::Terminal,
+      error: None,
+    }
+  }
+
+  pub fn handle_key_event(&mut self, ke: KeyEvent) -> Option<Action> {
+    match ke.code {
+      KeyCode::Esc => return Some(Action::CancelCurrentPopup),
+      KeyCode::Enter if !self.command.trim().is_empty() => {
+        return Some(match self.target {
+          CommandTarget::NewTab => Action::NewTrace(self.command.clone()),
+          CommandTarget::Terminal => Action::RunInTerminal(self.command.clone()),
+        })
+      }
+      KeyCode::Char('u') if ke.modifiers == KeyModifiers::CONTROL => {
+        self.command.clear();
+      }
+      KeyCode::Backspace => {
+        self.command.pop();
+      }
+      KeyCode::Char(c) if (ke.modifiers - KeyModifiers::SHIFT).is_empty() => {
+        self.command.push(c);
+      }
+      _ => return None,
+    }
+    self.error = None;
+    None
+  }
+}
+
+impl StatefulWidgetRef for NewTracePopup {
+  fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut NewTracePopupState) {
+    let mut lines = vec![Line::from(vec![
+      Span::raw("$ "),
+      Span::raw(state.command.as_str()),
+      symbols().cursor.into(),
+    ])];
+    if let Some(error) = state.error.as_ref() {
+      lines.push(Line::from(error.as_str().set_style(THEME.tracer_error)));
+    }
+    let popup_area = centered_popup_rect(60, lines.len() as u16, area);
+    Clear.render(popup_area, buf);
+    Paragraph::new(lines)
+      .block(
+        Block::default()
+          .title(match state.target {
+            CommandTarget::NewTab => "Trace in a new tab",
+            CommandTarget::Terminal => "Run in the terminal",
+          })
+          .title_alignment(Center)
+          .borders(Borders::ALL)
+          .border_style(THEME.popup_border),
+      )
+      .render(popup_area, buf);
+  }
+
+  type State = NewTracePopupState;
+}
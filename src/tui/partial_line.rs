@@ -1,83 +1,87 @@
-use std::borrow::Cow;
-
-use ratatui::text::Line;
+use ratatui::text::{Line, Span};
+use unicode_width::UnicodeWidthChar;
 
 pub trait PartialLine<'a> {
   fn substring(self, start: usize, len: u16) -> Line<'a>;
   #[allow(unused)]
   fn truncate_start(self, start: usize) -> Line<'a>;
+  /// Split the line into lines of at most `width` columns
+  fn wrap(self, width: u16) -> Vec<Line<'a>>;
 }
 
 impl<'a> PartialLine<'a> for Line<'a> {
-  // FIXME: Unicode is hard
-  fn substring(mut self, start: usize, len: u16) -> Line<'a> {
-    let len = len as usize;
-    let end = start + len;
-    let end = if end > self.width() {
-      self.width()
-    } else {
-      end
-    };
-    let mut cur = 0;
-    let mut discard_until = 0;
-    let mut discard_after = self.spans.len();
-    for (i, span) in self.spans.iter_mut().enumerate() {
-      let span_len = span.content.len();
-      if cur + span_len < start {
-        cur += span_len;
-        discard_until = i + 1;
-        continue;
-      }
-      if cur >= end {
-        discard_after = i;
-        break;
-      }
-      let start = if cur < start { start - cur } else { 0 };
-      let end = if cur + span_len > end {
-        end - cur
-      } else {
-        span_len
-      };
-      match span.content {
-        Cow::Borrowed(s) => {
-          span.content = Cow::Borrowed(&s[start..end]);
-        }
-        Cow::Owned(ref mut s) => {
-          s.drain(end..);
-          s.drain(..start);
-        }
-      }
-      cur += span_len;
-    }
-    self.spans.drain(discard_after..);
-    self.spans.drain(..discard_until);
+  /// Keep `len` columns starting from column `start`, wide characters that are cut in half are dropped
+  fn substring(self, start: usize, len: u16) -> Line<'a> {
+    columns(self, start, start + len as usize)
+  }
 
-    self
+  fn truncate_start(self, start: usize) -> Line<'a> {
+    columns(self, start, usize::MAX)
   }
 
-  fn truncate_start(mut self, start: usize) -> Line<'a> {
+  fn wrap(self, width: u16) -> Vec<Line<'a>> {
+    let width = (width as usize).max(1);
+    let mut lines = vec![Line::default()];
     let mut cur = 0;
-    let mut discard_until = 0;
-    for (i, span) in self.spans.iter_mut().enumerate() {
-      let span_len = span.content.len();
-      if cur + span_len < start {
-        cur += span_len;
-        discard_until = i + 1;
-        continue;
-      }
-      let start = if cur < start { start - cur } else { 0 };
-      match span.content {
-        Cow::Borrowed(s) => {
-          span.content = Cow::Borrowed(&s[start..]);
-        }
-        Cow::Owned(ref mut s) => {
-          s.drain(..start);
+    for span in self.spans {
+      let mut chunk = String::new();
+      for c in span.content.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if cur + char_width > width && cur > 0 {
+          if !chunk.is_empty() {
+            let chunk = std::mem::take(&mut chunk);
+            lines
+              .last_mut()
+              .unwrap()
+              .spans
+              .push(Span::styled(chunk, span.style));
+          }
+          lines.push(Line::default());
+          cur = 0;
         }
+        chunk.push(c);
+        cur += char_width;
+      }
+      if !chunk.is_empty() {
+        lines
+          .last_mut()
+          .unwrap()
+          .spans
+          .push(Span::styled(chunk, span.style));
       }
-      cur += span_len;
     }
-    self.spans.drain(..discard_until);
+    lines
+  }
+}
 
-    self
+/// Keep the columns from `start` up to `end` of the line
+fn columns(mut line: Line<'_>, start: usize, end: usize) -> Line<'_> {
+  let mut cur = 0;
+  let spans = std::mem::take(&mut line.spans);
+  for span in spans {
+    if cur >= end {
+      break;
+    }
+    let span_width = span.width();
+    if cur + span_width <= start {
+      cur += span_width;
+      continue;
+    }
+    if cur >= start && cur + span_width <= end {
+      // The span is kept as a whole, without copying it
+      cur += span_width;
+      line.spans.push(span);
+      continue;
+    }
+    let mut content = String::new();
+    for c in span.content.chars() {
+      let char_width = c.width().unwrap_or(0);
+      if cur >= start && cur + char_width <= end {
+        content.push(c);
+      }
+      cur += char_width;
+    }
+    line.spans.push(Span::styled(content, span.style));
   }
+  line
 }
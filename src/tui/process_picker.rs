@@ -0,0 +1,187 @@
+//! Picking a running process to attach to, when the TUI is launched without a command
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use nix::unistd::{getpid, Pid};
+use ratatui::{
+  buffer::Buffer,
+  layout::{Alignment::Center, Constraint, Layout, Rect},
+  style::Styled,
+  text::{Line, Span},
+  widgets::{
+    Block, Borders, Clear, HighlightSpacing, List, ListState, Paragraph, StatefulWidgetRef, Widget,
+  },
+};
+
+use crate::{action::Action, proc::read_argv, term::symbols};
+
+use super::theme::THEME;
+
+#[derive(Debug, Clone)]
+pub struct ProcessPicker;
+
+#[derive(Debug, Clone)]
+pub struct ProcessPickerState {
+  /// The pids and command lines of the processes, read when the popup is opened
+  processes: Vec<(Pid, String)>,
+  filter: String,
+  /// The indices of the processes that match the filter
+  matches: Vec<usize>,
+  state: ListState,
+  /// The error of the last attempt, the popup stays open so that another process can be picked
+  pub error: Option<String>,
+}
+
+impl Default for ProcessPickerState {
+  fn default() -> Self {
+    let mut state = Self {
+      processes: list_processes(),
+      filter: String::new(),
+      matches: vec![],
+      state: ListState::default(),
+      error: None,
+    };
+    state.update_matches();
+    state
+  }
+}
+
+impl ProcessPickerState {
+  /// Matches the pid or the command line, ignoring case
+  fn update_matches(&mut self) {
+    let filter = self.filter.to_lowercase();
+    self.matches = self
+      .processes
+      .iter()
+      .enumerate()
+      .filter(|(_, (pid, cmdline))| {
+        pid.to_string().contains(&filter) || cmdline.to_lowercase().contains(&filter)
+      })
+      .map(|(i, _)| i)
+      .collect();
+    self.state.select((!self.matches.is_empty()).then_some(0));
+  }
+
+  fn selected(&self) -> Option<&(Pid, String)> {
+    let i = *self.matches.get(self.state.selected()?)?;
+    self.processes.get(i)
+  }
+
+  pub fn handle_key_event(&mut self, ke: KeyEvent) -> Option<Action> {
+    let selected = self.state.selected().unwrap_or(0);
+    match (ke.code, ke.modifiers) {
+      (KeyCode::Esc, _) => return Some(Action::CancelCurrentPopup),
+      (KeyCode::Enter, _) => {
+        return self
+          .selected()
+          .map(|(pid, cmdline)| Action::Attach(*pid, cmdline.clone()))
+      }
+      (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+        if !self.matches.is_empty() {
+          self
+            .state
+            .select(Some((selected + 1).min(self.matches.len() - 1)));
+        }
+      }
+      (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+        if !self.matches.is_empty() {
+          self.state.select(Some(selected.saturating_sub(1)));
+        }
+      }
+      (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
+        self.processes = list_processes();
+        self.update_matches();
+      }
+      (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
+        self.filter.clear();
+        self.update_matches();
+      }
+      (KeyCode::Backspace, _) => {
+        self.filter.pop();
+        self.update_matches();
+      }
+      (KeyCode::Char(c), modifiers) if (modifiers - KeyModifiers::SHIFT).is_empty() => {
+        self.filter.push(c);
+        self.update_matches();
+      }
+      _ => return None,
+    }
+    self.error = None;
+    None
+  }
+}
+
+/// The processes in /proc, except for kernel threads and tracexec itself
+fn list_processes() -> Vec<(Pid, String)> {
+  let Ok(entries) = std::fs::read_dir("/proc") else {
+    return vec![];
+  };
+  let me = getpid();
+  let mut processes: Vec<(Pid, String)> = entries
+    .filter_map(|entry| {
+      let pid = Pid::from_raw(entry.ok()?.file_name().to_str()?.parse().ok()?);
+      if pid == me {
+        return None;
+      }
+      let argv = read_argv(pid).ok()?;
+      // Kernel threads have no command line
+      (!argv.is_empty()).then(|| {
+        let cmdline = argv
+          .iter()
+          .map(|arg| arg.to_string_lossy())
+          .collect::<Vec<_>>()
+          .join(" ");
+        (pid, cmdline)
+      })
+    })
+    .collect();
+  processes.sort_by_key(|(pid, _)| pid.as_raw());
+  processes
+}
+
+impl StatefulWidgetRef for ProcessPicker {
+  fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut ProcessPickerState) {
+    let popup_area = Rect {
+      x: area.x + area.width / 10,
+      y: area.y + area.height / 10,
+      width: area.width - area.width / 5,
+      height: area.height - area.height / 5,
+    };
+    Clear.render(popup_area, buf);
+    let block = Block::default()
+      .title(" Attach to a process ")
+      .title_alignment(Center)
+      .borders(Borders::ALL)
+      .border_style(THEME.popup_border);
+    let inner = block.inner(popup_area);
+    block.render(popup_area, buf);
+    let [filter_area, list_area] =
+      Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+    let filter_line = match state.error.as_ref() {
+      Some(error) => Line::from(error.as_str().set_style(THEME.tracer_error)),
+      None => Line::from(vec![
+        Span::raw("Filter: "),
+        Span::raw(state.filter.as_str()),
+        symbols().cursor.into(),
+      ]),
+    };
+    Paragraph::new(filter_line).render(filter_area, buf);
+    let items: Vec<Line> = state
+      .matches
+      .iter()
+      .map(|&i| {
+        let (pid, cmdline) = &state.processes[i];
+        Line::from(vec![
+          format!("{pid:>7} ").set_style(THEME.pid_in_msg),
+          cmdline.as_str().into(),
+        ])
+      })
+      .collect();
+    let list = List::new(items)
+      .highlight_style(THEME.selection)
+      .highlight_symbol(">")
+      .highlight_spacing(HighlightSpacing::Always);
+    StatefulWidgetRef::render_ref(&list, list_area, buf, &mut state.state);
+  }
+
+  type State = ProcessPickerState;
+}
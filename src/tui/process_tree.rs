@@ -0,0 +1,223 @@
+//! The pane that shows the hierarchy of the traced processes
+
+use std::collections::{HashMap, HashSet};
+
+use nix::unistd::Pid;
+use ratatui::{
+  buffer::Buffer,
+  layout::Rect,
+  style::Styled,
+  text::{Line, Span},
+  widgets::{HighlightSpacing, List, ListItem, ListState, StatefulWidget, Widget},
+};
+
+use crate::{
+  event::{ProcessExitStatus, TracerEvent},
+  term::symbols,
+};
+
+use super::theme::THEME;
+
+#[derive(Debug)]
+struct ProcessNode {
+  comm: String,
+  children: Vec<Pid>,
+  status: Option<ProcessExitStatus>,
+}
+
+#[derive(Debug, Default)]
+pub struct ProcessTree {
+  nodes: HashMap<Pid, ProcessNode>,
+  /// Processes whose parent is not traced, usually only the root process
+  roots: Vec<Pid>,
+  /// The pids in the order they are shown, updated on render
+  order: Vec<Pid>,
+  pub state: ListState,
+}
+
+impl ProcessTree {
+  pub fn on_event(&mut self, event: &TracerEvent) {
+    match event {
+      TracerEvent::TraceeSpawn(pid) => {
+        self.insert(*pid, None, String::new());
+      }
+      TracerEvent::NewChild {
+        ppid, pcomm, pid, ..
+      } => {
+        self.insert(*ppid, None, pcomm.clone());
+        // The child inherits the comm of its parent until it execs
+        self.insert(*pid, Some(*ppid), pcomm.clone());
+      }
+      TracerEvent::Exec(exec) => {
+        self.insert(exec.pid, exec.ppid, exec.comm.clone());
+        if exec.result == 0 {
+          if let Some(name) = exec
+            .filename
+            .as_ref()
+            .ok()
+            .and_then(|filename| filename.file_name())
+          {
+            let node = self.nodes.get_mut(&exec.pid).unwrap();
+            node.comm = name.to_string_lossy().into_owned();
+          }
+        }
+      }
+      TracerEvent::ProcessExit {
+        pid,
+        ppid,
+        comm,
+        status,
+        ..
+      } => {
+        self.insert(*pid, *ppid, comm.clone());
+        self.nodes.get_mut(pid).unwrap().status = Some(*status);
+      }
+      _ => {}
+    }
+  }
+
+  /// Insert the process into the tree if it isn't already there
+  fn insert(&mut self, pid: Pid, ppid: Option<Pid>, comm: String) {
+    if let Some(node) = self.nodes.get_mut(&pid) {
+      if node.comm.is_empty() {
+        node.comm = comm;
+      }
+      return;
+    }
+    self.nodes.insert(
+      pid,
+      ProcessNode {
+        comm,
+        children: vec![],
+        status: None,
+      },
+    );
+    match ppid.and_then(|ppid| self.nodes.get_mut(&ppid)) {
+      Some(parent) => parent.children.push(pid),
+      None => self.roots.push(pid),
+    }
+  }
+
+  /// Whether the process is traced and hasn't exited. Once it exited, its pid may belong to an unrelated process.
+  pub fn is_running(&self, pid: Pid) -> bool {
+    self
+      .nodes
+      .get(&pid)
+      .is_some_and(|node| node.status.is_none())
+  }
+
+  /// The child processes of the process, in the order they are created
+  pub fn children(&self, pid: Pid) -> &[Pid] {
+    self
+      .nodes
+      .get(&pid)
+      .map_or(&[], |node| node.children.as_slice())
+  }
+
+  /// All the descendants of the process, i.e. its children, their children and so on
+  pub fn descendants(&self, pid: Pid) -> HashSet<Pid> {
+    let mut descendants = HashSet::new();
+    let mut stack = self.children(pid).to_vec();
+    while let Some(pid) = stack.pop() {
+      if descendants.insert(pid) {
+        stack.extend_from_slice(self.children(pid));
+      }
+    }
+    descendants
+  }
+
+  /// returns the pid of the selected process if there is any
+  pub fn selection(&self) -> Option<Pid> {
+    self
+      .state
+      .selected()
+      .and_then(|i| self.order.get(i).copied())
+  }
+
+  pub fn next(&mut self) {
+    let i = match self.state.selected() {
+      Some(i) => (i + 1).min(self.order.len().saturating_sub(1)),
+      None => 0,
+    };
+    if !self.order.is_empty() {
+      self.state.select(Some(i));
+    }
+  }
+
+  /// Select the process shown at the row, counting from the top of the pane
+  pub fn select_row(&mut self, row: usize) {
+    let i = self.state.offset() + row;
+    if i < self.order.len() {
+      self.state.select(Some(i));
+    }
+  }
+
+  pub fn previous(&mut self) {
+    let i = self.state.selected().unwrap_or(0).saturating_sub(1);
+    if !self.order.is_empty() {
+      self.state.select(Some(i));
+    }
+  }
+
+  /// Flatten the subtree of `pid` into lines, depth first
+  fn flatten(
+    &self,
+    pid: Pid,
+    prefix: &str,
+    is_last: bool,
+    is_root: bool,
+    out: &mut Vec<(Pid, Line<'static>)>,
+  ) {
+    let node = &self.nodes[&pid];
+    let symbols = symbols();
+    let (guide, child_prefix) = match (is_root, is_last) {
+      (true, _) => (String::new(), String::new()),
+      (false, true) => (
+        format!("{prefix}{}", symbols.tree_last),
+        format!("{prefix}  "),
+      ),
+      (false, false) => (
+        format!("{prefix}{}", symbols.tree_branch),
+        format!("{prefix}{}", symbols.tree_vertical),
+      ),
+    };
+    let mut spans = vec![
+      guide.set_style(THEME.tree_guide),
+      pid.to_string().set_style(match node.status {
+        Some(status) if !status.is_success() => THEME.pid_failure,
+        _ => THEME.pid_success,
+      }),
+      " ".into(),
+      node.comm.clone().set_style(THEME.comm),
+    ];
+    if let Some(status) = node.status {
+      spans.push(Span::styled(format!(" ({status})"), THEME.process_status));
+    }
+    out.push((pid, Line::from(spans)));
+    for (i, child) in node.children.iter().enumerate() {
+      self.flatten(
+        *child,
+        &child_prefix,
+        i + 1 == node.children.len(),
+        false,
+        out,
+      );
+    }
+  }
+}
+
+impl Widget for &mut ProcessTree {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let mut lines = vec![];
+    for root in self.roots.iter() {
+      self.flatten(*root, "", true, true, &mut lines);
+    }
+    let (order, lines): (Vec<_>, Vec<_>) = lines.into_iter().unzip();
+    let list = List::new(lines.into_iter().map(ListItem::from))
+      .highlight_style(THEME.selection)
+      .highlight_symbol(">")
+      .highlight_spacing(HighlightSpacing::Always);
+    StatefulWidget::render(list, area, buf, &mut self.state);
+    self.order = order;
+  }
+}
@@ -28,7 +28,7 @@ use ratatui::prelude::{Buffer, Rect};
 use ratatui::widgets::Widget;
 use std::io::{BufWriter, Write};
 use std::sync::Arc;
-use tokio::sync::mpsc::channel;
+use tokio::sync::{mpsc::channel, Notify};
 use tracing::{trace, warn};
 use tui_term::widget::PseudoTerminal;
 
@@ -38,6 +38,33 @@ use std::sync::RwLock;
 
 use crate::pty::{MasterPty, PtySize, UnixMasterPty};
 
+use super::theme::THEME;
+
+/// A position in the pseudo terminal. The rows are counted from the top of the screen when
+/// it is scrolled to the bottom, so the rows in the scrollback history are negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct TermPos {
+  row: isize,
+  col: u16,
+}
+
+/// tmux style copy mode, for selecting text in the terminal and copying it
+#[derive(Debug, Clone, Copy)]
+struct CopyMode {
+  cursor: TermPos,
+  /// The other end of the selection, if text is being selected
+  anchor: Option<TermPos>,
+}
+
+impl CopyMode {
+  /// The start and the end of the selection, both inclusive
+  fn selection(&self) -> Option<(TermPos, TermPos)> {
+    self
+      .anchor
+      .map(|anchor| (anchor.min(self.cursor), anchor.max(self.cursor)))
+  }
+}
+
 pub struct PseudoTerminalPane {
   // cannot move out of `parser` because it is borrowed
   // term: PseudoTerminal<'a, Screen>,
@@ -50,20 +77,83 @@ pub struct PseudoTerminalPane {
   master_tx: tokio::sync::mpsc::Sender<Bytes>,
   master_cancellation_token: CancellationToken,
   size: PtySize,
+  /// The size given with --pty-size is kept instead of following the size of the pane
+  pub fixed_size: bool,
+  copy_mode: Option<CopyMode>,
 }
 
 const ESCAPE: u8 = 27;
+const DELETE: u8 = 127;
+
+/// The queries about the state of the terminal that are answered
+const QUERIES: &[(&[u8], Query)] = &[
+  (b"\x1b[6n", Query::CursorPosition),
+  (b"\x1b[5n", Query::Status),
+  (b"\x1b[c", Query::DeviceAttributes),
+  (b"\x1b[0c", Query::DeviceAttributes),
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Query {
+  CursorPosition,
+  Status,
+  DeviceAttributes,
+}
+
+/// Process the output of the programs, answering the queries in it,
+/// since some programs wait for the answers, e.g. for the cursor position.
+///
+/// vt100 only keeps the state of the screen, so the queries are answered here.
+fn process_and_answer(parser: &mut vt100::Parser, mut bytes: &[u8]) -> Vec<u8> {
+  let mut answers = vec![];
+  loop {
+    let next_query = QUERIES
+      .iter()
+      .filter_map(|(query, kind)| {
+        bytes
+          .windows(query.len())
+          .position(|window| window == *query)
+          .map(|start| (start + query.len(), *kind))
+      })
+      .min_by_key(|(end, _)| *end);
+    let Some((end, query)) = next_query else {
+      break;
+    };
+    // The cursor position is the one at the time of the query
+    parser.process(&bytes[..end]);
+    match query {
+      Query::CursorPosition => {
+        let (row, col) = parser.screen().cursor_position();
+        answers.extend(format!("\x1b[{};{}R", row + 1, col + 1).into_bytes());
+      }
+      Query::Status => answers.extend(b"\x1b[0n"),
+      // A VT100 with advanced video option, like xterm answers by default
+      Query::DeviceAttributes => answers.extend(b"\x1b[?1;2c"),
+    }
+    bytes = &bytes[end..];
+  }
+  parser.process(bytes);
+  answers
+}
 
 impl PseudoTerminalPane {
-  pub fn new(size: PtySize, pty_master: UnixMasterPty) -> color_eyre::Result<Self> {
-    let parser = vt100::Parser::new(size.rows, size.cols, 0);
+  pub fn new(
+    size: PtySize,
+    pty_master: UnixMasterPty,
+    scrollback: usize,
+    activity: Arc<Notify>,
+  ) -> color_eyre::Result<Self> {
+    let parser = vt100::Parser::new(size.rows, size.cols, scrollback);
     // let screen = parser.screen();
     let parser = Arc::new(RwLock::new(parser));
     // let term = PseudoTerminal::new(screen);
 
+    let (tx, mut rx) = channel::<Bytes>(32);
+
     let reader_task = {
       let mut reader = pty_master.try_clone_reader()?;
       let parser = parser.clone();
+      let tx = tx.clone();
       tokio::spawn(async move {
         let mut processed_buf = Vec::new();
         let mut buf = [0u8; 8192];
@@ -76,7 +166,12 @@ impl PseudoTerminalPane {
           if size > 0 {
             processed_buf.extend_from_slice(&buf[..size]);
             let mut parser = parser.write().unwrap();
-            parser.process(&processed_buf);
+            let answers = process_and_answer(&mut parser, &processed_buf);
+            if !answers.is_empty() {
+              tx.try_send(Bytes::from(answers)).ok();
+            }
+            // The output is shown right away even when the TUI renders less often while idle
+            activity.notify_one();
 
             // Clear the processed portion of the buffer
             processed_buf.clear();
@@ -86,7 +181,6 @@ impl PseudoTerminalPane {
       })
     };
 
-    let (tx, mut rx) = channel::<Bytes>(32);
     let master_cancellation_token = CancellationToken::new();
 
     let writer_task = {
@@ -111,16 +205,53 @@ impl PseudoTerminalPane {
     Ok(Self {
       // term,
       size,
+      fixed_size: false,
       parser,
       pty_master,
       reader_task,
       writer_task,
       master_tx: tx,
       master_cancellation_token,
+      copy_mode: None,
     })
   }
 
-  pub async fn handle_key_event(&self, key: &KeyEvent) -> bool {
+  /// Send the key to the terminal, or handle it in copy mode.
+  ///
+  /// Returns the text to copy to the clipboard when it is yanked in copy mode.
+  pub async fn handle_key_event(&mut self, key: &KeyEvent) -> Option<String> {
+    if self.copy_mode.is_some() {
+      return self.handle_copy_mode_key_event(key);
+    }
+    if key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::ALT {
+      self.enter_copy_mode();
+      return None;
+    }
+    let page = self.size.rows as isize;
+    if key.modifiers == KeyModifiers::SHIFT {
+      let lines = match key.code {
+        KeyCode::PageUp => Some(page),
+        KeyCode::PageDown => Some(-page),
+        KeyCode::Home => Some(isize::MAX),
+        KeyCode::End => Some(isize::MIN),
+        _ => None,
+      };
+      if let Some(lines) = lines {
+        self.scroll(lines);
+        return None;
+      }
+    }
+    // Typing returns to the bottom, like in other terminals
+    self.scroll(isize::MIN);
+    // Full screen programs switch the cursor keys to application mode, e.g. `ESC O A` for up
+    let application_cursor = self.parser.read().unwrap().screen().application_cursor();
+    let cursor_key = |key| {
+      if application_cursor {
+        vec![ESCAPE, b'O', key]
+      } else {
+        vec![ESCAPE, b'[', key]
+      }
+    };
     let input_bytes = match key.code {
       KeyCode::Char(ch) => {
         let mut send = vec![ch as u8];
@@ -137,15 +268,16 @@ impl PseudoTerminalPane {
         }
         send
       }
-      KeyCode::Enter => vec![b'\n'],
-      KeyCode::Backspace => vec![8],
-      KeyCode::Left => vec![ESCAPE, b'[', b'D'],
-      KeyCode::Right => vec![ESCAPE, b'[', b'C'],
-      KeyCode::Up => vec![ESCAPE, b'[', b'A'],
-      KeyCode::Down => vec![ESCAPE, b'[', b'B'],
+      KeyCode::Enter => vec![b'\r'],
+      KeyCode::Backspace if key.modifiers == KeyModifiers::ALT => vec![ESCAPE, DELETE],
+      KeyCode::Backspace => vec![DELETE],
+      KeyCode::Left => cursor_key(b'D'),
+      KeyCode::Right => cursor_key(b'C'),
+      KeyCode::Up => cursor_key(b'A'),
+      KeyCode::Down => cursor_key(b'B'),
       KeyCode::Tab => vec![b'\t'],
-      KeyCode::Home => vec![ESCAPE, b'O', b'H'],
-      KeyCode::End => vec![ESCAPE, b'O', b'F'],
+      KeyCode::Home => cursor_key(b'H'),
+      KeyCode::End => cursor_key(b'F'),
       KeyCode::PageUp => vec![ESCAPE, b'[', b'5', b'~'],
       KeyCode::PageDown => vec![ESCAPE, b'[', b'6', b'~'],
       KeyCode::BackTab => vec![ESCAPE, b'[', b'Z'],
@@ -167,17 +299,154 @@ impl PseudoTerminalPane {
       KeyCode::F(n) => {
         // TODO: Handle Other F keys
         warn!("Unhandled F key: {}", n);
-        return true;
+        return None;
       }
-      _ => return true,
+      _ => return None,
     };
 
     self.master_tx.send(Bytes::from(input_bytes)).await.ok();
-    true
+    None
+  }
+
+  pub fn is_in_copy_mode(&self) -> bool {
+    self.copy_mode.is_some()
+  }
+
+  pub fn has_selection(&self) -> bool {
+    self.copy_mode.is_some_and(|c| c.anchor.is_some())
+  }
+
+  /// Enter copy mode with the cursor at the cursor of the terminal
+  fn enter_copy_mode(&mut self) {
+    let parser = self.parser.read().unwrap();
+    let screen = parser.screen();
+    let (row, col) = screen.cursor_position();
+    self.copy_mode = Some(CopyMode {
+      cursor: TermPos {
+        row: row as isize - screen.scrollback() as isize,
+        col,
+      },
+      anchor: None,
+    });
   }
 
+  pub fn exit_copy_mode(&mut self) {
+    if self.copy_mode.take().is_some() {
+      self.scroll(isize::MIN);
+    }
+  }
+
+  /// Select the text between the positions on the screen, entering copy mode
+  pub fn select(&mut self, from: (u16, u16), to: (u16, u16)) {
+    let offset = self.scrollback_offset() as isize;
+    let pos = |(row, col): (u16, u16)| TermPos {
+      row: row as isize - offset,
+      col,
+    };
+    self.copy_mode = Some(CopyMode {
+      cursor: pos(to),
+      anchor: Some(pos(from)),
+    });
+  }
+
+  /// The selected text, leaving copy mode
+  pub fn yank(&mut self) -> Option<String> {
+    let text = self.selected_text();
+    self.exit_copy_mode();
+    text
+  }
+
+  fn selected_text(&self) -> Option<String> {
+    let (start, end) = self.copy_mode?.selection()?;
+    let mut parser = self.parser.write().unwrap();
+    let offset = parser.screen().scrollback();
+    let mut lines = vec![];
+    for row in start.row..=end.row {
+      let first = if row == start.row { start.col } else { 0 };
+      let last = if row == end.row {
+        end.col + 1
+      } else {
+        self.size.cols
+      };
+      // Scroll the row into view to read it
+      let row_offset = (-row).max(0) as usize;
+      parser.set_scrollback(row_offset);
+      let visible_row = (row + row_offset as isize) as u16;
+      let line = parser
+        .screen()
+        .contents_between(visible_row, first, visible_row, last);
+      lines.push(line.trim_end().to_owned());
+    }
+    parser.set_scrollback(offset);
+    Some(lines.join("\n"))
+  }
+
+  /// The number of lines in the scrollback history
+  fn history_len(&self) -> usize {
+    let mut parser = self.parser.write().unwrap();
+    let offset = parser.screen().scrollback();
+    parser.set_scrollback(usize::MAX);
+    let len = parser.screen().scrollback();
+    parser.set_scrollback(offset);
+    len
+  }
+
+  fn handle_copy_mode_key_event(&mut self, key: &KeyEvent) -> Option<String> {
+    let mut copy_mode = self.copy_mode?;
+    let rows = self.size.rows as isize;
+    match (key.code, key.modifiers - KeyModifiers::SHIFT) {
+      (KeyCode::Char('q') | KeyCode::Esc, KeyModifiers::NONE) => {
+        self.exit_copy_mode();
+        return None;
+      }
+      (KeyCode::Char('y') | KeyCode::Enter, KeyModifiers::NONE) => return self.yank(),
+      (KeyCode::Char('v') | KeyCode::Char(' '), KeyModifiers::NONE) => {
+        copy_mode.anchor = match copy_mode.anchor {
+          Some(_) => None,
+          None => Some(copy_mode.cursor),
+        };
+      }
+      (KeyCode::Left | KeyCode::Char('h'), KeyModifiers::NONE) => {
+        copy_mode.cursor.col = copy_mode.cursor.col.saturating_sub(1)
+      }
+      (KeyCode::Right | KeyCode::Char('l'), KeyModifiers::NONE) => copy_mode.cursor.col += 1,
+      (KeyCode::Up | KeyCode::Char('k'), KeyModifiers::NONE) => copy_mode.cursor.row -= 1,
+      (KeyCode::Down | KeyCode::Char('j'), KeyModifiers::NONE) => copy_mode.cursor.row += 1,
+      (KeyCode::Home | KeyCode::Char('0'), KeyModifiers::NONE) => copy_mode.cursor.col = 0,
+      (KeyCode::End | KeyCode::Char('$'), KeyModifiers::NONE) => copy_mode.cursor.col = u16::MAX,
+      (KeyCode::PageUp, _) | (KeyCode::Char('b'), KeyModifiers::CONTROL) => {
+        copy_mode.cursor.row -= rows
+      }
+      (KeyCode::PageDown, _) | (KeyCode::Char('f'), KeyModifiers::CONTROL) => {
+        copy_mode.cursor.row += rows
+      }
+      (KeyCode::Char('g'), KeyModifiers::NONE) => copy_mode.cursor.row = isize::MIN,
+      (KeyCode::Char('G'), KeyModifiers::NONE) => copy_mode.cursor.row = isize::MAX,
+      _ => {}
+    }
+    let cursor = &mut copy_mode.cursor;
+    cursor.row = cursor.row.clamp(-(self.history_len() as isize), rows - 1);
+    cursor.col = cursor.col.min(self.size.cols.saturating_sub(1));
+    // Keep the cursor on the screen
+    let offset = self.scrollback_offset() as isize;
+    if cursor.row + offset < 0 {
+      self.scroll(-cursor.row - offset);
+    } else if cursor.row + offset >= rows {
+      self.scroll(rows - 1 - cursor.row - offset);
+    }
+    self.copy_mode = Some(copy_mode);
+    None
+  }
+
+  /// Send the text to the terminal as if it is typed
+  pub async fn send_text(&self, text: &str) {
+    self.scroll(isize::MIN);
+    self.master_tx.send(Bytes::from(text.to_owned())).await.ok();
+  }
+
+  /// The kernel sends SIGWINCH to the foreground process group of the pseudo terminal
   pub fn resize(&mut self, size: PtySize) -> color_eyre::Result<()> {
-    if size == self.size {
+    if self.fixed_size || size == self.size {
       return Ok(());
     }
     self.size = size;
@@ -187,6 +456,19 @@ impl PseudoTerminalPane {
     Ok(())
   }
 
+  /// Scroll the view by `lines`, positive values go back into the scrollback history
+  pub fn scroll(&self, lines: isize) {
+    let mut parser = self.parser.write().unwrap();
+    let offset = parser.screen().scrollback().saturating_add_signed(lines);
+    // vt100 limits the offset to the length of the scrollback history
+    parser.set_scrollback(offset);
+  }
+
+  /// How many lines the view is scrolled back from the bottom
+  pub fn scrollback_offset(&self) -> usize {
+    self.parser.read().unwrap().screen().scrollback()
+  }
+
   /// Closes pty master
   pub fn exit(&self) {
     self.master_cancellation_token.cancel()
@@ -201,5 +483,26 @@ impl Widget for &PseudoTerminalPane {
     let parser = self.parser.read().unwrap();
     let pseudo_term = PseudoTerminal::new(parser.screen());
     pseudo_term.render(area, buf);
+    let Some(copy_mode) = self.copy_mode else {
+      return;
+    };
+    let offset = parser.screen().scrollback() as isize;
+    let selection = copy_mode.selection();
+    for y in 0..area.height.min(self.size.rows) {
+      for x in 0..area.width.min(self.size.cols) {
+        let pos = TermPos {
+          row: y as isize - offset,
+          col: x,
+        };
+        let style = if pos == copy_mode.cursor {
+          THEME.copy_mode_cursor
+        } else if selection.is_some_and(|(start, end)| (start..=end).contains(&pos)) {
+          THEME.copy_mode_selection
+        } else {
+          continue;
+        };
+        buf.get_mut(area.x + x, area.y + y).set_style(style);
+      }
+    }
   }
 }
@@ -0,0 +1,336 @@
+use std::{
+  ops::Range,
+  time::{Duration, Instant},
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use nix::unistd::Pid;
+use ratatui::{
+  buffer::Buffer,
+  layout::Rect,
+  style::Styled,
+  text::{Line, Span},
+  widgets::{Paragraph, Widget},
+};
+use regex::{Regex, RegexBuilder};
+
+use crate::{
+  action::{Action, GoToTarget},
+  term::symbols,
+};
+
+use super::{event_list::ListFilter, help::help_desc, theme::THEME};
+
+/// Filtering a long event list is slow, so the filter waits until typing pauses
+const FILTER_DEBOUNCE: Duration = Duration::from_millis(150);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchPurpose {
+  /// Highlight the matches and jump between them, started with `/`
+  Search,
+  /// Only show the matching events, started with `f`. The filter is applied when typing pauses.
+  Filter,
+  /// Notify when an exec matching the pattern occurs
+  Watch,
+  /// Stop the tracer at the execs matching the pattern
+  Breakpoint,
+  /// Jump to an event by its number or to an event of a pid, started with `:`
+  GoTo,
+}
+
+/// The prompt shown at the bottom of the screen for searching or filtering the event list
+#[derive(Debug, Clone)]
+pub struct SearchInput {
+  purpose: SearchPurpose,
+  text: String,
+  /// Whether the text is a regex or plain text
+  regex: bool,
+  /// Whether the text of a filter is a filter expression, e.g. `comm == "rustc"`
+  expr: bool,
+  /// Whether the matching events of a watch are selected
+  select: bool,
+  error: Option<String>,
+  /// The filter that is applied once typing pauses, and when it was typed
+  pending_filter: Option<(Option<ListFilter>, Instant)>,
+}
+
+impl SearchInput {
+  pub fn new(purpose: SearchPurpose) -> Self {
+    Self {
+      purpose,
+      text: String::new(),
+      regex: false,
+      expr: false,
+      select: false,
+      error: None,
+      pending_filter: None,
+    }
+  }
+
+  pub fn handle_key_event(&mut self, ke: KeyEvent) -> Option<Action> {
+    if self.purpose == SearchPurpose::Filter {
+      return self.handle_filter_key_event(ke);
+    }
+    match ke.code {
+      KeyCode::Esc => return Some(Action::CancelSearchInput),
+      KeyCode::Enter if self.text.is_empty() => {
+        return Some(match self.purpose {
+          SearchPurpose::Watch => Action::ClearWatches,
+          SearchPurpose::Breakpoint => Action::ClearBreakpoints,
+          SearchPurpose::GoTo => Action::CancelSearchInput,
+          _ => Action::ClearSearch,
+        })
+      }
+      KeyCode::Enter if self.purpose == SearchPurpose::GoTo => match parse_go_to(&self.text) {
+        Ok(target) => return Some(Action::GoTo(target)),
+        Err(e) => {
+          self.error = Some(e);
+          return None;
+        }
+      },
+      KeyCode::Enter => match self.build() {
+        Ok(regex) => {
+          return Some(match self.purpose {
+            SearchPurpose::Watch => Action::AddWatch {
+              regex,
+              select: self.select,
+            },
+            SearchPurpose::Breakpoint => Action::AddBreakpoint(regex),
+            _ => Action::Search(regex),
+          })
+        }
+        Err(e) => {
+          // The last line is the most informative one of the multi-line regex errors
+          self.error = e.to_string().lines().last().map(ToOwned::to_owned);
+          return None;
+        }
+      },
+      KeyCode::Char('r') if ke.modifiers == KeyModifiers::CONTROL => {
+        self.regex = !self.regex;
+      }
+      KeyCode::Char('a')
+        if ke.modifiers == KeyModifiers::CONTROL && self.purpose == SearchPurpose::Watch =>
+      {
+        self.select = !self.select;
+      }
+      KeyCode::Backspace => {
+        self.text.pop();
+      }
+      KeyCode::Char(c) if (ke.modifiers - KeyModifiers::SHIFT).is_empty() => {
+        self.text.push(c);
+      }
+      _ => return None,
+    }
+    self.error = None;
+    None
+  }
+
+  fn handle_filter_key_event(&mut self, ke: KeyEvent) -> Option<Action> {
+    match ke.code {
+      KeyCode::Esc => return Some(Action::ClearFilter),
+      // Keep the prompt open so that the error can be fixed
+      KeyCode::Enter if self.error.is_some() => return None,
+      KeyCode::Enter => return Some(Action::CancelSearchInput),
+      KeyCode::Char('r') if ke.modifiers == KeyModifiers::CONTROL => {
+        self.regex = !self.regex;
+      }
+      KeyCode::Char('e') if ke.modifiers == KeyModifiers::CONTROL => {
+        self.expr = !self.expr;
+      }
+      KeyCode::Backspace => {
+        self.text.pop();
+      }
+      KeyCode::Char(c) if (ke.modifiers - KeyModifiers::SHIFT).is_empty() => {
+        self.text.push(c);
+      }
+      _ => return None,
+    }
+    if self.text.is_empty() {
+      self.error = None;
+      self.pending_filter = Some((None, Instant::now()));
+      return None;
+    }
+    match self.build_filter() {
+      Ok(filter) => {
+        self.error = None;
+        self.pending_filter = Some((Some(filter), Instant::now()));
+      }
+      Err(e) => {
+        self.error = Some(e);
+        self.pending_filter = None;
+      }
+    }
+    None
+  }
+
+  /// The typed filter, once typing has paused for long enough
+  pub fn poll_filter(&mut self) -> Option<Option<ListFilter>> {
+    if self
+      .pending_filter
+      .as_ref()
+      .is_some_and(|(_, typed_at)| typed_at.elapsed() >= FILTER_DEBOUNCE)
+    {
+      self.take_pending_filter()
+    } else {
+      None
+    }
+  }
+
+  /// The typed filter that is not applied yet, when the prompt is closed
+  pub fn take_pending_filter(&mut self) -> Option<Option<ListFilter>> {
+    self.pending_filter.take().map(|(filter, _)| filter)
+  }
+
+  fn build_filter(&self) -> Result<ListFilter, String> {
+    if self.expr {
+      return self.text.parse().map(ListFilter::Expr);
+    }
+    self
+      .build()
+      .map(ListFilter::Regex)
+      .map_err(|e| e.to_string().lines().last().unwrap_or_default().to_owned())
+  }
+
+  /// Show an error that is found after the input is submitted, e.g. the event does not exist
+  pub fn set_error(&mut self, error: String) {
+    self.error = Some(error);
+  }
+
+  /// The search is case insensitive unless the text contains upper case characters
+  fn build(&self) -> Result<Regex, regex::Error> {
+    let pattern = if self.regex {
+      self.text.clone()
+    } else {
+      regex::escape(&self.text)
+    };
+    RegexBuilder::new(&pattern)
+      .case_insensitive(!self.text.chars().any(char::is_uppercase))
+      .build()
+  }
+}
+
+impl Widget for &SearchInput {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let mut spans = vec![
+      match self.purpose {
+        SearchPurpose::Search => "/",
+        SearchPurpose::Filter => "&",
+        SearchPurpose::Watch => "Watch: ",
+        SearchPurpose::Breakpoint => "Break at: ",
+        SearchPurpose::GoTo => ":",
+      }
+      .set_style(THEME.search_prompt),
+      Span::raw(self.text.as_str()),
+      symbols().cursor.into(),
+      " ".into(),
+    ];
+    if self.purpose == SearchPurpose::GoTo {
+      spans.push(help_desc("<number> or pid <pid> [first|last]"));
+    } else if self.expr {
+      spans.push(help_desc("Ctrl+E: Expression"));
+    } else {
+      spans.push(help_desc(if self.regex {
+        "Ctrl+R: Regex"
+      } else {
+        "Ctrl+R: Plain Text"
+      }));
+    }
+    if self.purpose == SearchPurpose::Filter && !self.expr {
+      spans.extend([" ".into(), help_desc("Ctrl+E: Match Lines")]);
+    }
+    if self.purpose == SearchPurpose::Watch {
+      spans.extend([
+        " ".into(),
+        help_desc(if self.select {
+          "Ctrl+A: Select Matches"
+        } else {
+          "Ctrl+A: Only Notify"
+        }),
+      ]);
+    }
+    if let Some(error) = self.error.as_deref() {
+      spans.push(" ".into());
+      spans.push(error.set_style(THEME.inline_tracer_error));
+    }
+    Paragraph::new(Line::from(spans)).render(area, buf);
+  }
+}
+
+/// Parse the input of the go-to prompt, e.g. `1234`, `pid 4321` or `pid 4321 last`
+fn parse_go_to(text: &str) -> Result<GoToTarget, String> {
+  let words: Vec<&str> = text.split_whitespace().collect();
+  match words[..] {
+    ["pid", pid, ref which @ ..] if which.len() <= 1 => {
+      let pid = pid
+        .parse()
+        .map(Pid::from_raw)
+        .map_err(|_| format!("Invalid pid {pid:?}"))?;
+      match which {
+        [] | ["first"] => Ok(GoToTarget::FirstOf(pid)),
+        ["last"] => Ok(GoToTarget::LastOf(pid)),
+        _ => Err("Expected first or last after the pid".to_string()),
+      }
+    }
+    [number] => number
+      .trim_start_matches('#')
+      .parse()
+      .ok()
+      .filter(|&number| number > 0)
+      .map(GoToTarget::Event)
+      .ok_or_else(|| format!("Invalid event number {number:?}")),
+    _ => Err("Expected an event number or pid <pid> [first|last]".to_string()),
+  }
+}
+
+/// Patch the style of the parts of the line that match the regex
+pub fn highlight_matches(line: Line<'static>, regex: &Regex) -> Line<'static> {
+  let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+  let mut matches = regex
+    .find_iter(&text)
+    .map(|m| m.range())
+    .filter(|r| !r.is_empty())
+    .peekable();
+  if matches.peek().is_none() {
+    return line;
+  }
+  let Line {
+    spans: old_spans,
+    style,
+    alignment,
+  } = line;
+  let mut spans = Vec::with_capacity(old_spans.len());
+  let mut offset = 0;
+  for span in old_spans {
+    let (start, end) = (offset, offset + span.content.len());
+    offset = end;
+    // Split the span at the boundaries of the matches
+    let mut pos = start;
+    while pos < end {
+      while matches.peek().is_some_and(|m| m.end <= pos) {
+        matches.next();
+      }
+      let (next, is_match) = match matches.peek() {
+        Some(Range {
+          start: m_start,
+          end: m_end,
+        }) if *m_start <= pos => ((*m_end).min(end), true),
+        Some(Range { start: m_start, .. }) => ((*m_start).min(end), false),
+        None => (end, false),
+      };
+      spans.push(Span::styled(
+        span.content[pos - start..next - start].to_owned(),
+        if is_match {
+          span.style.patch(THEME.search_match)
+        } else {
+          span.style
+        },
+      ));
+      pos = next;
+    }
+  }
+  Line {
+    spans,
+    style,
+    alignment,
+  }
+}
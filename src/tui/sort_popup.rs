@@ -0,0 +1,102 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  buffer::Buffer,
+  layout::{Alignment::Center, Rect},
+  widgets::{Block, Borders, Clear, HighlightSpacing, List, ListState, StatefulWidgetRef, Widget},
+};
+
+use crate::action::Action;
+
+use super::{
+  copy_popup::centered_popup_rect,
+  event_list::{EventSort, SortKey},
+  theme::THEME,
+};
+
+#[derive(Debug, Clone)]
+pub struct SortPopup;
+
+#[derive(Debug, Clone)]
+pub struct SortPopupState {
+  /// The first item is the order the events arrived in, followed by the sort keys
+  state: ListState,
+  descending: bool,
+}
+
+impl SortPopupState {
+  pub fn new(sort: Option<EventSort>) -> Self {
+    let mut state = ListState::default();
+    state.select(Some(
+      sort
+        .and_then(|sort| SortKey::ALL.iter().position(|key| *key == sort.key))
+        .map_or(0, |i| i + 1),
+    ));
+    Self {
+      state,
+      descending: sort.is_some_and(|sort| sort.descending),
+    }
+  }
+
+  fn selected(&self) -> usize {
+    self.state.selected().unwrap_or(0)
+  }
+
+  fn sort(&self) -> Option<EventSort> {
+    let key = *SortKey::ALL.get(self.selected().checked_sub(1)?)?;
+    Some(EventSort {
+      key,
+      descending: self.descending,
+    })
+  }
+
+  pub fn handle_key_event(&mut self, ke: KeyEvent) -> Option<Action> {
+    if ke.modifiers != KeyModifiers::NONE {
+      return None;
+    }
+    match ke.code {
+      KeyCode::Char('q') | KeyCode::Esc => return Some(Action::CancelCurrentPopup),
+      KeyCode::Down | KeyCode::Char('j') => {
+        self
+          .state
+          .select(Some((self.selected() + 1).min(SortKey::ALL.len())));
+      }
+      KeyCode::Up | KeyCode::Char('k') => {
+        self.state.select(Some(self.selected().saturating_sub(1)));
+      }
+      KeyCode::Char(' ') => self.descending = !self.descending,
+      KeyCode::Enter => return Some(Action::SetSort(self.sort())),
+      _ => {}
+    }
+    None
+  }
+}
+
+impl StatefulWidgetRef for SortPopup {
+  fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut SortPopupState) {
+    let arrow = if state.descending { "↓" } else { "↑" };
+    let items: Vec<String> = ["Arrival order".to_string()]
+      .into_iter()
+      .chain(
+        SortKey::ALL
+          .iter()
+          .map(|key| format!("{} {arrow}", key.name())),
+      )
+      .collect();
+    let list = List::new(items)
+      .block(
+        Block::default()
+          .title("Sort by")
+          .title_alignment(Center)
+          .borders(Borders::ALL)
+          .border_style(THEME.popup_border),
+      )
+      .highlight_style(THEME.selection)
+      .highlight_symbol(">")
+      .highlight_spacing(HighlightSpacing::Always);
+    let popup_area = centered_popup_rect(16, list.len() as u16, area);
+    Clear.render(popup_area, buf);
+    StatefulWidgetRef::render_ref(&list, popup_area, buf, &mut state.state);
+  }
+
+  type State = SortPopupState;
+}
@@ -0,0 +1,66 @@
+//! The state of the TUI that is remembered across sessions,
+//! which is stored at `~/.local/share/tracexec/state.toml` by default.
+
+use std::path::PathBuf;
+
+use color_eyre::eyre::WrapErr;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{cli::options::ActivePane, log::get_data_dir};
+
+use super::{app::AppLayout, event_list::EventColumn};
+
+const STATE_FILE: &str = "state.toml";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TuiState {
+  /// The percentage of the panes taken by the event list when the pseudo terminal is shown
+  pub split_percentage: Option<u16>,
+  pub layout: Option<AppLayout>,
+  pub active_pane: Option<ActivePane>,
+  pub follow: Option<bool>,
+  /// The columns of the event list, which take precedence over the ones in the config
+  pub columns: Option<Vec<EventColumn>>,
+  /// The filter of the event list when the TUI exited, either a regex or an expression
+  pub filter: Option<String>,
+  pub filter_expr: Option<String>,
+}
+
+impl TuiState {
+  fn path() -> PathBuf {
+    get_data_dir().join(STATE_FILE)
+  }
+
+  /// Load the state, a missing or broken state file is the same as an empty one
+  pub fn load() -> Self {
+    let Ok(content) = std::fs::read_to_string(Self::path()) else {
+      return Self::default();
+    };
+    toml::from_str(&content).unwrap_or_else(|e| {
+      warn!("Ignoring the broken state file: {e}");
+      Self::default()
+    })
+  }
+
+  /// Forget the last session, so that the TUI starts from the command line and the config
+  pub fn reset() -> color_eyre::Result<()> {
+    let path = Self::path();
+    match std::fs::remove_file(&path) {
+      Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+        Err(e).wrap_err_with(|| format!("Failed to remove state file {path:?}"))
+      }
+      _ => Ok(()),
+    }
+  }
+
+  pub fn save(&self) -> color_eyre::Result<()> {
+    let path = Self::path();
+    if let Some(dir) = path.parent() {
+      std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, toml::to_string(self)?)
+      .wrap_err_with(|| format!("Failed to write state file {path:?}"))
+  }
+}
@@ -0,0 +1,165 @@
+//! The statistics view, which shows how often each program is executed and how long it runs
+
+use std::{
+  collections::HashMap,
+  time::{Duration, SystemTime},
+};
+
+use nix::unistd::Pid;
+use ratatui::{
+  buffer::Buffer,
+  layout::{Constraint, Rect},
+  text::Line,
+  widgets::{Row, StatefulWidget, Table, TableState, Widget},
+};
+
+use crate::event::TracerEvent;
+
+use super::theme::THEME;
+
+#[derive(Debug, Default)]
+struct ProgramStats {
+  execs: u64,
+  failed_execs: u64,
+  /// Runtime of the finished runs of the program
+  runtime: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatsOrder {
+  #[default]
+  Execs,
+  Runtime,
+}
+
+#[derive(Debug, Default)]
+pub struct Stats {
+  programs: HashMap<String, ProgramStats>,
+  /// The program that each alive process is running and when it started
+  running: HashMap<Pid, (String, SystemTime)>,
+  pub order: StatsOrder,
+  pub state: TableState,
+}
+
+impl Stats {
+  pub fn on_event(&mut self, event: &TracerEvent) {
+    match event {
+      TracerEvent::Exec(exec) => {
+        let program = TracerEvent::filename_to_cow(&exec.filename).into_owned();
+        let stats = self.programs.entry(program.clone()).or_default();
+        stats.execs += 1;
+        if exec.result != 0 {
+          stats.failed_execs += 1;
+          return;
+        }
+        // The previous program of the process stops running when it execs
+        if let Some((previous, start)) = self.running.insert(exec.pid, (program, exec.timestamp)) {
+          self.add_runtime(&previous, start, exec.timestamp);
+        }
+      }
+      TracerEvent::ProcessExit { pid, timestamp, .. } => {
+        if let Some((program, start)) = self.running.remove(pid) {
+          self.add_runtime(&program, start, *timestamp);
+        }
+      }
+      _ => {}
+    }
+  }
+
+  fn add_runtime(&mut self, program: &str, start: SystemTime, end: SystemTime) {
+    if let Some(stats) = self.programs.get_mut(program) {
+      stats.runtime += end.duration_since(start).unwrap_or_default();
+    }
+  }
+
+  pub fn toggle_order(&mut self) {
+    self.order = match self.order {
+      StatsOrder::Execs => StatsOrder::Runtime,
+      StatsOrder::Runtime => StatsOrder::Execs,
+    };
+  }
+
+  pub fn next(&mut self) {
+    let i = match self.state.selected() {
+      Some(i) => (i + 1).min(self.programs.len().saturating_sub(1)),
+      None => 0,
+    };
+    if !self.programs.is_empty() {
+      self.state.select(Some(i));
+    }
+  }
+
+  pub fn previous(&mut self) {
+    let i = self.state.selected().unwrap_or(0).saturating_sub(1);
+    if !self.programs.is_empty() {
+      self.state.select(Some(i));
+    }
+  }
+}
+
+impl Widget for &mut Stats {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    // Processes that are still running count up to now, so that the table is updated live
+    let now = SystemTime::now();
+    let mut running_time: HashMap<&str, Duration> = HashMap::new();
+    for (program, start) in self.running.values() {
+      *running_time.entry(program.as_str()).or_default() +=
+        now.duration_since(*start).unwrap_or_default();
+    }
+    let mut rows: Vec<_> = self
+      .programs
+      .iter()
+      .map(|(program, stats)| {
+        let runtime = stats.runtime
+          + running_time
+            .get(program.as_str())
+            .copied()
+            .unwrap_or_default();
+        (program, stats, runtime)
+      })
+      .collect();
+    match self.order {
+      StatsOrder::Execs => rows.sort_by(|(a_name, a, a_runtime), (b_name, b, b_runtime)| {
+        (b.execs, b_runtime, a_name).cmp(&(a.execs, a_runtime, b_name))
+      }),
+      StatsOrder::Runtime => rows.sort_by(|(a_name, a, a_runtime), (b_name, b, b_runtime)| {
+        (b_runtime, b.execs, a_name).cmp(&(a_runtime, a.execs, b_name))
+      }),
+    }
+    let header = Row::new([
+      Line::from("EXECS").right_aligned(),
+      Line::from("FAILED").right_aligned(),
+      Line::from("RUNTIME").right_aligned(),
+      Line::from("PROGRAM"),
+    ])
+    .style(THEME.stats_header);
+    let rows = rows.into_iter().map(|(program, stats, runtime)| {
+      Row::new([
+        Line::from(stats.execs.to_string()).right_aligned(),
+        Line::styled(
+          stats.failed_execs.to_string(),
+          if stats.failed_execs > 0 {
+            THEME.pid_failure
+          } else {
+            Default::default()
+          },
+        )
+        .right_aligned(),
+        Line::from(format!("{:.3}s", runtime.as_secs_f64())).right_aligned(),
+        Line::styled(program.clone(), THEME.filename),
+      ])
+    });
+    let table = Table::new(
+      rows,
+      [
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(12),
+        Constraint::Fill(1),
+      ],
+    )
+    .header(header)
+    .highlight_style(THEME.selection);
+    StatefulWidget::render(table, area, buf, &mut self.state);
+  }
+}
@@ -0,0 +1,56 @@
+//! Traces started from within the TUI, each of which is shown in its own tab
+
+use std::{sync::Arc, thread::JoinHandle};
+
+use nix::unistd::Pid;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::{
+  event::TracerEvent,
+  proc::BaselineInfo,
+  tracer::{Tracer, TracerPause},
+};
+
+use super::{event_list::EventList, process_tree::ProcessTree, stats::Stats, timeline::Timeline};
+
+/// What a new trace traces
+pub enum TraceTarget {
+  /// Run the command and trace it
+  Command(Vec<String>),
+  /// Attach to the running process
+  Process(Pid),
+}
+
+/// Starts tracing the target in a new tracer thread
+pub type TraceSpawner = Box<dyn Fn(TraceTarget) -> color_eyre::Result<NewTrace>>;
+
+pub struct NewTrace {
+  pub baseline: BaselineInfo,
+  pub tracer_rx: UnboundedReceiver<TracerEvent>,
+  pub pause: Arc<TracerPause>,
+  pub thread: JoinHandle<color_eyre::Result<()>>,
+  pub tracer: Arc<Tracer>,
+}
+
+/// The state of a trace in a tab that is not active.
+///
+/// The state of the trace in the active tab lives in the `App`, they are swapped when switching tabs.
+pub struct TraceTab {
+  pub event_list: EventList,
+  pub process_tree: ProcessTree,
+  pub stats: Stats,
+  pub timeline: Timeline,
+  pub tracer_pause: Option<Arc<TracerPause>>,
+  pub tracer: Option<Arc<Tracer>>,
+  pub child_cycle_parent: Option<Pid>,
+  /// The events received while the tab is not active, they are handled when it becomes active
+  pub pending_events: Vec<TracerEvent>,
+}
+
+/// The title of a tab is the command line of the first exec in it
+pub fn title_of(event: &TracerEvent) -> Option<String> {
+  let TracerEvent::Exec(exec) = event else {
+    return None;
+  };
+  exec.argv.as_deref().ok().map(|argv| argv.join(" "))
+}
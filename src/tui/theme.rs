@@ -1,5 +1,14 @@
+use std::{str::FromStr, sync::OnceLock};
+
+use color_eyre::eyre::bail;
 use lazy_static::lazy_static;
-use ratatui::style::{Style, Stylize};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use serde::Deserialize;
+
+use crate::{
+  cli::config::ThemeConfig,
+  term::{term_caps, TermColor},
+};
 
 pub struct Theme {
   // Color for UI Elements
@@ -7,11 +16,33 @@ pub struct Theme {
   pub active_border: Style,
   pub popup_border: Style,
   pub app_title: Style,
+  pub status_bar: Style,
+  pub paused: Style,
+  pub watch_alert: Style,
   pub help_popup: Style,
+  pub selection: Style,
+  pub status_running: Style,
+  pub timestamp: Style,
+  pub user: Style,
+  pub bookmark: Style,
+  pub group_header: Style,
   // Color for help items
   pub cli_flag: Style,
   pub help_key: Style,
   pub help_desc: Style,
+  // Search
+  pub search_prompt: Style,
+  pub search_match: Style,
+  // Process Tree
+  pub tree_guide: Style,
+  pub process_status: Style,
+  // Statistics
+  pub stats_header: Style,
+  // Timeline
+  pub timeline_axis: Style,
+  pub timeline_running: Style,
+  pub timeline_exited: Style,
+  pub timeline_failed: Style,
   // Tracer Event
   pub pid_success: Style,
   pub pid_failure: Style,
@@ -46,6 +77,8 @@ pub struct Theme {
   pub added_env_val: Style,
   pub removed_env_key: Style,
   pub removed_env_val: Style,
+  pub changed_env_key: Style,
+  pub changed_env_val: Style,
   pub unchanged_env_key: Style,
   pub unchanged_env_val: Style,
   pub fd_label: Style,
@@ -62,6 +95,11 @@ pub struct Theme {
   pub visual_separator: Style,
   // Tabs
   pub active_tab: Style,
+  pub inactive_tab: Style,
+  // Pseudo Terminal
+  pub scrollback_indicator: Style,
+  pub copy_mode_cursor: Style,
+  pub copy_mode_selection: Style,
 }
 
 impl Default for Theme {
@@ -71,7 +109,16 @@ impl Default for Theme {
       active_border: Style::default().cyan(),
       popup_border: Style::default(),
       app_title: Style::default().bold(),
+      status_bar: Style::default().white().on_dark_gray(),
+      paused: Style::default().black().on_light_yellow().bold(),
+      watch_alert: Style::default().white().on_light_red().bold(),
       help_popup: Style::default().black().on_gray(),
+      selection: Style::default().bold().on_dark_gray(),
+      status_running: Style::default().light_yellow(),
+      timestamp: Style::default().dark_gray(),
+      user: Style::default().light_green(),
+      bookmark: Style::default().on_blue(),
+      group_header: Style::default().bold(),
       // -- Help Items --
       cli_flag: Style::default().yellow().on_dark_gray().bold(),
       help_key: Style::default().black().on_cyan().bold(),
@@ -80,6 +127,19 @@ impl Default for Theme {
         .on_dark_gray()
         .italic()
         .bold(),
+      // -- Search --
+      search_prompt: Style::default().light_cyan().bold(),
+      search_match: Style::default().black().on_yellow(),
+      // -- Process Tree --
+      tree_guide: Style::default().dark_gray(),
+      process_status: Style::default().gray().italic(),
+      // -- Statistics --
+      stats_header: Style::default().bold().underlined(),
+      // -- Timeline --
+      timeline_axis: Style::default().dark_gray(),
+      timeline_running: Style::default().light_yellow(),
+      timeline_exited: Style::default().light_green(),
+      timeline_failed: Style::default().light_red(),
       // -- Tracer Event --
       pid_success: Style::default().light_green(),
       pid_failure: Style::default().light_red(),
@@ -114,6 +174,8 @@ impl Default for Theme {
       added_env_val: Style::default().light_green(),
       removed_env_key: Style::default().light_red().bold(),
       removed_env_val: Style::default().light_red(),
+      changed_env_key: Style::default().light_yellow().bold(),
+      changed_env_val: Style::default().light_yellow(),
       unchanged_env_key: Style::default().white().bold(),
       unchanged_env_val: Style::default().white(),
       fd_label: Style::default().black().on_light_green().bold(),
@@ -130,10 +192,317 @@ impl Default for Theme {
       visual_separator: Style::default().light_green(),
       // -- Tabs --
       active_tab: Style::default().white().on_magenta(),
+      inactive_tab: Style::default().white().on_dark_gray(),
+      // -- Pseudo Terminal --
+      scrollback_indicator: Style::default().black().on_yellow(),
+      copy_mode_cursor: Style::default().black().on_light_yellow(),
+      copy_mode_selection: Style::default().reversed(),
+    }
+  }
+}
+
+impl Theme {
+  /// A theme for terminals with a light background
+  pub fn light() -> Self {
+    Self {
+      inactive_border: Style::default().dark_gray(),
+      active_border: Style::default().blue(),
+      help_popup: Style::default().white().on_dark_gray(),
+      selection: Style::default().bold().on_gray(),
+      status_running: Style::default().yellow(),
+      timestamp: Style::default().gray(),
+      user: Style::default().green(),
+      bookmark: Style::default().on_light_yellow(),
+      cli_flag: Style::default().black().on_yellow().bold(),
+      help_key: Style::default().white().on_blue().bold(),
+      help_desc: Style::default().black().on_gray().italic().bold(),
+      search_prompt: Style::default().blue().bold(),
+      tree_guide: Style::default().gray(),
+      process_status: Style::default().dark_gray().italic(),
+      timeline_axis: Style::default().gray(),
+      timeline_running: Style::default().yellow(),
+      timeline_exited: Style::default().green(),
+      timeline_failed: Style::default().red(),
+      pid_success: Style::default().green(),
+      pid_failure: Style::default().red(),
+      pid_enoent: Style::default().yellow(),
+      pid_in_msg: Style::default().magenta(),
+      comm: Style::default().blue(),
+      tracer_info: Style::default().blue().bold(),
+      tracer_warning: Style::default().yellow().bold(),
+      tracer_error: Style::default().red().bold(),
+      inline_tracer_error: Style::default().red().bold().slow_blink(),
+      filename: Style::default().blue(),
+      modified_fd_in_cmdline: Style::default().yellow().bold(),
+      removed_fd_in_cmdline: Style::default().red().bold(),
+      added_fd_in_cmdline: Style::default().green().bold(),
+      cloexec_fd_in_cmdline: Style::default().red().bold().italic(),
+      arg0: Style::default().black().italic(),
+      cwd: Style::default().cyan(),
+      deleted_env_var: Style::default().red(),
+      added_env_var: Style::default().green(),
+      fd_closed: Style::default().red(),
+      plus_sign: Style::default().green(),
+      minus_sign: Style::default().red(),
+      added_env_key: Style::default().green().bold(),
+      added_env_val: Style::default().green(),
+      removed_env_key: Style::default().red().bold(),
+      removed_env_val: Style::default().red(),
+      changed_env_key: Style::default().yellow().bold(),
+      changed_env_val: Style::default().yellow(),
+      unchanged_env_key: Style::default().black().bold(),
+      unchanged_env_val: Style::default().black(),
+      fd_label: Style::default().white().on_green().bold(),
+      fd_number_label: Style::default().white().on_magenta().bold(),
+      sublabel: Style::default().black().bold(),
+      label: Style::default().white().on_green().bold(),
+      selected_label: Style::default().white().on_magenta().bold(),
+      selection_indicator: Style::default().green().bold(),
+      open_flag_cloexec: Style::default().green().bold(),
+      open_flag_access_mode: Style::default().blue().bold(),
+      open_flag_creation: Style::default().cyan().bold(),
+      open_flag_status: Style::default().yellow().bold(),
+      open_flag_other: Style::default().red().bold(),
+      visual_separator: Style::default().green(),
+      ..Self::default()
+    }
+  }
+
+  /// The theme of the config file, without colors if `colorize` is false
+  pub fn from_config(config: &ThemeConfig, colorize: bool) -> color_eyre::Result<Self> {
+    let mut theme = match config.preset {
+      ThemePreset::Default => Self::default(),
+      ThemePreset::Light => Self::light(),
+    };
+    for (name, style) in config.styles.iter() {
+      let Some(field) = theme.style_mut(name) else {
+        bail!("Unknown style {name:?} in the theme section of the config file");
+      };
+      *field = style.0;
+    }
+    if !colorize {
+      for style in theme.styles_mut() {
+        *style = without_colors(*style);
+      }
+    }
+    Ok(theme)
+  }
+}
+
+/// Drop the colors of the style, the text with a background is reversed instead to stay distinguishable
+fn without_colors(style: Style) -> Style {
+  let reversed = style.bg.is_some_and(|bg| bg != Color::Reset);
+  let style = Style {
+    fg: None,
+    bg: None,
+    ..style
+  };
+  if reversed {
+    style.add_modifier(Modifier::REVERSED)
+  } else {
+    style
+  }
+}
+
+/// Lists the styles of the theme once, for looking them up by name and for going through all of them
+macro_rules! theme_styles {
+  ($($field:ident),* $(,)?) => {
+    impl Theme {
+      fn style_mut(&mut self, name: &str) -> Option<&mut Style> {
+        Some(match name {
+          $(stringify!($field) => &mut self.$field,)*
+          _ => return None,
+        })
+      }
+
+      fn styles_mut(&mut self) -> impl Iterator<Item = &mut Style> {
+        [$(&mut self.$field),*].into_iter()
+      }
+    }
+  };
+}
+
+theme_styles!(
+  inactive_border,
+  active_border,
+  popup_border,
+  app_title,
+  status_bar,
+  paused,
+  watch_alert,
+  help_popup,
+  selection,
+  status_running,
+  timestamp,
+  user,
+  bookmark,
+  group_header,
+  cli_flag,
+  help_key,
+  help_desc,
+  search_prompt,
+  search_match,
+  tree_guide,
+  process_status,
+  stats_header,
+  timeline_axis,
+  timeline_running,
+  timeline_exited,
+  timeline_failed,
+  pid_success,
+  pid_failure,
+  pid_enoent,
+  pid_in_msg,
+  comm,
+  tracer_info,
+  tracer_warning,
+  tracer_error,
+  new_child_pid,
+  tracer_event,
+  inline_tracer_error,
+  filename,
+  modified_fd_in_cmdline,
+  removed_fd_in_cmdline,
+  cloexec_fd_in_cmdline,
+  added_fd_in_cmdline,
+  arg0,
+  cwd,
+  deleted_env_var,
+  modified_env_var,
+  added_env_var,
+  argv,
+  exec_result_success,
+  exec_result_failure,
+  fd_closed,
+  plus_sign,
+  minus_sign,
+  equal_sign,
+  added_env_key,
+  added_env_val,
+  removed_env_key,
+  removed_env_val,
+  changed_env_key,
+  changed_env_val,
+  unchanged_env_key,
+  unchanged_env_val,
+  fd_label,
+  fd_number_label,
+  sublabel,
+  selected_label,
+  label,
+  selection_indicator,
+  open_flag_cloexec,
+  open_flag_access_mode,
+  open_flag_creation,
+  open_flag_status,
+  open_flag_other,
+  visual_separator,
+  active_tab,
+  inactive_tab,
+  scrollback_indicator,
+  copy_mode_cursor,
+  copy_mode_selection,
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemePreset {
+  #[default]
+  Default,
+  Light,
+}
+
+/// A style written as space separated words, e.g. `light-green bold on dark-gray` or `#ff8700 italic`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ThemeStyle(Style);
+
+impl TryFrom<String> for ThemeStyle {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    let mut style = Style::default();
+    let mut words = value.split_ascii_whitespace();
+    while let Some(word) = words.next() {
+      if word == "on" {
+        let Some(color) = words.next() else {
+          return Err(format!(
+            "Expected a background color after 'on' in {value:?}"
+          ));
+        };
+        style = style.bg(parse_color(color)?);
+      } else if let Some(modifier) = parse_modifier(word) {
+        style = style.add_modifier(modifier);
+      } else {
+        style = style.fg(parse_color(word)?);
+      }
     }
+    Ok(Self(style))
+  }
+}
+
+fn parse_color(s: &str) -> Result<Color, String> {
+  let color = Color::from_str(s).map_err(|_| format!("Unknown color or modifier {s:?}"))?;
+  // Downgrade the colors that the terminal doesn't support
+  let level = term_caps().color_level;
+  let color = match color {
+    Color::Rgb(r, g, b) => level.rgb(r, g, b),
+    Color::Indexed(index) => level.xterm(index),
+    color => return Ok(color),
+  };
+  Ok(match color {
+    TermColor::Rgb(r, g, b) => Color::Rgb(r, g, b),
+    TermColor::Xterm(index) => Color::Indexed(index),
+    TermColor::Ansi(index) => ANSI_COLORS[index as usize],
+  })
+}
+
+const ANSI_COLORS: [Color; 16] = [
+  Color::Black,
+  Color::Red,
+  Color::Green,
+  Color::Yellow,
+  Color::Blue,
+  Color::Magenta,
+  Color::Cyan,
+  Color::Gray,
+  Color::DarkGray,
+  Color::LightRed,
+  Color::LightGreen,
+  Color::LightYellow,
+  Color::LightBlue,
+  Color::LightMagenta,
+  Color::LightCyan,
+  Color::White,
+];
+
+fn parse_modifier(s: &str) -> Option<Modifier> {
+  Some(match s {
+    "bold" => Modifier::BOLD,
+    "dim" | "dimmed" => Modifier::DIM,
+    "italic" => Modifier::ITALIC,
+    "underline" | "underlined" => Modifier::UNDERLINED,
+    "blink" | "slow-blink" => Modifier::SLOW_BLINK,
+    "rapid-blink" => Modifier::RAPID_BLINK,
+    "reversed" => Modifier::REVERSED,
+    "hidden" => Modifier::HIDDEN,
+    "strikethrough" | "crossed-out" => Modifier::CROSSED_OUT,
+    _ => return None,
+  })
+}
+
+static CONFIGURED_THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Set the theme. This should be called at most once, before the TUI starts.
+pub fn set_theme(theme: Theme) {
+  if CONFIGURED_THEME.set(theme).is_err() {
+    panic!("The theme has already been set!");
   }
 }
 
 lazy_static! {
-  pub static ref THEME: Theme = Theme::default();
+  pub static ref THEME: &'static Theme = CONFIGURED_THEME.get_or_init(Theme::default);
 }
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::cli::config::ThemeConfig;
+
+use super::{Theme, ThemeStyle};
+
+fn style(s: &str) -> Result<Style, String> {
+  ThemeStyle::try_from(s.to_string()).map(|style| style.0)
+}
+
+#[test]
+fn styles_are_parsed() {
+  assert_eq!(
+    style("light-green bold on dark-gray"),
+    Ok(
+      Style::default()
+        .fg(Color::LightGreen)
+        .bg(Color::DarkGray)
+        .add_modifier(Modifier::BOLD)
+    )
+  );
+  assert_eq!(
+    style("italic crossed-out"),
+    Ok(Style::default().add_modifier(Modifier::ITALIC | Modifier::CROSSED_OUT))
+  );
+  assert_eq!(style(""), Ok(Style::default()));
+}
+
+#[test]
+fn bad_styles_are_rejected() {
+  assert!(style("bold on").unwrap_err().contains("background color"));
+  assert!(style("sparkly").unwrap_err().contains("Unknown color"));
+}
+
+#[test]
+fn theme_from_config() {
+  let config = ThemeConfig {
+    styles: BTreeMap::from([(
+      "selection".to_string(),
+      ThemeStyle(Style::default().bg(Color::Blue)),
+    )]),
+    ..Default::default()
+  };
+  let theme = Theme::from_config(&config, true).unwrap();
+  assert_eq!(theme.selection, Style::default().bg(Color::Blue));
+  // Without colors, the text with a background is reversed instead
+  let theme = Theme::from_config(&config, false).unwrap();
+  assert_eq!(
+    theme.selection,
+    Style::default().add_modifier(Modifier::REVERSED)
+  );
+  let config = ThemeConfig {
+    styles: BTreeMap::from([("nope".to_string(), ThemeStyle(Style::default()))]),
+    ..Default::default()
+  };
+  assert!(Theme::from_config(&config, true).is_err());
+}
@@ -0,0 +1,179 @@
+//! The timeline view, which shows the lifetime of each process as a horizontal bar
+
+use std::{
+  collections::HashMap,
+  time::{Duration, SystemTime},
+};
+
+use nix::unistd::Pid;
+use ratatui::{
+  buffer::Buffer,
+  layout::Rect,
+  style::Styled,
+  text::Line,
+  widgets::{Paragraph, Widget},
+};
+
+use crate::{
+  event::{ProcessExitStatus, TracerEvent},
+  term::symbols,
+};
+
+use super::theme::THEME;
+
+#[derive(Debug)]
+struct Lifetime {
+  pid: Pid,
+  program: String,
+  start: SystemTime,
+  end: Option<SystemTime>,
+  status: Option<ProcessExitStatus>,
+}
+
+#[derive(Debug, Default)]
+pub struct Timeline {
+  /// The processes in the order they started
+  processes: Vec<Lifetime>,
+  /// Index of the alive processes in `processes`
+  alive: HashMap<Pid, usize>,
+  /// The first process shown
+  offset: usize,
+}
+
+impl Timeline {
+  pub fn on_event(&mut self, event: &TracerEvent) {
+    match event {
+      TracerEvent::NewChild { pid, pcomm, .. } => {
+        // The event doesn't carry a timestamp, but it is received right after the fork
+        self.start(*pid, pcomm.clone(), SystemTime::now());
+      }
+      TracerEvent::Exec(exec) => {
+        let program = match exec.filename.as_ref() {
+          Ok(filename) if exec.result == 0 => filename
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| exec.comm.clone()),
+          _ => exec.comm.clone(),
+        };
+        match self.alive.get(&exec.pid) {
+          Some(&i) => self.processes[i].program = program,
+          None => self.start(exec.pid, program, exec.timestamp),
+        }
+      }
+      TracerEvent::ProcessExit {
+        pid,
+        status,
+        timestamp,
+        ..
+      } => {
+        if let Some(i) = self.alive.remove(pid) {
+          self.processes[i].end = Some(*timestamp);
+          self.processes[i].status = Some(*status);
+        }
+      }
+      _ => {}
+    }
+  }
+
+  fn start(&mut self, pid: Pid, program: String, start: SystemTime) {
+    if self.alive.contains_key(&pid) {
+      return;
+    }
+    self.alive.insert(pid, self.processes.len());
+    self.processes.push(Lifetime {
+      pid,
+      program,
+      start,
+      end: None,
+      status: None,
+    });
+  }
+
+  pub fn nr_alive(&self) -> usize {
+    self.alive.len()
+  }
+
+  pub fn nr_exited(&self) -> usize {
+    self.processes.len() - self.alive.len()
+  }
+
+  /// When the first process started and when the last one exited, or now if any is still running
+  fn bounds(&self) -> Option<(SystemTime, SystemTime)> {
+    let origin = self.processes.iter().map(|p| p.start).min()?;
+    let now = SystemTime::now();
+    let last = self
+      .processes
+      .iter()
+      .map(|p| p.end.unwrap_or(now))
+      .max()
+      .unwrap_or(now);
+    Some((origin, last))
+  }
+
+  /// How long the trace has been running
+  pub fn duration(&self) -> Option<Duration> {
+    let (origin, last) = self.bounds()?;
+    Some(last.duration_since(origin).unwrap_or_default())
+  }
+
+  pub fn scroll_down(&mut self) {
+    self.offset = (self.offset + 1).min(self.processes.len().saturating_sub(1));
+  }
+
+  pub fn scroll_up(&mut self) {
+    self.offset = self.offset.saturating_sub(1);
+  }
+}
+
+impl Widget for &Timeline {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let Some((origin, last)) = self.bounds() else {
+      Paragraph::new("No processes yet").render(area, buf);
+      return;
+    };
+    let span = last
+      .duration_since(origin)
+      .unwrap_or_default()
+      .max(Duration::from_millis(1));
+    let label_width = (area.width as usize / 3).min(24);
+    let bar_width = (area.width as usize).saturating_sub(label_width + 1);
+    let column_of = |time: SystemTime| {
+      let elapsed = time.duration_since(origin).unwrap_or_default();
+      ((elapsed.as_secs_f64() / span.as_secs_f64()) * bar_width as f64) as usize
+    };
+    let mut lines = vec![Line::from(vec![
+      format!("{:label_width$} ", "").into(),
+      format!(
+        "0s{:>width$}",
+        format!("{:.3}s", span.as_secs_f64()),
+        width = bar_width.saturating_sub(2)
+      )
+      .set_style(THEME.timeline_axis),
+    ])];
+    for process in self
+      .processes
+      .iter()
+      .skip(self.offset)
+      .take((area.height as usize).saturating_sub(1))
+    {
+      let label: String = format!("{} {}", process.pid, process.program)
+        .chars()
+        .take(label_width)
+        .collect();
+      let start = column_of(process.start).min(bar_width.saturating_sub(1));
+      // Every process gets at least one cell, no matter how short it lived
+      let end = column_of(process.end.unwrap_or(last)).clamp(start + 1, bar_width.max(1));
+      let style = match process.status {
+        None => THEME.timeline_running,
+        Some(status) if status.is_success() => THEME.timeline_exited,
+        Some(_) => THEME.timeline_failed,
+      };
+      lines.push(Line::from(vec![
+        format!("{label:label_width$} ").set_style(THEME.comm),
+        " ".repeat(start).into(),
+        symbols().bar.repeat(end - start).set_style(style),
+      ]));
+    }
+    Paragraph::new(lines).render(area, buf);
+  }
+}
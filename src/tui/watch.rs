@@ -0,0 +1,63 @@
+//! Watches, which notify when an exec matching a pattern occurs
+
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+
+use crate::event::ExecEvent;
+
+/// How long the status bar flashes after a watch matched
+const ALERT_DURATION: Duration = Duration::from_secs(5);
+/// The bell rings at most once in this long, a build can match a watch many times a second
+pub const BELL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+pub struct Watch {
+  pub regex: Regex,
+  /// Whether the matching events are selected in the event list
+  pub select: bool,
+}
+
+impl Watch {
+  /// Whether the regex matches the command line or one of the environment variables of the exec,
+  /// with the original values of the redacted ones
+  pub fn matches(&self, exec: &ExecEvent) -> bool {
+    exec
+      .argv
+      .as_deref()
+      .is_ok_and(|argv| self.regex.is_match(&argv.join(" ")))
+      || exec
+        .envp
+        .as_deref()
+        .is_ok_and(|envp| envp.iter().any(|entry| self.regex.is_match(entry)))
+      || exec
+        .secrets
+        .entries()
+        .any(|entry| self.regex.is_match(&entry))
+  }
+}
+
+/// The notification shown in the status bar when a watch matched
+#[derive(Debug, Clone)]
+pub struct WatchAlert {
+  pub message: String,
+  since: Instant,
+}
+
+impl WatchAlert {
+  pub fn new(message: String) -> Self {
+    Self {
+      message,
+      since: Instant::now(),
+    }
+  }
+
+  pub fn is_active(&self) -> bool {
+    self.since.elapsed() < ALERT_DURATION
+  }
+
+  /// The alert flashes by alternating between being lit and not
+  pub fn is_lit(&self) -> bool {
+    self.since.elapsed().as_millis() / 250 % 2 == 0
+  }
+}